@@ -0,0 +1,83 @@
+//! Benchmarks of parsing and evaluating expressions. Run them with `cargo bench`.
+
+extern crate mexprp;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use mexprp::{Context, Term};
+
+/// How long each benchmark runs for
+const DURATION: Duration = Duration::from_millis(500);
+
+const EXPR: &str = "3 * x^2 - 4 / (x + 1) + -x * 0.5";
+
+/// Run a function over and over for a while and print how long it took on average
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+	// Warm up first
+	for _ in 0..100 {
+		f();
+	}
+	let start = Instant::now();
+	let mut iters = 0u64;
+	while start.elapsed() < DURATION {
+		for _ in 0..100 {
+			f();
+		}
+		iters += 100;
+	}
+	let ns = start.elapsed().as_nanos() / u128::from(iters);
+	println!("{:<24} {:>10} ns/iter", name, ns);
+}
+
+fn main() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 1.5);
+	let term: Term<f64> = Term::parse_ctx(EXPR, &ctx).unwrap();
+	let constant: Term<f64> = Term::parse("3 * 1.5^2 - 4 / (1.5 + 1) + -1.5 * 0.5").unwrap();
+	let bound = term.bind(&Context::new()).unwrap();
+
+	bench("context_new", || {
+		black_box(Context::<f64>::new());
+	});
+	bench("parse", || {
+		black_box(Term::<f64>::parse_ctx(black_box(EXPR), &ctx).unwrap());
+	});
+	bench("parse_default", || {
+		black_box(Term::<f64>::parse(black_box(EXPR)).unwrap());
+	});
+	bench("eval", || {
+		black_box(constant.eval().unwrap());
+	});
+	bench("eval_ctx", || {
+		black_box(term.eval_ctx(&ctx).unwrap());
+	});
+	bench("eval_fast", || {
+		black_box(term.eval_fast(&ctx).unwrap());
+	});
+	bench("bound_eval", || {
+		black_box(bound.eval(&[1.5]).unwrap());
+	});
+
+	// A polynomial with 60 terms
+	let large: Vec<String> = (1..=60).map(|k| format!("{} * x^{}", k, k % 7)).collect();
+	let large: Term<f64> = Term::parse_ctx(&large.join(" + "), &ctx).unwrap();
+	let large_bound = large.bind(&Context::new()).unwrap();
+	bench("eval_ctx_large", || {
+		black_box(large.eval_ctx(&ctx).unwrap());
+	});
+	bench("bound_eval_large", || {
+		black_box(large_bound.eval(&[1.5]).unwrap());
+	});
+
+	// A term nested 100 operations deep, where the tree is the slowest to walk
+	let deep = (0..50).fold(String::from("x"), |term, k| format!("({} + {}) * 0.5", term, k));
+	let deep: Term<f64> = Term::parse_ctx(&deep, &ctx).unwrap();
+	let deep_bound = deep.bind(&Context::new()).unwrap();
+	bench("eval_ctx_deep", || {
+		black_box(deep.eval_ctx(&ctx).unwrap());
+	});
+	bench("bound_eval_deep", || {
+		black_box(deep_bound.eval(&[1.5]).unwrap());
+	});
+}
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mexprp::{Context, Expression, Term};
+
+fuzz_target!(|data: &[u8]| {
+	if let Ok(raw) = std::str::from_utf8(data) {
+		let mut ctx: Context<f64> = Context::new();
+		ctx.cfg.max_steps = Some(100_000);
+
+		let _ = Expression::check(raw, &ctx);
+		if let Ok(term) = Term::parse_ctx(raw, &ctx) {
+			let _ = term.eval_ctx(&ctx);
+			let _ = term.to_string();
+			let _ = term.fold_constants(&ctx);
+		}
+	}
+});
@@ -0,0 +1,40 @@
+/* C API for MEXPRP. Build the crate with the "ffi" feature as a cdylib or staticlib to use it. */
+
+#ifndef MEXPRP_H
+#define MEXPRP_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define MEXPRP_OK 0
+#define MEXPRP_INVALID_ARGUMENT 1
+#define MEXPRP_PARSE_ERROR 2
+#define MEXPRP_MATH_ERROR 3
+#define MEXPRP_BUFFER_TOO_SMALL 4
+#define MEXPRP_PANIC 5
+
+typedef struct MexprpContext MexprpContext;
+typedef struct MexprpTerm MexprpTerm;
+
+/* The message of the last error on this thread, or NULL. Valid until the next call on this thread. */
+const char *mexprp_last_error(void);
+
+MexprpContext *mexprp_context_new(void);
+void mexprp_context_free(MexprpContext *ctx);
+int mexprp_set_var(MexprpContext *ctx, const char *name, double value);
+
+/* ctx may be NULL to use the default context. Returns NULL on failure. */
+MexprpTerm *mexprp_parse(const char *raw, const MexprpContext *ctx, int *status);
+void mexprp_term_free(MexprpTerm *term);
+
+/* Writes up to capacity answers to out, and the total amount of answers to len. */
+int mexprp_eval_f64(const MexprpTerm *term, const MexprpContext *ctx, double *out, size_t capacity, size_t *len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -1,125 +1,466 @@
+use alloc::collections::BTreeMap;
+use core::cmp::Ordering;
+
 use crate::num::Num;
+use crate::context::Context;
+use crate::term::Term;
+use crate::format::AnswerFormat;
 use crate::opers::Calculation;
-use std::fmt;
-
-/// An answer of an evaluatation. Can be either a single answer or multiple. This struct contains some
-/// helper methods for performing operations on single or multiple answers. The `op` method takes another
-/// `Num`, and a function with two `Num` arguments, itself and the other (as references). It performs
-/// that function on all combinations and returns an answer with all of the results in one. The `unop`
-/// function is similar but it performs an operation on only itself, without another value (*un*ary
-/// *op*eration).
+use crate::errors::MathError;
+use crate::prelude::*;
+use core::fmt;
+use core::ops;
+
+/// An answer of an evaluatation. Can be either a single answer, multiple answers, or a vector of
+/// answers. This struct contains some helper methods for performing operations on answers. The `op`
+/// method takes another `Num`, and a function with two `Num` arguments, itself and the other (as
+/// references). It performs that function on all combinations and returns an answer with all of the
+/// results in one. The `unop` function is similar but it performs an operation on only itself, without
+/// another value (*un*ary *op*eration). Values that are equal to a previous result are left out of
+/// the results of both.
+///
+/// Operations on vectors are performed element-wise. An operation between a vector and a single or
+/// multiple answer is performed between each element of the vector and that answer. An operation
+/// between two vectors requires them to have the same length, or a `MathError::DimensionMismatch` is
+/// returned.
+///
+/// Some multiple answers have a label for each value, like the principal and negative roots of
+/// `sqrt`. Operations between a labeled answer and a single answer keep the labels as long as each
+/// label still has one value. Other operations on labeled answers treat them like multiple answers.
+/// The first value of multiple answers is always the principal one, which can be gotten with
+/// `principal`.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Answer<N: Num> {
 	/// A single answer
 	Single(N),
 	/// Multiple answers. Will always be at least two (probably)
 	Multiple(Vec<N>),
+	/// A vector (list) of answers, eg `[1, 2, 3]`. Vectors can be nested to represent matrices.
+	Vector(Vec<Answer<N>>),
+	/// Multiple answers that each have a label saying which one they are
+	Labeled(Vec<(Label, N)>),
+}
+
+/// A label of a value of a labeled answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Label {
+	/// The conventional value, eg the positive root of a square root
+	Principal,
+	/// The negated principal value, eg the negative root of a square root
+	Negative,
+	/// Another branch of a multivalued function, numbered from 0
+	Branch(usize),
+}
+
+impl fmt::Display for Label {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Label::Principal => write!(f, "principal"),
+			Label::Negative => write!(f, "negative"),
+			Label::Branch(i) => write!(f, "branch {}", i),
+		}
+	}
+}
+
+/// Perform an operation on each value of a labeled answer, keeping the labels if every value has one
+/// result
+fn labeled_op<N: Num, F: Fn(&N) -> Calculation<N>>(ls: &[(Label, N)], oper: F, limit: usize) -> Calculation<N> {
+	let mut labeled = Vec::new();
+	let mut answers = Values::new(limit);
+	let mut single = true;
+	for &(label, ref n) in ls {
+		let answer = oper(n)?;
+		match answer {
+			Answer::Single(ref n) if single => labeled.push((label, n.clone())),
+			_ => single = false,
+		}
+		answers.push_answer(answer)?;
+	}
+	Ok(if single {
+		Answer::Labeled(labeled)
+	} else {
+		Answer::Multiple(answers.list)
+	})
+}
+
+/// Distinct values collected from the results of an operation, up to a limit
+struct Values<N: Num> {
+	list: Vec<N>,
+	/// The indices of the values in `list` by the bits of their (lossy) conversion to `f64`, so that
+	/// only values that could be equal are compared. Values that can't be converted are under `None`.
+	index: BTreeMap<Option<u64>, Vec<usize>>,
+	limit: usize,
+}
+
+impl<N: Num> Values<N> {
+	fn new(limit: usize) -> Self {
+		Values {
+			list: Vec::new(),
+			index: BTreeMap::new(),
+			limit,
+		}
+	}
+
+	/// Values starting with the given list without a limit, keeping any duplicates already in it
+	fn with(list: Vec<N>) -> Self {
+		let mut values = Values::new(usize::MAX);
+		for (i, n) in list.iter().enumerate() {
+			values.index.entry(Self::key(n)).or_default().push(i);
+		}
+		values.list = list;
+		values
+	}
+
+	fn key(n: &N) -> Option<u64> {
+		// Make sure 0.0 and -0.0 have the same key, since they're equal
+		n.to_f64_lossy().ok().map(|f| if f == 0.0 { 0.0f64 } else { f }.to_bits())
+	}
+
+	/// Add a value if it isn't already in the list, failing if that makes more values than the limit
+	fn push(&mut self, n: N) -> Result<(), MathError> {
+		let Values { ref mut list, ref mut index, limit } = *self;
+		let indices = index.entry(Self::key(&n)).or_default();
+		if indices.iter().any(|&i| list[i] == n) {
+			return Ok(());
+		}
+		if list.len() >= limit {
+			return Err(MathError::TooManyAnswers);
+		}
+		indices.push(list.len());
+		list.push(n);
+		Ok(())
+	}
+
+	/// Push the values of an answer, skipping values that are already in the list. Vectors are
+	/// flattened.
+	fn push_answer(&mut self, answer: Answer<N>) -> Result<(), MathError> {
+		for n in answer.to_vec() {
+			self.push(n)?;
+		}
+		Ok(())
+	}
 }
 
 impl<N: Num> Answer<N> {
 	/// Perform an operation on all the values of an answer with all the values of another answer
 	pub fn op<F: Fn(&N, &N) -> Calculation<N>>(&self, other: &Self, oper: F) -> Calculation<N> {
-		fn push_answers<N: Num>(answer: Answer<N>, list: &mut Vec<N>) {
-			match answer {
-				Answer::Single(n) => list.push(n),
-				Answer::Multiple(ns) => for n in ns {
-					list.push(n)
-				},
-			}
-		}
+		self.op_with(other, &oper, usize::MAX)
+	}
 
-		match *self {
-			Answer::Single(ref n) => match *other {
-				Answer::Single(ref n2) => oper(n, n2),
-				Answer::Multiple(ref n2s) => {
-					let mut answers = Vec::new();
-					for n2 in n2s {
-						push_answers(oper(n, n2)?, &mut answers);
-					}
-					Ok(Answer::Multiple(answers))
+	/// Perform an operation on all the values of an answer with all the values of another answer like
+	/// `op`, but fail with `MathError::TooManyAnswers` as soon as the result has more values than the
+	/// `max_answers` of the context's config, instead of computing every combination first
+	pub fn op_ctx<F: Fn(&N, &N) -> Calculation<N>>(&self, other: &Self, ctx: &Context<N>, oper: F) -> Calculation<N> {
+		self.op_with(other, &oper, ctx.cfg.max_answers)
+	}
+
+	fn op_with<F: Fn(&N, &N) -> Calculation<N>>(&self, other: &Self, oper: &F, limit: usize) -> Calculation<N> {
+		match (self, other) {
+			(Answer::Vector(ref ns), Answer::Vector(ref n2s)) => {
+				if ns.len() != n2s.len() {
+					return Err(MathError::DimensionMismatch);
 				}
-			},
-			Answer::Multiple(ref ns) => match *other {
-				Answer::Single(ref n2) => {
-					let mut answers = Vec::new();
-					for n in ns {
-						push_answers(oper(n, n2)?, &mut answers);
-					}
-					Ok(Answer::Multiple(answers))
+				Ok(Answer::Vector(ns.iter()
+					.zip(n2s.iter())
+					.map(|(n, n2)| n.op_with(n2, oper, limit))
+					.collect::<Result<Vec<Answer<N>>, MathError>>()?))
+			}
+			(Answer::Vector(ref ns), _) => Ok(Answer::Vector(ns.iter()
+				.map(|n| n.op_with(other, oper, limit))
+				.collect::<Result<Vec<Answer<N>>, MathError>>()?)),
+			(_, Answer::Vector(ref n2s)) => Ok(Answer::Vector(n2s.iter()
+				.map(|n2| self.op_with(n2, oper, limit))
+				.collect::<Result<Vec<Answer<N>>, MathError>>()?)),
+			(Answer::Single(ref n), Answer::Single(ref n2)) => oper(n, n2),
+			(Answer::Labeled(ref ls), Answer::Single(ref n2)) => labeled_op(ls, |n| oper(n, n2), limit),
+			(Answer::Single(ref n), Answer::Labeled(ref l2s)) => labeled_op(l2s, |n2| oper(n, n2), limit),
+			(Answer::Labeled(_), _) | (_, Answer::Labeled(_)) => {
+				self.clone().unlabel().op_with(&other.clone().unlabel(), oper, limit)
+			}
+			(Answer::Single(ref n), Answer::Multiple(ref n2s)) => {
+				let mut answers = Values::new(limit);
+				for n2 in n2s {
+					answers.push_answer(oper(n, n2)?)?;
+				}
+				Ok(Answer::Multiple(answers.list))
+			}
+			(Answer::Multiple(ref ns), Answer::Single(ref n2)) => {
+				let mut answers = Values::new(limit);
+				for n in ns {
+					answers.push_answer(oper(n, n2)?)?;
 				}
-				Answer::Multiple(ref n2s) => {
-					let mut answers = Vec::new();
-					for n in ns {
-						for n2 in n2s {
-							push_answers(oper(n, n2)?, &mut answers);
-						}
+				Ok(Answer::Multiple(answers.list))
+			}
+			(Answer::Multiple(ref ns), Answer::Multiple(ref n2s)) => {
+				let mut answers = Values::new(limit);
+				for n in ns {
+					for n2 in n2s {
+						answers.push_answer(oper(n, n2)?)?;
 					}
-					Ok(Answer::Multiple(answers))
 				}
-			},
+				Ok(Answer::Multiple(answers.list))
+			}
 		}
 	}
 
 	/// Perform an operation on all the values of an answer
 	pub fn unop<F: Fn(&N) -> Calculation<N>>(&self, oper: F) -> Calculation<N> {
-		fn push_answers<N: Num>(answer: Answer<N>, list: &mut Vec<N>) {
-			match answer {
-				Answer::Single(n) => list.push(n),
-				Answer::Multiple(ns) => for n in ns {
-					list.push(n)
-				},
-			}
-		}
+		self.unop_with(&oper, usize::MAX)
+	}
+
+	/// Perform an operation on all the values of an answer like `unop`, but fail with
+	/// `MathError::TooManyAnswers` as soon as the result has more values than the `max_answers` of the
+	/// context's config
+	pub fn unop_ctx<F: Fn(&N) -> Calculation<N>>(&self, ctx: &Context<N>, oper: F) -> Calculation<N> {
+		self.unop_with(&oper, ctx.cfg.max_answers)
+	}
 
+	fn unop_with<F: Fn(&N) -> Calculation<N>>(&self, oper: &F, limit: usize) -> Calculation<N> {
 		match *self {
 			Answer::Single(ref n) => oper(n),
 			Answer::Multiple(ref ns) => {
-				let mut answers = Vec::new();
+				let mut answers = Values::new(limit);
 				for n in ns {
-					push_answers(oper(n)?, &mut answers);
+					answers.push_answer(oper(n)?)?;
 				}
-				Ok(Answer::Multiple(answers))
+				Ok(Answer::Multiple(answers.list))
 			}
+			Answer::Vector(ref ns) => Ok(Answer::Vector(ns.iter()
+				.map(|n| n.unop_with(oper, limit))
+				.collect::<Result<Vec<Answer<N>>, MathError>>()?)),
+			Answer::Labeled(ref ls) => labeled_op(ls, oper, limit),
+		}
+	}
+
+	/// Remove the labels of a labeled answer, turning it into a multiple answer. Other answers are
+	/// returned as they are.
+	pub fn unlabel(self) -> Self {
+		match self {
+			Answer::Labeled(ls) => Answer::Multiple(ls.into_iter().map(|(_, n)| n).collect()),
+			Answer::Vector(ns) => Answer::Vector(ns.into_iter().map(Answer::unlabel).collect()),
+			other => other,
+		}
+	}
+
+	/// Get the principal value of this answer: the single value of a single answer, the value labeled
+	/// `Label::Principal` of a labeled answer, or the first value of multiple answers. Vectors don't
+	/// have a principal value.
+	pub fn principal(&self) -> Option<&N> {
+		match *self {
+			Answer::Single(ref n) => Some(n),
+			Answer::Multiple(ref ns) => ns.first(),
+			Answer::Labeled(ref ls) => ls.iter()
+				.find(|&&(label, _)| label == Label::Principal)
+				.or_else(|| ls.first())
+				.map(|(_, n)| n),
+			Answer::Vector(_) => None,
 		}
 	}
 
+	/// Replace multiple and labeled answers with their principal value. The elements of vectors are
+	/// collapsed separately.
+	pub fn collapse(self) -> Self {
+		match self {
+			Answer::Single(n) => Answer::Single(n),
+			Answer::Vector(ns) => Answer::Vector(ns.into_iter().map(Answer::collapse).collect()),
+			other => match other.principal() {
+				Some(n) => Answer::Single(n.clone()),
+				None => other,
+			},
+		}
+	}
+
+	/// Get the value of a labeled answer with the given label
+	pub fn labeled(&self, label: Label) -> Option<&N> {
+		match *self {
+			Answer::Labeled(ref ls) => ls.iter().find(|&&(l, _)| l == label).map(|(_, n)| n),
+			_ => None,
+		}
+	}
+
+	/// Remove values that are equal to another value of the answer, according to either `==` or
+	/// `tryord`. Multiple answers that only have one distinct value are collapsed into a single
+	/// answer, so `{0, -0}` becomes `0`. The elements of vectors are deduplicated separately.
+	pub fn dedup(self, ctx: &Context<N>) -> Self {
+		match self {
+			Answer::Single(n) => Answer::Single(n),
+			Answer::Multiple(ns) => {
+				let mut unique: Vec<N> = Vec::new();
+				for n in ns {
+					let seen = unique.iter().any(|u| *u == n || u.tryord(&n, ctx).ok() == Some(Ordering::Equal));
+					if !seen {
+						unique.push(n);
+					}
+				}
+				if unique.len() == 1 {
+					Answer::Single(unique.pop().unwrap())
+				} else {
+					Answer::Multiple(unique)
+				}
+			}
+			Answer::Vector(ns) => Answer::Vector(ns.into_iter().map(|n| n.dedup(ctx)).collect()),
+			Answer::Labeled(ls) => {
+				let mut unique: Vec<(Label, N)> = Vec::new();
+				for (label, n) in ls {
+					let seen = unique.iter().any(|(_, u)| *u == n || u.tryord(&n, ctx).ok() == Some(Ordering::Equal));
+					if !seen {
+						unique.push((label, n));
+					}
+				}
+				if unique.len() == 1 {
+					Answer::Single(unique.pop().unwrap().1)
+				} else {
+					Answer::Labeled(unique)
+				}
+			}
+		}
+	}
+
+	/// Format the values of this answer with the given options, the same way `Display` would
+	pub fn format(&self, fmt: &AnswerFormat) -> String {
+		match *self {
+			Answer::Single(ref n) => n.format(fmt),
+			Answer::Multiple(ref ns) => format!("{{{}}}", ns.iter().map(|n| n.format(fmt)).collect::<Vec<String>>().join(", ")),
+			Answer::Vector(ref ns) => format!("[{}]", ns.iter().map(|n| n.format(fmt)).collect::<Vec<String>>().join(", ")),
+			Answer::Labeled(ref ls) => format!("{{{}}}", ls.iter().map(|(_, n)| n.format(fmt)).collect::<Vec<String>>().join(", ")),
+		}
+	}
+
+	/// Convert this answer to another number type by way of `f64`s, keeping its structure. Complex
+	/// numbers keep their imaginary part if the other type supports it. See
+	/// `Num::to_f64_complex_lossy` and `Num::from_f64_complex`.
+	pub fn convert<M: Num>(&self, ctx: &Context<M>) -> Result<Answer<M>, MathError> {
+		let convert = |n: &N| -> Result<M, MathError> {
+			match M::from_f64_complex(n.to_f64_complex_lossy()?, ctx)? {
+				Answer::Single(m) => Ok(m),
+				_ => Err(MathError::Other),
+			}
+		};
+		Ok(match *self {
+			Answer::Single(ref n) => Answer::Single(convert(n)?),
+			Answer::Multiple(ref ns) => Answer::Multiple(ns.iter().map(convert).collect::<Result<_, _>>()?),
+			Answer::Vector(ref ns) => Answer::Vector(ns.iter().map(|n| n.convert(ctx)).collect::<Result<_, _>>()?),
+			Answer::Labeled(ref ls) => Answer::Labeled(ls.iter()
+				.map(|&(label, ref n)| Ok((label, convert(n)?)))
+				.collect::<Result<_, MathError>>()?),
+		})
+	}
+
 	/// Unwrap the single variant of an answer
 	pub fn unwrap_single(self) -> N {
 		match self {
 			Answer::Single(n) => n,
-			Answer::Multiple(_) => panic!("Attempted to unwrap multiple answers as one"),
+			Answer::Multiple(_) | Answer::Labeled(_) => panic!("Attempted to unwrap multiple answers as one"),
+			Answer::Vector(_) => panic!("Attempted to unwrap a vector as one answer"),
+		}
+	}
+
+	/// Check whether every value of this answer is finite (not infinite or NaN)
+	pub fn is_finite(&self) -> bool {
+		self.all(&|n: &N| n.is_finite())
+	}
+
+	/// Check whether any value of this answer is NaN
+	pub fn has_nan(&self) -> bool {
+		!self.all(&|n: &N| !n.is_nan())
+	}
+
+	/// Check whether every value of this answer, including the elements of vectors, satisfies a
+	/// predicate
+	fn all<F: Fn(&N) -> bool>(&self, pred: &F) -> bool {
+		match *self {
+			Answer::Single(ref n) => pred(n),
+			Answer::Multiple(ref ns) => ns.iter().all(pred),
+			Answer::Labeled(ref ls) => ls.iter().all(|(_, n)| pred(n)),
+			Answer::Vector(ref ns) => ns.iter().all(|a| a.all(pred)),
 		}
 	}
 
-	/// Convert this answer into a vector
+	/// Convert this answer into a vector. The elements of vector answers are flattened into it.
 	pub fn to_vec(self) -> Vec<N> {
 		match self {
 			Answer::Single(n) => vec![n],
 			Answer::Multiple(ns) => ns,
+			Answer::Labeled(ls) => ls.into_iter().map(|(_, n)| n).collect(),
+			Answer::Vector(ns) => {
+				let mut new = Vec::new();
+				for n in ns {
+					new.append(&mut n.to_vec());
+				}
+				new
+			}
 		}
 	}
 
-	/// Adds all the answers of another answer to the asnwers of this answer, returning a new answer
+	/// Adds all the answers of another answer to the asnwers of this answer, returning a new answer.
+	/// Values of the other answer that this answer already has are left out.
+	/// Joining a vector with an answer joins each element of the vector with that answer, and joining
+	/// two vectors of the same length joins them element-wise.
 	pub fn join(self, other: Self) -> Self {
-		let mut new = Vec::new();
-		match self {
-			Answer::Single(n) => {
-				new.push(n);
+		match (self, other) {
+			(Answer::Vector(ns), Answer::Vector(n2s)) => {
+				if ns.len() == n2s.len() {
+					return Answer::Vector(ns.into_iter().zip(n2s).map(|(n, n2)| n.join(n2)).collect());
+				}
+				let mut new = Values::with(Answer::Vector(ns).to_vec());
+				// There's no limit
+				let _ = new.push_answer(Answer::Vector(n2s));
+				Answer::Multiple(new.list)
 			}
-			Answer::Multiple(mut ns) => {
-				new.append(&mut ns);
+			(Answer::Vector(ns), other) => Answer::Vector(ns.into_iter().map(|n| n.join(other.clone())).collect()),
+			(this, Answer::Vector(n2s)) => Answer::Vector(n2s.into_iter().map(|n2| this.clone().join(n2)).collect()),
+			(this, other) => {
+				let mut new = Values::with(this.to_vec());
+				// There's no limit
+				let _ = new.push_answer(other);
+				Answer::Multiple(new.list)
 			}
 		}
+	}
+}
 
-		match other {
-			Answer::Single(n) => {
-				new.push(n);
+/// Implement an operator for answers and references to answers, evaluating it like the operator in
+/// an expression
+macro_rules! answer_op {
+	($op:ident, $method:ident) => {
+		impl<N: Num + 'static> ops::$op for Answer<N> {
+			type Output = Calculation<N>;
+
+			fn $method(self, other: Answer<N>) -> Calculation<N> {
+				Term::$method(Term::Num(self), Term::Num(other)).eval_ctx(&Context::empty())
 			}
-			Answer::Multiple(mut ns) => {
-				new.append(&mut ns);
+		}
+
+		impl<'a, N: Num + 'static> ops::$op for &'a Answer<N> {
+			type Output = Calculation<N>;
+
+			fn $method(self, other: &'a Answer<N>) -> Calculation<N> {
+				Term::$method(Term::Num(self.clone()), Term::Num(other.clone())).eval_ctx(&Context::empty())
 			}
 		}
-		Answer::Multiple(new)
+	};
+}
+
+// Answers can be combined with the same operators as in expressions, which are evaluated with the
+// default config. Operations that fail (like dividing by zero) result in an `Err`.
+answer_op!(Add, add);
+answer_op!(Sub, sub);
+answer_op!(Mul, mul);
+answer_op!(Div, div);
+
+impl<N: Num + 'static> ops::Neg for Answer<N> {
+	type Output = Calculation<N>;
+
+	fn neg(self) -> Calculation<N> {
+		Term::neg(Term::Num(self)).eval_ctx(&Context::empty())
+	}
+}
+
+impl<N: Num + 'static> ops::Neg for &Answer<N> {
+	type Output = Calculation<N>;
+
+	fn neg(self) -> Calculation<N> {
+		Term::neg(Term::Num(self.clone())).eval_ctx(&Context::empty())
 	}
 }
 
@@ -138,6 +479,18 @@ impl<N: Num> fmt::Display for Answer<N> {
 				buf.push_str("}");
 				write!(f, "{}", &buf)
 			}
+			Answer::Vector(ref ns) => {
+				let mut buf = String::from("[");
+				for (i, n) in ns.iter().enumerate() {
+					buf.push_str(&format!("{}", n));
+					if i + 1 < ns.len() {
+						buf.push_str(", ");
+					}
+				}
+				buf.push(']');
+				write!(f, "{}", &buf)
+			}
+			Answer::Labeled(_) => write!(f, "{}", self.clone().unlabel()),
 		}
 	}
 }
@@ -0,0 +1,479 @@
+//! A command line calculator. Evaluates the expressions given as arguments, or starts a REPL when
+//! there aren't any. Variables and functions defined in the REPL are saved to a session file and
+//! loaded again on start.
+
+extern crate mexprp;
+#[cfg(feature = "rug")]
+extern crate rug;
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::slice;
+
+use mexprp::{Answer, BoundTerm, Context, DefinitionError, Num, Session, Term};
+
+const USAGE: &str = "\
+Usage: mexprp [OPTIONS] [EXPRESSION]...
+
+Evaluates each expression and prints its answer. Starts a REPL if no expressions are given.
+Variables and functions are defined with `x = 2` and `f(x, y) = x^2 + y`. Definitions made in the
+REPL are saved to the session file, and loaded again whenever mexprp starts.
+
+Options:
+    --ctx NAME=VALUE    Define a variable, can be given more than once
+    --precision BITS    The precision of arbitrary precision numbers
+    --type TYPE         The type of numbers to use: f64 (default), rational or complex
+    --session FILE      The session file (default: ~/.mexprp_session)
+    --no-session        Don't load or save a session file
+    --sweep NAME=START:END:STEP
+                        Evaluate the expressions for each value of a variable from START to END
+                        and print a row for each value
+    --format FORMAT     How to print the rows of a sweep: table (default) or csv
+    -h, --help          Print this message
+
+REPL commands:
+    :defs               Print the saved definitions
+    :forget NAME        Remove a definition
+    :history            Print the previous answers
+    :clear              Forget the previous answers
+    :quit               Exit";
+
+/// The options given on the command line
+struct Options {
+	vars: Vec<(String, String)>,
+	precision: Option<u32>,
+	num_type: String,
+	session: Option<PathBuf>,
+	sweep: Option<Sweep>,
+	csv: bool,
+	exprs: Vec<String>,
+}
+
+/// A variable to sweep over a range of values given with `--sweep NAME=START:END:STEP`. The bounds
+/// are expressions, so they're only evaluated once the number type is known.
+struct Sweep {
+	name: String,
+	start: String,
+	end: String,
+	step: String,
+}
+
+/// The definitions of a session, in the order they were made, along with the file they're saved to
+struct Definitions {
+	path: Option<PathBuf>,
+	/// The names that were defined along with the lines that defined them
+	lines: Vec<(String, String)>,
+}
+
+impl Definitions {
+	/// Replace any previous definition of the name and save the definitions
+	fn add(&mut self, name: String, line: String) {
+		self.lines.retain(|(other, _)| *other != name);
+		self.lines.push((name, line));
+		self.save();
+	}
+
+	/// Remove the definition of the name and save the definitions. Returns whether it was defined.
+	fn remove(&mut self, name: &str) -> bool {
+		let len = self.lines.len();
+		self.lines.retain(|(other, _)| other != name);
+		let removed = self.lines.len() != len;
+		if removed {
+			self.save();
+		}
+		removed
+	}
+
+	/// Write the definitions to the session file, if there is one
+	fn save(&self) {
+		let path = match self.path {
+			Some(ref path) => path,
+			None => return,
+		};
+		let mut contents = String::new();
+		for (_, line) in &self.lines {
+			contents.push_str(line);
+			contents.push('\n');
+		}
+		if let Err(e) = fs::write(path, contents) {
+			eprintln!("Failed to save the session to {}: {}", path.display(), e);
+		}
+	}
+}
+
+fn main() {
+	let opts = match parse_args(env::args().skip(1)) {
+		Ok(opts) => opts,
+		Err(e) => {
+			eprintln!("{}\n\n{}", e, USAGE);
+			process::exit(2);
+		}
+	};
+
+	let code = match opts.num_type.as_str() {
+		"f64" => run::<f64>(&opts),
+		#[cfg(feature = "rug")]
+		"rational" => run::<rug::Rational>(&opts),
+		#[cfg(feature = "rug")]
+		"complex" => run::<rug::Complex>(&opts),
+		#[cfg(not(feature = "rug"))]
+		"rational" | "complex" => {
+			eprintln!("The {} type needs mexprp to be built with the `rug` feature", opts.num_type);
+			2
+		}
+		other => {
+			eprintln!("Unknown number type '{}'\n\n{}", other, USAGE);
+			2
+		}
+	};
+	process::exit(code);
+}
+
+/// Parse the command line arguments, not including the name of the program
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+	let mut opts = Options {
+		vars: Vec::new(),
+		precision: None,
+		num_type: "f64".to_string(),
+		session: default_session(),
+		sweep: None,
+		csv: false,
+		exprs: Vec::new(),
+	};
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-h" | "--help" => {
+				println!("{}", USAGE);
+				process::exit(0);
+			}
+			"--ctx" => {
+				let def = args.next().ok_or("Missing a definition after --ctx")?;
+				let mut parts = def.splitn(2, '=');
+				match (parts.next(), parts.next()) {
+					(Some(name), Some(value)) if !name.trim().is_empty() => opts.vars.push((name.trim().to_string(), value.to_string())),
+					_ => return Err(format!("Invalid definition '{}', expected NAME=VALUE", def)),
+				}
+			}
+			"--precision" => {
+				let bits = args.next().ok_or("Missing a precision after --precision")?;
+				opts.precision = Some(bits.parse().map_err(|_| format!("Invalid precision '{}'", bits))?);
+			}
+			"--type" => opts.num_type = args.next().ok_or("Missing a type after --type")?,
+			"--session" => opts.session = Some(args.next().ok_or("Missing a file after --session")?.into()),
+			"--no-session" => opts.session = None,
+			"--sweep" => {
+				let def = args.next().ok_or("Missing a range after --sweep")?;
+				let invalid = || format!("Invalid sweep '{}', expected NAME=START:END:STEP", def);
+				let mut parts = def.splitn(2, '=');
+				let name = parts.next().map(str::trim).filter(|name| !name.is_empty()).ok_or_else(invalid)?;
+				let range: Vec<&str> = parts.next().ok_or_else(invalid)?.split(':').collect();
+				if range.len() != 3 {
+					return Err(invalid());
+				}
+				opts.sweep = Some(Sweep {
+					name: name.to_string(),
+					start: range[0].to_string(),
+					end: range[1].to_string(),
+					step: range[2].to_string(),
+				});
+			}
+			"--format" => match args.next().as_deref() {
+				Some("table") => opts.csv = false,
+				Some("csv") => opts.csv = true,
+				Some(other) => return Err(format!("Unknown format '{}'", other)),
+				None => return Err("Missing a format after --format".to_string()),
+			},
+			// Everything after -- is an expression, even if it starts with a dash
+			"--" => opts.exprs.extend(args.by_ref()),
+			_ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+			_ => opts.exprs.push(arg),
+		}
+	}
+
+	Ok(opts)
+}
+
+/// The session file in the home directory, if it can be found
+fn default_session() -> Option<PathBuf> {
+	env::var_os("HOME")
+		.or_else(|| env::var_os("USERPROFILE"))
+		.map(|home| PathBuf::from(home).join(".mexprp_session"))
+}
+
+/// Set up a session with the options and evaluate the expressions, or start a REPL. Returns the exit
+/// code.
+fn run<N: Num + 'static>(opts: &Options) -> i32 {
+	let mut builder = Context::builder();
+	if let Some(precision) = opts.precision {
+		builder = builder.precision(precision);
+	}
+	let mut session: Session<N> = Session::with_context(builder.build());
+
+	// Variables are evaluated in order, so later ones can use earlier ones
+	for (name, value) in &opts.vars {
+		match mexprp::eval_ctx::<N>(value, &session.ctx) {
+			Ok(answer) => session.ctx.set_var(name, answer),
+			Err(e) => {
+				eprintln!("Invalid value for {}: {}", name, e);
+				return 2;
+			}
+		}
+	}
+
+	let mut defs = Definitions {
+		path: None,
+		lines: Vec::new(),
+	};
+	if let Some(ref path) = opts.session {
+		load_session(&mut session, &mut defs, path);
+	}
+
+	if let Some(ref sweep) = opts.sweep {
+		return match run_sweep(&session.ctx, sweep, &opts.exprs, opts.csv) {
+			Ok(()) => 0,
+			Err(e) => {
+				eprintln!("{}", e);
+				1
+			}
+		};
+	}
+
+	if opts.exprs.is_empty() {
+		// Only the REPL saves definitions
+		defs.path = opts.session.clone();
+		return repl(&mut session, &mut defs);
+	}
+
+	let mut code = 0;
+	for raw in &opts.exprs {
+		match eval_line(&mut session, &mut defs, raw) {
+			Ok(output) => println!("{}", output),
+			Err(e) => {
+				eprintln!("{}", e);
+				code = 1;
+			}
+		}
+	}
+	code
+}
+
+/// Evaluate the expressions for each value of the swept variable and print a row for each value,
+/// with the value first. Cells whose expressions fail to evaluate are left empty.
+fn run_sweep<N: Num + 'static>(ctx: &Context<N>, sweep: &Sweep, exprs: &[String], csv: bool) -> Result<(), String> {
+	if exprs.is_empty() {
+		return Err("No expressions to sweep".to_string());
+	}
+
+	let bound = |raw: &str| -> Result<N, String> {
+		match mexprp::eval_ctx(raw, ctx) {
+			Ok(Answer::Single(n)) => Ok(n),
+			Ok(_) => Err(format!("The sweep bound '{}' has more than one value", raw)),
+			Err(e) => Err(format!("Invalid sweep bound '{}': {}", raw, e)),
+		}
+	};
+	let (start, end, step) = (bound(&sweep.start)?, bound(&sweep.end)?, bound(&sweep.step)?);
+	let count = end.sub(&start, ctx)
+		.and_then(|diff| diff.unwrap_single().div(&step, ctx))
+		.and_then(|steps| steps.unwrap_single().to_f64_lossy())
+		.map_err(|e| format!("Invalid sweep range: {}", e))?;
+	if !count.is_finite() || count < 0.0 {
+		return Err("The sweep step doesn't go from the start to the end".to_string());
+	}
+	// A little leeway so that the end is included despite rounding errors
+	let count = (count + 1e-9).floor() as usize + 1;
+
+	// The terms are bound without the swept variable, so that it's their only slot. Terms that can't
+	// be bound or that have other undefined variables fail for every value, so their cells are empty.
+	let mut unswept = ctx.clone();
+	unswept.remove_var(&sweep.name);
+	let terms = exprs.iter()
+		.map(|raw| {
+			let term = Term::parse_ctx(raw, ctx).map_err(|e| format!("Failed to parse '{}': {}", raw, e))?;
+			Ok(term.bind(&unswept).ok().filter(|bound| bound.slots().iter().all(|slot| *slot == sweep.name)))
+		})
+		.collect::<Result<Vec<Option<BoundTerm<N>>>, String>>()?;
+
+	let mut header = vec![sweep.name.clone()];
+	header.extend(exprs.iter().cloned());
+	let mut rows = vec![header];
+	for i in 0..count {
+		let value = N::from_f64(i as f64, ctx)
+			.and_then(|i| i.unwrap_single().mul(&step, ctx))
+			.and_then(|offset| start.add(&offset.unwrap_single(), ctx))
+			.map_err(|e| format!("Invalid sweep range: {}", e))?
+			.unwrap_single();
+
+		let mut row = vec![value.to_string()];
+		for bound in &terms {
+			let cell = bound.as_ref().and_then(|bound| {
+				let values = if bound.slots().is_empty() { &[] } else { slice::from_ref(&value) };
+				bound.eval(values).ok()
+			});
+			row.push(cell.map(|answer| answer.to_string()).unwrap_or_default());
+		}
+		rows.push(row);
+	}
+
+	if csv {
+		for row in &rows {
+			let cells: Vec<String> = row.iter().map(|cell| csv_cell(cell)).collect();
+			println!("{}", cells.join(","));
+		}
+	} else {
+		let mut widths = vec![0; rows[0].len()];
+		for row in &rows {
+			for (width, cell) in widths.iter_mut().zip(row) {
+				*width = (*width).max(cell.chars().count());
+			}
+		}
+		for row in &rows {
+			let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, &width)| format!("{:width$}", cell, width = width)).collect();
+			println!("{}", cells.join("  ").trim_end());
+		}
+	}
+
+	Ok(())
+}
+
+/// Quote a CSV cell if it has any characters that need quoting
+fn csv_cell(cell: &str) -> String {
+	if cell.contains([',', '"', '\n']) {
+		format!("\"{}\"", cell.replace('"', "\"\""))
+	} else {
+		cell.to_string()
+	}
+}
+
+/// Load the definitions of a session file. A missing file is an empty session.
+fn load_session<N: Num + 'static>(session: &mut Session<N>, defs: &mut Definitions, path: &Path) {
+	let contents = match fs::read_to_string(path) {
+		Ok(contents) => contents,
+		Err(ref e) if e.kind() == io::ErrorKind::NotFound => return,
+		Err(e) => {
+			eprintln!("Failed to load the session from {}: {}", path.display(), e);
+			return;
+		}
+	};
+
+	for (i, line) in contents.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		if let Err(e) = eval_line(session, defs, line) {
+			eprintln!("{}:{}: {}", path.display(), i + 1, e);
+		}
+	}
+}
+
+/// Evaluate an expression, or make a definition if the line has an `=`. Returns what should be
+/// printed.
+fn eval_line<N: Num + 'static>(session: &mut Session<N>, defs: &mut Definitions, line: &str) -> Result<String, String> {
+	let mut parts = line.splitn(2, '=');
+	match (parts.next(), parts.next()) {
+		(Some(lhs), Some(rhs)) => define(session, defs, lhs.trim(), rhs.trim()),
+		_ => session.eval(line).map(|answer| answer.to_string()).map_err(|e| e.to_string()),
+	}
+}
+
+/// Define a variable with `name = value` or a function with `name(params) = body`
+fn define<N: Num + 'static>(session: &mut Session<N>, defs: &mut Definitions, lhs: &str, rhs: &str) -> Result<String, String> {
+	let line = format!("{} = {}", lhs, rhs);
+	match lhs.find('(') {
+		Some(open) => {
+			// The other functions are loaded again along with this one, so that the ones that were
+			// defined before it can call it
+			let name = lhs[..open].trim();
+			let mut funcs: Vec<&str> = defs.lines.iter()
+				.filter(|(other, def)| other != name && def.split('=').next().is_some_and(|lhs| lhs.contains('(')))
+				.map(|(_, def)| def.as_str())
+				.collect();
+			funcs.push(&line);
+			session.ctx.load_definitions(&funcs.join("\n")).map_err(|e| match e {
+				DefinitionError::ParseError { error, .. } => format!("Failed to parse the expression: {}", error),
+				DefinitionError::InvalidDefinition { .. } => format!("'{}' can't be defined", lhs),
+			})?;
+			defs.add(name.to_string(), line.clone());
+			Ok(line)
+		}
+		// Variables are evaluated right away instead of when they're used, so that they don't change
+		// along with `ans`
+		None => {
+			let name = check_name(lhs)?;
+			let answer = session.eval(rhs).map_err(|e| e.to_string())?;
+			session.ctx.set_var(name, answer.clone());
+			defs.add(name.to_string(), line);
+			Ok(format!("{} = {}", name, answer))
+		}
+	}
+}
+
+/// Check that a name can be defined
+fn check_name(name: &str) -> Result<&str, String> {
+	// The same names the parser reads as one name
+	let valid = name.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphabetic() || c == '_'));
+	if valid {
+		Ok(name)
+	} else {
+		Err(format!("'{}' can't be defined", name))
+	}
+}
+
+/// Read expressions from stdin and print their answers until the input ends. Returns the exit code.
+fn repl<N: Num + 'static>(session: &mut Session<N>, defs: &mut Definitions) -> i32 {
+	let stdin = io::stdin();
+	let mut lines = stdin.lock().lines();
+	loop {
+		print!("> ");
+		io::stdout().flush().unwrap();
+
+		let line = match lines.next() {
+			Some(Ok(line)) => line,
+			Some(Err(e)) => {
+				eprintln!("Failed to read input: {}", e);
+				return 1;
+			}
+			None => {
+				println!();
+				return 0;
+			}
+		};
+
+		match line.trim() {
+			"" => {}
+			":quit" | ":q" => return 0,
+			":clear" => session.clear(),
+			":defs" => {
+				for (_, line) in &defs.lines {
+					println!("{}", line);
+				}
+			}
+			cmd if cmd.starts_with(":forget") => {
+				let name = cmd[":forget".len()..].trim();
+				if defs.remove(name) {
+					session.ctx.remove_var(name);
+					session.ctx.remove_func(name);
+				} else {
+					println!("'{}' isn't defined", name);
+				}
+			}
+			":history" => {
+				// Oldest first, numbered so that they can be used with ans(n)
+				let answers: Vec<_> = session.history.iter().collect();
+				for (i, answer) in answers.iter().enumerate().rev() {
+					println!("ans({}) = {}", i + 1, answer);
+				}
+			}
+			raw => match eval_line(session, defs, raw) {
+				// Definitions are echoed as they are instead of as an answer
+				Ok(output) if raw.contains('=') => println!("{}", output),
+				Ok(output) => println!("\t= {}", output),
+				Err(e) => println!("{}", e),
+			},
+		}
+	}
+}
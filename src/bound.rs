@@ -0,0 +1,245 @@
+use alloc::sync::Arc;
+
+use crate::term::Term;
+use crate::context::{Config, Context};
+use crate::errors::MathError;
+use crate::opers::{Arithmetic, BoundFunction, Calculation, Slot};
+use crate::answer::Answer;
+use crate::num::Num;
+use crate::prelude::*;
+use crate::state::EvalState;
+
+/// A `Term` whose names have all been resolved ahead of time with `Term::bind`. Evaluating a
+/// `BoundTerm` doesn't look up any variables or functions by name, apart from functions from a
+/// `FuncTable`, so it's much faster to evaluate the same term many times with different values.
+///
+/// When binding, variables defined in the context are evaluated once and their values are used
+/// directly. Every other variable is given a slot, in the order they first appear in the term. The
+/// values of the slots are passed to `eval` as a slice.
+///
+/// The bound term is also flattened into a list of nodes, so that arithmetic is evaluated in one pass
+/// over the list instead of by walking the tree. The answers are the same either way.
+///
+/// ```rust
+/// # use mexprp::{Term, Context, Answer};
+/// let ctx: Context<f64> = Context::new();
+/// let term: Term<f64> = Term::parse("x ^ 2 + y").unwrap();
+/// let bound = term.bind(&ctx).unwrap();
+/// assert_eq!(bound.slots(), &["x".to_string(), "y".to_string()]);
+/// let res = bound.eval(&[3.0, 1.0]); // Ok(Answer::Single(10.0))
+/// # assert_eq!(res.unwrap(), Answer::Single(10.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BoundTerm<N: Num> {
+	term: Term<N>,
+	slots: Vec<String>,
+	/// The context the term is evaluated with, which only has the config and function table of the
+	/// context it was bound to
+	ctx: Context<N>,
+	/// The term flattened for evaluating it quickly, if it can be
+	program: Option<Program<N>>,
+}
+
+/// A bound term flattened into a list of nodes, each of which only refers to nodes before it by
+/// their index. Evaluating it goes through the list once without following any pointers or
+/// recursing, which is much faster for large terms.
+#[derive(Debug, Clone)]
+struct Program<N: Num> {
+	/// The nodes, with the whole term last
+	nodes: Vec<Node<N>>,
+}
+
+/// A node of a `Program`
+#[derive(Debug, Clone)]
+enum Node<N: Num> {
+	/// A number
+	Num(N),
+	/// The value of a slot
+	Slot(usize),
+	/// Arithmetic on the values of other nodes. Negation has no second operand.
+	Arithmetic(Arithmetic, usize, Option<usize>),
+	/// Any other term, evaluated normally as if it were as deep as its depth
+	Term(Term<N>, usize),
+}
+
+impl<N: Num + 'static> BoundTerm<N> {
+	/// Bind a term to a context
+	pub(crate) fn new(term: &Term<N>, ctx: &Context<N>) -> Result<Self, MathError> {
+		let mut slots = Vec::new();
+		let term = bind(term, ctx, &mut slots)?;
+
+		let program = Program::new(&term, &ctx.cfg);
+
+		let mut bound_ctx = Context::empty();
+		bound_ctx.cfg = ctx.cfg.clone();
+		bound_ctx.table = ctx.table;
+
+		Ok(BoundTerm {
+			term,
+			slots,
+			ctx: bound_ctx,
+			program,
+		})
+	}
+
+	/// Evaluate the term with the given values for its slots. There must be exactly one value for
+	/// each slot, or a `MathError::IncorrectArguments` is returned.
+	pub fn eval(&self, values: &[N]) -> Calculation<N> {
+		if values.len() != self.slots.len() {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		// The term is evaluated with its own slots, even when a function evaluates it in the middle of
+		// another evaluation with the context, but it's as deep as that evaluation
+		let ctx = &self.ctx;
+		let depth = EvalState::current(ctx).map_or(0, |state| state.depth());
+		EvalState::run(ctx, EvalState::new(depth, 0, values.to_vec()), |state| {
+			if let Some(answer) = self.program.as_ref().and_then(|program| program.eval(values, ctx, state)) {
+				return Ok(answer);
+			}
+			self.term.eval_state(ctx, state)
+		})
+	}
+
+	/// The names of the variables of each slot, in order
+	pub fn slots(&self) -> &[String] {
+		&self.slots
+	}
+
+	/// Get the index of the slot for the variable with the given name
+	pub fn slot(&self, name: &str) -> Option<usize> {
+		self.slots.iter().position(|slot| slot == name)
+	}
+
+	/// The bound term
+	pub fn term(&self) -> &Term<N> {
+		&self.term
+	}
+}
+
+impl<N: Num + 'static> Program<N> {
+	/// Flatten a bound term. Terms that would be evaluated differently than normal by a program aren't
+	/// flattened: ones that are nested deeper than the `max_depth` of the config, and any term if the
+	/// config limits the steps of evaluations or memoizes answers.
+	fn new(term: &Term<N>, cfg: &Config) -> Option<Self> {
+		if cfg.max_steps.is_some() || cfg.memoize {
+			return None;
+		}
+		let mut nodes = Vec::new();
+		flatten(term, 0, cfg.max_depth, &mut nodes)?;
+		Some(Program { nodes })
+	}
+
+	/// Evaluate the program with the values of the slots. Nodes that aren't arithmetic are evaluated
+	/// normally as part of the evaluation, which has the values of the slots. Returns `None` if any node
+	/// fails or can't be evaluated by the program, in which case the term has to be evaluated normally
+	/// to get its answer or error.
+	fn eval(&self, slots: &[N], ctx: &Context<N>, state: &EvalState<N>) -> Option<Answer<N>> {
+		let mut values: Vec<Answer<N>> = Vec::with_capacity(self.nodes.len());
+		let base = state.depth();
+		for node in &self.nodes {
+			let value = match *node {
+				Node::Num(ref n) => Answer::Single(n.clone()),
+				Node::Slot(index) => Answer::Single(slots.get(index)?.clone()),
+				Node::Arithmetic(arithmetic, a, b) => {
+					let res = match (&values[a], b.map(|b| &values[b])) {
+						(Answer::Single(a), None) => arithmetic.apply(a, a, ctx),
+						(Answer::Single(a), Some(Answer::Single(b))) => arithmetic.apply(a, b, ctx),
+						// Vectors are multiplied with matrix multiplication, which only the term does
+						(Answer::Vector(_), Some(_)) | (_, Some(Answer::Vector(_))) if arithmetic == Arithmetic::Mul => return None,
+						(a, None) => a.unop_ctx(ctx, |a| arithmetic.apply(a, a, ctx)),
+						(a, Some(b)) => a.op_ctx(b, ctx, |a, b| arithmetic.apply(a, b, ctx)),
+					};
+					match res {
+						Ok(Answer::Single(n)) => Answer::Single(n),
+						res => Term::check_answer(res, ctx, state).ok()?,
+					}
+				}
+				Node::Term(ref term, depth) => {
+					state.set_depth(base + depth);
+					let res = term.eval_state(ctx, state);
+					state.set_depth(base);
+					res.ok()?
+				}
+			};
+			values.push(value);
+		}
+		values.pop()
+	}
+}
+
+/// Add the nodes of a term that's `depth` terms deep to a program, returning the index of its node
+fn flatten<N: Num + 'static>(term: &Term<N>, depth: usize, max_depth: usize, nodes: &mut Vec<Node<N>>) -> Option<usize> {
+	if depth >= max_depth {
+		return None;
+	}
+	let node = match *term {
+		Term::Num(Answer::Single(ref n)) => Node::Num(n.clone()),
+		Term::Operation(ref oper) => match (oper.arithmetic(), oper.slot()) {
+			(Some((arithmetic, a, b)), _) => {
+				let a = flatten(a, depth + 1, max_depth, nodes)?;
+				let b = match b {
+					Some(b) => Some(flatten(b, depth + 1, max_depth, nodes)?),
+					None => None,
+				};
+				Node::Arithmetic(arithmetic, a, b)
+			}
+			(None, Some(index)) => Node::Slot(index),
+			(None, None) => Node::Term(term.clone(), depth),
+		},
+		Term::Num(_) | Term::Function(..) | Term::Var(_) => Node::Term(term.clone(), depth),
+	};
+	nodes.push(node);
+	Some(nodes.len() - 1)
+}
+
+/// Resolve the names in a term, adding any unresolved variables to the list of slots
+fn bind<N: Num + 'static>(term: &Term<N>, ctx: &Context<N>, slots: &mut Vec<String>) -> Result<Term<N>, MathError> {
+	match *term {
+		Term::Num(_) => Ok(term.clone()),
+		Term::Var(ref name) => {
+			if let Some(var) = ctx.vars.get(&**name) {
+				// Variables in the context are evaluated once now
+				Ok(Term::Num(var.eval_ctx(ctx)?))
+			} else {
+				let index = match slots.iter().position(|slot| **slot == **name) {
+					Some(index) => index,
+					None => {
+						slots.push(name.to_string());
+						slots.len() - 1
+					}
+				};
+				Ok(Term::Operation(Arc::new(Slot {
+					index,
+					name: name.to_string(),
+				})))
+			}
+		}
+		Term::Function(ref name, ref args) => {
+			let func = match ctx.map_func(name) {
+				Some(func) => Some(Arc::clone(func)),
+				None if ctx.has_func(name) => None,
+				None => return Err(MathError::UndefinedFunction { name: name.to_string() }),
+			};
+			let args = args.iter()
+				.map(|arg| bind(arg, ctx, slots))
+				.collect::<Result<Vec<Term<N>>, MathError>>()?;
+			match func {
+				Some(func) => Ok(Term::Operation(Arc::new(BoundFunction {
+					name: name.to_string(),
+					func,
+					args,
+				}))),
+				// Functions from a `FuncTable` are looked up in the table kept by the bound term
+				None => Ok(Term::Function(name.clone(), args)),
+			}
+		}
+		Term::Operation(ref oper) => {
+			let operands = oper.operands()
+				.into_iter()
+				.map(|operand| bind(operand, ctx, slots))
+				.collect::<Result<Vec<Term<N>>, MathError>>()?;
+			Ok(Term::Operation(oper.with_operands(operands)))
+		}
+	}
+}
@@ -0,0 +1,101 @@
+use core::f64::consts;
+
+use crate::num::Num;
+use crate::opers::Calculation;
+use crate::context::Context;
+use crate::answer::Answer;
+use crate::errors::MathError;
+use crate::prelude::*;
+
+/// A set of constants that can be added to a context with `Context::with_constants` or
+/// `Context::install_constants`. The constants are computed with the operations of the number type,
+/// so types with more precision than `f64` (like `rug::Complex`) get more precise values. Types that
+/// can't compute a constant get its `f64` value instead.
+///
+/// ```rust
+/// # use mexprp::{Answer, Context, ConstantsPack, Term};
+/// let mut ctx: Context<f64> = Context::with_constants(ConstantsPack::Math);
+/// ctx.install_constants(ConstantsPack::Physics);
+/// let answer = Term::parse_ctx("tau / 2", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+/// assert_eq!(answer, Answer::Single(std::f64::consts::PI));
+/// let answer = Term::parse_ctx("c^2", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+/// assert_eq!(answer, Answer::Single(299_792_458.0f64.powi(2)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstantsPack {
+	/// Mathematical constants: `tau` (2π) and `phi` (the golden ratio)
+	Math,
+	/// Physical constants in SI units: `c` (the speed of light), `N_A` (the Avogadro constant), `h`
+	/// (the Planck constant) and `G` (the gravitational constant)
+	Physics,
+}
+
+impl ConstantsPack {
+	/// All of the packs
+	pub const ALL: [ConstantsPack; 2] = [ConstantsPack::Math, ConstantsPack::Physics];
+
+	/// The names of the constants in the pack
+	pub fn names(self) -> &'static [&'static str] {
+		match self {
+			ConstantsPack::Math => &["tau", "phi"],
+			ConstantsPack::Physics => &["c", "N_A", "h", "G"],
+		}
+	}
+
+	/// The constants of the pack along with their names, computed with the config of a context
+	pub(crate) fn values<N: Num + 'static>(self, ctx: &Context<N>) -> Vec<(&'static str, N)> {
+		let constants: Vec<(&'static str, f64, Calculation<N>)> = match self {
+			ConstantsPack::Math => vec![
+				("tau", consts::PI * 2.0, tau(ctx)),
+				("phi", 1.618_033_988_749_895, phi(ctx)),
+			],
+			ConstantsPack::Physics => vec![
+				// Exact since the 2019 redefinition of the SI base units
+				("c", 299_792_458.0, scientific(299_792_458.0, 0, ctx)),
+				("N_A", 6.022_140_76e23, scientific(602_214_076.0, 15, ctx)),
+				("h", 6.626_070_15e-34, scientific(662_607_015.0, -42, ctx)),
+				// CODATA 2018
+				("G", 6.674_30e-11, scientific(667_430.0, -16, ctx)),
+			],
+		};
+
+		constants
+			.into_iter()
+			.filter_map(|(name, value, computed)| {
+				let value = match computed {
+					Ok(Answer::Single(value)) => value,
+					_ => N::from_f64(value, ctx).ok()?.unwrap_single(),
+				};
+				Some((name, value))
+			})
+			.collect()
+	}
+}
+
+/// Get a number that's an integer in `f64`, which is exact for every type
+fn int<N: Num>(n: f64, ctx: &Context<N>) -> Result<N, MathError> {
+	Ok(N::from_f64(n, ctx)?.unwrap_single())
+}
+
+/// Compute `mantissa × 10^exponent`, where the mantissa is an integer
+fn scientific<N: Num>(mantissa: f64, exponent: i32, ctx: &Context<N>) -> Calculation<N> {
+	let scale = int(10.0, ctx)?.pow(&int(f64::from(exponent.abs()), ctx)?, ctx)?.unwrap_single();
+	let mantissa = int(mantissa, ctx)?;
+	if exponent < 0 {
+		mantissa.div(&scale, ctx)
+	} else {
+		mantissa.mul(&scale, ctx)
+	}
+}
+
+/// Compute 2π from the `Num::pi` of the type
+fn tau<N: Num>(ctx: &Context<N>) -> Calculation<N> {
+	int(2.0, ctx)?.mul(&N::pi(ctx)?.unwrap_single(), ctx)
+}
+
+/// Compute the golden ratio as `(1 + 5^(1/2)) / 2`
+fn phi<N: Num>(ctx: &Context<N>) -> Calculation<N> {
+	let half = int(1.0, ctx)?.div(&int(2.0, ctx)?, ctx)?.unwrap_single();
+	let root = int(5.0, ctx)?.pow(&half, ctx)?.unwrap_single();
+	int(1.0, ctx)?.add(&root, ctx)?.unwrap_single().div(&int(2.0, ctx)?, ctx)
+}
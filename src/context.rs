@@ -1,11 +1,27 @@
-use std::collections::HashMap;
-use std::f64::consts;
-use std::rc::Rc;
-use std::fmt;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::fmt;
 
 use crate::term::Term;
-use crate::func::Func;
-use crate::num::Num;
+use crate::func::{self, Arity, ExprFunc, Func, FuncTable, FunctionPack, TableLookup};
+use crate::num::{Num, NumKind};
+use crate::opers::{Calculation, Constant};
+use crate::errors::{DefinitionError, MathError};
+use crate::constants::ConstantsPack;
+use crate::prelude::*;
+use crate::state::EvalState;
+#[cfg(not(feature = "std"))]
+use crate::state::Evaluations;
+#[cfg(feature = "std")]
+use crate::registry;
 
 /// A context holds values for variables and functions to be used in expressions. It is useful for both
 /// parsing and evaluation expressions. During parsing, all names will be treated as variables unless
@@ -53,6 +69,12 @@ use crate::num::Num;
 ///                     sum += n;
 ///                 }
 ///             }
+///             Answer::Labeled(ls) => {
+///                 for (_, n) in ls {
+///                     sum += n;
+///                 }
+///             }
+///             Answer::Vector(_) => return Err(MathError::IncorrectArguments),
 ///         }
 ///     }
 ///     Ok(Answer::Single(sum))
@@ -67,7 +89,11 @@ use crate::num::Num;
 /// evaluated with. It's important to remember to evaluate any arguments you receive with the reference
 /// to the `Context` you received with `Term::eval_ctx()`. If the function is given arguments in an
 /// incorrect way, return a `MathError::IncorrectArguments`. If any errors occur during evaluation, you
-/// can try to find a `MathError` variant that fits or return `MathError::Other`.
+/// can try to find a `MathError` variant that fits or return `MathError::Other`. Structs implementing
+/// `Func` can also override `arity` (or `min_args`/`max_args`) to have calls with the wrong amount of
+/// arguments rejected while parsing instead. Functions can also be defined under a namespace with
+/// `set_func_ns`, or installed in bulk from a `FunctionPack`, and are then called like
+/// `stats.mean(1, 2)`.
 ///
 /// ## Builtin
 /// ### Constants
@@ -85,63 +111,573 @@ use crate::num::Num;
 /// - atant (atan2)
 /// - floor
 /// - ceil
-/// - round
+/// - round (round(x, n) rounds to n decimal places, with the `rounding` mode of the config)
 /// - sqrt
+/// - nrt (nrt(x, n) is the nth root of x)
+/// - log (log(x, b) is the logarithm of x with base b)
+/// - fact (fact(n) is the factorial n!)
+/// - ncr, binom (ncr(n, k) is the binomial coefficient, the amount of ways to choose k things out of
+///   n)
+/// - npr (npr(n, k) is the amount of ways to arrange k things out of n)
+/// - gamma (the gamma function, so gamma(n + 1) = n!)
+/// - lgamma (the natural logarithm of the absolute value of the gamma function)
+/// - erf, erfc (the error function and the complementary error function)
+/// - beta (beta(a, b) is the beta function)
+/// - zeta (the Riemann zeta function)
 /// - max
 /// - min
+/// - sign
+/// - step (Heaviside step, 1 for x ≥ 0)
+/// - clamp (clamp(x, lo, hi))
+/// - lerp (lerp(a, b, t))
+/// - piecewise (piecewise((c1, v1), (c2, v2), ..., default) is the value of the first pair whose
+///   condition isn't zero or NaN, or the default if there isn't one. The pairs can also be written
+///   with square brackets. Only the conditions up to that pair and the value that's chosen are
+///   evaluated.)
+/// - xor (bitwise exclusive or, the other bitwise operations are operators: `&`, `|`, `<<`, `>>`, `~`)
+/// - hex, oct, bin (show the answer in base 16, 8 or 2 with `Expression::eval_format`)
+/// - base (base(x, n) shows the answer in base n, from 2 to 36)
+/// - to_fraction (to_fraction(x, max_den) is the fraction closest to x with a denominator of at most
+///   max_den, as a vector `[numerator, denominator]`)
+/// - len (length of a vector)
+/// - dot (dot product of two vectors)
+/// - cross (cross product of two 3D vectors)
+/// - norm (euclidean norm of a vector)
+/// - transpose (transpose of a matrix)
+/// - det (determinant of a square matrix)
+/// - inv (inverse of a square matrix)
+/// - solve (solve(A, b) solves the linear system Ax = b)
+///
+/// `Context::new()` and `Context::full()` have all of these. `Context::scientific()` only has the
+/// constants and the functions up to `lerp`, and `Context::minimal()` only has `abs`, `min`, `max`,
+/// `floor`, `ceil`, `round`, `sign` and `clamp`.
+///
+/// ## Vectors
+/// Expressions can also evaluate to vectors, written with square brackets (`[1, 2, 3]`). Operators
+/// are applied to vectors element-wise, and vectors can be indexed with square brackets after them,
+/// starting from 1 (`v[2]`). A matrix is a vector of rows (`[[1, 2], [3, 4]]`) and can be indexed
+/// with `m[row, column]`. Multiplying matrices performs matrix multiplication. A list in parentheses
+/// (`(1, 2)`) is a vector too.
+///
+/// Square brackets around a single expression (`2[3 + 4]`) group it like parentheses instead of
+/// making a vector, as do curly braces (`{1 + 2} * 3`). Every kind of bracket has to be closed by
+/// the same kind.
 #[derive(Clone)]
 pub struct Context<N: Num> {
-	/// HashMap of variables
-	pub vars: HashMap<String, Term<N>>,
-	/// HashMap of functions
-	pub funcs: HashMap<String, Rc<dyn Func<N>>>,
+	/// Map of variables (a `HashMap`, or a `BTreeMap` without `std`)
+	pub vars: Map<String, Term<N>>,
+	/// Map of functions (a `HashMap`, or a `BTreeMap` without `std`)
+	pub funcs: Map<String, Arc<dyn Func<N>>>,
+	/// Functions known at compile time, looked up when a function isn't in the map
+	pub(crate) table: Option<TableLookup<N>>,
 	/// The configuration used when evaluating expressions
 	pub cfg: Config,
+	/// The evaluations in progress with the context, see `EvalState`
+	#[cfg(not(feature = "std"))]
+	pub(crate) active: Evaluations<N>,
+	/// A token that can be used to cancel evaluations from another thread
+	pub(crate) cancel: Option<CancelToken>,
+	/// Callbacks for each term that's evaluated
+	pub(crate) observer: Option<Arc<dyn EvalObserver<N>>>,
+	/// Whether only builtin functions and functions defined by expressions can be called, see
+	/// `Context::sandboxed`
+	pub(crate) sandboxed: bool,
+}
+
+/// A name that can be completed from a prefix, returned by `Context::complete`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+	/// The full name
+	pub name: String,
+	/// Whether the name is a variable or a function
+	pub kind: CompletionKind,
+	/// The amount of arguments the function takes, if it's a function
+	pub arity: Option<Arity>,
+}
+
+/// The kind of name a `Completion` is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+	/// A variable
+	Variable,
+	/// A function
+	Function,
+}
+
+/// Callbacks for watching evaluations, installed with `Context::set_observer`. Every term that's
+/// evaluated with the context, including operands, function arguments and the values of variables,
+/// is passed to `enter` before it's evaluated and to `exit` after. The methods take `&self` and
+/// contexts can be shared between threads, so an observer that keeps track of anything needs to use
+/// atomics or a `Mutex`.
+///
+/// ```rust
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use mexprp::{Context, EvalObserver, Term, MathError};
+/// #[derive(Default)]
+/// struct Counter(AtomicUsize);
+///
+/// impl EvalObserver<f64> for Counter {
+///     fn enter(&self, _term: &Term<f64>, _depth: usize) -> Result<(), MathError> {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut ctx: Context<f64> = Context::new();
+/// ctx.set_observer(Counter::default());
+/// let term: Term<f64> = Term::parse_ctx("1 + 2", &ctx).unwrap();
+/// term.eval_ctx(&ctx).unwrap();
+/// ```
+pub trait EvalObserver<N: Num>: Send + Sync {
+	/// Called before a term is evaluated, with how many terms deep it is in the evaluation (0 for the
+	/// term being evaluated itself). Returning an error stops the evaluation with that error, which
+	/// can be used to put limits on evaluations.
+	fn enter(&self, _term: &Term<N>, _depth: usize) -> Result<(), MathError> {
+		Ok(())
+	}
+
+	/// Called after a term was evaluated, with its answer or the error it failed with
+	fn exit(&self, _term: &Term<N>, _depth: usize, _result: &Calculation<N>) {}
+}
+
+/// A token used to cancel an evaluation that is running on another thread. Once a context has been
+/// given a token with `Context::set_cancel_token`, calling `cancel` on any clone of the token makes
+/// evaluations with that context stop and return `MathError::Cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+	cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+	/// Create a new token that hasn't been cancelled
+	pub fn new() -> Self {
+		CancelToken {
+			cancelled: Arc::new(AtomicBool::new(false)),
+		}
+	}
+
+	/// Cancel all evaluations using this token
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::SeqCst);
+	}
+
+	/// Check whether or not the token has been cancelled
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::SeqCst)
+	}
+
+	/// Reset the token so it can be used for new evaluations
+	pub fn reset(&self) {
+		self.cancelled.store(false, Ordering::SeqCst);
+	}
 }
 
 /// Struct that holds configuration values used when evaluating expressions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
 	/// Whether or not to automatically insert multiplication signs between two operands (default = true)
 	pub implicit_multiplication: bool,
+	/// Whether or not a function can be called without parentheses, like `sin 2x` (default = false).
+	/// The argument binds tighter than addition and subtraction, and goes on until the next operator
+	/// that doesn't, so `sin x / 2 + 1` is `sin(x / 2) + 1` and `sin cos x` is `sin(cos(x))`.
+	pub loose_function_call: bool,
+	/// Whether or not `^` (and `**`) is left-associative like it is in spreadsheets, so that `2^3^2`
+	/// is `(2^3)^2` = 64 instead of `2^(3^2)` = 512 (default = false)
+	pub left_associative_pow: bool,
+	/// Whether or not unary minus (and `+` and `±`) binds tighter than `^` and postfix operators,
+	/// like it's part of the number, so that `-3^2` is `(-3)^2` = 9 instead of `-(3^2)` = -9
+	/// (default = false)
+	pub tight_unary_minus: bool,
 	/// The precision to be used for arbitrary precision floating point numbers (default = 53)
 	pub precision: u32,
-	/// Whether or not sqrt should return the positive and negative values
+	/// Whether or not sqrt and even roots with `nrt` should return the positive and negative values
 	pub sqrt_both: bool,
+	/// The maximum depth of nesting in an expression, and the maximum depth an evaluation can recurse
+	/// to (default = 256). Exceeding it results in a `ParseError::TooDeep` or a
+	/// `MathError::RecursionLimit`. Nested function calls are evaluated recursively, so raising it a
+	/// lot can overflow the stack of a thread with a small one.
+	pub max_depth: usize,
+	/// The maximum amount of tokens in an expression. Exceeding it results in a
+	/// `ParseError::TooLarge` (default = 65536).
+	pub max_nodes: usize,
+	/// The maximum amount of steps a single evaluation can take before it's aborted with a
+	/// `MathError::Cancelled` (default = None). Every term evaluated costs one step per answer it
+	/// produces, so expressions with lots of answers use up the budget quicker.
+	pub max_steps: Option<usize>,
+	/// The maximum amount of answers any term can evaluate to, checked after every term is evaluated.
+	/// Exceeding it results in a `MathError::TooManyAnswers` (default = 1024).
+	pub max_answers: usize,
+	/// Whether or not to remove equal values from multiple answers after every term is evaluated,
+	/// collapsing them into a single answer if only one value is left (default = false). See
+	/// `Answer::dedup`.
+	pub dedup_answers: bool,
+	/// Whether or not parsing should fail with a `ParseError::UnknownName` if a name isn't a variable
+	/// or function in the context, instead of failing when it's evaluated (default = false)
+	pub strict_names: bool,
+	/// Whether or not to cache the answers of operations and functions during an evaluation, so
+	/// that identical subexpressions are only evaluated once (default = false). Functions that don't
+	/// always return the same answer for the same arguments shouldn't be used with this enabled.
+	pub memoize: bool,
+	/// The maximum amount of answers to cache during an evaluation when `memoize` is enabled
+	/// (default = 1024)
+	pub cache_limit: usize,
+	/// Whether or not to cache the values of variables defined by terms during an evaluation, so that
+	/// a variable that's used many times is only evaluated once each time an expression is evaluated
+	/// (default = true). Variables that use functions that don't always return the same answer (like
+	/// a random number) keep the same value for the whole evaluation when this is enabled.
+	pub cache_vars: bool,
+	/// Whether or not a percentage added to or subtracted from a value is relative to that value, so
+	/// that `50 + 10%` is `55` instead of `50.1` (default = true)
+	pub relative_percent: bool,
+	/// Whether or not every term should evaluate to only one value (default = false). When enabled,
+	/// `sqrt` and `±` only return their principal (positive) value, and any other multiple answers
+	/// are replaced with their principal value after every term is evaluated. See
+	/// `Answer::principal`.
+	pub single_valued: bool,
+	/// How `round()` rounds numbers, and how the number types that round exactly (currently
+	/// `Decimal`) round numbers that have too many digits (default = `RoundingMode::HalfUp`).
+	pub rounding: RoundingMode,
+	/// The amount of digits after the decimal point to round the results of division to, for the
+	/// number types that round exactly (currently `Decimal`, which allows at most 28)
+	/// (default = 28)
+	pub decimal_places: u32,
+	/// The character that separates the integer and fractional parts of numbers while parsing
+	/// (default = '.'). It can be set to ',' along with `arg_separator` set to ';' to parse
+	/// expressions like `max(3,14; 2)`.
+	pub decimal_separator: char,
+	/// The character that separates the arguments of functions and the elements of vectors while
+	/// parsing (default = ',')
+	pub arg_separator: char,
+	/// Whether or not to replace characters that often end up in text pasted from word processors
+	/// and websites with the ones the parser understands before parsing (default = true). This
+	/// handles non-breaking and other unusual spaces, zero-width characters, fullwidth characters
+	/// (like `１２＋３`), the minus sign `−`, the fraction and division slashes `⁄` and `∕`, and smart
+	/// quotes.
+	pub normalize_input: bool,
+	/// Whether or not an integer followed by whitespace and a proper fraction is a mixed number, so
+	/// that `1 1/2` is 1.5 instead of an error (default = false). An integer followed directly by a
+	/// fraction character, like `2½`, is always a mixed number.
+	pub mixed_numbers: bool,
+	/// What dividing by zero (including raising zero to a negative power) results in
+	/// (default = `ZeroDivision::Error`). This is the same for every number type, except that types
+	/// that can't represent infinity or NaN always fail with `MathError::DivideByZero`.
+	pub zero_division: ZeroDivision,
+	/// What happens when an operation on `f64`s results in NaN (default = `NanPolicy::Propagate`)
+	pub nan_policy: NanPolicy,
+	/// What the square root of a negative number results in for `f64`s, which is also used for the
+	/// other functions given numbers outside of their domain: `asin` and `acos` of numbers outside of
+	/// -1 to 1, `log` of negative numbers or with a negative base, and even (or fractional) roots of
+	/// negative numbers with `nrt` (default = `DomainPolicy::NaN`)
+	pub negative_sqrt: DomainPolicy,
+	/// The relative tolerance used when numbers are compared approximately, like in
+	/// `Term::probably_equivalent` (default = 1e-9)
+	pub tolerance: f64,
+	/// The kind of number `AnyNum`s are created as, like when numbers in expressions are parsed
+	/// (default = `NumKind::F64`). Other number types ignore it.
+	pub num_kind: NumKind,
+	/// Whether `AnyNum`s are promoted to more general kinds of numbers when an operation needs it,
+	/// like `sqrt(-1)` to a complex number (default = false). See [`AnyNum`](crate::num::AnyNum).
+	pub promote_numbers: bool,
+}
+
+/// A way of rounding a number to fewer digits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundingMode {
+	/// To the nearest number, or to the one with an even last digit if it's halfway between two
+	/// (banker's rounding)
+	HalfEven,
+	/// To the nearest number, or away from zero if it's halfway between two
+	HalfUp,
+	/// To the nearest number, or towards zero if it's halfway between two
+	HalfDown,
+	/// Away from zero
+	AwayFromZero,
+	/// Towards zero (truncation)
+	ToZero,
+	/// Towards negative infinity
+	Floor,
+	/// Towards positive infinity
+	Ceil,
+}
+
+/// What dividing by zero results in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZeroDivision {
+	/// Fail with a `MathError::DivideByZero`. `0^0` is 1.
+	Error,
+	/// Positive or negative infinity depending on the sign of the dividend, like IEEE 754 floats.
+	/// `0 / 0` is NaN and `0^0` is 1.
+	Infinity,
+	/// NaN, including `0^0`
+	NaN,
+}
+
+/// What a function given a number outside of its domain results in, see `Config::negative_sqrt`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DomainPolicy {
+	/// Fail with a `MathError::DomainError` that says which function was given which number
+	Error,
+	/// NaN, which is then handled by the `nan_policy` of the config
+	NaN,
+	/// The complex answer for the types that support complex numbers. Since `f64`s don't, they fail
+	/// with a `MathError::DomainError` like `Error` does, which tells to use a complex type instead of
+	/// silently resulting in NaN.
+	PromoteComplex,
+}
+
+/// What happens when an operation results in NaN
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NanPolicy {
+	/// The NaN is the answer, and later operations on it usually result in NaN as well
+	Propagate,
+	/// Fail with a `MathError::NaN` as soon as NaN appears
+	Error,
+}
+
+impl RoundingMode {
+	/// Check whether a number being rounded to an integer (or to fewer digits) should be rounded away
+	/// from zero rather than truncated, given whether the truncated number is odd, whether the number is
+	/// negative, how the removed part compares to a half, and whether the removed part isn't zero
+	pub(crate) fn rounds_away(self, odd: bool, negative: bool, half: core::cmp::Ordering, inexact: bool) -> bool {
+		use core::cmp::Ordering::*;
+
+		match self {
+			RoundingMode::HalfEven => half == Greater || (half == Equal && odd),
+			RoundingMode::HalfUp => half != Less,
+			RoundingMode::HalfDown => half == Greater,
+			RoundingMode::AwayFromZero => inexact,
+			RoundingMode::ToZero => false,
+			RoundingMode::Floor => inexact && negative,
+			RoundingMode::Ceil => inexact && !negative,
+		}
+	}
 }
 
 impl<N: Num + 'static> Context<N> {
-	/// Returns a default Context
+	/// Returns a default Context, with all of the builtin constants and functions. The same as
+	/// `Context::full()`, plus the functions registered with `Context::register_default_func`.
 	pub fn new() -> Self {
+		#[allow(unused_mut)]
+		let mut ctx = Self::full();
+		#[cfg(feature = "std")]
+		registry::install(&mut ctx);
+		ctx
+	}
+
+	/// Register a function that every default context gets from now on, replacing any registered
+	/// function with the same name, so that plugins can add functions once at startup. Contexts
+	/// created with `Context::new()` (and `Expression::parse`, `Session::new` and the builder) and
+	/// the context used by `Term::parse` and `Term::eval` get the registered functions. The other
+	/// constructors, like `Context::full()` and `Context::sandboxed()`, don't.
+	///
+	/// ```rust
+	/// # use mexprp::{Context, Term, Calculation, Answer};
+	/// Context::<f64>::register_default_func("double", |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+	///     args[0].eval_ctx(ctx)?.unop_ctx(ctx, |x| Ok(Answer::Single(x * 2.0)))
+	/// });
+	/// let ctx: Context<f64> = Context::new();
+	/// assert!(ctx.has_func("double"));
+	/// assert_eq!(mexprp::eval::<f64>("double(21)").unwrap(), Answer::Single(42.0));
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn register_default_func<F: Func<N> + 'static>(name: &str, func: F) {
+		registry::register(name.to_string(), Arc::new(func));
+	}
+
+	/// Register all the functions of a pack for every default context, under the pack's namespace.
+	/// See `Context::register_default_func`.
+	#[cfg(feature = "std")]
+	pub fn register_default_pack<P: FunctionPack<N> + ?Sized>(pack: &P) {
+		let namespace = pack.namespace();
+		for (name, func) in pack.funcs() {
+			let name = if namespace.is_empty() { name } else { format!("{}.{}", namespace, name) };
+			registry::register(name, func);
+		}
+	}
+
+	/// Returns a context with only the most basic builtin functions (`abs`, `min`, `max`, `floor`,
+	/// `ceil`, `round`, `sign` and `clamp`) and no constants, for hosts that want to allow as little as
+	/// possible
+	pub fn minimal() -> Self {
 		use self::funcs::*;
 
 		let mut ctx: Context<N> = Context::empty();
 
-		let empty = Context::empty();
-
-		ctx.set_var("pi", N::from_f64(consts::PI, &empty).unwrap());
-		ctx.set_var("e", N::from_f64(consts::E, &empty).unwrap());
-		ctx.set_var("i", N::from_f64_complex((0.0, 1.0), &empty).unwrap());
-
-		ctx.funcs.insert("sin".to_string(), Rc::new(Sin));
-		ctx.funcs.insert("cos".to_string(), Rc::new(Cos));
-		ctx.funcs.insert("max".to_string(), Rc::new(Max));
-		ctx.funcs.insert("min".to_string(), Rc::new(Min));
-		ctx.funcs.insert("sqrt".to_string(), Rc::new(Sqrt));
-		ctx.funcs.insert("nrt".to_string(), Rc::new(Nrt));
-		ctx.funcs.insert("tan".to_string(), Rc::new(Tan));
-		ctx.funcs.insert("abs".to_string(), Rc::new(Abs));
-		ctx.funcs.insert("asin".to_string(), Rc::new(Asin));
-		ctx.funcs.insert("acos".to_string(), Rc::new(Acos));
-		ctx.funcs.insert("atan".to_string(), Rc::new(Atan));
-		ctx.funcs.insert("atant".to_string(), Rc::new(Atan2));
-		ctx.funcs.insert("floor".to_string(), Rc::new(Floor));
-		ctx.funcs.insert("round".to_string(), Rc::new(Round));
-		ctx.funcs.insert("log".to_string(), Rc::new(Log));
+		ctx.funcs.insert("abs".to_string(), Arc::new(Abs));
+		ctx.funcs.insert("min".to_string(), Arc::new(Min));
+		ctx.funcs.insert("max".to_string(), Arc::new(Max));
+		ctx.funcs.insert("floor".to_string(), Arc::new(Floor));
+		ctx.funcs.insert("ceil".to_string(), Arc::new(Ceil));
+		ctx.funcs.insert("round".to_string(), Arc::new(Round));
+		ctx.funcs.insert("sign".to_string(), Arc::new(Sign));
+		ctx.funcs.insert("clamp".to_string(), Arc::new(Clamp));
+
+		ctx
+	}
+
+	/// Returns a context with the builtin constants, the functions of `Context::minimal()`, and the
+	/// other functions of numbers (roots, trigonometry, logarithms, combinatorics, special functions,
+	/// `step` and `lerp`), but without the functions for radixes, fractions, vectors and matrices
+	pub fn scientific() -> Self {
+		use self::funcs::*;
+
+		let mut ctx: Context<N> = Context::minimal();
+
+		// The constants are created when they're evaluated, with the precision of that context
+		ctx.set_var("pi", Constant::term("pi", N::pi));
+		ctx.set_var("e", Constant::term("e", N::e));
+		ctx.set_var("i", Constant::term("i", |ctx| N::from_f64_complex((0.0, 1.0), ctx)));
+
+		ctx.funcs.insert("sin".to_string(), Arc::new(Sin));
+		ctx.funcs.insert("cos".to_string(), Arc::new(Cos));
+		ctx.funcs.insert("sqrt".to_string(), Arc::new(Sqrt));
+		ctx.funcs.insert("nrt".to_string(), Arc::new(Nrt));
+		ctx.funcs.insert("tan".to_string(), Arc::new(Tan));
+		ctx.funcs.insert("asin".to_string(), Arc::new(Asin));
+		ctx.funcs.insert("acos".to_string(), Arc::new(Acos));
+		ctx.funcs.insert("atan".to_string(), Arc::new(Atan));
+		ctx.funcs.insert("atant".to_string(), Arc::new(Atan2));
+		ctx.funcs.insert("log".to_string(), Arc::new(Log));
+		ctx.funcs.insert("fact".to_string(), Arc::new(Fact));
+		ctx.funcs.insert("ncr".to_string(), Arc::new(Ncr));
+		ctx.funcs.insert("binom".to_string(), Arc::new(Ncr));
+		ctx.funcs.insert("npr".to_string(), Arc::new(Npr));
+		ctx.funcs.insert("gamma".to_string(), Arc::new(Gamma));
+		ctx.funcs.insert("lgamma".to_string(), Arc::new(LnGamma));
+		ctx.funcs.insert("erf".to_string(), Arc::new(Erf));
+		ctx.funcs.insert("erfc".to_string(), Arc::new(Erfc));
+		ctx.funcs.insert("beta".to_string(), Arc::new(Beta));
+		ctx.funcs.insert("zeta".to_string(), Arc::new(Zeta));
+		ctx.funcs.insert("step".to_string(), Arc::new(Step));
+		ctx.funcs.insert("lerp".to_string(), Arc::new(Lerp));
+		ctx.funcs.insert("piecewise".to_string(), Arc::new(Piecewise));
+
+		ctx
+	}
+
+	/// Returns a context with all of the builtin constants and functions
+	pub fn full() -> Self {
+		use self::funcs::*;
+
+		let mut ctx: Context<N> = Context::scientific();
+
+		ctx.funcs.insert("to_fraction".to_string(), Arc::new(ToFraction));
+		ctx.funcs.insert("xor".to_string(), Arc::new(Xor));
+		ctx.funcs.insert("hex".to_string(), Arc::new(Radix(16)));
+		ctx.funcs.insert("oct".to_string(), Arc::new(Radix(8)));
+		ctx.funcs.insert("bin".to_string(), Arc::new(Radix(2)));
+		ctx.funcs.insert("base".to_string(), Arc::new(Base));
+		ctx.funcs.insert("len".to_string(), Arc::new(Len));
+		ctx.funcs.insert("dot".to_string(), Arc::new(Dot));
+		ctx.funcs.insert("cross".to_string(), Arc::new(Cross));
+		ctx.funcs.insert("norm".to_string(), Arc::new(Norm));
+		ctx.funcs.insert("transpose".to_string(), Arc::new(Transpose));
+		ctx.funcs.insert("det".to_string(), Arc::new(Det));
+		ctx.funcs.insert("inv".to_string(), Arc::new(Inv));
+		ctx.funcs.insert("solve".to_string(), Arc::new(Solve));
+
+		ctx
+	}
+
+	/// Returns a context for evaluating untrusted input, like on a server. It has the builtin constants
+	/// and functions of `Context::new()`, which all only depend on their arguments, and a config that
+	/// limits how much work an expression can cause:
+	///
+	/// - `strict_names` is enabled, so unknown names are rejected while parsing
+	/// - `max_depth` is 64, `max_nodes` is 4096, `max_steps` is 100000 and `max_answers` is 64
+	/// - `memoize` is disabled
+	///
+	/// Functions added to the context in Rust (with `set_func`, including closures) are ignored, so
+	/// only the builtin functions and functions defined by expressions (like the ones loaded with
+	/// `load_definitions`) can be called. Functions defined by expressions are evaluated as part of the
+	/// evaluation that calls them, so the depth and steps of their bodies count towards the limits
+	/// too. The limits can still be lowered.
+	///
+	/// ```rust
+	/// # use mexprp::{Answer, Context, Term, Calculation, ParseError};
+	/// let mut ctx: Context<f64> = Context::sandboxed();
+	/// ctx.set_func("host", |_args: &[Term<f64>], _ctx: &Context<f64>| -> Calculation<f64> { Ok(Answer::Single(1.0)) });
+	/// assert!(matches!(Term::parse_ctx("host(1)", &ctx), Err(ParseError::UnknownFunction { .. })));
+	/// ctx.load_definitions("f(x) = x^2 + 1").unwrap();
+	/// let term = Term::parse_ctx("f(sin(0))", &ctx).unwrap();
+	/// assert_eq!(term.eval_ctx(&ctx).unwrap().unwrap_single(), 1.0);
+	/// ```
+	pub fn sandboxed() -> Self {
+		let mut ctx: Context<N> = Context::builtin();
+		ctx.sandboxed = true;
+		ctx.cfg.strict_names = true;
+		ctx.cfg.max_depth = 64;
+		ctx.cfg.max_nodes = 4096;
+		ctx.cfg.max_steps = Some(100_000);
+		ctx.cfg.max_answers = 64;
+		ctx.cfg.memoize = false;
+		ctx
+	}
+
+	/// Check whether this context was created with `Context::sandboxed`
+	pub fn is_sandboxed(&self) -> bool {
+		self.sandboxed
+	}
+
+	/// A context with the same constants and functions as `Context::new()`, but with the functions
+	/// looked up in the `Builtins` table instead of the map, which makes it much cheaper to create. Used
+	/// where a default context is only needed for one parse or evaluation.
+	pub(crate) fn builtin() -> Self {
+		let mut ctx: Context<N> = Context::with_table::<Builtins>();
+
+		ctx.set_var("pi", Constant::term("pi", N::pi));
+		ctx.set_var("e", Constant::term("e", N::e));
+		ctx.set_var("i", Constant::term("i", |ctx| N::from_f64_complex((0.0, 1.0), ctx)));
+
+		ctx
+	}
+
+	/// Call a function with a default context like `Context::builtin()`, with the registered
+	/// functions added. With `std`, the context is only created once per thread for each number type
+	/// and reused after that, until more functions are registered.
+	#[cfg(feature = "std")]
+	pub(crate) fn with_default<R, F: FnOnce(&Context<N>) -> R>(f: F) -> R {
+		use std::any::{Any, TypeId};
 
+		/// The default context for each number type, with the registry version it was created at
+		type Defaults = Map<TypeId, (usize, Rc<dyn Any>)>;
+
+		thread_local! {
+			static DEFAULTS: RefCell<Defaults> = RefCell::new(Map::new());
+		}
+
+		// The map is only borrowed while getting the context, so that the context can be used again
+		// while it's in use, like by a term parsed while evaluating another one
+		let ctx = DEFAULTS.with(|defaults| {
+			let mut defaults = defaults.borrow_mut();
+			let version = registry::version();
+			let default = defaults.entry(TypeId::of::<N>()).or_insert_with(|| (version, Rc::new(Context::<N>::default_builtin())));
+			if default.0 != version {
+				*default = (version, Rc::new(Context::<N>::default_builtin()));
+			}
+			Rc::clone(&default.1)
+		});
+		match ctx.downcast::<Context<N>>() {
+			Ok(ctx) => f(&ctx),
+			// The contexts are stored by the type of their numbers, so this doesn't happen
+			Err(_) => f(&Context::default_builtin()),
+		}
+	}
+
+	/// `Context::builtin()` with the registered functions added
+	#[cfg(feature = "std")]
+	fn default_builtin() -> Self {
+		let mut ctx = Context::builtin();
+		registry::install(&mut ctx);
 		ctx
 	}
 
+	/// Call a function with a default context like `Context::builtin()`
+	#[cfg(not(feature = "std"))]
+	pub(crate) fn with_default<R, F: FnOnce(&Context<N>) -> R>(f: F) -> R {
+		f(&Context::builtin())
+	}
+
 	/// Add a variable definition to the context, replacing any existing one with the same name
 	pub fn set_var<T: Into<Term<N>>>(&mut self, name: &str, val: T) {
 		self.vars.insert(name.to_string(), val.into());
@@ -149,15 +685,372 @@ impl<N: Num + 'static> Context<N> {
 
 	/// Add a function definition to the context, replacing any existing one with the same name
 	pub fn set_func<F: Func<N> + 'static>(&mut self, name: &str, func: F) {
-		self.funcs.insert(name.to_string(), Rc::new(func));
+		self.funcs.insert(name.to_string(), Arc::new(func));
+	}
+
+	/// Add a function definition to the context under a namespace, so that it's called with
+	/// `namespace.name(...)`
+	pub fn set_func_ns<F: Func<N> + 'static>(&mut self, namespace: &str, name: &str, func: F) {
+		self.funcs.insert(format!("{}.{}", namespace, name), Arc::new(func));
+	}
+
+	/// Add all the functions of a pack to the context under the pack's namespace, replacing any
+	/// existing ones with the same names
+	pub fn install<P: FunctionPack<N> + ?Sized>(&mut self, pack: &P) {
+		self.install_as(pack.namespace(), pack);
+	}
+
+	/// Add all the functions of a pack to the context under a different namespace than the pack's,
+	/// eg to avoid conflicts between packs
+	pub fn install_as<P: FunctionPack<N> + ?Sized>(&mut self, namespace: &str, pack: &P) {
+		for (name, func) in pack.funcs() {
+			let name = if namespace.is_empty() { name } else { format!("{}.{}", namespace, name) };
+			self.funcs.insert(name, func);
+		}
+	}
+
+	/// Returns a default context like `Context::new()` with the constants of a pack added
+	pub fn with_constants(pack: ConstantsPack) -> Self {
+		let mut ctx = Self::new();
+		ctx.install_constants(pack);
+		ctx
+	}
+
+	/// Add the constants of a pack to the context, computed with the precision of its config,
+	/// replacing any existing variables with the same names
+	pub fn install_constants(&mut self, pack: ConstantsPack) {
+		for (name, value) in pack.values(self) {
+			self.set_var(name, value);
+		}
+	}
+
+	/// Load variables and functions from definitions like `x = 3.2` and `f(a, b) = a*b + 1`, one on
+	/// each line, replacing any existing ones with the same names. Blank lines and lines starting
+	/// with `#` are skipped. Variables are defined by their expressions, which are evaluated when the
+	/// variables are used. Functions can call any function defined in the same text, no matter which
+	/// line it's on. If any definition is invalid, nothing is loaded.
+	///
+	/// ```rust
+	/// # use mexprp::{Context, Term, Answer};
+	/// let mut ctx: Context<f64> = Context::new();
+	/// ctx.load_definitions("
+	///     area(b, h) = half(b * h)
+	///     half(x) = x / 2
+	///     base = 3
+	/// ").unwrap();
+	/// let res = Term::parse_ctx("area(base, 4)", &ctx).unwrap().eval_ctx(&ctx);
+	/// assert_eq!(res.unwrap(), Answer::Single(6.0));
+	/// ```
+	pub fn load_definitions(&mut self, raw: &str) -> Result<(), DefinitionError> {
+		let mut defs = Vec::new();
+		for (i, line) in raw.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			let def = parse_definition(line).ok_or(DefinitionError::InvalidDefinition { line: i + 1 })?;
+			defs.push((i + 1, def));
+		}
+
+		// The functions are all defined before any bodies are parsed, so that calls to functions
+		// defined later are parsed as calls and not implicit multiplication
+		let mut ctx = self.clone();
+		for &(_, (name, ref params, _)) in &defs {
+			if let Some(ref params) = *params {
+				ctx.set_func(name, ExprFunc {
+					params: params.clone(),
+					body: Term::Var("".into()),
+				});
+			}
+		}
+		for (line, (name, params, value)) in defs {
+			let term = match params {
+				// The parameters have to be known names when `strict_names` is enabled
+				Some(ref params) if ctx.cfg.strict_names => {
+					let mut scope = ctx.clone();
+					for param in params {
+						scope.set_var(param, Term::var(param));
+					}
+					Term::parse_ctx(value, &scope)
+				}
+				_ => Term::parse_ctx(value, &ctx),
+			};
+			let term = term.map_err(|error| DefinitionError::ParseError { line, error })?;
+			match params {
+				Some(params) => ctx.set_func(name, ExprFunc { params, body: term }),
+				None => ctx.set_var(name, term),
+			}
+		}
+		*self = ctx;
+		Ok(())
+	}
+
+	/// Write out the variables, and the functions defined by expressions, in the format read by
+	/// `load_definitions`, sorted by name. Variables with the same definitions as the builtin
+	/// constants are left out.
+	pub fn dump_definitions(&self) -> String {
+		let builtin: Context<N> = Context::scientific();
+		let mut vars: Vec<(&String, &Term<N>)> = self.vars.iter()
+			.filter(|&(name, var)| builtin.vars.get(name).map(|b| b.to_string()) != Some(var.to_string()))
+			.collect();
+		vars.sort_by(|a, b| a.0.cmp(b.0));
+		let mut funcs: Vec<(&String, &[String], &Term<N>)> = self.funcs.iter()
+			.filter_map(|(name, func)| func.body().map(|(params, body)| (name, params, body)))
+			.collect();
+		funcs.sort_by(|a, b| a.0.cmp(b.0));
+
+		let mut output = String::new();
+		for (name, var) in vars {
+			output.push_str(&format!("{} = {}\n", name, var));
+		}
+		for (name, params, body) in funcs {
+			output.push_str(&format!("{}({}) = {}\n", name, params.join(", "), body));
+		}
+		output
+	}
+
+	/// Remove a variable definition from the context, returning it if there was one
+	pub fn remove_var(&mut self, name: &str) -> Option<Term<N>> {
+		self.vars.remove(name)
+	}
+
+	/// Remove a function definition from the context, returning it if there was one
+	pub fn remove_func(&mut self, name: &str) -> Option<Arc<dyn Func<N>>> {
+		self.funcs.remove(name)
+	}
+
+	/// The names of the functions that are most similar to a name, for suggesting what a misspelled
+	/// name might have been meant to be. At most 3 names are returned, closest first.
+	///
+	/// ```rust
+	/// # use mexprp::Context;
+	/// let ctx: Context<f64> = Context::new();
+	/// assert_eq!(ctx.similar_funcs("sqr"), vec!["sqrt".to_string()]);
+	/// ```
+	pub fn similar_funcs(&self, name: &str) -> Vec<String> {
+		similar(name, self.func_names())
+	}
+
+	/// The names of the variables, constants and functions that are most similar to a name, like
+	/// `similar_funcs`.
+	///
+	/// ```rust
+	/// # use mexprp::Context;
+	/// let ctx: Context<f64> = Context::new();
+	/// assert_eq!(ctx.similar_names("pii"), vec!["pi".to_string()]);
+	/// ```
+	pub fn similar_names(&self, name: &str) -> Vec<String> {
+		similar(name, self.vars.keys().map(String::as_str).chain(self.func_names()))
+	}
+
+	/// The names of the functions in the map and the table
+	fn func_names(&self) -> impl Iterator<Item = &str> {
+		let table = self.table.map(|table| table("")).unwrap_or(&[]).iter().map(|&(func, _)| func);
+		self.funcs.keys().map(String::as_str).chain(table)
+	}
+
+	/// Get the definition of a variable
+	pub fn get_var(&self, name: &str) -> Option<&Term<N>> {
+		self.vars.get(name)
+	}
+
+	/// Creates an empty `Context` with the default config that looks up functions in the table `T`
+	/// instead of a map. Functions can still be added to the map, and are looked up before the table.
+	///
+	/// ```rust
+	/// # use mexprp::{Context, Builtins, Term, Answer};
+	/// let ctx: Context<f64> = Context::with_table::<Builtins>();
+	/// let answer = Term::parse_ctx("max(abs(-3), 2)", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+	/// assert_eq!(answer, Answer::Single(3.0));
+	/// ```
+	pub fn with_table<T: FuncTable<N>>() -> Self {
+		let mut ctx = Context::empty();
+		ctx.set_table::<T>();
+		ctx
+	}
+
+	/// Look up functions that aren't in the map in the table `T`, replacing any previous table
+	pub fn set_table<T: FuncTable<N>>(&mut self) {
+		self.table = Some(func::table_prefixed::<N, T>);
+	}
+
+	/// Find a function in the map, or in the table if it isn't in the map
+	pub(crate) fn lookup_func<'a>(&'a self, name: &'a str) -> Option<&'a dyn Func<N>> {
+		match self.map_func(name) {
+			Some(func) => Some(&**func),
+			None => self.table?(name)
+				.first()
+				.filter(|&&(func, _)| func == name)
+				.map(|&(_, func)| func),
+		}
+	}
+
+	/// Find a function in the map that can be called. Sandboxed contexts can only call the ones
+	/// defined by expressions.
+	pub(crate) fn map_func(&self, name: &str) -> Option<&Arc<dyn Func<N>>> {
+		self.funcs.get(name).filter(|func| !self.sandboxed || func.body().is_some())
+	}
+
+	/// Get the definition of a function in the map. Functions from a `FuncTable` aren't included.
+	pub fn get_func(&self, name: &str) -> Option<&Arc<dyn Func<N>>> {
+		self.funcs.get(name)
+	}
+
+	/// Check whether a variable is defined
+	pub fn has_var(&self, name: &str) -> bool {
+		self.vars.contains_key(name)
+	}
+
+	/// Check whether a function is defined, in the map or in the table
+	pub fn has_func(&self, name: &str) -> bool {
+		self.lookup_func(name).is_some()
+	}
+
+	/// Iterate over the names and definitions of the variables, in no particular order
+	pub fn iter_vars(&self) -> impl Iterator<Item = (&str, &Term<N>)> {
+		self.vars.iter().map(|(name, var)| (name.as_str(), var))
+	}
+
+	/// Iterate over the names and definitions of the functions in the map, in no particular order.
+	/// Functions from a `FuncTable` aren't included.
+	pub fn iter_funcs(&self) -> impl Iterator<Item = (&str, &Arc<dyn Func<N>>)> {
+		self.funcs.iter().map(|(name, func)| (name.as_str(), func))
+	}
+
+	/// Remove all the variables and functions, including the builtin ones and the table. The config is
+	/// kept.
+	pub fn clear(&mut self) {
+		self.vars.clear();
+		self.funcs.clear();
+		self.table = None;
+	}
+
+	/// Add all the variables from an iterator of names and values, like a `HashMap` of parameters,
+	/// replacing any existing ones with the same names
+	pub fn extend_vars<I: IntoIterator<Item = (String, N)>>(&mut self, vars: I) {
+		for (name, val) in vars {
+			self.vars.insert(name, val.into());
+		}
+	}
+
+	/// Create a default context with the variables from an iterator of names and values added to it.
+	/// See `extend_vars`.
+	pub fn from_map<I: IntoIterator<Item = (String, N)>>(vars: I) -> Self {
+		let mut ctx = Context::new();
+		ctx.extend_vars(vars);
+		ctx
+	}
+
+	/// Start building a context with the default variables, functions and config
+	pub fn builder() -> ContextBuilder<N> {
+		ContextBuilder::new()
 	}
 
 	/// Creates an empty `Context` with the default config
 	pub fn empty() -> Self {
 		Context {
-			vars: HashMap::new(),
-			funcs: HashMap::new(),
+			vars: Map::new(),
+			funcs: Map::new(),
+			table: None,
 			cfg: Config::new(),
+			#[cfg(not(feature = "std"))]
+			active: Evaluations::new(),
+			cancel: None,
+			observer: None,
+			sandboxed: false,
+		}
+	}
+
+	/// An empty context with the config, for the operations of a number type made of `N`s (like
+	/// `AnyNum` and `Quantity`), which need one every time. With `std`, the context is only created
+	/// once per thread for each type and reused after that, until the config changes.
+	#[cfg(feature = "std")]
+	pub(crate) fn inner(cfg: &Config) -> Arc<Self> {
+		use std::any::{Any, TypeId};
+		use std::collections::HashMap;
+
+		thread_local! {
+			static INNER: RefCell<HashMap<TypeId, Arc<dyn Any + Send + Sync>>> = RefCell::new(HashMap::new());
+		}
+
+		INNER.with(|contexts| {
+			let mut contexts = contexts.borrow_mut();
+			let cached = contexts.get(&TypeId::of::<N>()).and_then(|inner| Arc::clone(inner).downcast::<Context<N>>().ok());
+			match cached {
+				Some(inner) if inner.cfg == *cfg => inner,
+				_ => {
+					let mut inner = Context::empty();
+					inner.cfg = cfg.clone();
+					let inner = Arc::new(inner);
+					contexts.insert(TypeId::of::<N>(), Arc::clone(&inner) as Arc<dyn Any + Send + Sync>);
+					inner
+				}
+			}
+		})
+	}
+
+	/// An empty context with the config, for the operations of a number type made of `N`s (like
+	/// `AnyNum` and `Quantity`)
+	#[cfg(not(feature = "std"))]
+	pub(crate) fn inner(cfg: &Config) -> Arc<Self> {
+		let mut inner = Context::empty();
+		inner.cfg = cfg.clone();
+		Arc::new(inner)
+	}
+
+	/// Get all the variables and functions whose names start with the given prefix, sorted by name
+	pub fn complete(&self, prefix: &str) -> Vec<Completion> {
+		let vars = self.vars.keys()
+			.filter(|name| name.starts_with(prefix))
+			.map(|name| Completion {
+				name: name.clone(),
+				kind: CompletionKind::Variable,
+				arity: None,
+			});
+		let funcs = self.funcs.iter()
+			.filter(|&(name, _)| name.starts_with(prefix))
+			.map(|(name, func)| Completion {
+				name: name.clone(),
+				kind: CompletionKind::Function,
+				arity: Some(func.arity()),
+			});
+		let table = self.table.map(|table| table(prefix)).unwrap_or(&[]).iter()
+			.filter(|&&(name, _)| !self.funcs.contains_key(name))
+			.map(|&(name, func)| Completion {
+				name: name.to_string(),
+				kind: CompletionKind::Function,
+				arity: Some(func.arity()),
+			});
+
+		let mut completions: Vec<Completion> = vars.chain(funcs).chain(table).collect();
+		completions.sort_by(|a, b| a.name.cmp(&b.name));
+		completions
+	}
+
+	/// Use the given token to cancel evaluations with this context. See
+	/// [`CancelToken`](crate::CancelToken) for more info.
+	pub fn set_cancel_token(&mut self, token: CancelToken) {
+		self.cancel = Some(token);
+	}
+
+	/// Call the methods of an observer for each term that's evaluated with this context, replacing the
+	/// previous observer. See [`EvalObserver`](crate::EvalObserver) for more info.
+	pub fn set_observer<O: EvalObserver<N> + 'static>(&mut self, observer: O) {
+		self.observer = Some(Arc::new(observer));
+	}
+
+	/// Stop calling the observer set with `set_observer`
+	pub fn remove_observer(&mut self) {
+		self.observer = None;
+	}
+
+	/// Clear the answers cached by the evaluation in progress with the context, for functions whose
+	/// answers can change partway through an evaluation. The answers of subterms are only cached if
+	/// `memoize` is enabled in the config and the values of variables if `cache_vars` is, and only
+	/// for as long as an evaluation lasts, so outside of one there's nothing to clear.
+	pub fn clear_cache(&self) {
+		if let Some(state) = EvalState::current(self) {
+			state.clear();
 		}
 	}
 }
@@ -167,8 +1060,34 @@ impl Config {
 	pub fn new() -> Self {
 		Config {
 			implicit_multiplication: true,
+			loose_function_call: false,
+			left_associative_pow: false,
+			tight_unary_minus: false,
 			precision: 53,
 			sqrt_both: true,
+			relative_percent: true,
+			max_depth: 256,
+			max_nodes: 65536,
+			max_steps: None,
+			max_answers: 1024,
+			dedup_answers: false,
+			strict_names: false,
+			memoize: false,
+			cache_limit: 1024,
+			cache_vars: true,
+			single_valued: false,
+			rounding: RoundingMode::HalfUp,
+			decimal_places: 28,
+			decimal_separator: '.',
+			arg_separator: ',',
+			normalize_input: true,
+			mixed_numbers: false,
+			zero_division: ZeroDivision::Error,
+			nan_policy: NanPolicy::Propagate,
+			negative_sqrt: DomainPolicy::NaN,
+			tolerance: 1e-9,
+			num_kind: NumKind::F64,
+			promote_numbers: false,
 		}
 	}
 }
@@ -200,19 +1119,236 @@ impl<N: Num> fmt::Debug for Context<N> {
 	}
 }
 
+/// Split a definition into its name, its parameters if it's a function, and its value or body.
+/// Returns `None` if it isn't a definition.
+fn parse_definition(line: &str) -> Option<(&str, Option<Vec<String>>, &str)> {
+	let eq = line.find('=')?;
+	let (lhs, value) = (line[..eq].trim(), line[eq + 1..].trim());
+	let (name, params) = match lhs.find('(') {
+		Some(open) if lhs.ends_with(')') => {
+			let params = lhs[open + 1..lhs.len() - 1].trim();
+			let params: Vec<String> = if params.is_empty() {
+				Vec::new()
+			} else {
+				params.split(',').map(|param| param.trim().to_string()).collect()
+			};
+			if !params.iter().all(|param| is_name(param)) {
+				return None;
+			}
+			(lhs[..open].trim(), Some(params))
+		}
+		_ => (lhs, None),
+	};
+	if is_name(name) {
+		Some((name, params, value))
+	} else {
+		None
+	}
+}
+
+/// The names that are most similar to a name, at most 3 of them, closest first
+fn similar<'a>(name: &str, names: impl Iterator<Item = &'a str>) -> Vec<String> {
+	// Only names that are different in at most a third of their characters are similar enough
+	let max = (name.chars().count() / 3).max(1);
+	let mut similar: Vec<(usize, &str)> = names
+		.filter(|&other| other != name)
+		.map(|other| (edit_distance(name, other), other))
+		.filter(|&(distance, _)| distance <= max)
+		.collect();
+	similar.sort();
+	similar.dedup();
+	similar.into_iter().take(3).map(|(_, other)| other.to_string()).collect()
+}
+
+/// The amount of characters that have to be inserted, removed or replaced to turn one string into the
+/// other (the Levenshtein distance)
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut prev: Vec<usize> = (0..=b.len()).collect();
+	for (i, ca) in a.chars().enumerate() {
+		let mut row = vec![i + 1];
+		for (j, &cb) in b.iter().enumerate() {
+			let replace = prev[j] + if ca == cb { 0 } else { 1 };
+			row.push(replace.min(prev[j + 1] + 1).min(row[j] + 1));
+		}
+		prev = row;
+	}
+	prev[b.len()]
+}
+
+/// Check whether a string can be used as a name in a definition, which is when it would be parsed as
+/// a single name
+fn is_name(name: &str) -> bool {
+	name.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphabetic() || c == '_'))
+}
+
+/// Builds a `Context` by chaining calls, for when a context is set up all at once.
+///
+/// ```rust
+/// # use mexprp::{Context, Expression, Answer};
+/// let ctx: Context<f64> = Context::builder()
+///     .var("x", 2.0)
+///     .var("y", Expression::parse("x * 3").unwrap())
+///     .implicit_multiplication(false)
+///     .build();
+/// let expr = Expression::parse_ctx("x + y", ctx).unwrap();
+/// assert_eq!(expr.eval().unwrap(), Answer::Single(8.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextBuilder<N: Num> {
+	ctx: Context<N>,
+}
+
+impl<N: Num + 'static> ContextBuilder<N> {
+	/// Start with the default variables, functions and config
+	pub fn new() -> Self {
+		ContextBuilder { ctx: Context::new() }
+	}
+
+	/// Start with no variables or functions, and the default config
+	pub fn empty() -> Self {
+		ContextBuilder { ctx: Context::empty() }
+	}
+
+	/// Add a variable, replacing any existing one with the same name
+	pub fn var<T: Into<Term<N>>>(mut self, name: &str, val: T) -> Self {
+		self.ctx.set_var(name, val);
+		self
+	}
+
+	/// Add all the variables from an iterator of names and values. See `Context::extend_vars`.
+	pub fn vars<I: IntoIterator<Item = (String, N)>>(mut self, vars: I) -> Self {
+		self.ctx.extend_vars(vars);
+		self
+	}
+
+	/// Add a function, replacing any existing one with the same name
+	pub fn func<F: Func<N> + 'static>(mut self, name: &str, func: F) -> Self {
+		self.ctx.set_func(name, func);
+		self
+	}
+
+	/// Look up functions that aren't in the map in the table `T`. See `Context::set_table`.
+	pub fn table<T: FuncTable<N>>(mut self) -> Self {
+		self.ctx.set_table::<T>();
+		self
+	}
+
+	/// Replace the whole config
+	pub fn config(mut self, cfg: Config) -> Self {
+		self.ctx.cfg = cfg;
+		self
+	}
+
+	/// Set the precision of arbitrary precision floating point numbers
+	pub fn precision(mut self, precision: u32) -> Self {
+		self.ctx.cfg.precision = precision;
+		self
+	}
+
+	/// Set whether or not multiplication signs are inserted between operands
+	pub fn implicit_multiplication(mut self, implicit_multiplication: bool) -> Self {
+		self.ctx.cfg.implicit_multiplication = implicit_multiplication;
+		self
+	}
+
+	/// Set whether or not `sqrt` returns the positive and negative values
+	pub fn sqrt_both(mut self, sqrt_both: bool) -> Self {
+		self.ctx.cfg.sqrt_both = sqrt_both;
+		self
+	}
+
+	/// Finish building the context
+	pub fn build(self) -> Context<N> {
+		self.ctx
+	}
+}
+
+impl<N: Num + 'static> Default for ContextBuilder<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// The builtin functions of `Context::full()` as a `FuncTable`, for contexts that don't store
+/// their functions in a map. The constants aren't included, since they can't be created at compile
+/// time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Builtins;
+
+impl<N: Num + 'static> FuncTable<N> for Builtins {
+	const FUNCS: &'static [(&'static str, &'static dyn Func<N>)] = {
+		use self::funcs::*;
+
+		&[
+			("abs", &Abs),
+			("acos", &Acos),
+			("asin", &Asin),
+			("atan", &Atan),
+			("atant", &Atan2),
+			("base", &Base),
+			("beta", &Beta),
+			("bin", &Radix(2)),
+			("binom", &Ncr),
+			("ceil", &Ceil),
+			("clamp", &Clamp),
+			("cos", &Cos),
+			("cross", &Cross),
+			("det", &Det),
+			("dot", &Dot),
+			("erf", &Erf),
+			("erfc", &Erfc),
+			("fact", &Fact),
+			("floor", &Floor),
+			("gamma", &Gamma),
+			("hex", &Radix(16)),
+			("inv", &Inv),
+			("len", &Len),
+			("lerp", &Lerp),
+			("lgamma", &LnGamma),
+			("log", &Log),
+			("max", &Max),
+			("min", &Min),
+			("ncr", &Ncr),
+			("norm", &Norm),
+			("npr", &Npr),
+			("nrt", &Nrt),
+			("oct", &Radix(8)),
+			("piecewise", &Piecewise),
+			("round", &Round),
+			("sign", &Sign),
+			("sin", &Sin),
+			("solve", &Solve),
+			("sqrt", &Sqrt),
+			("step", &Step),
+			("tan", &Tan),
+			("to_fraction", &ToFraction),
+			("transpose", &Transpose),
+			("xor", &Xor),
+			("zeta", &Zeta),
+		]
+	};
+}
+
 pub(in crate::context) mod funcs {
-	use std::cmp::Ordering;
+	use core::cmp::Ordering;
 
 	use crate::context::Context;
 	use crate::term::Term;
 	use crate::errors::MathError;
-	use crate::func::Func;
+	use crate::func::{Arity, Func};
 	use crate::opers::Calculation;
 	use crate::num::Num;
 	use crate::answer::Answer;
+	use crate::matrix;
+	use crate::prelude::*;
 
 	pub struct Sin;
 	impl<N: Num + 'static> Func<N> for Sin {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -220,12 +1356,16 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::sin(a, ctx))
+			a.unop_ctx(ctx, |a| Num::sin(a, ctx))
 		}
 	}
 
 	pub struct Cos;
 	impl<N: Num + 'static> Func<N> for Cos {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -233,12 +1373,16 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::cos(a, ctx))
+			a.unop_ctx(ctx, |a| Num::cos(a, ctx))
 		}
 	}
 
 	pub struct Max;
 	impl<N: Num + 'static> Func<N> for Max {
+		fn arity(&self) -> Arity {
+			Arity::at_least(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.is_empty() {
 				return Err(MathError::IncorrectArguments);
@@ -246,8 +1390,9 @@ pub(in crate::context) mod funcs {
 			let mut extra = Vec::new();
 			let mut max = match args[0].eval_ctx(ctx)? {
 				Answer::Single(n) => n,
-				Answer::Multiple(mut ns) => {
-					let one = ns.pop().unwrap();
+				a => {
+					let mut ns = a.to_vec();
+					let one = ns.pop().ok_or(MathError::IncorrectArguments)?;
 					extra = ns;
 					one
 				}
@@ -262,7 +1407,7 @@ pub(in crate::context) mod funcs {
 			for a in args {
 				match a {
 					Answer::Single(n) => new_args.push(n),
-					Answer::Multiple(mut ns) => new_args.append(&mut ns),
+					a => new_args.append(&mut a.to_vec()),
 				}
 			}
 			// For every argument as well as the extraneous solutions from the first one
@@ -277,6 +1422,10 @@ pub(in crate::context) mod funcs {
 
 	pub struct Min;
 	impl<N: Num + 'static> Func<N> for Min {
+		fn arity(&self) -> Arity {
+			Arity::at_least(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.is_empty() {
 				return Err(MathError::IncorrectArguments);
@@ -284,8 +1433,9 @@ pub(in crate::context) mod funcs {
 			let mut extra = Vec::new();
 			let mut min = match args[0].eval_ctx(ctx)? {
 				Answer::Single(n) => n,
-				Answer::Multiple(mut ns) => {
-					let one = ns.pop().unwrap();
+				a => {
+					let mut ns = a.to_vec();
+					let one = ns.pop().ok_or(MathError::IncorrectArguments)?;
 					extra = ns;
 					one
 				}
@@ -300,7 +1450,7 @@ pub(in crate::context) mod funcs {
 			for a in args {
 				match a {
 					Answer::Single(n) => new_args.push(n),
-					Answer::Multiple(mut ns) => new_args.append(&mut ns),
+					a => new_args.append(&mut a.to_vec()),
 				}
 			}
 			// For every argument as well as the extraneous solutions from the first one
@@ -315,6 +1465,10 @@ pub(in crate::context) mod funcs {
 
 	pub struct Sqrt;
 	impl<N: Num + 'static> Func<N> for Sqrt {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -322,12 +1476,16 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::sqrt(a, ctx))
+			a.unop_ctx(ctx, |a| Num::sqrt(a, ctx))
 		}
 	}
 
 	pub struct Nrt;
 	impl<N: Num + 'static> Func<N> for Nrt {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 2 {
 				return Err(MathError::IncorrectArguments);
@@ -336,12 +1494,16 @@ pub(in crate::context) mod funcs {
 			let a = args[0].eval_ctx(ctx)?;
 			let b = args[1].eval_ctx(ctx)?;
 
-			a.op(&b, |a, b| Num::nrt(a, b, ctx))
+			a.op_ctx(&b, ctx, |a, b| Num::nrt(a, b, ctx))
 		}
 	}
 
 	pub struct Abs;
 	impl<N: Num + 'static> Func<N> for Abs {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -349,12 +1511,16 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::abs(a, ctx))
+			a.unop_ctx(ctx, |a| Num::abs(a, ctx))
 		}
 	}
 
 	pub struct Tan;
 	impl<N: Num + 'static> Func<N> for Tan {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -362,12 +1528,16 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::tan(a, ctx))
+			a.unop_ctx(ctx, |a| Num::tan(a, ctx))
 		}
 	}
 
 	pub struct Asin;
 	impl<N: Num + 'static> Func<N> for Asin {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -375,12 +1545,16 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::asin(a, ctx))
+			a.unop_ctx(ctx, |a| Num::asin(a, ctx))
 		}
 	}
 
 	pub struct Acos;
 	impl<N: Num + 'static> Func<N> for Acos {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -388,25 +1562,33 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::acos(a, ctx))
+			a.unop_ctx(ctx, |a| Num::acos(a, ctx))
 		}
 	}
 
 	pub struct Atan;
 	impl<N: Num + 'static> Func<N> for Atan {
-		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
 			}
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::atan(a, ctx))
+			a.unop_ctx(ctx, |a| Num::atan(a, ctx))
 		}
 	}
 
 	pub struct Atan2;
 	impl<N: Num + 'static> Func<N> for Atan2 {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 2 {
 				return Err(MathError::IncorrectArguments);
@@ -415,12 +1597,16 @@ pub(in crate::context) mod funcs {
 			let a = args[0].eval_ctx(ctx)?;
 			let b = args[1].eval_ctx(ctx)?;
 
-			a.op(&b, |a, b| Num::atan2(a, b, ctx))
+			a.op_ctx(&b, ctx, |a, b| Num::atan2(a, b, ctx))
 		}
 	}
 
 	pub struct Floor;
 	impl<N: Num + 'static> Func<N> for Floor {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -428,12 +1614,16 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::floor(a, ctx))
+			a.unop_ctx(ctx, |a| Num::floor(a, ctx))
 		}
 	}
 
 	pub struct Ceil;
 	impl<N: Num + 'static> Func<N> for Ceil {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 1 {
 				return Err(MathError::IncorrectArguments);
@@ -441,25 +1631,414 @@ pub(in crate::context) mod funcs {
 
 			let a = args[0].eval_ctx(ctx)?;
 
-			a.unop(|a| Num::ceil(a, ctx))
+			a.unop_ctx(ctx, |a| Num::ceil(a, ctx))
 		}
 	}
 
 	pub struct Round;
 	impl<N: Num + 'static> Func<N> for Round {
+		fn arity(&self) -> Arity {
+			Arity::range(1, 2)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
-			if args.len() != 1 {
+			if args.is_empty() || args.len() > 2 {
 				return Err(MathError::IncorrectArguments);
 			}
 
 			let a = args[0].eval_ctx(ctx)?;
+			if args.len() == 1 {
+				return a.unop_ctx(ctx, |a| Num::round(a, ctx));
+			}
+
+			let digits = match args[1].eval_ctx(ctx)? {
+				Answer::Single(n) => n.to_f64_lossy()?,
+				_ => return Err(MathError::IncorrectArguments),
+			};
+			if digits % 1.0 != 0.0 {
+				return Err(MathError::NotAnInteger);
+			}
 
-			a.unop(|a| Num::round(a, ctx))
+			// Round x * 10^digits to an integer, then scale it back
+			let ten = N::from_f64(10.0, ctx)?;
+			let scale = ten.op_ctx(&N::from_f64(digits, ctx)?, ctx, |ten, digits| ten.pow(digits, ctx))?;
+			a.op_ctx(&scale, ctx, |a, scale| {
+				a.mul(scale, ctx)?
+					.unop_ctx(ctx, |a| Num::round(a, ctx))?
+					.unop_ctx(ctx, |a| a.div(scale, ctx))
+			})
 		}
 	}
 
 	pub struct Log;
 	impl<N: Num + 'static> Func<N> for Log {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 2 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+			let b = args[1].eval_ctx(ctx)?;
+
+			a.op_ctx(&b, ctx, |a, b| Num::log(a, b, ctx))
+		}
+	}
+
+	pub struct Sign;
+	impl<N: Num + 'static> Func<N> for Sign {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+
+			a.unop_ctx(ctx, |a| Num::sign(a, ctx))
+		}
+	}
+
+	pub struct Step;
+	impl<N: Num + 'static> Func<N> for Step {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+			let zero = N::from_f64(0.0, ctx)?.unwrap_single();
+
+			a.unop_ctx(ctx, |a| match Num::tryord(a, &zero, ctx)? {
+				Ordering::Less => N::from_f64(0.0, ctx),
+				_ => N::from_f64(1.0, ctx),
+			})
+		}
+	}
+
+	pub struct Clamp;
+	impl<N: Num + 'static> Func<N> for Clamp {
+		fn arity(&self) -> Arity {
+			Arity::exact(3)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 3 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+			let lo = args[1].eval_ctx(ctx)?;
+			let hi = args[2].eval_ctx(ctx)?;
+
+			a.op_ctx(&lo, ctx, |a, lo| {
+				if Num::tryord(a, lo, ctx)? == Ordering::Less {
+					Ok(Answer::Single(lo.clone()))
+				} else {
+					Ok(Answer::Single(a.clone()))
+				}
+			})?.op_ctx(&hi, ctx, |a, hi| {
+				if Num::tryord(a, hi, ctx)? == Ordering::Greater {
+					Ok(Answer::Single(hi.clone()))
+				} else {
+					Ok(Answer::Single(a.clone()))
+				}
+			})
+		}
+	}
+
+	pub struct Fact;
+	impl<N: Num + 'static> Func<N> for Fact {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+
+			a.unop_ctx(ctx, |a| Num::fact(a, ctx))
+		}
+	}
+
+	pub struct Ncr;
+	impl<N: Num + 'static> Func<N> for Ncr {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 2 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let n = args[0].eval_ctx(ctx)?;
+			let k = args[1].eval_ctx(ctx)?;
+
+			n.op_ctx(&k, ctx, |n, k| Num::binomial(n, k, ctx))
+		}
+	}
+
+	pub struct Npr;
+	impl<N: Num + 'static> Func<N> for Npr {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 2 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let n = args[0].eval_ctx(ctx)?;
+			let k = args[1].eval_ctx(ctx)?;
+
+			n.op_ctx(&k, ctx, |n, k| Num::permutations(n, k, ctx))
+		}
+	}
+
+	pub struct Gamma;
+	impl<N: Num + 'static> Func<N> for Gamma {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+
+			a.unop_ctx(ctx, |a| Num::gamma(a, ctx))
+		}
+	}
+
+	pub struct LnGamma;
+	impl<N: Num + 'static> Func<N> for LnGamma {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+
+			a.unop_ctx(ctx, |a| Num::lgamma(a, ctx))
+		}
+	}
+
+	pub struct Erf;
+	impl<N: Num + 'static> Func<N> for Erf {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+
+			a.unop_ctx(ctx, |a| Num::erf(a, ctx))
+		}
+	}
+
+	pub struct Erfc;
+	impl<N: Num + 'static> Func<N> for Erfc {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+
+			a.unop_ctx(ctx, |a| Num::erfc(a, ctx))
+		}
+	}
+
+	pub struct Beta;
+	impl<N: Num + 'static> Func<N> for Beta {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 2 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+			let b = args[1].eval_ctx(ctx)?;
+
+			a.op_ctx(&b, ctx, |a, b| Num::beta(a, b, ctx))
+		}
+	}
+
+	pub struct Zeta;
+	impl<N: Num + 'static> Func<N> for Zeta {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+
+			a.unop_ctx(ctx, |a| Num::zeta(a, ctx))
+		}
+	}
+
+	pub struct Lerp;
+	impl<N: Num + 'static> Func<N> for Lerp {
+		fn arity(&self) -> Arity {
+			Arity::exact(3)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 3 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = args[0].eval_ctx(ctx)?;
+			let b = args[1].eval_ctx(ctx)?;
+			let t = args[2].eval_ctx(ctx)?;
+
+			// a + (b - a) × t
+			let diff = b.op_ctx(&a, ctx, |b, a| Num::sub(b, a, ctx))?;
+			let scaled = diff.op_ctx(&t, ctx, |d, t| Num::mul(d, t, ctx))?;
+			a.op_ctx(&scaled, ctx, |a, s| Num::add(a, s, ctx))
+		}
+	}
+
+	pub struct Piecewise;
+	impl<N: Num + 'static> Func<N> for Piecewise {
+		fn arity(&self) -> Arity {
+			Arity::at_least(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let (default, cases) = match args.split_last() {
+				Some((default, cases)) if !cases.is_empty() => (default, cases),
+				_ => return Err(MathError::IncorrectArguments),
+			};
+
+			let zero = N::from_f64(0.0, ctx)?.unwrap_single();
+			for case in cases {
+				// The pairs are taken apart without evaluating them, so that only the value that's chosen
+				// is evaluated. Pairs of constants may have been folded into vectors already.
+				let (cond, value) = match *case {
+					Term::Operation(ref oper) if oper.name() == "[,]" && oper.operands().len() == 2 => {
+						let operands = oper.operands();
+						(operands[0].clone(), operands[1].clone())
+					}
+					Term::Num(Answer::Vector(ref items)) if items.len() == 2 => (Term::Num(items[0].clone()), Term::Num(items[1].clone())),
+					_ => return Err(MathError::IncorrectArguments),
+				};
+				match cond.eval_ctx(ctx)? {
+					// A condition that's NaN, like a comparison with NaN, doesn't hold
+					Answer::Single(ref c) if *c == zero || c.is_nan() => {}
+					Answer::Single(_) => return value.eval_ctx(ctx),
+					_ => return Err(MathError::IncorrectArguments),
+				}
+			}
+
+			default.eval_ctx(ctx)
+		}
+	}
+
+	pub struct ToFraction;
+	impl<N: Num + 'static> Func<N> for ToFraction {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 2 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let x = args[0].eval_ctx(ctx)?;
+			let max_den = args[1].eval_ctx(ctx)?;
+
+			x.op_ctx(&max_den, ctx, |x, max_den| {
+				let max_den = max_den.to_f64_lossy()?;
+				if max_den.is_nan() || max_den < 1.0 || max_den % 1.0 != 0.0 {
+					return Err(MathError::IncorrectArguments);
+				}
+				let (n, d) = nearest_fraction(x.to_f64_lossy()?, max_den)?;
+				Ok(Answer::Vector(vec![N::from_f64(n, ctx)?, N::from_f64(d, ctx)?]))
+			})
+		}
+	}
+
+	/// Finds the fraction closest to `x` with a denominator of at most `max_den` using its continued
+	/// fraction. The best approximation is either a convergent or a semiconvergent between the last two
+	/// convergents.
+	fn nearest_fraction(x: f64, max_den: f64) -> Result<(f64, f64), MathError> {
+		if !x.is_finite() {
+			return Err(MathError::NaN);
+		}
+
+		let floor = |y: f64| y - y % 1.0;
+		let mut y = x.abs();
+		// The last two convergents
+		let (mut p0, mut q0, mut p1, mut q1) = (0.0, 1.0, 1.0, 0.0);
+		loop {
+			let a = floor(y);
+			let (p2, q2) = (a * p1 + p0, a * q1 + q0);
+			if q2 > max_den {
+				let k = floor((max_den - q0) / q1);
+				let (ps, qs) = (k * p1 + p0, k * q1 + q0);
+				if (ps / qs - x.abs()).abs() < (p1 / q1 - x.abs()).abs() {
+					p1 = ps;
+					q1 = qs;
+				}
+				break;
+			}
+			p0 = p1;
+			q0 = q1;
+			p1 = p2;
+			q1 = q2;
+			if y == a || p1 / q1 == x.abs() {
+				break;
+			}
+			y = 1.0 / (y - a);
+		}
+
+		Ok((if x < 0.0 { -p1 } else { p1 }, q1))
+	}
+
+	pub struct Xor;
+	impl<N: Num + 'static> Func<N> for Xor {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
 		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 			if args.len() != 2 {
 				return Err(MathError::IncorrectArguments);
@@ -468,7 +2047,230 @@ pub(in crate::context) mod funcs {
 			let a = args[0].eval_ctx(ctx)?;
 			let b = args[1].eval_ctx(ctx)?;
 
-			a.op(&b, |a, b| Num::log(a, b, ctx))
+			a.op_ctx(&b, ctx, |a, b| Num::bitxor(a, b, ctx))
+		}
+	}
+
+	/// Evaluates to its argument, but is shown in another radix
+	pub struct Radix(pub u32);
+	impl<N: Num + 'static> Func<N> for Radix {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 1 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			args[0].eval_ctx(ctx)
+		}
+
+		fn display_radix(&self, _args: &[Term<N>], _ctx: &Context<N>) -> Option<u32> {
+			Some(self.0)
+		}
+	}
+
+	/// Evaluates the radix argument of `base`
+	fn radix_arg<N: Num + 'static>(arg: &Term<N>, ctx: &Context<N>) -> Result<u32, MathError> {
+		let radix = match arg.eval_ctx(ctx)? {
+			Answer::Single(n) => n.to_f64_lossy()?,
+			_ => return Err(MathError::IncorrectArguments),
+		};
+		if (2.0..=36.0).contains(&radix) && radix % 1.0 == 0.0 {
+			Ok(radix as u32)
+		} else {
+			Err(MathError::IncorrectArguments)
+		}
+	}
+
+	pub struct Base;
+	impl<N: Num + 'static> Func<N> for Base {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 2 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			radix_arg(&args[1], ctx)?;
+			args[0].eval_ctx(ctx)
+		}
+
+		fn display_radix(&self, args: &[Term<N>], ctx: &Context<N>) -> Option<u32> {
+			args.get(1).and_then(|arg| radix_arg(arg, ctx).ok())
+		}
+	}
+
+	/// Evaluates the arguments of a function that takes vectors
+	fn vector_args<N: Num + 'static>(args: &[Term<N>], count: usize, ctx: &Context<N>) -> Result<Vec<Vec<Answer<N>>>, MathError> {
+		if args.len() != count {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		let mut vectors = Vec::new();
+		for arg in args {
+			match arg.eval_ctx(ctx)? {
+				Answer::Vector(items) => vectors.push(items),
+				_ => return Err(MathError::IncorrectArguments),
+			}
+		}
+		Ok(vectors)
+	}
+
+	/// Computes the dot product of two vectors of the same length
+	fn dot<N: Num + 'static>(a: &[Answer<N>], b: &[Answer<N>], ctx: &Context<N>) -> Calculation<N> {
+		if a.len() != b.len() {
+			return Err(MathError::DimensionMismatch);
+		}
+
+		let mut sum = Answer::Single(N::from_f64(0.0, ctx)?.unwrap_single());
+		for (a, b) in a.iter().zip(b.iter()) {
+			let product = a.op_ctx(b, ctx, |a, b| Num::mul(a, b, ctx))?;
+			sum = sum.op_ctx(&product, ctx, |a, b| Num::add(a, b, ctx))?;
+		}
+		Ok(sum)
+	}
+
+	pub struct Len;
+	impl<N: Num + 'static> Func<N> for Len {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let v = vector_args(args, 1, ctx)?;
+
+			N::from_f64(v[0].len() as f64, ctx)
+		}
+	}
+
+	pub struct Dot;
+	impl<N: Num + 'static> Func<N> for Dot {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let v = vector_args(args, 2, ctx)?;
+
+			dot(&v[0], &v[1], ctx)
+		}
+	}
+
+	pub struct Cross;
+	impl<N: Num + 'static> Func<N> for Cross {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let v = vector_args(args, 2, ctx)?;
+			let (a, b) = (&v[0], &v[1]);
+			if a.len() != 3 || b.len() != 3 {
+				return Err(MathError::DimensionMismatch);
+			}
+
+			// The component (i, j) of the cross product is a[i]b[j] - a[j]b[i]
+			let component = |i: usize, j: usize| -> Calculation<N> {
+				let x = a[i].op_ctx(&b[j], ctx, |a, b| Num::mul(a, b, ctx))?;
+				let y = a[j].op_ctx(&b[i], ctx, |a, b| Num::mul(a, b, ctx))?;
+				x.op_ctx(&y, ctx, |x, y| Num::sub(x, y, ctx))
+			};
+
+			Ok(Answer::Vector(vec![component(1, 2)?, component(2, 0)?, component(0, 1)?]))
+		}
+	}
+
+	pub struct Norm;
+	impl<N: Num + 'static> Func<N> for Norm {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let v = vector_args(args, 1, ctx)?;
+
+			// Only the principal (positive) root is a norm
+			dot(&v[0], &v[0], ctx)?.unop_ctx(ctx, |sum| match Num::sqrt(sum, ctx)? {
+				Answer::Single(n) => Ok(Answer::Single(n)),
+				a => Ok(Answer::Single(a.to_vec().remove(0))),
+			})
+		}
+	}
+
+	/// Evaluates the argument of a function that takes one matrix
+	fn matrix_arg<N: Num + 'static>(args: &[Term<N>], ctx: &Context<N>) -> Result<matrix::Matrix<N>, MathError> {
+		if args.len() != 1 {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		matrix::as_matrix(&args[0].eval_ctx(ctx)?).ok_or(MathError::IncorrectArguments)
+	}
+
+	pub struct Transpose;
+	impl<N: Num + 'static> Func<N> for Transpose {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let m = matrix_arg(args, ctx)?;
+
+			Ok(matrix::matrix_answer(matrix::transpose(&m)))
+		}
+	}
+
+	pub struct Det;
+	impl<N: Num + 'static> Func<N> for Det {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let m = matrix_arg(args, ctx)?;
+
+			Ok(Answer::Single(matrix::det(&m, ctx)?))
+		}
+	}
+
+	pub struct Inv;
+	impl<N: Num + 'static> Func<N> for Inv {
+		fn arity(&self) -> Arity {
+			Arity::exact(1)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			let m = matrix_arg(args, ctx)?;
+
+			Ok(matrix::matrix_answer(matrix::inv(&m, ctx)?))
+		}
+	}
+
+	pub struct Solve;
+	impl<N: Num + 'static> Func<N> for Solve {
+		fn arity(&self) -> Arity {
+			Arity::exact(2)
+		}
+
+		fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+			if args.len() != 2 {
+				return Err(MathError::IncorrectArguments);
+			}
+
+			let a = matrix::as_matrix(&args[0].eval_ctx(ctx)?).ok_or(MathError::IncorrectArguments)?;
+			let b = args[1].eval_ctx(ctx)?;
+			if let Some(b) = matrix::as_matrix(&b) {
+				Ok(matrix::matrix_answer(matrix::solve(&a, &b, ctx)?))
+			} else if let Some(b) = matrix::as_vector(&b) {
+				let column: Vec<Vec<N>> = b.into_iter().map(|n| vec![n]).collect();
+				let x = matrix::solve(&a, &column, ctx)?;
+				Ok(matrix::vector_answer(x.into_iter().map(|mut row| row.remove(0)).collect()))
+			} else {
+				Err(MathError::IncorrectArguments)
+			}
 		}
 	}
 }
@@ -0,0 +1,202 @@
+use core::fmt;
+use core::ops::Range;
+
+use crate::context::Context;
+use crate::lexer::{tokenize_cfg, SpannedToken, TokenKind};
+use crate::num::Num;
+use crate::term::Term;
+use crate::prelude::*;
+
+/// A problem found in an expression by `Expression::check`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+	/// The kind of problem
+	pub kind: DiagnosticKind,
+	/// The byte range of the string the problem is at
+	pub span: Range<usize>,
+}
+
+/// The kinds of problems `Expression::check` can find
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+	/// A character that can't be parsed at its position
+	UnexpectedCharacter,
+	/// A name that isn't a variable or function in the context
+	UnknownName {
+		/// The name
+		name: String,
+	},
+	/// An opening parenthesis or bracket that is never closed
+	Unclosed,
+	/// A closing parenthesis or bracket without an opening one
+	Unmatched,
+	/// A closing parenthesis or bracket that doesn't match the kind of the opening one, eg `(1]`
+	Mismatched,
+	/// An empty argument or element, eg the one between the commas in `max(1,,2)`, or empty
+	/// parentheses that aren't a function call
+	EmptyArgument,
+	/// An operator that is missing an operand, eg the `+` in `2 +`
+	MissingOperand,
+	/// Any other problem that prevents the expression from being parsed
+	Other {
+		/// A description of the problem
+		message: String,
+	},
+}
+
+impl fmt::Display for DiagnosticKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			DiagnosticKind::UnexpectedCharacter => write!(f, "Unexpected character"),
+			DiagnosticKind::UnknownName { ref name } => write!(f, "Unknown name '{}'", name),
+			DiagnosticKind::Unclosed => write!(f, "Unclosed parenthesis"),
+			DiagnosticKind::Unmatched => write!(f, "Unmatched closing parenthesis"),
+			DiagnosticKind::Mismatched => write!(f, "Closing parenthesis doesn't match the opening one"),
+			DiagnosticKind::EmptyArgument => write!(f, "Empty argument"),
+			DiagnosticKind::MissingOperand => write!(f, "Operator is missing an operand"),
+			DiagnosticKind::Other { ref message } => write!(f, "{}", message),
+		}
+	}
+}
+
+impl fmt::Display for Diagnostic {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+	}
+}
+
+/// A decision made while parsing an expression that might not be what the user meant, reported by
+/// `Term::parse_with_warnings`. Unlike a `Diagnostic`, a warning doesn't stop the expression from
+/// being parsed.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+	/// A multiplication was inserted between two operands right next to each other, like `2` and `x`
+	/// in `2x`
+	ImplicitMultiplication {
+		/// The operand on the left
+		left: String,
+		/// The operand on the right
+		right: String,
+	},
+	/// A name that isn't a variable or function in the context was treated as a variable
+	UnknownVariable {
+		/// The name
+		name: String,
+	},
+	/// A name followed by parentheses was treated as a variable multiplied by them, since there's no
+	/// function with that name
+	VariableCall {
+		/// The name
+		name: String,
+	},
+	/// A unary minus was applied to a function call, like in `-sin(x)`, which negates the whole call
+	/// (including any power of it, like in `-sin(x)^2`)
+	NegatedCall {
+		/// The name of the function
+		name: String,
+	},
+}
+
+impl fmt::Display for Warning {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Warning::ImplicitMultiplication { ref left, ref right } => {
+				write!(f, "Implicit multiplication inserted between `{}` and `{}`", left, right)
+			}
+			Warning::UnknownVariable { ref name } => write!(f, "Name `{}` treated as a variable", name),
+			Warning::VariableCall { ref name } => write!(f, "Variable `{}` multiplied by the parentheses after it", name),
+			Warning::NegatedCall { ref name } => write!(f, "Unary minus applied to a call to `{}`", name),
+		}
+	}
+}
+
+/// Check whether a token can start an operand
+fn starts_operand(token: &SpannedToken) -> bool {
+	matches!(
+		token.kind,
+		TokenKind::Number | TokenKind::Name | TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::OpenBrace | TokenKind::PrefixOp
+	)
+}
+
+/// Find all the problems in a string. See `Expression::check`.
+pub(crate) fn check<N: Num + 'static>(raw: &str, ctx: &Context<N>) -> Vec<Diagnostic> {
+	let tokens = tokenize_cfg(raw, &ctx.cfg);
+	let mut diagnostics = Vec::new();
+	// The indices of the tokens of the currently open parentheses and brackets
+	let mut open: Vec<usize> = Vec::new();
+
+	for (i, token) in tokens.iter().enumerate() {
+		let next = tokens.get(i + 1);
+		let mut report = |kind| {
+			diagnostics.push(Diagnostic {
+				kind,
+				span: token.span.clone(),
+			})
+		};
+
+		match token.kind {
+			// Commas can only fail to be parsed when the argument before them is missing
+			TokenKind::Unknown if token.text.chars().eq(Some(ctx.cfg.arg_separator)) => report(DiagnosticKind::EmptyArgument),
+			TokenKind::Unknown => report(DiagnosticKind::UnexpectedCharacter),
+			TokenKind::Name => {
+				if !ctx.vars.contains_key(&token.text) && !ctx.has_func(&token.text) {
+					report(DiagnosticKind::UnknownName { name: token.text.clone() });
+				}
+			}
+			TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::OpenBrace => open.push(i),
+			TokenKind::CloseParen | TokenKind::CloseBracket | TokenKind::CloseBrace => {
+				let expected = match token.kind {
+					TokenKind::CloseParen => TokenKind::OpenParen,
+					TokenKind::CloseBracket => TokenKind::OpenBracket,
+					_ => TokenKind::OpenBrace,
+				};
+				match open.pop() {
+					Some(o) if tokens[o].kind != expected => report(DiagnosticKind::Mismatched),
+					Some(o) => {
+						// Empty parentheses are only allowed for calling a function without arguments
+						let call = o > 0 && tokens[o - 1].kind == TokenKind::Name;
+						if o + 1 == i && expected != TokenKind::OpenBracket && !call {
+							diagnostics.push(Diagnostic {
+								kind: DiagnosticKind::EmptyArgument,
+								span: tokens[o].span.start..token.span.end,
+							});
+						}
+					}
+					None => report(DiagnosticKind::Unmatched),
+				}
+			}
+			TokenKind::Comma => {
+				if matches!(next, Some(n) if matches!(n.kind, TokenKind::CloseParen | TokenKind::CloseBracket | TokenKind::CloseBrace)) {
+					report(DiagnosticKind::EmptyArgument);
+				}
+			}
+			TokenKind::InfixOp | TokenKind::PrefixOp => {
+				// Unknown characters already have their own diagnostic
+				if !matches!(next, Some(n) if starts_operand(n) || n.kind == TokenKind::Unknown) {
+					report(DiagnosticKind::MissingOperand);
+				}
+			}
+			TokenKind::Number | TokenKind::PostfixOp => {}
+		}
+	}
+
+	for o in open {
+		diagnostics.push(Diagnostic {
+			kind: DiagnosticKind::Unclosed,
+			span: tokens[o].span.clone(),
+		});
+	}
+
+	// Catch anything else the parser doesn't like
+	if diagnostics.is_empty() && !tokens.is_empty() {
+		if let Err(e) = Term::parse_ctx(raw, ctx) {
+			diagnostics.push(Diagnostic {
+				kind: DiagnosticKind::Other { message: e.to_string() },
+				span: 0..raw.len(),
+			});
+		}
+	}
+
+	diagnostics
+}
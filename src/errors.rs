@@ -1,82 +1,383 @@
-use thiserror::Error;
+use core::fmt;
 
-/// An error that can occur during parsing
-#[derive(Debug, Error)]
+use crate::prelude::*;
+
+/// An error that can occur during parsing. More kinds of errors may be added in the future, so
+/// matches on it need a wildcard arm; `code` can be used to identify the kind of an error without
+/// matching.
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum ParseError {
 	/// Got an unexpected token
-	#[error("Got unexpected token: '{token}'")]
 	UnexpectedToken {
 		/// The token
 		token: String,
 	},
 	/// Parentheses didn't match
-	#[error("Parentheses didn't match")]
 	MismatchedParentheses,
 	/// Expected something but it wasn't found
-	#[error("Expected something that wasn't found: {expected}")]
 	Expected {
 		/// The thing that was expected
 		expected: Expected,
 	},
+	/// A number couldn't be represented by the number type being parsed, eg a number too large to fit
+	/// in an `f64` being parsed as a `Rational`
+	InvalidNumber {
+		/// The number
+		number: String,
+	},
+	/// A name wasn't a variable or function in the context while `strict_names` was enabled
+	UnknownName {
+		/// The name
+		name: String,
+	},
+	/// A function was called with an amount of arguments it doesn't take
+	IncorrectArguments {
+		/// The name of the function
+		name: String,
+		/// The amount of arguments it was called with
+		count: usize,
+	},
+	/// A name followed by parentheses wasn't a function or a variable in the context while
+	/// `strict_names` was enabled
+	UnknownFunction {
+		/// The name
+		name: String,
+		/// The names of similar functions that might have been meant, closest first
+		suggestions: Vec<String>,
+	},
+	/// The expression was nested deeper than the `max_depth` in the config
+	TooDeep,
+	/// The expression had more tokens than the `max_nodes` in the config
+	TooLarge,
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ParseError::UnexpectedToken { ref token } => write!(f, "Got unexpected token: '{}'", token),
+			ParseError::MismatchedParentheses => write!(f, "Parentheses didn't match"),
+			ParseError::Expected { ref expected } => write!(f, "Expected something that wasn't found: {}", expected),
+			ParseError::InvalidNumber { ref number } => write!(f, "The number {} can't be represented", number),
+			ParseError::UnknownName { ref name } => write!(f, "Unknown name: '{}'", name),
+			ParseError::UnknownFunction { ref name, ref suggestions } => write!(f, "Unknown function: '{}'{}", name, did_you_mean(suggestions)),
+			ParseError::IncorrectArguments { ref name, ref count } => write!(f, "The function '{}' can't take {} arguments", name, count),
+			ParseError::TooDeep => write!(f, "The expression is nested too deeply"),
+			ParseError::TooLarge => write!(f, "The expression is too large"),
+		}
+	}
+}
+
+impl ParseError {
+	/// A short code identifying the kind of this error that will stay the same between versions,
+	/// like `"parse.unexpected_token"`. Useful for handling errors without matching on them, or for
+	/// looking up translated messages.
+	pub fn code(&self) -> &'static str {
+		match *self {
+			ParseError::UnexpectedToken { .. } => "parse.unexpected_token",
+			ParseError::MismatchedParentheses => "parse.mismatched_parentheses",
+			ParseError::Expected { .. } => "parse.expected",
+			ParseError::InvalidNumber { .. } => "parse.invalid_number",
+			ParseError::UnknownName { .. } => "parse.unknown_name",
+			ParseError::UnknownFunction { .. } => "parse.unknown_function",
+			ParseError::IncorrectArguments { .. } => "parse.incorrect_arguments",
+			ParseError::TooDeep => "parse.too_deep",
+			ParseError::TooLarge => "parse.too_large",
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match *self {
+			ParseError::Expected { ref expected } => Some(expected),
+			_ => None,
+		}
+	}
 }
 
-/// An error that can occur while evaluating an expression
-#[derive(Debug, Error)]
+/// An error that can occur while evaluating an expression. More kinds of errors may be added in the
+/// future, so matches on it need a wildcard arm; `code` can be used to identify the kind of an error
+/// without matching.
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum MathError {
 	/// A variable that was not defined in the context was referenced
-	#[error("Variable '{name}' is not defined")]
 	UndefinedVariable {
 		/// The name of the variable
 		name: String,
+		/// The names of similar variables and functions that might have been meant, closest first
+		suggestions: Vec<String>,
 	},
 	/// A function that was not defined in the context was referenced
-	#[error("Function '{name}' is not defined")]
 	UndefinedFunction {
 		/// The name of the function
 		name: String,
 	},
+	/// A name followed by parentheses, like `foo(3)`, was parsed as a variable multiplied by the
+	/// parentheses since it wasn't a function, but it isn't a variable either
+	VariableCalledAsFunction {
+		/// The name
+		name: String,
+		/// The names of similar functions that might have been meant, closest first
+		suggestions: Vec<String>,
+	},
 	/// A function was given arguments in an incorrect form
-	#[error("A function was passed incorrect arguments")]
 	IncorrectArguments,
 	/// Attempted to divide by zero
-	#[error("Attempted to divide by zero")]
 	DivideByZero,
 	/// A NaN value was used in a way that is not possible
-	#[error("A NaN value was attempted to be used as an operand")]
 	NaN,
 	/// Tried to compare a value that can't be compared (eg NaN, Infinity, etc.)
-	#[error("Tried to compare a value that can't be compared (eg NaN, Infinity, etc.)")]
 	CmpError,
 	/// Attempted an operation on a Number that wasn't implemented for that type
-	#[error("The operation '{op}' is not supported for the type {num_type}")]
 	Unimplemented {
 		/// The name of the operation that was attempted
 		op: String,
 		/// The type of number it was attempted for
 		num_type: String,
 	},
+	/// An operation was attempted between vectors or matrices of incompatible sizes
+	DimensionMismatch,
+	/// Attempted to invert a singular matrix or solve a system without a unique solution
+	SingularMatrix,
+	/// A value was indexed with something that isn't a valid index, or the value isn't a vector
+	InvalidIndex,
+	/// The evaluation recursed deeper than the `max_depth` in the config, possibly because a function
+	/// was defined in terms of itself
+	RecursionLimit,
+	/// A variable was defined in terms of itself, like `a` as `b + 1` and `b` as `a + 1`
+	CircularDefinition {
+		/// The names of the variables in the cycle, starting and ending with the same one, like
+		/// `["a", "b", "a"]`
+		path: Vec<String>,
+	},
+	/// A term evaluated to more answers than the `max_answers` in the config
+	TooManyAnswers,
+	/// The evaluation was cancelled with a `CancelToken` or took more steps than the `max_steps` in
+	/// the config
+	Cancelled,
+	/// An operation that only works on integers (like the bitwise operations) was attempted on a
+	/// number that isn't one
+	NotAnInteger,
+	/// The result of an operation was too large to be represented by the number type
+	Overflow,
+	/// A function was given a number outside of its domain, like the square root of a negative number
+	/// for a type without complex numbers, while the `negative_sqrt` of the config isn't
+	/// `DomainPolicy::NaN`
+	DomainError {
+		/// The name of the function
+		func: String,
+		/// The number it was given
+		value: String,
+	},
+	/// An operation was given a quantity with a different unit than it needs, like adding a time span
+	/// to a plain number (see the `time` module)
+	UnitMismatch {
+		/// What the operation needed, like "a time span"
+		expected: String,
+		/// What it was given
+		found: String,
+	},
 	/// Another type of Error occurred.
-	#[error("An unknown error occurred during evaluation")]
 	Other,
 }
 
+impl fmt::Display for MathError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			MathError::UndefinedVariable { ref name, ref suggestions } => write!(f, "Variable '{}' is not defined{}", name, did_you_mean(suggestions)),
+			MathError::UndefinedFunction { ref name } => write!(f, "Function '{}' is not defined", name),
+			MathError::VariableCalledAsFunction { ref name, ref suggestions } => write!(f, "'{}' was called like a function, but it isn't a function or a variable{}", name, did_you_mean(suggestions)),
+			MathError::IncorrectArguments => write!(f, "A function was passed incorrect arguments"),
+			MathError::DivideByZero => write!(f, "Attempted to divide by zero"),
+			MathError::NaN => write!(f, "A NaN value was attempted to be used as an operand"),
+			MathError::CmpError => write!(f, "Tried to compare a value that can't be compared (eg NaN, Infinity, etc.)"),
+			MathError::Unimplemented { ref op, ref num_type } => write!(f, "The operation '{}' is not supported for the type {}", op, num_type),
+			MathError::DimensionMismatch => write!(f, "Tried to operate on vectors or matrices of incompatible sizes"),
+			MathError::SingularMatrix => write!(f, "The matrix is singular"),
+			MathError::InvalidIndex => write!(f, "Invalid index or indexed value is not a vector"),
+			MathError::RecursionLimit => write!(f, "The evaluation recursed too deeply"),
+			MathError::CircularDefinition { ref path } => write!(f, "The variable '{}' is defined in terms of itself ({})", path[0], path.join(" -> ")),
+			MathError::TooManyAnswers => write!(f, "The expression evaluated to too many answers"),
+			MathError::Cancelled => write!(f, "The evaluation was cancelled"),
+			MathError::NotAnInteger => write!(f, "Tried to use a number that isn't an integer as one"),
+			MathError::Overflow => write!(f, "The result was too large to be represented"),
+			MathError::DomainError { ref func, ref value } => write!(f, "{} is undefined for {}", func, value),
+			MathError::UnitMismatch { ref expected, ref found } => write!(f, "Expected {} but got {}", expected, found),
+			MathError::Other => write!(f, "An unknown error occurred during evaluation"),
+		}
+	}
+}
+
+impl MathError {
+	/// A short code identifying the kind of this error that will stay the same between versions,
+	/// like `"math.divide_by_zero"`. See `ParseError::code`.
+	pub fn code(&self) -> &'static str {
+		match *self {
+			MathError::UndefinedVariable { .. } => "math.undefined_variable",
+			MathError::UndefinedFunction { .. } => "math.undefined_function",
+			MathError::VariableCalledAsFunction { .. } => "math.variable_called_as_function",
+			MathError::IncorrectArguments => "math.incorrect_arguments",
+			MathError::DivideByZero => "math.divide_by_zero",
+			MathError::NaN => "math.nan",
+			MathError::CmpError => "math.cmp_error",
+			MathError::Unimplemented { .. } => "math.unimplemented",
+			MathError::DimensionMismatch => "math.dimension_mismatch",
+			MathError::SingularMatrix => "math.singular_matrix",
+			MathError::InvalidIndex => "math.invalid_index",
+			MathError::RecursionLimit => "math.recursion_limit",
+			MathError::CircularDefinition { .. } => "math.circular_definition",
+			MathError::TooManyAnswers => "math.too_many_answers",
+			MathError::Cancelled => "math.cancelled",
+			MathError::NotAnInteger => "math.not_an_integer",
+			MathError::Overflow => "math.overflow",
+			MathError::DomainError { .. } => "math.domain_error",
+			MathError::UnitMismatch { .. } => "math.unit_mismatch",
+			MathError::Other => "math.other",
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MathError {}
+
+/// A hint listing the names that might have been meant instead of an unknown one
+fn did_you_mean(suggestions: &[String]) -> String {
+	match suggestions.split_last() {
+		None => String::new(),
+		Some((last, [])) => format!(" (did you mean '{}'?)", last),
+		Some((last, rest)) => {
+			let rest: Vec<String> = rest.iter().map(|name| format!("'{}'", name)).collect();
+			format!(" (did you mean {} or '{}'?)", rest.join(", "), last)
+		}
+	}
+}
+
+/// An error that can occur while loading definitions with `Context::load_definitions`. Lines are
+/// numbered from 1.
+#[derive(Debug)]
+pub enum DefinitionError {
+	/// A line wasn't a definition like `x = 3.2` or `f(a, b) = a*b + 1`
+	InvalidDefinition {
+		/// The line of the definition
+		line: usize,
+	},
+	/// The value or body of a definition couldn't be parsed
+	ParseError {
+		/// The line of the definition
+		line: usize,
+		/// The error
+		error: ParseError,
+	},
+}
+
+impl fmt::Display for DefinitionError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			DefinitionError::InvalidDefinition { line } => write!(f, "Line {} isn't a definition", line),
+			DefinitionError::ParseError { line, ref error } => write!(f, "Failed to parse the definition on line {}: {}", line, error),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DefinitionError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match *self {
+			DefinitionError::ParseError { ref error, .. } => Some(error),
+			DefinitionError::InvalidDefinition { .. } => None,
+		}
+	}
+}
+
+/// An error that occurred while evaluating a term, along with the part of the term it came from
+#[derive(Debug)]
+pub struct TracedError {
+	/// The error
+	pub error: MathError,
+	/// The smallest part of the term that failed with the error
+	pub term: String,
+	/// The indices of the operands (or function arguments) to follow from the whole term to get to
+	/// the part that failed
+	pub path: Vec<usize>,
+}
+
+impl fmt::Display for TracedError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} in `{}`", self.error, self.term)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TracedError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.error)
+	}
+}
+
 /// An error that occurs when evaluating a string
-#[derive(Debug, Error)]
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum EvalError {
 	/// An error occurred during parsing
-	#[error("Failed to parse the expression: {error}")]
 	ParseError {
 		/// The error
 		error: ParseError,
 	},
 	/// An error occurred during evaluation
-	#[error("Failed to evaluate the expression: {error}")]
 	MathError {
 		/// The error
 		error: MathError,
 	},
 }
 
+impl fmt::Display for EvalError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			EvalError::ParseError { ref error } => write!(f, "Failed to parse the expression: {}", error),
+			EvalError::MathError { ref error } => write!(f, "Failed to evaluate the expression: {}", error),
+		}
+	}
+}
+
+impl EvalError {
+	/// The code of the parse or math error this error contains. See `ParseError::code`.
+	pub fn code(&self) -> &'static str {
+		match *self {
+			EvalError::ParseError { ref error } => error.code(),
+			EvalError::MathError { ref error } => error.code(),
+		}
+	}
+
+	/// The parse error this error contains, if it happened while parsing
+	pub fn parse_error(&self) -> Option<&ParseError> {
+		match *self {
+			EvalError::ParseError { ref error } => Some(error),
+			EvalError::MathError { .. } => None,
+		}
+	}
+
+	/// The math error this error contains, if it happened while evaluating
+	pub fn math_error(&self) -> Option<&MathError> {
+		match *self {
+			EvalError::MathError { ref error } => Some(error),
+			EvalError::ParseError { .. } => None,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EvalError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match *self {
+			EvalError::ParseError { ref error } => Some(error),
+			EvalError::MathError { ref error } => Some(error),
+		}
+	}
+}
+
 impl From<ParseError> for EvalError {
 	fn from(t: ParseError) -> EvalError {
 		EvalError::ParseError { error: t }
@@ -90,18 +391,28 @@ impl From<MathError> for EvalError {
 }
 
 /// Expected a token but was not met
-#[derive(Debug, Error)]
+#[derive(Debug)]
 pub enum Expected {
 	/// Expected an operator
-	#[error("Expected another operator")]
 	Operator,
 	/// Expected an expression
-	#[error("Expected another expression")]
 	Expression,
 	/// Expected a parenthesis
-	#[error("Expected a parenthesis")]
 	Paren,
 	/// Expected a function
-	#[error("Expected a function")]
 	Function,
 }
+
+impl fmt::Display for Expected {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Expected::Operator => write!(f, "Expected another operator"),
+			Expected::Expression => write!(f, "Expected another expression"),
+			Expected::Paren => write!(f, "Expected a parenthesis"),
+			Expected::Function => write!(f, "Expected a function"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Expected {}
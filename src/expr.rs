@@ -1,4 +1,6 @@
-use std::fmt;
+use core::fmt;
+use core::str::FromStr;
+use core::convert::TryFrom;
 
 
 
@@ -9,6 +11,11 @@ use crate::context::*;
 use crate::num::*;
 
 use crate::term::*;
+use crate::answer::Answer;
+use crate::diagnostic::{self, Diagnostic, Warning};
+use crate::format::AnswerFormat;
+use crate::future::EvalFuture;
+use crate::prelude::*;
 
 /// The main Expression struct. Contains the string that was originally requested to be parsed, the
 /// context the Expression was parsed with, and the Term the raw form was parsed as. For just the
@@ -42,6 +49,57 @@ impl<N: Num + 'static> Expression<N> {
 		})
 	}
 
+	/// Parse a string into an expression with the given context, also returning warnings about the
+	/// decisions made while parsing it. See `Term::parse_with_warnings`.
+	pub fn parse_with_warnings(raw: &str, ctx: Context<N>) -> Result<(Self, Vec<Warning>), ParseError> {
+		let raw = raw.trim();
+		let (term, warnings) = Term::parse_with_warnings(raw, &ctx)?;
+
+		Ok((
+			Self {
+				string: raw.to_string(),
+				ctx,
+				term,
+			},
+			warnings,
+		))
+	}
+
+	/// Find all the problems with a string that would stop it from being parsed or evaluated with the
+	/// given context, along with where they are. Unlike parsing, this doesn't stop at the first
+	/// problem, so it can be used to validate input as it's being typed. An empty list means the
+	/// string is fine. Whitespace-only strings have no problems.
+	pub fn check(raw: &str, ctx: &Context<N>) -> Vec<Diagnostic> {
+		diagnostic::check(raw, ctx)
+	}
+
+	/// Find the variables and functions the expression uses that aren't defined in the given context,
+	/// which can be another context than the one it was parsed with. See
+	/// `Term::missing_definitions`.
+	///
+	/// ```rust
+	/// # use mexprp::{Expression, Context};
+	/// let expr: Expression<f64> = Expression::parse("rate * hours + bonus").unwrap();
+	/// let mut ctx: Context<f64> = Context::new();
+	/// ctx.set_var("rate", 20.0);
+	/// ctx.set_var("hours", 38.0);
+	/// assert_eq!(expr.missing_definitions(&ctx).vars, vec!["bonus"]);
+	/// ctx.set_var("bonus", 50.0);
+	/// assert!(expr.missing_definitions(&ctx).is_empty());
+	/// ```
+	pub fn missing_definitions(&self, ctx: &Context<N>) -> Missing {
+		self.term.missing_definitions(ctx)
+	}
+
+	/// Copy this expression, including all the operations of its term. See `Term::deep_clone`.
+	pub fn deep_clone(&self) -> Self {
+		Self {
+			string: self.string.clone(),
+			ctx: self.ctx.clone(),
+			term: self.term.deep_clone(),
+		}
+	}
+
 	/// Evaluate the expression
 	pub fn eval(&self) -> Calculation<N> {
 		self.eval_ctx(&self.ctx)
@@ -51,6 +109,52 @@ impl<N: Num + 'static> Expression<N> {
 	pub fn eval_ctx(&self, ctx: &Context<N>) -> Calculation<N> {
 		self.term.eval_ctx(ctx)
 	}
+
+	/// Evaluate the expression asynchronously, yielding to the executor after every `yield_every`
+	/// operations. See `Term::eval_async`.
+	pub fn eval_async(&self, yield_every: usize) -> EvalFuture<'_, N> {
+		self.term.eval_async(&self.ctx, yield_every)
+	}
+
+	/// Evaluate the expression with another precision for arbitrary precision floating point numbers.
+	/// See `Term::eval_ctx_with_precision`.
+	pub fn eval_with_precision(&self, precision: u32) -> Calculation<N> {
+		self.term.eval_ctx_with_precision(&self.ctx, precision)
+	}
+
+	/// Evaluate the expression, and if it fails, find the part of it that failed. See
+	/// `Term::eval_traced`.
+	pub fn eval_traced(&self) -> Result<Answer<N>, TracedError> {
+		self.term.eval_traced(&self.ctx)
+	}
+
+	/// Evaluate the expression and format the answer. If the expression is a call to a function that
+	/// shows its answer in another radix (like `hex(x)` or `base(x, n)`), that radix is used instead of
+	/// the one in the format.
+	pub fn eval_format(&self, fmt: &AnswerFormat) -> Result<String, MathError> {
+		let answer = self.eval()?;
+		match self.term.display_radix(&self.ctx) {
+			Some(radix) => Ok(answer.format(&AnswerFormat { radix, ..fmt.clone() })),
+			None => Ok(answer.format(fmt)),
+		}
+	}
+}
+
+/// Parses with the default context, like `Expression::parse`
+impl<N: Num + 'static> FromStr for Expression<N> {
+	type Err = ParseError;
+
+	fn from_str(raw: &str) -> Result<Self, ParseError> {
+		Self::parse(raw)
+	}
+}
+
+impl<'a, N: Num + 'static> TryFrom<&'a str> for Expression<N> {
+	type Error = ParseError;
+
+	fn try_from(raw: &'a str) -> Result<Self, ParseError> {
+		Self::parse(raw)
+	}
 }
 
 impl<N: Num> fmt::Display for Expression<N> {
@@ -0,0 +1,198 @@
+//! A C API for embedding MEXPRP in other languages. Only `f64` numbers are supported. Enable it with
+//! the `"ffi"` feature, and build a `cdylib` or `staticlib` (eg with
+//! `cargo rustc --release --features ffi --crate-type cdylib`). The declarations are in
+//! `include/mexprp.h`.
+//!
+//! Functions that can fail return one of the `MEXPRP_*` status codes, and a description of the last
+//! error on the current thread can be retrieved with `mexprp_last_error`. A panic inside MEXPRP is
+//! caught and reported as `MEXPRP_PANIC` instead of unwinding into the caller.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::context::Context;
+use crate::errors::{MathError, ParseError};
+use crate::term::Term;
+
+/// The operation succeeded
+pub const MEXPRP_OK: c_int = 0;
+/// A pointer was null or a string wasn't valid UTF-8
+pub const MEXPRP_INVALID_ARGUMENT: c_int = 1;
+/// The expression couldn't be parsed
+pub const MEXPRP_PARSE_ERROR: c_int = 2;
+/// The expression couldn't be evaluated
+pub const MEXPRP_MATH_ERROR: c_int = 3;
+/// The output buffer was too small to hold all the answers
+pub const MEXPRP_BUFFER_TOO_SMALL: c_int = 4;
+/// MEXPRP panicked, which is a bug
+pub const MEXPRP_PANIC: c_int = 5;
+
+thread_local! {
+	static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Remember an error message for `mexprp_last_error` and return its code
+fn set_error(code: c_int, message: String) -> c_int {
+	let message = CString::new(message).unwrap_or_default();
+	LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+	code
+}
+
+fn parse_error(e: ParseError) -> c_int {
+	set_error(MEXPRP_PARSE_ERROR, e.to_string())
+}
+
+fn math_error(e: MathError) -> c_int {
+	set_error(MEXPRP_MATH_ERROR, e.to_string())
+}
+
+/// Run the body of a function of this API, returning `fallback` if it panics instead of unwinding
+/// into the caller (which would abort it)
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+	panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| {
+		set_error(MEXPRP_PANIC, String::from("MEXPRP panicked"));
+		fallback
+	})
+}
+
+/// Convert a C string to a `&str`
+unsafe fn to_str<'a>(raw: *const c_char) -> Result<&'a str, c_int> {
+	if raw.is_null() {
+		return Err(set_error(MEXPRP_INVALID_ARGUMENT, String::from("Got a null pointer")));
+	}
+	CStr::from_ptr(raw)
+		.to_str()
+		.map_err(|_| set_error(MEXPRP_INVALID_ARGUMENT, String::from("Got a string that isn't valid UTF-8")))
+}
+
+/// Get the message of the last error that happened on this thread, or null if there wasn't one. The
+/// string is valid until the next call to a function of this API on the same thread.
+#[no_mangle]
+pub extern "C" fn mexprp_last_error() -> *const c_char {
+	LAST_ERROR.with(|last| match *last.borrow() {
+		Some(ref message) => message.as_ptr(),
+		None => ptr::null(),
+	})
+}
+
+/// Create a new context with the default builtin variables and functions. It must be freed with
+/// `mexprp_context_free`.
+#[no_mangle]
+pub extern "C" fn mexprp_context_new() -> *mut Context<f64> {
+	guard(ptr::null_mut(), || Box::into_raw(Box::new(Context::new())))
+}
+
+/// Free a context
+///
+/// # Safety
+/// `ctx` must be null or a pointer returned by `mexprp_context_new` that hasn't been freed yet
+#[no_mangle]
+pub unsafe extern "C" fn mexprp_context_free(ctx: *mut Context<f64>) {
+	guard((), || {
+		if !ctx.is_null() {
+			drop(Box::from_raw(ctx));
+		}
+	})
+}
+
+/// Set a variable in a context to a number
+///
+/// # Safety
+/// `ctx` must be a valid context and `name` must be a valid null-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn mexprp_set_var(ctx: *mut Context<f64>, name: *const c_char, value: f64) -> c_int {
+	guard(MEXPRP_PANIC, || {
+		let name = match to_str(name) {
+			Ok(name) => name,
+			Err(code) => return code,
+		};
+		match ctx.as_mut() {
+			Some(ctx) => {
+				ctx.set_var(name, value);
+				MEXPRP_OK
+			}
+			None => set_error(MEXPRP_INVALID_ARGUMENT, String::from("Got a null context")),
+		}
+	})
+}
+
+/// Parse an expression with a context (or the default context if `ctx` is null). Returns null if
+/// it failed, and writes the status code to `status` if it isn't null. The term must be freed with
+/// `mexprp_term_free`.
+///
+/// # Safety
+/// `raw` must be a valid null-terminated string, `ctx` must be null or a valid context, and `status`
+/// must be null or valid to write to
+#[no_mangle]
+pub unsafe extern "C" fn mexprp_parse(raw: *const c_char, ctx: *const Context<f64>, status: *mut c_int) -> *mut Term<f64> {
+	let (term, code) = guard((ptr::null_mut(), MEXPRP_PANIC), || {
+		let result = to_str(raw).and_then(|raw| {
+			let parsed = match ctx.as_ref() {
+				Some(ctx) => Term::parse_ctx(raw, ctx),
+				None => Term::parse(raw),
+			};
+			parsed.map_err(parse_error)
+		});
+		match result {
+			Ok(term) => (Box::into_raw(Box::new(term)), MEXPRP_OK),
+			Err(code) => (ptr::null_mut(), code),
+		}
+	});
+	if !status.is_null() {
+		*status = code;
+	}
+	term
+}
+
+/// Free a term
+///
+/// # Safety
+/// `term` must be null or a pointer returned by `mexprp_parse` that hasn't been freed yet
+#[no_mangle]
+pub unsafe extern "C" fn mexprp_term_free(term: *mut Term<f64>) {
+	guard((), || {
+		if !term.is_null() {
+			drop(Box::from_raw(term));
+		}
+	})
+}
+
+/// Evaluate a term with a context (or the default context if `ctx` is null), writing its answers to
+/// `out`, which has room for `capacity` numbers. The amount of answers is written to `len`, even if
+/// they didn't all fit. Vectors are flattened.
+///
+/// # Safety
+/// `term` must be a valid term, `ctx` must be null or a valid context, `out` must be valid to write
+/// `capacity` numbers to, and `len` must be null or valid to write to
+#[no_mangle]
+pub unsafe extern "C" fn mexprp_eval_f64(term: *const Term<f64>, ctx: *const Context<f64>, out: *mut f64, capacity: usize, len: *mut usize) -> c_int {
+	guard(MEXPRP_PANIC, || {
+		let term = match term.as_ref() {
+			Some(term) => term,
+			None => return set_error(MEXPRP_INVALID_ARGUMENT, String::from("Got a null term")),
+		};
+		let answer = match ctx.as_ref() {
+			Some(ctx) => term.eval_ctx(ctx),
+			None => term.eval(),
+		};
+		let answers = match answer {
+			Ok(answer) => answer.to_vec(),
+			Err(e) => return math_error(e),
+		};
+
+		if !len.is_null() {
+			*len = answers.len();
+		}
+		if answers.len() > capacity {
+			return set_error(MEXPRP_BUFFER_TOO_SMALL, format!("There are {} answers", answers.len()));
+		}
+		if !answers.is_empty() && out.is_null() {
+			return set_error(MEXPRP_INVALID_ARGUMENT, String::from("Got a null output buffer"));
+		}
+		ptr::copy_nonoverlapping(answers.as_ptr(), out, answers.len());
+		MEXPRP_OK
+	})
+}
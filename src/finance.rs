@@ -0,0 +1,343 @@
+use alloc::sync::Arc;
+
+use crate::answer::Answer;
+use crate::context::{Context, RoundingMode};
+use crate::errors::MathError;
+use crate::func::{Arity, Func, FunctionPack};
+use crate::num::Num;
+use crate::opers::Calculation;
+use crate::term::Term;
+use crate::prelude::*;
+
+/// Spreadsheet-style financial functions, installed without a namespace with `Context::install`.
+/// They follow the sign conventions of spreadsheets, where money paid out is negative and money
+/// received is positive. A `type` of 0 (the default) means payments are due at the end of each period,
+/// and 1 means at the beginning.
+///
+/// - `pmt(rate, nper, pv, [fv], [type])`: the payment for each period of a loan or investment
+/// - `fv(rate, nper, pmt, [pv], [type])`: the future value of a loan or investment
+/// - `pv(rate, nper, pmt, [fv], [type])`: the present value of a loan or investment
+/// - `npv(rate, value...)`: the net present value of cash flows at the end of each period
+/// - `irr(value...)`: the internal rate of return of cash flows, where the first is at the start
+/// - `round_bankers(x, [digits])`: rounds to the nearest number with `digits` digits after the
+///   decimal point, or to the even one if it's halfway between two (regardless of the `rounding` of
+///   the config)
+///
+/// The cash flows of `npv` and `irr` can be given as separate arguments or as vectors. Everything
+/// except `irr` is computed with the operations of the number type, so with `Decimal` the results are
+/// exact up to its `decimal_places`, without the rounding errors of `f64`. `irr` is found numerically
+/// with `f64` precision.
+///
+/// ```rust
+/// # use mexprp::{Answer, Context, FinancePack, Term};
+/// let mut ctx: Context<f64> = Context::new();
+/// ctx.install(&FinancePack);
+/// let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap();
+/// assert_eq!(eval("round_bankers(pmt(0.05 / 12, 360, 200000), 2)"), Answer::Single(-1073.64));
+/// assert_eq!(eval("round_bankers(2.5) + round_bankers(3.5)"), Answer::Single(6.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FinancePack;
+
+impl<N: Num + 'static> FunctionPack<N> for FinancePack {
+	fn namespace(&self) -> &str {
+		""
+	}
+
+	fn funcs(&self) -> Vec<(String, Arc<dyn Func<N>>)> {
+		vec![
+			("pmt".to_string(), Arc::new(Pmt)),
+			("fv".to_string(), Arc::new(Fv)),
+			("pv".to_string(), Arc::new(Pv)),
+			("npv".to_string(), Arc::new(Npv)),
+			("irr".to_string(), Arc::new(Irr)),
+			("round_bankers".to_string(), Arc::new(RoundBankers)),
+		]
+	}
+}
+
+/// Get the single value of an answer
+fn single<N: Num>(answer: Calculation<N>) -> Result<N, MathError> {
+	match answer? {
+		Answer::Single(n) => Ok(n),
+		_ => Err(MathError::IncorrectArguments),
+	}
+}
+
+/// Get a number that's an integer in `f64`, which is exact for every type
+fn int<N: Num>(n: f64, ctx: &Context<N>) -> Result<N, MathError> {
+	single(N::from_f64(n, ctx))
+}
+
+/// Evaluate the arguments of a function that takes single numbers, filling in the missing optional
+/// ones with zero
+fn scalar_args<N: Num + 'static>(args: &[Term<N>], min: usize, max: usize, ctx: &Context<N>) -> Result<Vec<N>, MathError> {
+	if args.len() < min || args.len() > max {
+		return Err(MathError::IncorrectArguments);
+	}
+
+	let mut values = args.iter().map(|arg| single(arg.eval_ctx(ctx))).collect::<Result<Vec<N>, MathError>>()?;
+	while values.len() < max {
+		values.push(int(0.0, ctx)?);
+	}
+	Ok(values)
+}
+
+/// Evaluate the cash flows of `npv` and `irr`, which can be single numbers or vectors of them
+fn cash_flows<N: Num + 'static>(args: &[Term<N>], ctx: &Context<N>) -> Result<Vec<N>, MathError> {
+	let mut values = Vec::new();
+	for arg in args {
+		match arg.eval_ctx(ctx)? {
+			Answer::Single(n) => values.push(n),
+			Answer::Vector(ns) => {
+				for n in ns {
+					values.push(single(Ok(n))?);
+				}
+			}
+			_ => return Err(MathError::IncorrectArguments),
+		}
+	}
+	Ok(values)
+}
+
+/// The parts of the time value of money formulas that `pmt`, `fv` and `pv` share
+struct Annuity<N: Num> {
+	/// How much the value grows over all of the periods, `(1 + rate)^nper`
+	growth: N,
+	/// How much the payments add up to over all of the periods for each unit of payment,
+	/// `(1 + rate × type) × (growth - 1) / rate`, or `nper` if the rate is zero
+	payments: N,
+}
+
+impl<N: Num> Annuity<N> {
+	/// The parts for a rate, number of periods and payment type. A zero rate is handled separately since
+	/// the formulas would divide by it.
+	fn new(rate: &N, nper: &N, due: &N, ctx: &Context<N>) -> Result<Self, MathError> {
+		let one = int(1.0, ctx)?;
+		if rate.to_f64_lossy()? == 0.0 {
+			return Ok(Annuity { growth: one, payments: nper.clone() });
+		}
+
+		let due = if due.to_f64_lossy()? == 0.0 { int(0.0, ctx)? } else { one.clone() };
+		let growth = single(single(one.add(rate, ctx))?.pow(nper, ctx))?;
+		let timing = single(one.add(&single(rate.mul(&due, ctx))?, ctx))?;
+		let payments = single(single(timing.mul(&single(growth.sub(&one, ctx))?, ctx))?.div(rate, ctx))?;
+		Ok(Annuity { growth, payments })
+	}
+}
+
+/// The payment for each period of a loan or investment
+struct Pmt;
+
+impl<N: Num + 'static> Func<N> for Pmt {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let args = scalar_args(args, 3, 5, ctx)?;
+		let (rate, nper, pv, fv, due) = (&args[0], &args[1], &args[2], &args[3], &args[4]);
+		let annuity = Annuity::new(rate, nper, due, ctx)?;
+
+		// -(pv × growth + fv) / payments
+		let total = single(single(pv.mul(&annuity.growth, ctx))?.add(fv, ctx))?;
+		single(int(0.0, ctx)?.sub(&total, ctx))?.div(&annuity.payments, ctx)
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::range(3, 5)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The payment for each period of a loan, like pmt(rate, nper, pv, [fv], [type])")
+	}
+}
+
+/// The future value of a loan or investment
+struct Fv;
+
+impl<N: Num + 'static> Func<N> for Fv {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let args = scalar_args(args, 3, 5, ctx)?;
+		let (rate, nper, pmt, pv, due) = (&args[0], &args[1], &args[2], &args[3], &args[4]);
+		let annuity = Annuity::new(rate, nper, due, ctx)?;
+
+		// -(pv × growth + pmt × payments)
+		let total = single(single(pv.mul(&annuity.growth, ctx))?.add(&single(pmt.mul(&annuity.payments, ctx))?, ctx))?;
+		int(0.0, ctx)?.sub(&total, ctx)
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::range(3, 5)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The future value of a loan or investment, like fv(rate, nper, pmt, [pv], [type])")
+	}
+}
+
+/// The present value of a loan or investment
+struct Pv;
+
+impl<N: Num + 'static> Func<N> for Pv {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let args = scalar_args(args, 3, 5, ctx)?;
+		let (rate, nper, pmt, fv, due) = (&args[0], &args[1], &args[2], &args[3], &args[4]);
+		let annuity = Annuity::new(rate, nper, due, ctx)?;
+
+		// -(fv + pmt × payments) / growth
+		let total = single(fv.add(&single(pmt.mul(&annuity.payments, ctx))?, ctx))?;
+		single(int(0.0, ctx)?.sub(&total, ctx))?.div(&annuity.growth, ctx)
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::range(3, 5)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The present value of a loan or investment, like pv(rate, nper, pmt, [fv], [type])")
+	}
+}
+
+/// The net present value of cash flows at the end of each period
+struct Npv;
+
+impl<N: Num + 'static> Func<N> for Npv {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		if args.len() < 2 {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		let rate = single(args[0].eval_ctx(ctx))?;
+		let growth = single(int(1.0, ctx)?.add(&rate, ctx))?;
+		let mut discount = growth.clone();
+		let mut total = int(0.0, ctx)?;
+		for value in cash_flows(&args[1..], ctx)? {
+			total = single(total.add(&single(value.div(&discount, ctx))?, ctx))?;
+			discount = single(discount.mul(&growth, ctx))?;
+		}
+		Ok(Answer::Single(total))
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::at_least(2)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The net present value of cash flows at the end of each period, like npv(rate, values...)")
+	}
+}
+
+/// The net present value of cash flows where the first is at the start, and its derivative by the rate
+fn npv_f64(rate: f64, values: &[f64]) -> (f64, f64) {
+	let mut npv = 0.0;
+	let mut derivative = 0.0;
+	let mut discount = 1.0;
+	for (i, value) in values.iter().enumerate() {
+		npv += value / discount;
+		derivative -= i as f64 * value / (discount * (1.0 + rate));
+		discount *= 1.0 + rate;
+	}
+	(npv, derivative)
+}
+
+/// Find the rate where the net present value of cash flows is zero, with Newton's method from a 10%
+/// guess like spreadsheets, or by bisection if that doesn't converge
+fn irr_f64(values: &[f64]) -> Option<f64> {
+	let mut rate = 0.1;
+	for _ in 0..100 {
+		let (npv, derivative) = npv_f64(rate, values);
+		let next = rate - npv / derivative;
+		if !next.is_finite() || next <= -1.0 {
+			break;
+		}
+		if (next - rate).abs() <= 1e-12 * next.abs().max(1.0) {
+			return Some(next);
+		}
+		rate = next;
+	}
+
+	// The net present value goes to the sign of the last cash flow as the rate goes to -1, and to the
+	// sign of the first one as the rate goes to infinity
+	let sign = |rate: f64| npv_f64(rate, values).0 > 0.0;
+	let mut low = -1.0 + 1e-9;
+	let mut high = 1.0;
+	while sign(high) == sign(low) {
+		high *= 2.0;
+		if high > 1e9 {
+			return None;
+		}
+	}
+	for _ in 0..200 {
+		let mid = (low + high) / 2.0;
+		if sign(mid) == sign(low) {
+			low = mid;
+		} else {
+			high = mid;
+		}
+	}
+	Some((low + high) / 2.0)
+}
+
+/// The internal rate of return of cash flows
+struct Irr;
+
+impl<N: Num + 'static> Func<N> for Irr {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let values = cash_flows(args, ctx)?
+			.iter()
+			.map(Num::to_f64_lossy)
+			.collect::<Result<Vec<f64>, MathError>>()?;
+		// There's only a rate of return if money is both paid and received
+		if !values.iter().any(|&v| v > 0.0) || !values.iter().any(|&v| v < 0.0) {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		let rate = irr_f64(&values).ok_or(MathError::NaN)?;
+		N::from_f64(rate, ctx)
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::at_least(1)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The internal rate of return of cash flows, where the first is at the start, like irr(values...)")
+	}
+}
+
+/// Rounding to an amount of digits with banker's rounding
+struct RoundBankers;
+
+impl<N: Num + 'static> Func<N> for RoundBankers {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		if args.is_empty() || args.len() > 2 {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		let mut bankers = Context::empty();
+		bankers.cfg = ctx.cfg.clone();
+		bankers.cfg.rounding = RoundingMode::HalfEven;
+
+		let a = args[0].eval_ctx(ctx)?;
+		let digits = match args.get(1) {
+			Some(arg) => single(arg.eval_ctx(ctx))?.to_f64_lossy()?,
+			None => 0.0,
+		};
+		if digits % 1.0 != 0.0 {
+			return Err(MathError::NotAnInteger);
+		}
+
+		// Round x * 10^digits to an integer, then scale it back
+		let scale = single(int(10.0, ctx)?.pow(&int(digits, ctx)?, ctx))?;
+		a.unop_ctx(ctx, |a| {
+			a.mul(&scale, ctx)?
+				.unop_ctx(ctx, |a| a.round(&bankers))?
+				.unop_ctx(ctx, |a| a.div(&scale, ctx))
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::range(1, 2)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("Round to the nearest number with this many digits, or to the even one if it's halfway between two")
+	}
+}
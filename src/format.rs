@@ -0,0 +1,190 @@
+use crate::prelude::*;
+
+/// Options for formatting numbers as strings with `Answer::format`. `rug::Complex` only supports
+/// `precision`, `radix` and `show_precision`, and ignores the others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnswerFormat {
+	/// The amount of significant digits to show, or `None` for as many as are needed to represent the
+	/// number exactly (default = None)
+	pub precision: Option<usize>,
+	/// Whether to use fixed or scientific notation (default = `Notation::Auto`)
+	pub notation: Notation,
+	/// Whether or not to remove zeros at the end of the fractional part (default = true)
+	pub trim_zeros: bool,
+	/// A character to put between every three digits of the integer part, eg `,` for `1,000,000`
+	/// (default = None)
+	pub thousands_separator: Option<char>,
+	/// The base to show numbers in, from 2 to 36. Numbers in bases other than 10 are always shown in
+	/// fixed notation, and have their fractional part truncated instead of rounded (default = 10).
+	pub radix: u32,
+	/// How to show numbers that aren't integers for the rational types (`rug::Rational` and
+	/// `ComplexRugRat`). Other types always show decimals (default = `FractionStyle::Improper`).
+	pub fraction: FractionStyle,
+	/// Whether or not to show the precision of arbitrary precision floating point numbers (currently
+	/// `rug::Complex`) in bits after them, eg `3.1416 (256 bits)`. Other types ignore it (default = false).
+	pub show_precision: bool,
+}
+
+/// The notation to format a number with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+	/// Scientific notation for very large or very small numbers, fixed notation otherwise
+	Auto,
+	/// Always fixed notation, eg `1234.5`
+	Fixed,
+	/// Always scientific notation, eg `1.2345e3`
+	Scientific,
+}
+
+/// The way to show a rational number that isn't an integer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractionStyle {
+	/// A plain fraction, eg `5/3`
+	Improper,
+	/// An integer followed by a proper fraction, eg `1 2/3`
+	Mixed,
+	/// A decimal with the repeating digits in parentheses, eg `1.(6)`. If the digits don't repeat
+	/// within `precision` digits (or 100 if there's no precision), they are cut off and followed by
+	/// `...`.
+	Decimal,
+}
+
+impl AnswerFormat {
+	/// Create a new format with the default values
+	pub fn new() -> Self {
+		AnswerFormat {
+			precision: None,
+			notation: Notation::Auto,
+			trim_zeros: true,
+			thousands_separator: None,
+			radix: 10,
+			fraction: FractionStyle::Improper,
+			show_precision: false,
+		}
+	}
+}
+
+impl Default for AnswerFormat {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Format an `f64` with the given options
+pub(crate) fn format_f64(x: f64, fmt: &AnswerFormat) -> String {
+	if x.is_nan() {
+		return String::from("NaN");
+	} else if x.is_infinite() {
+		return String::from(if x > 0.0 { "inf" } else { "-inf" });
+	}
+
+	let sign = if x < 0.0 { "-" } else { "" };
+	if fmt.radix != 10 && fmt.radix >= 2 && fmt.radix <= 36 {
+		return format!("{}{}", sign, radix_digits(x.abs(), fmt));
+	}
+
+	// Get the significant digits and the exponent from the scientific representation
+	let sci = match fmt.precision {
+		Some(p) => format!("{:.*e}", p.max(1) - 1, x.abs()),
+		None => format!("{:e}", x.abs()),
+	};
+	let (mantissa, exp) = sci.split_at(sci.find('e').unwrap_or(sci.len()));
+	let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+	let exp: i32 = exp.trim_start_matches('e').parse().unwrap_or(0);
+
+	let scientific = match fmt.notation {
+		Notation::Auto => x != 0.0 && !(-5..15).contains(&exp),
+		Notation::Fixed => false,
+		Notation::Scientific => true,
+	};
+	let formatted = if scientific {
+		let fraction = finish_fraction(&digits[1..], fmt);
+		format!("{}{}e{}", &digits[..1], point(&fraction), exp)
+	} else if exp >= 0 {
+		let exp = exp as usize;
+		let mut int = String::from(&digits[..digits.len().min(exp + 1)]);
+		while int.len() < exp + 1 {
+			int.push('0');
+		}
+		let fraction = finish_fraction(digits.get(exp + 1..).unwrap_or(""), fmt);
+		format!("{}{}", separate(&int, fmt), point(&fraction))
+	} else {
+		let mut fraction = "0".repeat((-exp - 1) as usize);
+		fraction.push_str(&digits);
+		let fraction = finish_fraction(&fraction, fmt);
+		format!("0{}", point(&fraction))
+	};
+	format!("{}{}", sign, formatted)
+}
+
+/// Remove the trailing zeros of a fractional part if the format says to
+fn finish_fraction(fraction: &str, fmt: &AnswerFormat) -> String {
+	if fmt.trim_zeros {
+		fraction.trim_end_matches('0').to_string()
+	} else {
+		fraction.to_string()
+	}
+}
+
+/// Put a decimal point in front of a fractional part if it isn't empty
+fn point(fraction: &str) -> String {
+	if fraction.is_empty() {
+		String::new()
+	} else {
+		format!(".{}", fraction)
+	}
+}
+
+/// Put thousands separators in the digits of an integer
+pub(crate) fn separate(int: &str, fmt: &AnswerFormat) -> String {
+	let sep = match fmt.thousands_separator {
+		Some(sep) => sep,
+		None => return int.to_string(),
+	};
+	let mut separated = String::new();
+	let first = match int.len() % 3 {
+		0 => 3,
+		n => n,
+	};
+	for (i, c) in int.chars().enumerate() {
+		if i >= first && (i - first) % 3 == 0 {
+			separated.push(sep);
+		}
+		separated.push(c);
+	}
+	separated
+}
+
+/// Get the digits of a positive number in a radix other than 10
+fn radix_digits(x: f64, fmt: &AnswerFormat) -> String {
+	let radix = f64::from(fmt.radix);
+	let digit = |d: f64| core::char::from_digit(d as u32, fmt.radix).unwrap_or('?');
+
+	let mut fraction = x % 1.0;
+	let mut int = x - fraction;
+	let mut int_digits = Vec::new();
+	while int >= 1.0 {
+		let d = int % radix;
+		int_digits.push(digit(d));
+		int = (int - d) / radix;
+	}
+	if int_digits.is_empty() {
+		int_digits.push('0');
+	}
+	let int: String = int_digits.into_iter().rev().collect();
+
+	// Show as many fractional digits as an f64 has bits for, unless there's a precision
+	let bits = 31 - fmt.radix.leading_zeros() as usize;
+	let max = match fmt.precision {
+		Some(p) => p.saturating_sub(int.len()),
+		None => 52 / bits,
+	};
+	let mut digits = String::new();
+	while digits.len() < max && (fraction != 0.0 || !fmt.trim_zeros) {
+		fraction *= radix;
+		let d = fraction - fraction % 1.0;
+		digits.push(digit(d));
+		fraction -= d;
+	}
+	format!("{}{}", separate(&int, fmt), point(&finish_fraction(&digits, fmt)))
+}
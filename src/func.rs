@@ -1,20 +1,218 @@
+use alloc::sync::Arc;
+
 use crate::term::Term;
 use crate::context::Context;
 use crate::opers::Calculation;
+use crate::answer::Answer;
 use crate::num::Num;
+use crate::errors::MathError;
+use crate::state::EvalState;
+use crate::prelude::*;
 
-/// Implemented by functions defined in a context
-pub trait Func<N: Num> {
+/// Implemented by functions defined in a context. Functions have to be `Send` and `Sync` so that
+/// contexts can be shared between threads.
+pub trait Func<N: Num>: Send + Sync {
 	/// Evaluate the function in this context with the given arguments. When implementing,
 	/// simply evaluate the arguments with the context and return an `Err(MathError::IncorrectArguments)`
 	/// if there are too many or too few.
 	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N>;
+
+	/// The amount of arguments the function takes. Defaults to any amount.
+	fn arity(&self) -> Arity {
+		Arity::any()
+	}
+
+	/// The name of the function, if it has one regardless of the name it's defined with in a context
+	fn name(&self) -> Option<&str> {
+		None
+	}
+
+	/// The minimum amount of arguments the function takes. Calls with less arguments fail to parse.
+	/// Defaults to the minimum of `arity`.
+	fn min_args(&self) -> usize {
+		self.arity().min
+	}
+
+	/// The maximum amount of arguments the function takes, if any. Calls with more arguments fail to
+	/// parse. Defaults to the maximum of `arity`.
+	fn max_args(&self) -> Option<usize> {
+		self.arity().max
+	}
+
+	/// A short description of what the function does and how to use it
+	fn help_text(&self) -> Option<&str> {
+		None
+	}
+
+	/// The radix the answer of a call with these arguments should be shown in, if it's not 10. Used by
+	/// `Expression::eval_format`, eg for `hex(x)`. Defaults to `None`.
+	fn display_radix(&self, _args: &[Term<N>], _ctx: &Context<N>) -> Option<u32> {
+		None
+	}
+
+	/// The parameters and body of the function, if it's defined by an expression like the functions
+	/// loaded with `Context::load_definitions`. Functions with a body are written out by
+	/// `Context::dump_definitions`. Defaults to `None`.
+	fn body(&self) -> Option<(&[String], &Term<N>)> {
+		None
+	}
+}
+
+/// A function defined by an expression of its parameters, like `f(a, b) = a*b + 1`
+pub(crate) struct ExprFunc<N: Num> {
+	pub params: Vec<String>,
+	pub body: Term<N>,
+}
+
+impl<N: Num + 'static> Func<N> for ExprFunc<N> {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		if args.len() != self.params.len() {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		// The parameters shadow any variables with the same names while the body is evaluated
+		let params = self.params.iter()
+			.zip(args)
+			.map(|(param, arg)| Ok((param.clone(), arg.eval_ctx(ctx)?)))
+			.collect::<Result<Vec<(String, Answer<N>)>, MathError>>()?;
+		EvalState::with_params(ctx, params, |state| self.body.eval_state(ctx, state))
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(self.params.len())
+	}
+
+	fn body(&self) -> Option<(&[String], &Term<N>)> {
+		Some((&self.params, &self.body))
+	}
+}
+
+/// A bundle of functions that can be installed into a context all at once with
+/// `Context::install`, so that crates can provide functions for others to use. The functions are
+/// installed under a namespace, so that a function `mean` in the namespace `stats` is called with
+/// `stats.mean(...)`.
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use mexprp::{Context, Func, FunctionPack, Term, Calculation, Answer};
+/// struct Geometry;
+///
+/// impl FunctionPack<f64> for Geometry {
+///     fn namespace(&self) -> &str {
+///         "geom"
+///     }
+///
+///     fn funcs(&self) -> Vec<(String, Arc<dyn Func<f64>>)> {
+///         let area_circle = |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+///             args[0].eval_ctx(ctx)?.unop(|r| Ok(Answer::Single(std::f64::consts::PI * r * r)))
+///         };
+///         vec![("area_circle".to_string(), Arc::new(area_circle))]
+///     }
+/// }
+///
+/// let mut ctx = Context::new();
+/// ctx.install(&Geometry);
+/// let area = Term::parse_ctx("geom.area_circle(2)", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+/// assert_eq!(area, Answer::Single(std::f64::consts::PI * 4.0));
+/// ```
+pub trait FunctionPack<N: Num> {
+	/// The namespace the functions are installed under. An empty namespace installs them without a
+	/// prefix.
+	fn namespace(&self) -> &str;
+
+	/// The names of the functions, without the namespace, along with their definitions
+	fn funcs(&self) -> Vec<(String, Arc<dyn Func<N>>)>;
+}
+
+/// A table of functions known at compile time. A context set up with `Context::with_table` looks
+/// functions up in the table with a binary search, so the functions don't have to be allocated or
+/// put in a map, eg on embedded targets.
+///
+/// ```rust
+/// # use mexprp::{Context, Func, FuncTable, Term, Calculation, Answer};
+/// struct Double;
+///
+/// impl Func<f64> for Double {
+///     fn eval(&self, args: &[Term<f64>], ctx: &Context<f64>) -> Calculation<f64> {
+///         args[0].eval_ctx(ctx)?.unop(|x| Ok(Answer::Single(x * 2.0)))
+///     }
+/// }
+///
+/// struct Table;
+///
+/// impl FuncTable<f64> for Table {
+///     const FUNCS: &'static [(&'static str, &'static dyn Func<f64>)] = &[("double", &Double)];
+/// }
+///
+/// let ctx: Context<f64> = Context::with_table::<Table>();
+/// let answer = Term::parse_ctx("double(4)", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+/// assert_eq!(answer, Answer::Single(8.0));
+/// ```
+pub trait FuncTable<N: Num + 'static> {
+	/// The names of the functions along with their definitions. Must be sorted by name.
+	const FUNCS: &'static [(&'static str, &'static dyn Func<N>)];
+}
+
+/// A function that gets the functions of a `FuncTable` whose names start with a prefix, so that the
+/// table can be stored in a context as a function pointer
+pub(crate) type TableLookup<N> = for<'a> fn(&'a str) -> &'a [(&'a str, &'a dyn Func<N>)];
+
+/// Get the functions of the table `T` whose names start with a prefix. They're next to each other
+/// since the table is sorted.
+pub(crate) fn table_prefixed<N: Num + 'static, T: FuncTable<N>>(prefix: &str) -> &[(&str, &dyn Func<N>)] {
+	let funcs = T::FUNCS;
+	let start = match funcs.binary_search_by(|&(name, _)| name.cmp(prefix)) {
+		Ok(i) | Err(i) => i,
+	};
+	let len = funcs[start..].iter()
+		.take_while(|&&(name, _)| name.starts_with(prefix))
+		.count();
+	&funcs[start..start + len]
+}
+
+/// The amount of arguments a function takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Arity {
+	/// The minimum amount of arguments
+	pub min: usize,
+	/// The maximum amount of arguments, or `None` if there is no maximum
+	pub max: Option<usize>,
+}
+
+impl Arity {
+	/// Exactly `n` arguments
+	pub fn exact(n: usize) -> Self {
+		Arity { min: n, max: Some(n) }
+	}
+
+	/// At least `n` arguments
+	pub fn at_least(n: usize) -> Self {
+		Arity { min: n, max: None }
+	}
+
+	/// Between `min` and `max` arguments (inclusive)
+	pub fn range(min: usize, max: usize) -> Self {
+		Arity { min, max: Some(max) }
+	}
+
+	/// Any amount of arguments
+	pub fn any() -> Self {
+		Arity { min: 0, max: None }
+	}
+
+	/// Check whether a function with this arity accepts the given amount of arguments
+	pub fn accepts(&self, n: usize) -> bool {
+		match self.max {
+			Some(max) => n >= self.min && n <= max,
+			None => n >= self.min,
+		}
+	}
 }
 
 /// Blanket impl for closures
 impl<T, N: Num> Func<N> for T
 where
-	T: Fn(&[Term<N>], &Context<N>) -> Calculation<N>,
+	T: Fn(&[Term<N>], &Context<N>) -> Calculation<N> + Send + Sync,
 {
 	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
 		self(args, ctx)
@@ -0,0 +1,79 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{self, Poll};
+
+use crate::term::{Evaluation, Term};
+use crate::context::Context;
+use crate::num::Num;
+use crate::opers::Calculation;
+use crate::state::EvalState;
+use crate::prelude::*;
+
+/// A future that evaluates a term a few operations at a time, returned by `Term::eval_async` and
+/// `Expression::eval_async`. Every time it's polled, it evaluates at most `yield_every` of the
+/// operations and terms of the term, and then yields to the executor if it isn't done, so that long
+/// evaluations don't block other tasks. It works with any executor.
+///
+/// Functions are evaluated all at once, without yielding in the middle of them. Contexts with an
+/// observer or with `memoize` enabled are evaluated entirely the first time the future is polled.
+/// The limits of the config and cancellation with a `CancelToken` work like they do for `eval_ctx`.
+pub struct EvalFuture<'a, N: Num> {
+	term: &'a Term<N>,
+	ctx: &'a Context<N>,
+	yield_every: usize,
+	evaluation: Option<Evaluation<'a, N>>,
+	/// The steps taken so far, since every poll continues the evaluation with a new state
+	steps: usize,
+}
+
+impl<'a, N: Num + 'static> EvalFuture<'a, N> {
+	pub(crate) fn new(term: &'a Term<N>, ctx: &'a Context<N>, yield_every: usize) -> Self {
+		EvalFuture {
+			term,
+			ctx,
+			yield_every: yield_every.max(1),
+			evaluation: None,
+			steps: 0,
+		}
+	}
+}
+
+// Nothing in the future is pinned, it only keeps the state of the evaluation
+impl<'a, N: Num> Unpin for EvalFuture<'a, N> {}
+
+impl<'a, N: Num + 'static> Future for EvalFuture<'a, N> {
+	type Output = Calculation<N>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Calculation<N>> {
+		let this = self.get_mut();
+		let ctx = this.ctx;
+		if ctx.observer.is_some() || ctx.cfg.memoize {
+			return Poll::Ready(this.term.eval_ctx(ctx));
+		}
+
+		let term = this.term;
+		let evaluation = &mut this.evaluation;
+		let yield_every = this.yield_every;
+		let mut run = |state: &EvalState<N>| evaluation.get_or_insert_with(|| Evaluation::new(term, state)).run(ctx, state, yield_every);
+		let res = match EvalState::current(ctx) {
+			// The future is polled in the middle of another evaluation with the context, which the steps
+			// are counted for
+			Some(state) => run(&state),
+			None => {
+				let steps = &mut this.steps;
+				EvalState::run(ctx, EvalState::new(0, *steps, Vec::new()), |state| {
+					let res = run(state);
+					*steps = state.steps();
+					res
+				})
+			}
+		};
+		match res {
+			Some(res) => Poll::Ready(res),
+			None => {
+				cx.waker().wake_by_ref();
+				Poll::Pending
+			}
+		}
+	}
+}
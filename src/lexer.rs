@@ -0,0 +1,144 @@
+//! The tokenizer used to parse expressions, for use in things like syntax highlighting and completion.
+//!
+//! ```
+//! use mexprp::lexer::{tokenize, TokenKind};
+//!
+//! let tokens = tokenize("sin(x) + 2");
+//! assert_eq!(tokens[0].kind, TokenKind::Name);
+//! assert_eq!(tokens[0].span, 0..3);
+//! assert_eq!(tokens[4].text, "+");
+//! assert_eq!(tokens[4].kind, TokenKind::InfixOp);
+//! ```
+
+use core::ops::Range;
+use alloc::borrow::Cow;
+
+use crate::op::{Op, Paren};
+use crate::parse::{next_token, normalize, normalize_char, Token};
+use crate::context::Config;
+use crate::prelude::*;
+
+/// The kind of a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+	/// A number literal, eg `2.5`
+	Number,
+	/// The name of a variable or function
+	Name,
+	/// An operator between two operands, eg the `*` in `2 * 3`
+	InfixOp,
+	/// An operator before an operand, eg the `-` in `-x`
+	PrefixOp,
+	/// An operator after an operand, eg the `!` in `3!`
+	PostfixOp,
+	/// `(`
+	OpenParen,
+	/// `)`
+	CloseParen,
+	/// `[`
+	OpenBracket,
+	/// `]`
+	CloseBracket,
+	/// `{`
+	OpenBrace,
+	/// `}`
+	CloseBrace,
+	/// The argument separator, `,` by default
+	Comma,
+	/// A character that couldn't be parsed as any token at its position
+	Unknown,
+}
+
+/// A token and its location in the string it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+	/// The kind of token
+	pub kind: TokenKind,
+	/// The text of the token, after normalizing it if the config does (see `Config::normalize_input`)
+	pub text: String,
+	/// The byte range of the token in the original string
+	pub span: Range<usize>,
+}
+
+/// Split a string into tokens the same way the parser does. Unlike parsing, this never fails: any
+/// character that can't be a token at its position becomes a token of kind `TokenKind::Unknown`, and
+/// the rest of the string is still tokenized. Whitespace isn't included in the output.
+pub fn tokenize(raw: &str) -> Vec<SpannedToken> {
+	tokenize_cfg(raw, &Config::new())
+}
+
+/// Split a string into tokens like `tokenize`, with the separators of a config
+pub fn tokenize_cfg(raw: &str, cfg: &Config) -> Vec<SpannedToken> {
+	if !cfg.normalize_input {
+		return tokenize_normalized(raw, cfg);
+	}
+	let normalized = match normalize(raw) {
+		Cow::Borrowed(raw) => return tokenize_normalized(raw, cfg),
+		Cow::Owned(normalized) => normalized,
+	};
+
+	// The byte ranges in the original string of the characters at each byte of the normalized one
+	let mut ranges = Vec::with_capacity(normalized.len());
+	for (i, c) in raw.char_indices() {
+		if let Some(n) = normalize_char(c) {
+			for _ in 0..n.len_utf8() {
+				ranges.push(i..i + c.len_utf8());
+			}
+		}
+	}
+	let mut tokens = tokenize_normalized(&normalized, cfg);
+	for token in &mut tokens {
+		token.span = ranges[token.span.start].start..ranges[token.span.end - 1].end;
+	}
+	tokens
+}
+
+/// Split a string that's already been normalized into tokens
+fn tokenize_normalized(raw: &str, cfg: &Config) -> Vec<SpannedToken> {
+	let mut tokens = Vec::new();
+	let mut last: Option<Token> = None;
+	let mut rest = raw;
+	loop {
+		let trimmed = rest.trim_start();
+		let first = match trimmed.chars().next() {
+			Some(c) => c,
+			None => break,
+		};
+		let start = raw.len() - trimmed.len();
+
+		let (kind, end) = match next_token(trimmed, last.as_ref(), cfg) {
+			Ok((token, new_rest)) => {
+				let kind = kind_of(&token);
+				last = Some(token);
+				(kind, raw.len() - new_rest.len())
+			}
+			Err(_) => (TokenKind::Unknown, start + first.len_utf8()),
+		};
+
+		tokens.push(SpannedToken {
+			kind,
+			text: raw[start..end].to_string(),
+			span: start..end,
+		});
+		rest = &raw[end..];
+	}
+	tokens
+}
+
+/// Get the public kind of a token
+fn kind_of(token: &Token) -> TokenKind {
+	match *token {
+		Token::Paren(Paren::Open) => TokenKind::OpenParen,
+		Token::Paren(Paren::Close) => TokenKind::CloseParen,
+		Token::Bracket(Paren::Open) => TokenKind::OpenBracket,
+		Token::Bracket(Paren::Close) => TokenKind::CloseBracket,
+		Token::Brace(Paren::Open) => TokenKind::OpenBrace,
+		Token::Brace(Paren::Close) => TokenKind::CloseBrace,
+		Token::Op(Op::In(_)) => TokenKind::InfixOp,
+		Token::Op(Op::Pre(_)) => TokenKind::PrefixOp,
+		Token::Op(Op::Post(_)) => TokenKind::PostfixOp,
+		Token::Name(_) => TokenKind::Name,
+		Token::Num(_) | Token::Frac(_) => TokenKind::Number,
+		Token::Comma => TokenKind::Comma,
+	}
+}
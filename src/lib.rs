@@ -61,6 +61,12 @@
 //! the arguments. More info about that and helper methods for it can be found in the documentation
 //! for the `Answer` enum.
 //!
+//! An answer can also be a vector of answers, written in an expression with square brackets
+//! (`[1, 2, 3]`). Operations on vectors are performed element-wise, and they can be indexed with
+//! `v[1]` (starting at 1). A vector of vectors is a matrix (`[[1, 2], [3, 4]]`), and multiplying two
+//! matrices or a matrix and a vector performs matrix multiplication. Square brackets around a single
+//! expression and curly braces group like parentheses, for formulas like `2{3 + [4 - 1]}`.
+//!
 //! ### Multiple Precisions
 //! MEXPRP supports evaluating expressions with different precisions with the [`Num`](num::Num) trait.
 //! Currently supported number types are
@@ -69,6 +75,7 @@
 //! - [`ComplexRugRat`](num::ComplexRugRat) (using the rug crate)
 //! - [`Rational`](::rug::Rational) (from the rug crate)
 //! - [`Complex`](::rug::Complex) (from the rug crate)
+//! - [`Decimal`](num::Decimal) (exact base 10 numbers, with the `"decimal"` feature)
 //!
 //! However, the implementation for certain types is incomplete. Only the `f64` type fully implements
 //! all of the operations. `Complex` is the next best, but even it is still missing some. The others
@@ -78,7 +85,8 @@
 //! to implement for arbitrary precision numbers.
 //!
 //! The `Complex` number also supports selecting the precision to use with a `Context`. Set the `precision`
-//! field of the `cfg` field of a Context to set the precision to be used by `Complex` numbers.
+//! field of the `cfg` field of a Context to set the precision to be used by `Complex` numbers, or use
+//! `Term::eval_ctx_with_precision` to override it for a single evaluation.
 //!
 //! For more info on the types, see the documentation for the [`num`](num) module.
 //!
@@ -102,6 +110,16 @@
 //!
 //! In case you don't want a dependency on `rug`, compile MEXPRP without the `"rug"` feature.
 //!
+//! ### `no_std`
+//! MEXPRP can be used without the standard library (but with `alloc`) by disabling the default
+//! `"std"` feature and enabling the `"libm"` feature, which provides the floating point functions
+//! `f64` needs. The `"rug"` feature requires `"std"`. Without `"std"`, the variables and functions of a
+//! `Context` are stored in a `BTreeMap` instead of a `HashMap`.
+//!
+//! ```toml
+//! mexprp = { version = "0.3", default-features = false, features = ["libm"] }
+//! ```
+//!
 //! ### Using Contexts
 //! You can evaluate expressions with custom variable and function definition's by defining a context.
 //! When defining custom functions, it's important to remember to parse the expression with the custom
@@ -113,17 +131,48 @@
 //! These configuration values include enabling/disabling implicit multiplication, the precision to
 //! use for types that support selecting precisions (just `Complex` for now), and the behaviour of
 //! the `sqrt()` function. More info can be found in the API docs (check the [`context`](context) module).
+//!
+//! Terms, answers, contexts and expressions are `Send` and `Sync`, so they can be parsed once, stored
+//! in a global like a `OnceLock`, and evaluated from several threads at the same time. Because of
+//! this, custom functions and observers have to be `Send` and `Sync` too. Without `"std"` threads
+//! can't be told apart, so contexts and expressions are only `Send`.
+//!
+//! ### Untrusted input
+//! Parsing and evaluating aren't meant to panic with the builtin number types and functions, no
+//! matter the input: malformed input results in a `ParseError` or `MathError` instead. The few
+//! remaining `unwrap`s rely on invariants of the parser and evaluator, which are noted where they
+//! are, so a panic would be a bug. To keep pathological input from using too much time or memory,
+//! the config also has limits on the size and depth of expressions (`max_nodes` and `max_depth`),
+//! the number of answers (`max_answers`), and the amount of work an evaluation can do
+//! (`max_steps`). There is a fuzzing target for `cargo fuzz` in the `fuzz` directory.
 
 #![deny(missing_docs)]
 #![cfg_attr(feature = "cargo-clippy", allow(collapsible_if))]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("Either the \"std\" or the \"libm\" feature is needed for floating point math");
 
+extern crate alloc;
 #[cfg(feature = "rug")]
 extern crate rug;
 
+/// The parts of the standard library prelude that come from `alloc`, so they can be used without `std`
+#[allow(unused_imports)]
+mod prelude {
+	pub(crate) use alloc::borrow::ToOwned;
+	pub(crate) use alloc::boxed::Box;
+	pub(crate) use alloc::string::{String, ToString};
+	pub(crate) use alloc::vec::Vec;
+	pub(crate) use alloc::{format, vec};
+}
+
 /// Contains Function trait
 mod func;
 /// Contains methods for parsing equations into token representations
 mod parse;
+/// The public tokenizer
+pub mod lexer;
 /// Contains definitions for Operations
 mod op;
 /// Contains expressions
@@ -140,17 +189,63 @@ mod context;
 pub mod num;
 /// Answer enum
 mod answer;
+/// Matrix operations
+mod matrix;
+/// Formatting options for answers
+mod format;
+/// Terms bound to a context
+mod bound;
+/// Validation of partially typed expressions
+mod diagnostic;
+/// Sessions with a history of answers
+mod session;
+/// Packs of constants
+mod constants;
+/// Financial functions
+mod finance;
+/// Probability distributions
+mod stats;
+/// Evaluating terms asynchronously
+mod future;
+/// The state of evaluations in progress
+mod state;
+/// Functions registered for every default context
+#[cfg(feature = "std")]
+mod registry;
+/// C API
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// Plots of expressions
+#[cfg(feature = "plot")]
+pub mod plot;
+/// Time spans and dates
+#[cfg(feature = "time")]
+pub mod time;
+/// Bindings for JavaScript
+#[cfg(feature = "wasm")]
+pub mod wasm;
+/// Random expressions and a reference evaluator for testing
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 #[cfg(test)]
 mod tests;
 
-pub use crate::func::Func;
+pub use crate::func::{Arity, Func, FuncTable, FunctionPack};
 pub use crate::expr::Expression;
-pub use crate::term::Term;
-pub use crate::context::{Config, Context};
-pub use crate::errors::{EvalError, MathError, ParseError};
+pub use crate::term::{Missing, Step, Term};
+pub use crate::bound::BoundTerm;
+pub use crate::context::{Builtins, CancelToken, Completion, CompletionKind, Config, Context, ContextBuilder, DomainPolicy, EvalObserver, NanPolicy, RoundingMode, ZeroDivision};
+pub use crate::errors::{DefinitionError, EvalError, MathError, ParseError, TracedError};
 pub use crate::num::Num;
 pub use crate::opers::Calculation;
-pub use crate::answer::Answer;
+pub use crate::answer::{Answer, Label};
+pub use crate::format::{AnswerFormat, FractionStyle, Notation};
+pub use crate::diagnostic::{Diagnostic, DiagnosticKind, Warning};
+pub use crate::session::{History, Session};
+pub use crate::constants::ConstantsPack;
+pub use crate::finance::FinancePack;
+pub use crate::stats::StatsPack;
+pub use crate::future::EvalFuture;
 
 /// Parse and evaluate a string
 pub fn eval<N: Num + 'static>(expr: &str) -> Result<Answer<N>, EvalError> {
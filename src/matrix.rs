@@ -0,0 +1,181 @@
+use core::cmp::Ordering;
+
+use crate::answer::Answer;
+use crate::context::Context;
+use crate::errors::MathError;
+use crate::num::Num;
+use crate::opers::Calculation;
+use crate::prelude::*;
+
+/// A matrix stored as a list of rows
+pub(crate) type Matrix<N> = Vec<Vec<N>>;
+
+/// Get the single value of a calculation, or an error if there were multiple
+fn single<N: Num>(calc: Calculation<N>) -> Result<N, MathError> {
+	match calc? {
+		Answer::Single(n) => Ok(n),
+		_ => Err(MathError::IncorrectArguments),
+	}
+}
+
+/// Get the values of a vector answer whose elements are all single answers
+pub(crate) fn as_vector<N: Num>(answer: &Answer<N>) -> Option<Vec<N>> {
+	match *answer {
+		Answer::Vector(ref items) => items.iter()
+			.map(|item| match *item {
+				Answer::Single(ref n) => Some(n.clone()),
+				_ => None,
+			})
+			.collect(),
+		_ => None,
+	}
+}
+
+/// Get the rows of a vector answer whose elements are all vectors of single answers of the same
+/// (non-zero) length
+pub(crate) fn as_matrix<N: Num>(answer: &Answer<N>) -> Option<Matrix<N>> {
+	let rows: Matrix<N> = match *answer {
+		Answer::Vector(ref rows) => rows.iter().map(as_vector).collect::<Option<Matrix<N>>>()?,
+		_ => return None,
+	};
+	if rows.is_empty() || rows[0].is_empty() || rows.iter().any(|row| row.len() != rows[0].len()) {
+		return None;
+	}
+	Some(rows)
+}
+
+/// Convert a list of values to a vector answer
+pub(crate) fn vector_answer<N: Num>(items: Vec<N>) -> Answer<N> {
+	Answer::Vector(items.into_iter().map(Answer::Single).collect())
+}
+
+/// Convert a matrix to a vector answer of vectors
+pub(crate) fn matrix_answer<N: Num>(m: Matrix<N>) -> Answer<N> {
+	Answer::Vector(m.into_iter().map(vector_answer).collect())
+}
+
+/// Multiply two matrices
+pub(crate) fn mul<N: Num>(a: &[Vec<N>], b: &[Vec<N>], ctx: &Context<N>) -> Result<Matrix<N>, MathError> {
+	if a[0].len() != b.len() {
+		return Err(MathError::DimensionMismatch);
+	}
+
+	let columns = transpose(b);
+	let mut product = Vec::new();
+	for row in a {
+		let mut new_row = Vec::new();
+		for column in &columns {
+			let mut sum = single(N::from_f64(0.0, ctx))?;
+			for (x, y) in row.iter().zip(column.iter()) {
+				let term = single(x.mul(y, ctx))?;
+				sum = single(sum.add(&term, ctx))?;
+			}
+			new_row.push(sum);
+		}
+		product.push(new_row);
+	}
+	Ok(product)
+}
+
+/// Transpose a matrix
+pub(crate) fn transpose<N: Num>(m: &[Vec<N>]) -> Matrix<N> {
+	(0..m[0].len()).map(|j| m.iter().map(|row| row[j].clone()).collect()).collect()
+}
+
+/// Find the best row to use as a pivot for the given column, starting at the given row. Prefers the
+/// value with the largest magnitude if the type supports it, otherwise any non-zero value.
+fn pivot<N: Num>(m: &[Vec<N>], col: usize, start: usize, ctx: &Context<N>) -> Result<Option<usize>, MathError> {
+	let zero = single(N::from_f64(0.0, ctx))?;
+	let mut best: Option<(usize, N)> = None;
+	for (row, values) in m.iter().enumerate().skip(start) {
+		if values[col] == zero {
+			continue;
+		}
+		let magnitude = match values[col].abs(ctx) {
+			Ok(Answer::Single(n)) => n,
+			_ => return Ok(Some(row)),
+		};
+		let better = match best {
+			Some((_, ref b)) => magnitude.tryord(b, ctx)? == Ordering::Greater,
+			None => true,
+		};
+		if better {
+			best = Some((row, magnitude));
+		}
+	}
+	Ok(best.map(|(row, _)| row))
+}
+
+/// Compute the determinant of a square matrix with gaussian elimination
+pub(crate) fn det<N: Num>(m: &[Vec<N>], ctx: &Context<N>) -> Result<N, MathError> {
+	if m.len() != m[0].len() {
+		return Err(MathError::DimensionMismatch);
+	}
+
+	let mut m = m.to_vec();
+	let mut det = single(N::from_f64(1.0, ctx))?;
+	for col in 0..m.len() {
+		let p = match pivot(&m, col, col, ctx)? {
+			Some(p) => p,
+			None => return single(N::from_f64(0.0, ctx)),
+		};
+		if p != col {
+			m.swap(p, col);
+			det = single(det.mul(&single(N::from_f64(-1.0, ctx))?, ctx))?;
+		}
+		det = single(det.mul(&m[col][col], ctx))?;
+		let pivot_row = m[col].clone();
+		for row in m.iter_mut().skip(col + 1) {
+			let factor = single(row[col].div(&pivot_row[col], ctx))?;
+			for (value, p) in row.iter_mut().zip(pivot_row.iter()).skip(col) {
+				let x = single(factor.mul(p, ctx))?;
+				*value = single(value.sub(&x, ctx))?;
+			}
+		}
+	}
+	Ok(det)
+}
+
+/// Solve `A X = B` for `X` with gauss-jordan elimination
+pub(crate) fn solve<N: Num>(a: &[Vec<N>], b: &[Vec<N>], ctx: &Context<N>) -> Result<Matrix<N>, MathError> {
+	if a.len() != a[0].len() || a.len() != b.len() {
+		return Err(MathError::DimensionMismatch);
+	}
+
+	let n = a.len();
+	// The augmented matrix [A | B]
+	let mut m: Matrix<N> = a.iter().zip(b.iter()).map(|(a, b)| a.iter().chain(b.iter()).cloned().collect()).collect();
+	for col in 0..n {
+		let p = pivot(&m, col, col, ctx)?.ok_or(MathError::SingularMatrix)?;
+		m.swap(p, col);
+		let pivot = m[col][col].clone();
+		for value in m[col].iter_mut() {
+			*value = single(value.div(&pivot, ctx))?;
+		}
+		let pivot_row = m[col].clone();
+		for (i, row) in m.iter_mut().enumerate() {
+			if i == col {
+				continue;
+			}
+			let factor = row[col].clone();
+			for (value, p) in row.iter_mut().zip(pivot_row.iter()) {
+				let x = single(factor.mul(p, ctx))?;
+				*value = single(value.sub(&x, ctx))?;
+			}
+		}
+	}
+	Ok(m.into_iter().map(|row| row[n..].to_vec()).collect())
+}
+
+/// Compute the inverse of a square matrix
+pub(crate) fn inv<N: Num>(m: &[Vec<N>], ctx: &Context<N>) -> Result<Matrix<N>, MathError> {
+	let mut identity = Vec::new();
+	for i in 0..m.len() {
+		let mut row = Vec::new();
+		for j in 0..m.len() {
+			row.push(single(N::from_f64(if i == j { 1.0 } else { 0.0 }, ctx))?);
+		}
+		identity.push(row);
+	}
+	solve(m, &identity, ctx)
+}
@@ -0,0 +1,600 @@
+use core::cmp::Ordering;
+use core::fmt;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+
+#[cfg(feature = "rug")]
+use rug::{Complex, Rational};
+use crate::opers::Calculation;
+use crate::errors::MathError;
+use crate::answer::Answer;
+use crate::num::{Num, NumCapabilities};
+use crate::context::{Config, Context, DomainPolicy};
+use crate::format::AnswerFormat;
+#[cfg(feature = "decimal")]
+use crate::num::Decimal;
+use crate::prelude::*;
+
+/// A number whose type is chosen at runtime, so that an application can let its users pick the
+/// number type (like from a settings menu) without being generic over `Num` everywhere. Numbers
+/// parsed from expressions and constants are created as the kind of number in the `num_kind` of the
+/// config when they're parsed, and every operation is done by the type inside with the same config.
+///
+/// Operations on two numbers of different kinds (like a variable set before `num_kind` was changed)
+/// convert both numbers to the kind in the config first, by way of `f64`s.
+///
+/// With `promote_numbers` enabled in the config, numbers are promoted to more general kinds when
+/// needed instead, going from `Decimal` to `Rational` to `F64` to `Complex` (skipping the kinds that
+/// aren't enabled by features). Operations on numbers of different kinds promote the less general
+/// one, and an operation that fails for a kind of number (because it's unimplemented, outside of the
+/// domain, not an integer or too large) or gives NaN is tried again with the numbers promoted to the
+/// next kind. So with `num_kind` set to `Rational`, `1/3` stays exact until it's used with a float
+/// like `pi` or `sqrt(2)`, and `sqrt(-1)` is promoted to a complex number. Constants like `pi` are
+/// created as at least `F64`s.
+///
+/// ```rust
+/// # use mexprp::{Context, Term, Answer};
+/// # use mexprp::num::{AnyNum, NumKind};
+/// let mut ctx: Context<AnyNum> = Context::new();
+/// ctx.cfg.num_kind = NumKind::F64;
+/// let term = Term::parse_ctx("3 / 4", &ctx).unwrap();
+/// assert_eq!(term.eval_ctx(&ctx).unwrap(), Answer::Single(AnyNum::F64(0.75)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnyNum {
+	/// An `f64`
+	F64(f64),
+	/// A `rug::Complex` (with the `rug` feature)
+	#[cfg(feature = "rug")]
+	Complex(Complex),
+	/// A `rug::Rational` (with the `rug` feature)
+	#[cfg(feature = "rug")]
+	Rational(Rational),
+	/// A `Decimal` (with the `decimal` feature)
+	#[cfg(feature = "decimal")]
+	Decimal(Decimal),
+}
+
+/// The kinds of numbers an `AnyNum` can be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NumKind {
+	/// `f64`
+	#[default]
+	F64,
+	/// `rug::Complex` (with the `rug` feature)
+	#[cfg(feature = "rug")]
+	Complex,
+	/// `rug::Rational` (with the `rug` feature)
+	#[cfg(feature = "rug")]
+	Rational,
+	/// `Decimal` (with the `decimal` feature)
+	#[cfg(feature = "decimal")]
+	Decimal,
+}
+
+/// Evaluate an expression with the number inside an `AnyNum` bound to `$n`
+macro_rules! each {
+	($num:expr, $n:ident => $e:expr) => {
+		match *$num {
+			AnyNum::F64(ref $n) => $e,
+			#[cfg(feature = "rug")]
+			AnyNum::Complex(ref $n) => $e,
+			#[cfg(feature = "rug")]
+			AnyNum::Rational(ref $n) => $e,
+			#[cfg(feature = "decimal")]
+			AnyNum::Decimal(ref $n) => $e,
+		}
+	};
+}
+
+/// Evaluate an expression with the numbers inside two `AnyNum`s of the same kind bound to `$x` and
+/// `$y`. The expression must give a `Result` with a `MathError`, which is `Other` if the numbers are
+/// of different kinds (they're always converted to the same kind first).
+macro_rules! each_pair {
+	($a:expr, $b:expr, $x:ident, $y:ident => $e:expr) => {
+		match (&*$a, &*$b) {
+			(&AnyNum::F64(ref $x), &AnyNum::F64(ref $y)) => $e,
+			#[cfg(feature = "rug")]
+			(&AnyNum::Complex(ref $x), &AnyNum::Complex(ref $y)) => $e,
+			#[cfg(feature = "rug")]
+			(&AnyNum::Rational(ref $x), &AnyNum::Rational(ref $y)) => $e,
+			#[cfg(feature = "decimal")]
+			(&AnyNum::Decimal(ref $x), &AnyNum::Decimal(ref $y)) => $e,
+			#[allow(unreachable_patterns)]
+			_ => Err(MathError::Other),
+		}
+	};
+}
+
+/// Evaluate an expression with the type of a kind of number as `$t`
+macro_rules! each_kind {
+	($kind:expr, $t:ident => $e:expr) => {
+		match $kind {
+			NumKind::F64 => {
+				type $t = f64;
+				$e
+			}
+			#[cfg(feature = "rug")]
+			NumKind::Complex => {
+				type $t = Complex;
+				$e
+			}
+			#[cfg(feature = "rug")]
+			NumKind::Rational => {
+				type $t = Rational;
+				$e
+			}
+			#[cfg(feature = "decimal")]
+			NumKind::Decimal => {
+				type $t = Decimal;
+				$e
+			}
+		}
+	};
+}
+
+/// Evaluate a unary operation with the number inside
+macro_rules! unary {
+	($num:expr, $ctx:expr, $op:ident) => {
+		promoting(&[$num], $ctx, |nums| each!(nums[0], n => lift(n.$op(&inner($ctx)))))
+	};
+}
+
+/// Evaluate a binary operation with the numbers inside, converted to the same kind
+macro_rules! binary {
+	($a:expr, $b:expr, $ctx:expr, $op:ident) => {{
+		let (a, b) = $a.pair($b, $ctx)?;
+		promoting(&[&*a, &*b], $ctx, |nums| each_pair!(nums[0], nums[1], x, y => lift(x.$op(y, &inner($ctx)))))
+	}};
+}
+
+/// A type an `AnyNum` can hold
+trait Variant: Num + 'static {
+	const KIND: NumKind;
+
+	fn into_any(self) -> AnyNum;
+}
+
+impl Variant for f64 {
+	const KIND: NumKind = NumKind::F64;
+
+	fn into_any(self) -> AnyNum {
+		AnyNum::F64(self)
+	}
+}
+
+#[cfg(feature = "rug")]
+impl Variant for Complex {
+	const KIND: NumKind = NumKind::Complex;
+
+	fn into_any(self) -> AnyNum {
+		AnyNum::Complex(self)
+	}
+}
+
+#[cfg(feature = "rug")]
+impl Variant for Rational {
+	const KIND: NumKind = NumKind::Rational;
+
+	fn into_any(self) -> AnyNum {
+		AnyNum::Rational(self)
+	}
+}
+
+#[cfg(feature = "decimal")]
+impl Variant for Decimal {
+	const KIND: NumKind = NumKind::Decimal;
+
+	fn into_any(self) -> AnyNum {
+		AnyNum::Decimal(self)
+	}
+}
+
+/// The config of the context for the type inside an `AnyNum`, which is the same as the config of the
+/// `AnyNum` context. When the number can be promoted, numbers outside of the domain of functions are
+/// errors, so that they're promoted instead of giving NaN.
+fn inner_config<M: Variant>(ctx: &Context<AnyNum>) -> Config {
+	let mut cfg = ctx.cfg.clone();
+	if cfg.promote_numbers && M::KIND.promoted().is_some() {
+		cfg.negative_sqrt = DomainPolicy::Error;
+	}
+	cfg
+}
+
+/// A context for the type inside an `AnyNum`, with the config of `inner_config`
+fn inner<M: Variant>(ctx: &Context<AnyNum>) -> Arc<Context<M>> {
+	Context::inner(&inner_config::<M>(ctx))
+}
+
+/// Do an operation on numbers of the same kind. With `promote_numbers`, if it fails in a way that a
+/// more general kind of number might not, the numbers are promoted and it's tried again, until there
+/// are no more general kinds. If it fails for every kind, the first error is returned.
+fn promoting<F: Fn(&[&AnyNum]) -> Calculation<AnyNum>>(nums: &[&AnyNum], ctx: &Context<AnyNum>, op: F) -> Calculation<AnyNum> {
+	let mut res = op(nums);
+	if !ctx.cfg.promote_numbers {
+		return res;
+	}
+
+	let mut kind = nums[0].kind();
+	let mut first = None;
+	loop {
+		let retry = match res {
+			Ok(ref answer) => answer.has_nan() && !nums.iter().any(|n| n.is_nan()),
+			Err(MathError::Unimplemented { .. })
+			| Err(MathError::DomainError { .. })
+			| Err(MathError::NotAnInteger)
+			| Err(MathError::Overflow)
+			| Err(MathError::NaN) => true,
+			Err(_) => false,
+		};
+		let next = match kind.promoted() {
+			Some(next) if retry => next,
+			_ => break,
+		};
+		let promoted = match nums.iter().map(|n| n.to_kind(next, ctx)).collect::<Result<Vec<AnyNum>, _>>() {
+			Ok(promoted) => promoted,
+			Err(_) => break,
+		};
+		if let Err(e) = res {
+			first.get_or_insert(e);
+		}
+		kind = next;
+		res = op(&promoted.iter().collect::<Vec<&AnyNum>>());
+	}
+
+	match (res, first) {
+		(Err(_), Some(first)) => Err(first),
+		(res, _) => res,
+	}
+}
+
+/// Wrap the values of an answer in `AnyNum`s
+fn wrap<M: Variant>(answer: Answer<M>) -> Answer<AnyNum> {
+	match answer {
+		Answer::Single(n) => Answer::Single(n.into_any()),
+		Answer::Multiple(ns) => Answer::Multiple(ns.into_iter().map(Variant::into_any).collect()),
+		Answer::Vector(ns) => Answer::Vector(ns.into_iter().map(wrap).collect()),
+		Answer::Labeled(ls) => Answer::Labeled(ls.into_iter().map(|(label, n)| (label, n.into_any())).collect()),
+	}
+}
+
+/// Wrap the answer of an operation on the type inside an `AnyNum`
+fn lift<M: Variant>(res: Calculation<M>) -> Calculation<AnyNum> {
+	res.map(wrap)
+}
+
+impl AnyNum {
+	/// The kind of number this is
+	pub fn kind(&self) -> NumKind {
+		match *self {
+			AnyNum::F64(_) => NumKind::F64,
+			#[cfg(feature = "rug")]
+			AnyNum::Complex(_) => NumKind::Complex,
+			#[cfg(feature = "rug")]
+			AnyNum::Rational(_) => NumKind::Rational,
+			#[cfg(feature = "decimal")]
+			AnyNum::Decimal(_) => NumKind::Decimal,
+		}
+	}
+
+	/// Convert this number to another kind by way of `f64`s, like `Answer::convert`. Numbers that are
+	/// already of that kind are returned unchanged.
+	pub fn to_kind(&self, kind: NumKind, ctx: &Context<AnyNum>) -> Result<AnyNum, MathError> {
+		if self.kind() == kind {
+			return Ok(self.clone());
+		}
+		// Decimals are rational numbers, so they can be converted exactly
+		#[cfg(all(feature = "decimal", feature = "rug"))]
+		{
+			if let (&AnyNum::Decimal(ref d), NumKind::Rational) = (self, kind) {
+				let denom = rug::Integer::from(rug::Integer::u_pow_u(10, d.scale()));
+				return Ok(AnyNum::Rational(Rational::from((rug::Integer::from(d.mantissa()), denom))));
+			}
+		}
+		let parts = self.to_f64_complex_lossy()?;
+		match each_kind!(kind, T => lift(T::from_f64_complex(parts, &inner(ctx))))? {
+			Answer::Single(n) => Ok(n),
+			_ => Err(MathError::Other),
+		}
+	}
+
+	/// Get two numbers as the same kind for an operation on both of them
+	fn pair<'a>(&'a self, other: &'a Self, ctx: &Context<AnyNum>) -> Result<(Cow<'a, AnyNum>, Cow<'a, AnyNum>), MathError> {
+		if self.kind() == other.kind() {
+			Ok((Cow::Borrowed(self), Cow::Borrowed(other)))
+		} else {
+			let kind = if !ctx.cfg.promote_numbers {
+				ctx.cfg.num_kind
+			} else if self.kind().rank() > other.kind().rank() {
+				self.kind()
+			} else {
+				other.kind()
+			};
+			let convert = |n: &'a AnyNum| -> Result<Cow<'a, AnyNum>, MathError> {
+				if n.kind() == kind {
+					Ok(Cow::Borrowed(n))
+				} else {
+					n.to_kind(kind, ctx).map(Cow::Owned)
+				}
+			};
+			Ok((convert(self)?, convert(other)?))
+		}
+	}
+}
+
+impl NumKind {
+	/// How general the kind of number is, for promotion
+	fn rank(self) -> u8 {
+		match self {
+			#[cfg(feature = "decimal")]
+			NumKind::Decimal => 0,
+			#[cfg(feature = "rug")]
+			NumKind::Rational => 1,
+			NumKind::F64 => 2,
+			#[cfg(feature = "rug")]
+			NumKind::Complex => 3,
+		}
+	}
+
+	/// The next more general kind of number that's enabled, which numbers of this kind are promoted to
+	fn promoted(self) -> Option<NumKind> {
+		match self {
+			#[cfg(all(feature = "decimal", feature = "rug"))]
+			NumKind::Decimal => Some(NumKind::Rational),
+			#[cfg(all(feature = "decimal", not(feature = "rug")))]
+			NumKind::Decimal => Some(NumKind::F64),
+			#[cfg(feature = "rug")]
+			NumKind::Rational => Some(NumKind::F64),
+			#[cfg(feature = "rug")]
+			NumKind::F64 => Some(NumKind::Complex),
+			#[cfg(not(feature = "rug"))]
+			NumKind::F64 => None,
+			#[cfg(feature = "rug")]
+			NumKind::Complex => None,
+		}
+	}
+
+	/// The kind constants like `pi` are created as, which is at least `F64` when promoting
+	fn inexact(ctx: &Context<AnyNum>) -> NumKind {
+		let kind = ctx.cfg.num_kind;
+		if ctx.cfg.promote_numbers && kind.rank() < NumKind::F64.rank() {
+			NumKind::F64
+		} else {
+			kind
+		}
+	}
+
+	/// The name of the type, like `Num::typename`
+	pub fn typename(self) -> String {
+		each_kind!(self, T => T::typename())
+	}
+
+	/// The operations the type supports, like `Num::capabilities`
+	pub fn capabilities(self) -> NumCapabilities {
+		each_kind!(self, T => T::capabilities())
+	}
+}
+
+impl fmt::Display for AnyNum {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		each!(self, n => fmt::Display::fmt(n, f))
+	}
+}
+
+impl From<f64> for AnyNum {
+	fn from(n: f64) -> Self {
+		AnyNum::F64(n)
+	}
+}
+
+#[cfg(feature = "rug")]
+impl From<Complex> for AnyNum {
+	fn from(n: Complex) -> Self {
+		AnyNum::Complex(n)
+	}
+}
+
+#[cfg(feature = "rug")]
+impl From<Rational> for AnyNum {
+	fn from(n: Rational) -> Self {
+		AnyNum::Rational(n)
+	}
+}
+
+#[cfg(feature = "decimal")]
+impl From<Decimal> for AnyNum {
+	fn from(n: Decimal) -> Self {
+		AnyNum::Decimal(n)
+	}
+}
+
+impl Num for AnyNum {
+	fn from_f64(t: f64, ctx: &Context<Self>) -> Calculation<Self> {
+		each_kind!(ctx.cfg.num_kind, T => lift(T::from_f64(t, &inner(ctx))))
+	}
+
+	fn from_f64_complex(t: (f64, f64), ctx: &Context<Self>) -> Calculation<Self> {
+		each_kind!(ctx.cfg.num_kind, T => lift(T::from_f64_complex(t, &inner(ctx))))
+	}
+
+	fn typename() -> String {
+		String::from("AnyNum")
+	}
+
+	fn pi(ctx: &Context<Self>) -> Calculation<Self> {
+		each_kind!(NumKind::inexact(ctx), T => lift(T::pi(&inner(ctx))))
+	}
+
+	fn e(ctx: &Context<Self>) -> Calculation<Self> {
+		each_kind!(NumKind::inexact(ctx), T => lift(T::e(&inner(ctx))))
+	}
+
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		each!(self, n => n.to_f64_lossy())
+	}
+
+	fn to_f64_complex_lossy(&self) -> Result<(f64, f64), MathError> {
+		each!(self, n => n.to_f64_complex_lossy())
+	}
+
+	fn is_finite(&self) -> bool {
+		each!(self, n => n.is_finite())
+	}
+
+	fn is_nan(&self) -> bool {
+		each!(self, n => n.is_nan())
+	}
+
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		each!(self, n => n.format(fmt))
+	}
+
+	fn tryord(&self, other: &Self, ctx: &Context<Self>) -> Result<Ordering, MathError> {
+		let (a, b) = self.pair(other, ctx)?;
+		each_pair!(a, b, x, y => x.tryord(y, &inner(ctx)))
+	}
+
+	fn add(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, add)
+	}
+
+	fn sub(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, sub)
+	}
+
+	fn mul(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, mul)
+	}
+
+	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, div)
+	}
+
+	fn pow(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, pow)
+	}
+
+	fn sqrt(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, sqrt)
+	}
+
+	fn nrt(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, nrt)
+	}
+
+	fn abs(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, abs)
+	}
+
+	fn sin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, sin)
+	}
+
+	fn cos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, cos)
+	}
+
+	fn tan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, tan)
+	}
+
+	fn asin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, asin)
+	}
+
+	fn acos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, acos)
+	}
+
+	fn atan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, atan)
+	}
+
+	fn atan2(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, atan2)
+	}
+
+	fn floor(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, floor)
+	}
+
+	fn ceil(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, ceil)
+	}
+
+	fn round(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, round)
+	}
+
+	fn log(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, log)
+	}
+
+	fn fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, fact)
+	}
+
+	fn double_fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, double_fact)
+	}
+
+	fn binomial(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, k, ctx, binomial)
+	}
+
+	fn permutations(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, k, ctx, permutations)
+	}
+
+	fn gamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, gamma)
+	}
+
+	fn lgamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, lgamma)
+	}
+
+	fn erf(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, erf)
+	}
+
+	fn erfc(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, erfc)
+	}
+
+	fn beta(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, beta)
+	}
+
+	fn zeta(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, zeta)
+	}
+
+	fn bitand(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, bitand)
+	}
+
+	fn bitor(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, bitor)
+	}
+
+	fn bitxor(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, bitxor)
+	}
+
+	fn shl(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, shl)
+	}
+
+	fn shr(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary!(self, other, ctx, shr)
+	}
+
+	fn bitnot(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, bitnot)
+	}
+
+	fn sign(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary!(self, ctx, sign)
+	}
+}
@@ -1,11 +1,13 @@
-use std::fmt;
-use std::cmp::Ordering;
+use core::fmt;
+use core::cmp::Ordering;
 
 use crate::opers::Calculation;
-use crate::num::Num;
+use crate::num::{self, Num};
+use crate::format::{self, AnswerFormat};
 use crate::answer::Answer;
 use crate::errors::MathError;
 use crate::context::Context;
+use crate::prelude::*;
 
 /// A complex number made of a real `f64` and an imaginary `f64`.
 #[derive(Debug, Clone)]
@@ -29,6 +31,25 @@ impl Num for ComplexFloat {
 		String::from("ComplexFloat")
 	}
 
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		Ok(self.r)
+	}
+
+	fn to_f64_complex_lossy(&self) -> Result<(f64, f64), MathError> {
+		Ok((self.r, self.i))
+	}
+
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		let (r, i) = (format::format_f64(self.r, fmt), format::format_f64(self.i, fmt));
+		if self.i == 0.0 {
+			r
+		} else if self.r == 0.0 {
+			format!("{}i", i)
+		} else {
+			format!("({} + {}i)", r, i)
+		}
+	}
+
 	fn tryord(&self, other: &Self, _ctx: &Context<Self>) -> Result<Ordering, MathError> {
 		if let Some(ord) = self.partial_cmp(other) {
 			Ok(ord)
@@ -63,6 +84,11 @@ impl Num for ComplexFloat {
 	}
 
 	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if other.r == 0.0 && other.i == 0.0 {
+			let sign = if self.r != 0.0 { self.r } else { self.i };
+			return num::div_by_zero(sign.partial_cmp(&0.0).unwrap_or(Ordering::Equal), ctx);
+		}
+
 		let conj = other.conjugate();
 		let num = self.mul(&conj, ctx)?.unwrap_single();
 		let den = other.mul(&conj, ctx)?.unwrap_single();
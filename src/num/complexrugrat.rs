@@ -5,8 +5,10 @@ use rug::Rational;
 use crate::opers::Calculation;
 use crate::errors::MathError;
 use crate::answer::Answer;
-use crate::num::Num;
+use crate::num::{self, Num};
 use crate::context::Context;
+use crate::format::AnswerFormat;
+use crate::num::rugrat::format_rational;
 
 /// A complex number made of a real part and an imaginary part, both of which are `rug::Rationals`.
 /// Requires the `rug` feature.
@@ -51,6 +53,34 @@ impl Num for ComplexRugRat {
 		String::from("ComplexRugRat")
 	}
 
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		Ok(self.r.to_f64())
+	}
+
+	fn to_f64_complex_lossy(&self) -> Result<(f64, f64), MathError> {
+		Ok((self.r.to_f64(), self.i.to_f64()))
+	}
+
+	/// Rationals are always finite
+	fn is_finite(&self) -> bool {
+		true
+	}
+
+	fn is_nan(&self) -> bool {
+		false
+	}
+
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		let (r, i) = (format_rational(&self.r, fmt), format_rational(&self.i, fmt));
+		if self.i == 0 {
+			r
+		} else if self.r == 0 {
+			format!("{}i", i)
+		} else {
+			format!("({} + {}i)", r, i)
+		}
+	}
+
 	fn tryord(&self, other: &Self, _ctx: &Context<Self>) -> Result<Ordering, MathError> {
 		if let Some(ord) = self.partial_cmp(other) {
 			Ok(ord)
@@ -85,6 +115,11 @@ impl Num for ComplexRugRat {
 	}
 
 	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if other.r == 0 && other.i == 0 {
+			let sign = if self.r != 0 { self.r.cmp0() } else { self.i.cmp0() };
+			return num::div_by_zero(sign, ctx);
+		}
+
 		let conj = other.conjugate();
 		let num = self.mul(&conj, ctx)?.unwrap_single();
 		let den = other.mul(&conj, ctx)?.unwrap_single();
@@ -0,0 +1,388 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::opers::Calculation;
+use crate::errors::{MathError, ParseError};
+use crate::answer::Answer;
+use crate::num::{self, Num};
+use crate::context::{Context, RoundingMode};
+use crate::prelude::*;
+
+/// The maximum amount of digits after the decimal point a `Decimal` can have
+const MAX_SCALE: u32 = 28;
+
+/// An exact base 10 number with up to 38 significant digits, up to 28 of which can be after the
+/// decimal point. Adding, subtracting and multiplying decimals is exact (so `0.1 + 0.2` is exactly
+/// `0.3`), except that digits past the 28th decimal place are rounded off. Division rounds to the
+/// `decimal_places` of the config. Rounding uses the `rounding` mode of the config. Results that need
+/// more significant digits fail with a `MathError::Overflow`. Requires the `decimal` feature.
+///
+/// Numbers in expressions are parsed as `f64`s first, so decimals in expressions are limited to the
+/// about 17 significant digits an `f64` can represent exactly. Larger decimals can be made with
+/// `Decimal::new` or parsed from a string with `str::parse` and used as variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+	/// The digits of the number, without the decimal point. Never ends with a zero if the scale
+	/// isn't zero.
+	mantissa: i128,
+	/// The amount of digits after the decimal point
+	scale: u32,
+}
+
+/// The state of the digits removed from a number when rounding it: how they compare to half of the
+/// last digit kept, and whether any of them weren't zero
+type Tail = (Ordering, bool);
+
+/// The tail of a number that didn't lose any digits
+const EXACT: Tail = (Ordering::Less, false);
+
+/// Remove the last `drop` digits of a magnitude, rounding it. `tail` is the state of the digits that
+/// were already removed after those.
+fn round_digits(mag: u128, negative: bool, drop: u32, tail: Tail, mode: RoundingMode) -> u128 {
+	let mut q = mag;
+	let mut tail = tail;
+	if drop > 0 {
+		let mut first = 0;
+		let mut sticky = tail.1;
+		for _ in 0..drop {
+			sticky |= first != 0;
+			first = q % 10;
+			q /= 10;
+		}
+		let half = match first.cmp(&5) {
+			Ordering::Equal if sticky => Ordering::Greater,
+			half => half,
+		};
+		tail = (half, first != 0 || sticky);
+	}
+
+	if mode.rounds_away(q % 2 == 1, negative, tail.0, tail.1) {
+		q + 1
+	} else {
+		q
+	}
+}
+
+/// Multiply two magnitudes into a 256 bit product, as its high and low halves
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+	const LOW: u128 = u64::MAX as u128;
+	let (a1, a0) = (a >> 64, a & LOW);
+	let (b1, b0) = (b >> 64, b & LOW);
+	let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+	// Less than 3 × 2^64, so it can't overflow
+	let mid = (p00 >> 64) + (p01 & LOW) + (p10 & LOW);
+	let lo = (p00 & LOW) | (mid << 64);
+	let hi = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+	(hi, lo)
+}
+
+/// Remove the last `drop` digits of a 256 bit magnitude without rounding it, returning the rest if it
+/// fits in 128 bits along with the state of the removed digits
+fn drop_wide_digits(hi: u128, lo: u128, drop: u32) -> Option<(u128, Tail)> {
+	const LOW: u128 = u64::MAX as u128;
+	let (mut hi, mut lo) = (hi, lo);
+	let mut first = 0;
+	let mut sticky = false;
+	for _ in 0..drop {
+		sticky |= first != 0;
+		// Long division by 10 with 64 bit digits, where each partial dividend fits in 128 bits
+		let upper = ((hi % 10) << 64) | (lo >> 64);
+		let lower = ((upper % 10) << 64) | (lo & LOW);
+		hi /= 10;
+		lo = ((upper / 10) << 64) | (lower / 10);
+		first = lower % 10;
+	}
+	if hi != 0 {
+		return None;
+	}
+
+	let half = match first.cmp(&5) {
+		Ordering::Equal if sticky => Ordering::Greater,
+		half => half,
+	};
+	Some((lo, (half, first != 0 || sticky)))
+}
+
+impl Decimal {
+	/// Create a decimal equal to `mantissa / 10^scale`. Returns `None` if the scale is more than 28.
+	pub fn new(mantissa: i128, scale: u32) -> Option<Self> {
+		if scale > MAX_SCALE {
+			None
+		} else {
+			Some(Self::normalized(mantissa, scale))
+		}
+	}
+
+	/// The digits of the number without the decimal point, with trailing zeros after the decimal
+	/// point removed
+	pub fn mantissa(&self) -> i128 {
+		self.mantissa
+	}
+
+	/// The amount of digits after the decimal point
+	pub fn scale(&self) -> u32 {
+		self.scale
+	}
+
+	/// Remove trailing zeros after the decimal point
+	fn normalized(mut mantissa: i128, mut scale: u32) -> Self {
+		while scale > 0 && mantissa % 10 == 0 {
+			mantissa /= 10;
+			scale -= 1;
+		}
+		if mantissa == 0 {
+			scale = 0;
+		}
+		Decimal { mantissa, scale }
+	}
+
+	/// Create a decimal from its sign, magnitude and scale, rounding it to at most `max_scale` digits
+	/// after the decimal point
+	fn from_parts(negative: bool, mag: u128, scale: u32, max_scale: u32, tail: Tail, mode: RoundingMode) -> Result<Self, MathError> {
+		let (mag, scale) = if scale > max_scale {
+			(round_digits(mag, negative, scale - max_scale, tail, mode), max_scale)
+		} else {
+			(round_digits(mag, negative, 0, tail, mode), scale)
+		};
+
+		if mag > i128::MAX as u128 {
+			return Err(MathError::Overflow);
+		}
+		let mantissa = if negative { -(mag as i128) } else { mag as i128 };
+		Ok(Self::normalized(mantissa, scale))
+	}
+
+	/// The mantissa of this decimal with the given (larger) scale, or `None` if it doesn't fit
+	fn rescaled(&self, scale: u32) -> Option<i128> {
+		10i128.checked_pow(scale - self.scale).and_then(|p| self.mantissa.checked_mul(p))
+	}
+
+	/// The mantissas of two decimals with the same scale, and that scale
+	fn aligned(&self, other: &Self) -> Result<(i128, i128, u32), MathError> {
+		let scale = self.scale.max(other.scale);
+		match (self.rescaled(scale), other.rescaled(scale)) {
+			(Some(a), Some(b)) => Ok((a, b, scale)),
+			_ => Err(MathError::Overflow),
+		}
+	}
+
+	/// Check if this decimal is an integer
+	fn is_integer(&self) -> bool {
+		self.scale == 0
+	}
+
+	/// Round this decimal to an integer with the given rounding mode
+	fn round_with(&self, mode: RoundingMode) -> Self {
+		if self.is_integer() {
+			return *self;
+		}
+
+		let negative = self.mantissa < 0;
+		let mag = round_digits(self.mantissa.unsigned_abs(), negative, self.scale, EXACT, mode);
+		// Rounding can't make the magnitude larger than it was
+		Self::normalized(if negative { -(mag as i128) } else { mag as i128 }, 0)
+	}
+}
+
+impl Ord for Decimal {
+	fn cmp(&self, other: &Self) -> Ordering {
+		let scale = self.scale.max(other.scale);
+		match (self.rescaled(scale), other.rescaled(scale)) {
+			(Some(a), Some(b)) => a.cmp(&b),
+			// A mantissa that doesn't fit when rescaled is larger than any that does
+			(None, _) => self.mantissa.cmp(&0),
+			(_, None) => 0.cmp(&other.mantissa),
+		}
+	}
+}
+
+impl PartialOrd for Decimal {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl FromStr for Decimal {
+	type Err = ParseError;
+
+	/// Parse a decimal like `-12.345`. Digits past the 28th decimal place are rounded off with
+	/// `RoundingMode::HalfEven`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || ParseError::InvalidNumber { number: s.to_string() };
+		let (negative, digits) = match s.strip_prefix('-') {
+			Some(digits) => (true, digits),
+			None => (false, s),
+		};
+		let (int, frac) = match digits.find('.') {
+			Some(i) => (&digits[..i], &digits[i + 1..]),
+			None => (digits, ""),
+		};
+		if int.is_empty() && frac.is_empty() {
+			return Err(invalid());
+		}
+
+		let mut mag: u128 = 0;
+		for c in int.chars().chain(frac.chars()) {
+			let d = c.to_digit(10).ok_or_else(invalid)?;
+			mag = mag.checked_mul(10).and_then(|m| m.checked_add(u128::from(d))).ok_or_else(invalid)?;
+		}
+		Self::from_parts(negative, mag, frac.len() as u32, MAX_SCALE, EXACT, RoundingMode::HalfEven).map_err(|_| invalid())
+	}
+}
+
+impl fmt::Display for Decimal {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let sign = if self.mantissa < 0 { "-" } else { "" };
+		let mut digits = self.mantissa.unsigned_abs().to_string();
+		if self.scale == 0 {
+			return write!(f, "{}{}", sign, digits);
+		}
+
+		let scale = self.scale as usize;
+		while digits.len() <= scale {
+			digits.insert(0, '0');
+		}
+		let (int, frac) = digits.split_at(digits.len() - scale);
+		write!(f, "{}{}.{}", sign, int, frac)
+	}
+}
+
+impl Num for Decimal {
+	/// Converts the shortest representation of the float that parses back to it, so `0.1` becomes
+	/// exactly `0.1`
+	fn from_f64(t: f64, _ctx: &Context<Self>) -> Calculation<Self> {
+		if !t.is_finite() {
+			return Err(MathError::NaN);
+		}
+
+		match t.to_string().parse() {
+			Ok(d) => Ok(Answer::Single(d)),
+			Err(_) => Err(MathError::Overflow),
+		}
+	}
+
+	fn from_f64_complex((r, _i): (f64, f64), ctx: &Context<Self>) -> Calculation<Self> {
+		Self::from_f64(r, ctx)
+	}
+
+	fn typename() -> String {
+		String::from("Decimal")
+	}
+
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		self.to_string().parse().map_err(|_| MathError::Other)
+	}
+
+	/// Decimals are always finite
+	fn is_finite(&self) -> bool {
+		true
+	}
+
+	fn is_nan(&self) -> bool {
+		false
+	}
+
+	fn tryord(&self, other: &Self, _ctx: &Context<Self>) -> Result<Ordering, MathError> {
+		Ok(self.cmp(other))
+	}
+
+	fn add(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		let (a, b, scale) = self.aligned(other)?;
+		let sum = a.checked_add(b).ok_or(MathError::Overflow)?;
+
+		Ok(Answer::Single(Self::normalized(sum, scale)))
+	}
+
+	fn sub(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		let (a, b, scale) = self.aligned(other)?;
+		let diff = a.checked_sub(b).ok_or(MathError::Overflow)?;
+
+		Ok(Answer::Single(Self::normalized(diff, scale)))
+	}
+
+	fn mul(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let negative = (self.mantissa < 0) != (other.mantissa < 0);
+		let (hi, lo) = wide_mul(self.mantissa.unsigned_abs(), other.mantissa.unsigned_abs());
+		// Remove the digits past the last decimal place first, since the product of the mantissas can
+		// be too large for 128 bits even when the rounded product isn't
+		let scale = self.scale + other.scale;
+		let drop = scale.saturating_sub(MAX_SCALE);
+		let (mag, tail) = drop_wide_digits(hi, lo, drop).ok_or(MathError::Overflow)?;
+
+		Ok(Answer::Single(Self::from_parts(negative, mag, scale - drop, MAX_SCALE, tail, ctx.cfg.rounding)?))
+	}
+
+	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if other.mantissa == 0 {
+			return num::div_by_zero(self.mantissa.cmp(&0), ctx);
+		}
+
+		let negative = (self.mantissa < 0) != (other.mantissa < 0);
+		let (a, b) = (self.mantissa.unsigned_abs(), other.mantissa.unsigned_abs());
+		let places = ctx.cfg.decimal_places.min(MAX_SCALE);
+		// The quotient of the mantissas is off by a factor of 10^(self.scale - other.scale), so it needs
+		// this many digits after the decimal point to have `places` digits in the answer
+		let digits = (places + other.scale).saturating_sub(self.scale);
+
+		// Long division, stopping early once it's exact (but not before the scale of the answer is at
+		// least zero) so that exact quotients of large numbers don't overflow
+		let min_digits = other.scale.saturating_sub(self.scale);
+		let mut q = a / b;
+		let mut r = a % b;
+		let mut computed = 0;
+		while computed < digits && (r != 0 || computed < min_digits) {
+			let shifted = r.checked_mul(10).ok_or(MathError::Overflow)?;
+			q = q.checked_mul(10).and_then(|q| q.checked_add(shifted / b)).ok_or(MathError::Overflow)?;
+			r = shifted % b;
+			computed += 1;
+		}
+
+		// `r < b <= i128::MAX`, so doubling it can't overflow
+		let tail = ((r * 2).cmp(&b), r != 0);
+		let scale = computed + self.scale - other.scale;
+		Ok(Answer::Single(Self::from_parts(negative, q, scale, places, tail, ctx.cfg.rounding)?))
+	}
+
+	/// Only integer exponents are supported, since other powers usually aren't exact
+	fn pow(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if !other.is_integer() {
+			return Err(MathError::NotAnInteger);
+		}
+
+		num::pow_int(self, other.mantissa, ctx)
+	}
+
+	fn abs(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		let mantissa = self.mantissa.checked_abs().ok_or(MathError::Overflow)?;
+
+		Ok(Answer::Single(Decimal { mantissa, scale: self.scale }))
+	}
+
+	fn floor(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(self.round_with(RoundingMode::Floor)))
+	}
+
+	fn ceil(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(self.round_with(RoundingMode::Ceil)))
+	}
+
+	/// Rounds with the `rounding` mode of the config
+	fn round(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(self.round_with(ctx.cfg.rounding)))
+	}
+
+	fn fact(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		if !self.is_integer() {
+			return Err(MathError::NotAnInteger);
+		} else if self.mantissa < 0 {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		let mut product: i128 = 1;
+		for n in 2..=self.mantissa {
+			product = product.checked_mul(n).ok_or(MathError::Overflow)?;
+		}
+		Ok(Answer::Single(Decimal::normalized(product, 0)))
+	}
+}
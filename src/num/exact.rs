@@ -0,0 +1,741 @@
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::opers::Calculation;
+use crate::errors::MathError;
+use crate::answer::Answer;
+use crate::num::{self, Num};
+use crate::num::float64::math;
+use crate::context::Context;
+use crate::format::{self, AnswerFormat};
+use crate::prelude::*;
+
+/// The most terms a sum can have before it's converted to a float, so that products of sums can't
+/// grow without bound
+const MAX_TERMS: usize = 32;
+
+/// Square roots are only kept exact for numbers whose prime factors below this can be found by
+/// trial division, with at most two prime factors above it
+const TRIAL_LIMIT: u128 = 1 << 16;
+
+/// An exact real number, which keeps `pi`, `e` and square roots symbolic instead of rounding them
+/// to floats, so that `sin(pi)` is exactly 0 and `sqrt(2)^2` is exactly 2. It's only converted to a
+/// float when asked to, with `to_f64`.
+///
+/// An exact number is a sum of rational multiples of products of powers of π and e and a square
+/// root, like `3/2 + 2π√3`. Operations whose answers can be written like that are exact:
+///
+/// - Adding, subtracting and multiplying, and dividing by a single term (like `π/2` or `1/√2`)
+/// - Integer powers, and square roots of rational multiples of even powers of π and e
+/// - `sin`, `cos` and `tan` of multiples of π/6 and π/4, and their inverses for the same values
+/// - `log` of exact powers of the base, `floor`, `ceil`, `round`, `abs` and factorials of small
+///   integers
+///
+/// Every other operation is done with `f64`s (with the same config), and its answer is a float.
+/// Once a float is part of an operation, its answer is a float too. Numbers in expressions are made
+/// exact from the shortest decimal that parses to the same `f64`, so `0.1` is exactly 1/10. The
+/// imaginary parts of complex numbers are ignored, like for `Decimal`.
+///
+/// ```rust
+/// # use mexprp::{Context, Term};
+/// # use mexprp::num::Exact;
+/// let ctx: Context<Exact> = Context::new();
+/// let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+/// assert_eq!(eval("sin(pi)"), Exact::rational(0, 1).unwrap());
+/// assert_eq!(eval("cos(pi/4)").to_string(), "√2/2");
+/// assert_eq!(eval("0.1 + 0.2").to_string(), "3/10");
+/// assert!(!eval("sin(1)").is_exact());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exact {
+	repr: Repr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Repr {
+	/// A sum of distinct monomials with nonzero coefficients, sorted by monomial. Zero is the empty
+	/// sum.
+	Sum(Vec<(Monomial, Ratio)>),
+	/// A number that couldn't be kept exact
+	Float(f64),
+}
+
+/// A rational number in lowest terms, with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ratio {
+	num: i128,
+	den: i128,
+}
+
+/// A product of powers of π and e and the square root of a square-free integer, π^pi × e^e × √root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Monomial {
+	pi: i32,
+	e: i32,
+	root: u64,
+}
+
+const ONE: Monomial = Monomial { pi: 0, e: 0, root: 1 };
+const PI: Monomial = Monomial { pi: 1, e: 0, root: 1 };
+const E: Monomial = Monomial { pi: 0, e: 1, root: 1 };
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+	while b != 0 {
+		let t = a % b;
+		a = b;
+		b = t;
+	}
+	a
+}
+
+/// Raise a float to an integer power by squaring
+fn powi(mut x: f64, n: i32) -> f64 {
+	let mut exp = n.unsigned_abs();
+	let mut res = 1.0;
+	while exp > 0 {
+		if exp & 1 == 1 {
+			res *= x;
+		}
+		x *= x;
+		exp >>= 1;
+	}
+	if n < 0 { 1.0 / res } else { res }
+}
+
+/// Split a number into the square of an integer and a square-free integer, `n = s² × r`, returning
+/// `s` and `r`. Fails for numbers that can't be factored quickly enough (see `TRIAL_LIMIT`).
+fn square_free(mut n: u128) -> Option<(u128, u64)> {
+	let (mut s, mut r) = (1, 1);
+	let mut p = 2;
+	while p < TRIAL_LIMIT && p * p <= n {
+		while n.is_multiple_of(p * p) {
+			n /= p * p;
+			s *= p;
+		}
+		if n.is_multiple_of(p) {
+			n /= p;
+			r *= p;
+		}
+		p += 1;
+	}
+	// What's left is 1 or a prime, unless the loop stopped at the limit, in which case its prime
+	// factors are all larger than the limit
+	if p >= TRIAL_LIMIT && n >= TRIAL_LIMIT * TRIAL_LIMIT {
+		if n >= TRIAL_LIMIT * TRIAL_LIMIT * TRIAL_LIMIT {
+			return None;
+		}
+		// Two prime factors, which might be the same
+		let mut q = math::sqrt(n as f64) as u128;
+		while q * q > n {
+			q -= 1;
+		}
+		while (q + 1) * (q + 1) <= n {
+			q += 1;
+		}
+		if q * q == n {
+			s *= q;
+			n = 1;
+		}
+	}
+	Some((s, u64::try_from(r.checked_mul(n)?).ok()?))
+}
+
+impl Ratio {
+	const ZERO: Ratio = Ratio { num: 0, den: 1 };
+
+	fn new(num: i128, den: i128) -> Option<Ratio> {
+		if den == 0 || num == i128::MIN || den == i128::MIN {
+			return None;
+		}
+		let g = gcd(num.unsigned_abs(), den.unsigned_abs()) as i128;
+		let (num, den) = (num / g, den / g);
+		if den < 0 {
+			Some(Ratio { num: -num, den: -den })
+		} else {
+			Some(Ratio { num, den })
+		}
+	}
+
+	fn int(n: i128) -> Ratio {
+		Ratio { num: n, den: 1 }
+	}
+
+	fn add(self, other: Ratio) -> Option<Ratio> {
+		let g = gcd(self.den as u128, other.den as u128) as i128;
+		let den = (self.den / g).checked_mul(other.den)?;
+		let num = self.num.checked_mul(other.den / g)?.checked_add(other.num.checked_mul(self.den / g)?)?;
+		Ratio::new(num, den)
+	}
+
+	fn mul(self, other: Ratio) -> Option<Ratio> {
+		// Cancel common factors first to keep the numbers small
+		let g1 = gcd(self.num.unsigned_abs(), other.den as u128) as i128;
+		let g2 = gcd(other.num.unsigned_abs(), self.den as u128) as i128;
+		Ratio::new((self.num / g1).checked_mul(other.num / g2)?, (self.den / g2).checked_mul(other.den / g1)?)
+	}
+
+	fn neg(self) -> Option<Ratio> {
+		Some(Ratio { num: self.num.checked_neg()?, den: self.den })
+	}
+
+	fn recip(self) -> Option<Ratio> {
+		Ratio::new(self.den, self.num)
+	}
+
+	fn to_f64(self) -> f64 {
+		self.num as f64 / self.den as f64
+	}
+}
+
+impl Monomial {
+	/// Multiply two monomials, returning an integer factor that comes out of the square roots
+	fn mul(self, other: Monomial) -> Option<(i128, Monomial)> {
+		let g = gcd(u128::from(self.root), u128::from(other.root)) as u64;
+		Some((i128::from(g), Monomial {
+			pi: self.pi.checked_add(other.pi)?,
+			e: self.e.checked_add(other.e)?,
+			root: (self.root / g).checked_mul(other.root / g)?,
+		}))
+	}
+
+	/// The reciprocal of the monomial, with a rational factor to keep the square root in the numerator
+	fn recip(self) -> Option<(Ratio, Monomial)> {
+		Some((Ratio::new(1, i128::from(self.root))?, Monomial {
+			pi: self.pi.checked_neg()?,
+			e: self.e.checked_neg()?,
+			root: self.root,
+		}))
+	}
+
+	fn to_f64(self) -> f64 {
+		powi(core::f64::consts::PI, self.pi) * powi(core::f64::consts::E, self.e) * math::sqrt(self.root as f64)
+	}
+}
+
+/// The sine of `t` twelfths of π, if it's exact
+fn sin_twelfths(t: i128) -> Option<Exact> {
+	let t = t.rem_euclid(24);
+	let (t, negative) = if t >= 12 { (t - 12, true) } else { (t, false) };
+	let t = if t > 6 { 12 - t } else { t };
+	let (root, coef) = match t {
+		0 => return Some(Exact::zero()),
+		2 => (1, Ratio::new(1, 2)?),
+		3 => (2, Ratio::new(1, 2)?),
+		4 => (3, Ratio::new(1, 2)?),
+		6 => (1, Ratio::int(1)),
+		_ => return None,
+	};
+	let coef = if negative { coef.neg()? } else { coef };
+	Some(Exact::term(Monomial { pi: 0, e: 0, root }, coef))
+}
+
+/// The tangent of `t` twelfths of π, if it's exact and defined
+fn tan_twelfths(t: i128) -> Option<Exact> {
+	let cos = sin_twelfths(t + 6)?;
+	if cos.is_zero() {
+		return None;
+	}
+	sin_twelfths(t)?.exact_mul(&cos.exact_recip()?)
+}
+
+/// A context for doing operations with `f64`s, with the same config
+fn float_ctx(ctx: &Context<Exact>) -> Context<f64> {
+	let mut inner = Context::empty();
+	inner.cfg = ctx.cfg.clone();
+	inner
+}
+
+/// Wrap the values of an answer of an operation done with `f64`s
+fn from_floats(answer: Answer<f64>) -> Answer<Exact> {
+	match answer {
+		Answer::Single(x) => Answer::Single(Exact::float(x)),
+		Answer::Multiple(xs) => Answer::Multiple(xs.into_iter().map(Exact::float).collect()),
+		Answer::Vector(xs) => Answer::Vector(xs.into_iter().map(from_floats).collect()),
+		Answer::Labeled(ls) => Answer::Labeled(ls.into_iter().map(|(label, x)| (label, Exact::float(x))).collect()),
+	}
+}
+
+/// Do a unary operation with `f64`s
+fn unary_float(x: &Exact, ctx: &Context<Exact>, op: fn(&f64, &Context<f64>) -> Calculation<f64>) -> Calculation<Exact> {
+	Ok(from_floats(op(&x.to_f64(), &float_ctx(ctx))?))
+}
+
+/// Do a binary operation with `f64`s
+fn binary_float(x: &Exact, y: &Exact, ctx: &Context<Exact>, op: fn(&f64, &f64, &Context<f64>) -> Calculation<f64>) -> Calculation<Exact> {
+	Ok(from_floats(op(&x.to_f64(), &y.to_f64(), &float_ctx(ctx))?))
+}
+
+/// The exact answer if there is one, or else the answer of doing the operation with floats
+fn exact_or<F: FnOnce() -> Calculation<Exact>>(exact: Option<Exact>, float: F) -> Calculation<Exact> {
+	match exact {
+		Some(x) => Ok(Answer::Single(x)),
+		None => float(),
+	}
+}
+
+impl Exact {
+	/// The exact rational number `num / den`, or `None` if `den` is zero
+	pub fn rational(num: i128, den: i128) -> Option<Self> {
+		Ratio::new(num, den).map(|r| Exact::term(ONE, r))
+	}
+
+	/// Check whether the number is exact, rather than a float
+	pub fn is_exact(&self) -> bool {
+		match self.repr {
+			Repr::Sum(_) => true,
+			Repr::Float(_) => false,
+		}
+	}
+
+	/// Convert the number to an `f64`
+	pub fn to_f64(&self) -> f64 {
+		match self.repr {
+			Repr::Sum(ref terms) => terms.iter().map(|&(m, c)| c.to_f64() * m.to_f64()).sum(),
+			Repr::Float(x) => x,
+		}
+	}
+
+	fn zero() -> Self {
+		Exact { repr: Repr::Sum(Vec::new()) }
+	}
+
+	fn float(x: f64) -> Self {
+		Exact { repr: Repr::Float(x) }
+	}
+
+	fn term(m: Monomial, c: Ratio) -> Self {
+		if c.num == 0 {
+			Exact::zero()
+		} else {
+			Exact { repr: Repr::Sum(vec![(m, c)]) }
+		}
+	}
+
+	/// Make a sum of terms canonical, failing if it has too many terms or overflows
+	fn sum(mut terms: Vec<(Monomial, Ratio)>) -> Option<Self> {
+		terms.sort_by_key(|term| term.0);
+		let mut merged: Vec<(Monomial, Ratio)> = Vec::with_capacity(terms.len());
+		for (m, c) in terms {
+			match merged.last_mut() {
+				Some(last) if last.0 == m => last.1 = last.1.add(c)?,
+				_ => merged.push((m, c)),
+			}
+		}
+		merged.retain(|&(_, c)| c.num != 0);
+		if merged.len() > MAX_TERMS {
+			return None;
+		}
+		Some(Exact { repr: Repr::Sum(merged) })
+	}
+
+	/// Make a float exact from the shortest decimal that parses to it
+	fn from_decimal(t: f64) -> Option<Self> {
+		if !t.is_finite() {
+			return None;
+		}
+		let s = t.to_string();
+		let (negative, s) = match s.strip_prefix('-') {
+			Some(s) => (true, s),
+			None => (false, s.as_str()),
+		};
+		let (int, frac) = match s.find('.') {
+			Some(i) => (&s[..i], &s[i + 1..]),
+			None => (s, ""),
+		};
+		let mut num: i128 = 0;
+		for c in int.chars().chain(frac.chars()) {
+			num = num.checked_mul(10)?.checked_add(i128::from(c.to_digit(10)?))?;
+		}
+		let den = 10i128.checked_pow(u32::try_from(frac.len()).ok()?)?;
+		Exact::rational(if negative { -num } else { num }, den)
+	}
+
+	fn terms(&self) -> Option<&[(Monomial, Ratio)]> {
+		match self.repr {
+			Repr::Sum(ref terms) => Some(terms),
+			Repr::Float(_) => None,
+		}
+	}
+
+	fn is_zero(&self) -> bool {
+		self.terms().is_some_and(|terms| terms.is_empty())
+	}
+
+	/// The only term of the number, if it has exactly one
+	fn single(&self) -> Option<(Monomial, Ratio)> {
+		match self.terms()? {
+			[term] => Some(*term),
+			_ => None,
+		}
+	}
+
+	/// The number as a rational, if it is one
+	fn ratio(&self) -> Option<Ratio> {
+		match self.terms()? {
+			[] => Some(Ratio::ZERO),
+			[(m, c)] if *m == ONE => Some(*c),
+			_ => None,
+		}
+	}
+
+	/// The number as an integer, if it is one
+	fn integer(&self) -> Option<i128> {
+		self.ratio().filter(|r| r.den == 1).map(|r| r.num)
+	}
+
+	/// How many twelfths of π the number is, if it's a whole number of them
+	fn pi_twelfths(&self) -> Option<i128> {
+		match self.terms()? {
+			[] => Some(0),
+			[(m, c)] if *m == PI => c.mul(Ratio::int(12)).filter(|r| r.den == 1).map(|r| r.num),
+			_ => None,
+		}
+	}
+
+	fn sign(&self) -> Ordering {
+		self.to_f64().partial_cmp(&0.0).unwrap_or(Ordering::Equal)
+	}
+
+	fn exact_add(&self, other: &Self) -> Option<Self> {
+		Exact::sum(self.terms()?.iter().chain(other.terms()?).cloned().collect())
+	}
+
+	fn exact_neg(&self) -> Option<Self> {
+		Exact::sum(self.terms()?.iter().map(|&(m, c)| Some((m, c.neg()?))).collect::<Option<_>>()?)
+	}
+
+	fn exact_mul(&self, other: &Self) -> Option<Self> {
+		let (a, b) = (self.terms()?, other.terms()?);
+		if a.len() * b.len() > MAX_TERMS * MAX_TERMS {
+			return None;
+		}
+		let mut terms = Vec::with_capacity(a.len() * b.len());
+		for &(m1, c1) in a {
+			for &(m2, c2) in b {
+				let (g, m) = m1.mul(m2)?;
+				terms.push((m, c1.mul(c2)?.mul(Ratio::int(g))?));
+			}
+		}
+		Exact::sum(terms)
+	}
+
+	/// The reciprocal of a number with a single term
+	fn exact_recip(&self) -> Option<Self> {
+		let (m, c) = self.single()?;
+		let (r, m) = m.recip()?;
+		Some(Exact::term(m, c.recip()?.mul(r)?))
+	}
+
+	/// The principal square root of a rational multiple of even powers of π and e
+	fn exact_sqrt(&self) -> Option<Self> {
+		if self.is_zero() {
+			return Some(Exact::zero());
+		}
+		let (m, c) = self.single()?;
+		if c.num < 0 || m.pi % 2 != 0 || m.e % 2 != 0 || m.root != 1 {
+			return None;
+		}
+		// √(n/d) = √(nd)/d
+		let (s, root) = square_free((c.num as u128).checked_mul(c.den as u128)?)?;
+		let coef = Ratio::new(i128::try_from(s).ok()?, c.den)?;
+		Some(Exact::term(Monomial { pi: m.pi / 2, e: m.e / 2, root }, coef))
+	}
+
+	/// The logarithm of the number in a base, if it's an integer
+	fn exact_log(&self, base: &Self, ctx: &Context<Exact>) -> Option<Self> {
+		match (self.single()?, base.single()?) {
+			// Powers of e in base e
+			((m, c), (b, d)) if m.pi == 0 && m.root == 1 && c == Ratio::int(1) && b == E && d == Ratio::int(1) => {
+				Exact::rational(i128::from(m.e), 1)
+			}
+			// Integer powers of a rational base
+			((m, c), (b, d)) if m == ONE && b == ONE && c.num > 0 && d.num > 0 && d != Ratio::int(1) => {
+				let n = math::log(c.to_f64(), d.to_f64());
+				if !n.is_finite() || math::abs(n) > 256.0 {
+					return None;
+				}
+				let n = (n + 0.5 * n.signum()) as i128;
+				match num::pow_int(base, n, ctx) {
+					Ok(Answer::Single(ref power)) if power == self => Exact::rational(n, 1),
+					_ => None,
+				}
+			}
+			_ => None,
+		}
+	}
+
+	/// Round a rational to an integer
+	fn exact_round(&self, ctx: &Context<Exact>) -> Option<Self> {
+		let r = self.ratio()?;
+		let t = r.num / r.den;
+		let rem = (r.num % r.den).unsigned_abs();
+		if rem == 0 {
+			return Exact::rational(t, 1);
+		}
+		let half = (rem * 2).cmp(&(r.den as u128));
+		if ctx.cfg.rounding.rounds_away(t % 2 != 0, r.num < 0, half, true) {
+			Exact::rational(if r.num < 0 { t - 1 } else { t + 1 }, 1)
+		} else {
+			Exact::rational(t, 1)
+		}
+	}
+
+	/// The product of the integers from `n` down to 1, going down by `step`
+	fn exact_fact(&self, step: i128) -> Option<Self> {
+		let n = self.integer().filter(|&n| n >= 0)?;
+		let mut product: i128 = 1;
+		let mut k = n;
+		while k > 1 {
+			product = product.checked_mul(k)?;
+			k -= step;
+		}
+		Exact::rational(product, 1)
+	}
+}
+
+impl fmt::Display for Exact {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let terms = match self.repr {
+			Repr::Sum(ref terms) => terms,
+			Repr::Float(x) => return write!(f, "{}", x),
+		};
+		if terms.is_empty() {
+			return write!(f, "0");
+		}
+
+		for (i, &(m, c)) in terms.iter().enumerate() {
+			if i == 0 {
+				if c.num < 0 {
+					write!(f, "-")?;
+				}
+			} else {
+				write!(f, "{}", if c.num < 0 { " - " } else { " + " })?;
+			}
+
+			let factor = |name: &str, power: i32| if power == 1 { name.to_string() } else { format!("{}^{}", name, power) };
+			let (mut num, mut den) = (Vec::new(), Vec::new());
+			for &(name, power) in [("π", m.pi), ("e", m.e)].iter() {
+				match power.cmp(&0) {
+					Ordering::Greater => num.push(factor(name, power)),
+					Ordering::Less => den.push(factor(name, -power)),
+					Ordering::Equal => {}
+				}
+			}
+			if m.root != 1 {
+				num.push(format!("√{}", m.root));
+			}
+
+			let coef = c.num.unsigned_abs();
+			if coef != 1 || num.is_empty() {
+				write!(f, "{}", coef)?;
+			}
+			write!(f, "{}", num.join("·"))?;
+			if c.den != 1 || !den.is_empty() {
+				let parts = den.len() + if c.den != 1 { 1 } else { 0 };
+				let den = format!("{}{}", if c.den != 1 { c.den.to_string() } else { String::new() }, den.join("·"));
+				if parts > 1 {
+					write!(f, "/({})", den)?;
+				} else {
+					write!(f, "/{}", den)?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+impl Num for Exact {
+	fn from_f64(t: f64, ctx: &Context<Self>) -> Calculation<Self> {
+		match Exact::from_decimal(t) {
+			Some(x) => Ok(Answer::Single(x)),
+			None => Ok(from_floats(f64::from_f64(t, &float_ctx(ctx))?)),
+		}
+	}
+
+	fn from_f64_complex((r, _i): (f64, f64), ctx: &Context<Self>) -> Calculation<Self> {
+		Self::from_f64(r, ctx)
+	}
+
+	fn typename() -> String {
+		String::from("Exact")
+	}
+
+	fn pi(_ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Exact::term(PI, Ratio::int(1))))
+	}
+
+	fn e(_ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Exact::term(E, Ratio::int(1))))
+	}
+
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		Ok(self.to_f64())
+	}
+
+	/// Exact numbers are formatted exactly, and floats like `f64`s
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		match self.repr {
+			Repr::Sum(_) => self.to_string(),
+			Repr::Float(x) => format::format_f64(x, fmt),
+		}
+	}
+
+	/// Exact numbers are compared exactly when they're equal, and by their float values otherwise
+	fn tryord(&self, other: &Self, ctx: &Context<Self>) -> Result<Ordering, MathError> {
+		match other.exact_neg().and_then(|neg| self.exact_add(&neg)) {
+			Some(diff) => Ok(diff.sign()),
+			None => self.to_f64().tryord(&other.to_f64(), &float_ctx(ctx)),
+		}
+	}
+
+	fn add(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.exact_add(other), || binary_float(self, other, ctx, <f64 as Num>::add))
+	}
+
+	fn sub(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let diff = other.exact_neg().and_then(|neg| self.exact_add(&neg));
+		exact_or(diff, || binary_float(self, other, ctx, <f64 as Num>::sub))
+	}
+
+	fn mul(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.exact_mul(other), || binary_float(self, other, ctx, <f64 as Num>::mul))
+	}
+
+	/// Dividing by a sum of more than one term isn't exact
+	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if other.is_zero() {
+			return num::div_by_zero(self.sign(), ctx);
+		}
+		let quotient = other.exact_recip().and_then(|recip| self.exact_mul(&recip));
+		exact_or(quotient, || binary_float(self, other, ctx, <f64 as Num>::div))
+	}
+
+	/// Integer powers are exact, and so are powers of halves of numbers with exact square roots
+	fn pow(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if let Some(r) = other.ratio() {
+			if r.den == 1 {
+				return num::pow_int(self, r.num, ctx);
+			} else if r.den == 2 {
+				if let Some(root) = self.exact_sqrt() {
+					return num::pow_int(&root, r.num, ctx);
+				}
+			}
+		}
+		binary_float(self, other, ctx, <f64 as Num>::pow)
+	}
+
+	fn sqrt(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		match self.exact_sqrt() {
+			Some(root) => {
+				let negative = root.exact_neg().unwrap_or_else(|| Exact::float(-root.to_f64()));
+				Ok(num::roots(root, negative, true, ctx))
+			}
+			None => unary_float(self, ctx, <f64 as Num>::sqrt),
+		}
+	}
+
+	/// Only square roots are exact
+	fn nrt(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if other.integer() == Some(2) && self.exact_sqrt().is_some() {
+			return self.sqrt(ctx);
+		}
+		binary_float(self, other, ctx, <f64 as Num>::nrt)
+	}
+
+	fn abs(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		if self.is_exact() && self.sign() == Ordering::Less {
+			return exact_or(self.exact_neg(), || unary_float(self, ctx, <f64 as Num>::abs));
+		}
+		match self.repr {
+			Repr::Sum(_) => Ok(Answer::Single(self.clone())),
+			Repr::Float(_) => unary_float(self, ctx, <f64 as Num>::abs),
+		}
+	}
+
+	fn sin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.pi_twelfths().and_then(sin_twelfths), || unary_float(self, ctx, <f64 as Num>::sin))
+	}
+
+	fn cos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.pi_twelfths().and_then(|t| sin_twelfths(t + 6)), || unary_float(self, ctx, <f64 as Num>::cos))
+	}
+
+	fn tan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.pi_twelfths().and_then(tan_twelfths), || unary_float(self, ctx, <f64 as Num>::tan))
+	}
+
+	fn asin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let angle = (-6..=6).find(|&t| sin_twelfths(t).as_ref() == Some(self));
+		exact_or(angle.and_then(|t| Exact::rational(t, 12)).and_then(|t| t.exact_mul(&Exact::term(PI, Ratio::int(1)))), || {
+			unary_float(self, ctx, <f64 as Num>::asin)
+		})
+	}
+
+	fn acos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let angle = (0..=12).find(|&t| sin_twelfths(t + 6).as_ref() == Some(self));
+		exact_or(angle.and_then(|t| Exact::rational(t, 12)).and_then(|t| t.exact_mul(&Exact::term(PI, Ratio::int(1)))), || {
+			unary_float(self, ctx, <f64 as Num>::acos)
+		})
+	}
+
+	fn atan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let angle = (-5..=5).find(|&t| tan_twelfths(t).as_ref() == Some(self));
+		exact_or(angle.and_then(|t| Exact::rational(t, 12)).and_then(|t| t.exact_mul(&Exact::term(PI, Ratio::int(1)))), || {
+			unary_float(self, ctx, <f64 as Num>::atan)
+		})
+	}
+
+	fn atan2(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		binary_float(self, other, ctx, <f64 as Num>::atan2)
+	}
+
+	fn floor(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let floor = self.ratio().and_then(|r| Exact::rational(r.num.div_euclid(r.den), 1));
+		exact_or(floor, || unary_float(self, ctx, <f64 as Num>::floor))
+	}
+
+	fn ceil(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let ceil = self.ratio().and_then(|r| Exact::rational(-(-r.num).div_euclid(r.den), 1));
+		exact_or(ceil, || unary_float(self, ctx, <f64 as Num>::ceil))
+	}
+
+	/// Rounds with the `rounding` mode of the config
+	fn round(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.exact_round(ctx), || unary_float(self, ctx, <f64 as Num>::round))
+	}
+
+	fn log(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.exact_log(other, ctx), || binary_float(self, other, ctx, <f64 as Num>::log))
+	}
+
+	fn fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.exact_fact(1), || unary_float(self, ctx, <f64 as Num>::fact))
+	}
+
+	fn double_fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		exact_or(self.exact_fact(2), || unary_float(self, ctx, <f64 as Num>::double_fact))
+	}
+
+	/// Exact for positive integers, where it's a factorial
+	fn gamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let fact = self.integer().filter(|&n| n >= 1).and_then(|n| Exact::rational(n - 1, 1)?.exact_fact(1));
+		exact_or(fact, || unary_float(self, ctx, <f64 as Num>::gamma))
+	}
+
+	fn lgamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary_float(self, ctx, <f64 as Num>::lgamma)
+	}
+
+	fn erf(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary_float(self, ctx, <f64 as Num>::erf)
+	}
+
+	fn erfc(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary_float(self, ctx, <f64 as Num>::erfc)
+	}
+
+	fn zeta(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		unary_float(self, ctx, <f64 as Num>::zeta)
+	}
+}
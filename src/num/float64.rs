@@ -1,25 +1,404 @@
-use std::f64;
-use std::cmp::Ordering;
+use core::cmp::Ordering;
 
 use crate::opers::Calculation;
 use crate::errors::MathError;
-use crate::num::Num;
+use crate::format::{self, AnswerFormat};
+use crate::num::{self, Num};
 use crate::answer::Answer;
-use crate::context::Context;
+use crate::context::{Context, DomainPolicy, NanPolicy, RoundingMode};
+use crate::prelude::*;
+
+/// The floating point functions used by `f64`, from the standard library
+#[cfg(feature = "std")]
+pub(crate) mod math {
+	pub fn sqrt(x: f64) -> f64 {
+		x.sqrt()
+	}
+
+	pub fn abs(x: f64) -> f64 {
+		x.abs()
+	}
+
+	pub fn sin(x: f64) -> f64 {
+		x.sin()
+	}
+
+	pub fn cos(x: f64) -> f64 {
+		x.cos()
+	}
+
+	pub fn tan(x: f64) -> f64 {
+		x.tan()
+	}
+
+	pub fn asin(x: f64) -> f64 {
+		x.asin()
+	}
+
+	pub fn acos(x: f64) -> f64 {
+		x.acos()
+	}
+
+	pub fn atan(x: f64) -> f64 {
+		x.atan()
+	}
+
+	pub fn floor(x: f64) -> f64 {
+		x.floor()
+	}
+
+	pub fn ceil(x: f64) -> f64 {
+		x.ceil()
+	}
+
+	pub fn trunc(x: f64) -> f64 {
+		x.trunc()
+	}
+
+	pub fn atan2(y: f64, x: f64) -> f64 {
+		y.atan2(x)
+	}
+
+	pub fn pow(x: f64, y: f64) -> f64 {
+		x.powf(y)
+	}
+
+	pub fn log(x: f64, base: f64) -> f64 {
+		x.log(base)
+	}
+
+	pub fn exp(x: f64) -> f64 {
+		x.exp()
+	}
+
+	pub fn ln(x: f64) -> f64 {
+		x.ln()
+	}
+}
+
+/// The floating point functions used by `f64`, from `libm`
+#[cfg(not(feature = "std"))]
+pub(crate) mod math {
+	pub use libm::{acos, asin, atan, atan2, ceil, cos, exp, fabs as abs, floor, log as ln, pow, sin, sqrt, tan, trunc};
+
+	pub fn log(x: f64, base: f64) -> f64 {
+		libm::log(x) / libm::log(base)
+	}
+}
+
+/// The answer of an operation, unless it's NaN and the config doesn't let NaN propagate
+fn checked(x: f64, ctx: &Context<f64>) -> Calculation<f64> {
+	if x.is_nan() && ctx.cfg.nan_policy == NanPolicy::Error {
+		Err(MathError::NaN)
+	} else {
+		Ok(Answer::Single(x))
+	}
+}
+
+/// Fail with a `MathError::DomainError` if a number is outside of the domain of a function, unless
+/// the config says to result in NaN
+fn check_domain(func: &str, x: f64, outside: bool, ctx: &Context<f64>) -> Result<(), MathError> {
+	if outside && ctx.cfg.negative_sqrt != DomainPolicy::NaN {
+		Err(MathError::DomainError { func: func.to_string(), value: x.to_string() })
+	} else {
+		Ok(())
+	}
+}
+
+/// Round a float to an integer with a rounding mode
+fn round(x: f64, mode: RoundingMode) -> f64 {
+	let t = math::trunc(x);
+	let frac = math::abs(x - t);
+	// Also catches infinities and NaN
+	if frac == 0.0 || frac.is_nan() {
+		return t;
+	}
+
+	let half = if frac > 0.5 { Ordering::Greater } else if frac < 0.5 { Ordering::Less } else { Ordering::Equal };
+	if mode.rounds_away(t % 2.0 != 0.0, x < 0.0, half, true) {
+		t + x.signum()
+	} else {
+		t
+	}
+}
+
+/// The `g` parameter of the Lanczos approximation
+const LANCZOS_G: f64 = 7.0;
+
+/// The coefficients of the Lanczos approximation for `LANCZOS_G`
+const LANCZOS: [f64; 9] = [
+	0.999_999_999_999_809_9,
+	676.520_368_121_885_1,
+	-1_259.139_216_722_402_8,
+	771.323_428_777_653_1,
+	-176.615_029_162_140_6,
+	12.507_343_278_686_905,
+	-0.138_571_095_265_720_12,
+	9.984_369_578_019_572e-6,
+	1.505_632_735_149_311_6e-7,
+];
+
+/// The series of the Lanczos approximation for `gamma(x + 1)`
+fn lanczos_sum(x: f64) -> f64 {
+	let mut sum = LANCZOS[0];
+	for (i, c) in LANCZOS.iter().enumerate().skip(1) {
+		sum += c / (x + i as f64);
+	}
+	sum
+}
+
+/// The gamma function, using the Lanczos approximation
+fn gamma(x: f64) -> f64 {
+	use core::f64::consts::PI;
+
+	if x < 0.5 {
+		// Reflection formula
+		PI / (math::sin(PI * x) * gamma(1.0 - x))
+	} else {
+		let x = x - 1.0;
+		let t = x + LANCZOS_G + 0.5;
+		// t^(x + 0.5) alone overflows for the largest x whose gamma function doesn't, so it's split
+		// in half around e^-t
+		let half = math::pow(t, (x + 0.5) / 2.0);
+		math::sqrt(2.0 * PI) * half * (half * math::exp(-t)) * lanczos_sum(x)
+	}
+}
+
+/// The natural logarithm of the absolute value of the gamma function, using the Lanczos
+/// approximation
+fn ln_gamma(x: f64) -> f64 {
+	use core::f64::consts::PI;
+
+	if x < 0.5 {
+		// Reflection formula
+		math::ln(PI / math::abs(math::sin(PI * x))) - ln_gamma(1.0 - x)
+	} else {
+		let x = x - 1.0;
+		let t = x + LANCZOS_G + 0.5;
+		0.5 * math::ln(2.0 * PI) + (x + 0.5) * math::ln(t) - t + math::ln(lanczos_sum(x))
+	}
+}
+
+/// The Chebyshev coefficients of the complementary error function, from Numerical Recipes
+const ERFC_CHEBYSHEV: [f64; 28] = [
+	-1.302_653_719_781_709_4,
+	6.419_697_923_564_902e-1,
+	1.947_647_320_418_583_6e-2,
+	-9.561_514_786_808_63e-3,
+	-9.465_953_444_820_36e-4,
+	3.668_394_978_527_61e-4,
+	4.252_332_480_690_7e-5,
+	-2.027_857_811_253_4e-5,
+	-1.624_290_004_647e-6,
+	1.303_655_835_580e-6,
+	1.562_644_172_2e-8,
+	-8.523_809_591_5e-8,
+	6.529_054_439e-9,
+	5.059_343_495e-9,
+	-9.913_641_56e-10,
+	-2.273_651_22e-10,
+	9.646_791_1e-11,
+	2.394_038e-12,
+	-6.886_027e-12,
+	8.944_87e-13,
+	3.130_92e-13,
+	-1.127_08e-13,
+	3.81e-16,
+	7.106e-15,
+	-1.523e-15,
+	-9.4e-17,
+	1.21e-16,
+	-2.8e-17,
+];
+
+/// The complementary error function of a non-negative number
+fn erfc_positive(z: f64) -> f64 {
+	let t = 2.0 / (2.0 + z);
+	let ty = 4.0 * t - 2.0;
+	let (mut d, mut dd) = (0.0, 0.0);
+	for c in ERFC_CHEBYSHEV.iter().skip(1).rev() {
+		let tmp = d;
+		d = ty * d - dd + c;
+		dd = tmp;
+	}
+	t * math::exp(-z * z + 0.5 * (ERFC_CHEBYSHEV[0] + ty * d) - dd)
+}
+
+/// The error function
+fn erf(x: f64) -> f64 {
+	if math::abs(x) < 0.5 {
+		// The Taylor series, since 1 - erfc(x) loses the precision of small numbers
+		let mut sum = 0.0;
+		let mut power = x;
+		let mut n = 0.0;
+		loop {
+			let term = power / (2.0 * n + 1.0);
+			sum += term;
+			if math::abs(term) <= 1e-17 * math::abs(sum) {
+				break;
+			}
+			n += 1.0;
+			power *= -x * x / n;
+		}
+		sum * core::f64::consts::FRAC_2_SQRT_PI
+	} else if x > 0.0 {
+		1.0 - erfc_positive(x)
+	} else {
+		erfc_positive(-x) - 1.0
+	}
+}
+
+/// The Riemann zeta function, with the algorithm of Borwein for numbers of at least 1/2 and the
+/// functional equation for the rest
+fn zeta(s: f64) -> f64 {
+	use core::f64::consts::PI;
+	const TERMS: usize = 30;
+
+	if s == 1.0 {
+		return f64::INFINITY;
+	} else if s == 0.0 {
+		return -0.5;
+	} else if s < 0.0 && s % 2.0 == 0.0 {
+		// The trivial zeros, which the functional equation only gets close to
+		return 0.0;
+	} else if s < 0.5 {
+		return math::pow(2.0, s) * math::pow(PI, s - 1.0) * math::sin(PI * s / 2.0) * gamma(1.0 - s) * zeta(1.0 - s);
+	}
+
+	let n = TERMS as f64;
+	let mut d = [0.0; TERMS + 1];
+	let mut term = 1.0;
+	d[0] = 1.0;
+	for i in 1..=TERMS {
+		let i_f = i as f64;
+		term *= 4.0 * (n + i_f - 1.0) * (n - i_f + 1.0) / ((2.0 * i_f) * (2.0 * i_f - 1.0));
+		d[i] = d[i - 1] + term;
+	}
+	let mut sum = 0.0;
+	for k in 0..TERMS {
+		let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+		sum += sign * (d[k] - d[TERMS]) / math::pow(k as f64 + 1.0, s);
+	}
+	-sum / (d[TERMS] * (1.0 - math::pow(2.0, 1.0 - s)))
+}
+
+/// The continued fraction of the regularized incomplete beta function, evaluated with the modified
+/// Lentz method
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+	const TINY: f64 = 1e-300;
+	let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+	let mut c = 1.0;
+	let mut d = 1.0 - qab * x / qap;
+	if math::abs(d) < TINY {
+		d = TINY;
+	}
+	d = 1.0 / d;
+	let mut h = d;
+	for m in 1..300 {
+		let m = f64::from(m);
+		let m2 = 2.0 * m;
+		// The even and odd steps of the recurrence
+		for &aa in [m * (b - m) * x / ((qam + m2) * (a + m2)), -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2))].iter() {
+			d = 1.0 + aa * d;
+			if math::abs(d) < TINY {
+				d = TINY;
+			}
+			c = 1.0 + aa / c;
+			if math::abs(c) < TINY {
+				c = TINY;
+			}
+			d = 1.0 / d;
+			h *= d * c;
+		}
+		if math::abs(d * c - 1.0) < 1e-16 {
+			break;
+		}
+	}
+	h
+}
+
+/// The regularized incomplete beta function `I_x(a, b)` of positive `a` and `b`
+pub(crate) fn beta_inc(a: f64, b: f64, x: f64) -> f64 {
+	if x <= 0.0 {
+		return 0.0;
+	} else if x >= 1.0 {
+		return 1.0;
+	}
+
+	let front = math::exp(ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * math::ln(x) + b * math::ln(1.0 - x));
+	// The continued fraction converges quickly on this side, and the symmetry I_x(a, b) =
+	// 1 - I_(1-x)(b, a) covers the other
+	if x < (a + 1.0) / (a + b + 2.0) {
+		front * beta_fraction(a, b, x) / a
+	} else {
+		1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+	}
+}
+
+/// The largest integer below which every integer is exactly representable
+const EXACT_INTS: f64 = 9_007_199_254_740_992.0;
+
+/// Check that the arguments of `binomial` and `permutations` are non-negative integers
+fn check_counts(n: f64, k: f64) -> Result<(), MathError> {
+	if n % 1.0 != 0.0 || k % 1.0 != 0.0 {
+		Err(MathError::NotAnInteger)
+	} else if n < 0.0 || k < 0.0 {
+		Err(MathError::IncorrectArguments)
+	} else {
+		Ok(())
+	}
+}
+
+/// Multiply a product by `factor / divisor`, exactly while the product is small enough to be an exact
+/// integer, and as the ratio after that so that it doesn't overflow before the result does
+fn mul_ratio(product: f64, factor: f64, divisor: f64) -> f64 {
+	let exact = product * factor;
+	if exact < EXACT_INTS {
+		exact / divisor
+	} else {
+		product * (factor / divisor)
+	}
+}
+
+/// Convert a float to an integer for the bitwise operations, which treat floats as 64 bit signed
+/// integers
+fn to_int(x: f64) -> Result<i64, MathError> {
+	if x % 1.0 == 0.0 && x >= i64::MIN as f64 && x < i64::MAX as f64 {
+		Ok(x as i64)
+	} else {
+		Err(MathError::NotAnInteger)
+	}
+}
+
+/// Convert the amount to shift by to an integer. Only shifts by 0 to 63 bits are allowed.
+fn to_shift(x: f64) -> Result<u32, MathError> {
+	match to_int(x)? {
+		n @ 0..=63 => Ok(n as u32),
+		_ => Err(MathError::IncorrectArguments),
+	}
+}
 
 impl Num for f64 {
-	fn from_f64(t: f64, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(t))
+	fn from_f64(t: f64, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(t, ctx)
 	}
 
-	fn from_f64_complex((r, _i): (f64, f64), _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(r))
+	fn from_f64_complex((r, _i): (f64, f64), ctx: &Context<Self>) -> Calculation<Self> {
+		checked(r, ctx)
 	}
 
 	fn typename() -> String {
 		String::from("f64")
 	}
 
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		Ok(*self)
+	}
+
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		format::format_f64(*self, fmt)
+	}
+
 	/// Compares two floats. Errors if either is NaN. Infinity is greater than anything except equal
 	/// to infinity. Negative infinity is less than anything except equal to negative infinity.
 	fn tryord(&self, other: &Self, ctx: &Context<Self>) -> Result<Ordering, MathError> {
@@ -42,89 +421,266 @@ impl Num for f64 {
 		} else if other.is_infinite() {
 			Ok(other.tryord(&self, ctx)?.reverse())
 		} else {
-			Ok(self.partial_cmp(other).unwrap())
+			self.partial_cmp(other).ok_or(MathError::CmpError)
 		}
 	}
 
-	fn add(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(*self + *other))
+	fn add(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(*self + *other, ctx)
 	}
 
-	fn sub(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(*self - *other))
+	fn sub(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(*self - *other, ctx)
 	}
 
-	fn mul(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(*self * *other))
+	fn mul(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(*self * *other, ctx)
 	}
 
-	fn div(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
 		if *other == 0.0 {
-			return Err(MathError::DivideByZero);
+			// Dividing by negative zero flips the sign of the infinity
+			let sign = self.partial_cmp(&0.0).unwrap_or(Ordering::Equal);
+			return num::div_by_zero(if other.is_sign_negative() { sign.reverse() } else { sign }, ctx);
 		}
 
-		Ok(Answer::Single(*self / *other))
+		checked(*self / *other, ctx)
 	}
 
-	fn pow(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(self.powf(*other)))
+	fn pow(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if *self == 0.0 && *other == 0.0 {
+			return num::zero_pow_zero(ctx);
+		} else if *self == 0.0 && *other < 0.0 {
+			// Only odd powers keep the sign of negative zero
+			let odd = *other % 2.0 == -1.0 && self.is_sign_negative();
+			return num::div_by_zero(if odd { Ordering::Less } else { Ordering::Greater }, ctx);
+		}
+
+		// Integer powers aren't multiplied out like they are for the other types, since `pow` is more
+		// accurate and doesn't overflow partway through negative powers
+		checked(math::pow(*self, *other), ctx)
 	}
 
 	fn sqrt(&self, ctx: &Context<Self>) -> Calculation<Self> {
-		let sqrt = f64::sqrt(*self);
+		check_domain("sqrt", *self, *self < 0.0, ctx)?;
+		let sqrt = math::sqrt(*self);
+		if sqrt.is_nan() && ctx.cfg.nan_policy == NanPolicy::Error {
+			return Err(MathError::NaN);
+		}
+
+		Ok(num::roots(sqrt, -sqrt, true, ctx))
+	}
+
+	/// The real `other`th root. Negative numbers only have one for odd integer roots, and the roots of
+	/// positive numbers are both positive and negative for even integer roots.
+	fn nrt(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let odd = *other % 2.0 == 1.0 || *other % 2.0 == -1.0;
+		let even = *other % 2.0 == 0.0;
+		check_domain("nrt", *self, *self < 0.0 && !odd, ctx)?;
+		let root = if *self < 0.0 && odd {
+			-math::pow(-*self, 1.0 / *other)
+		} else {
+			math::pow(*self, 1.0 / *other)
+		};
+		if root.is_nan() && ctx.cfg.nan_policy == NanPolicy::Error {
+			return Err(MathError::NaN);
+		}
+
+		Ok(num::roots(root, -root, even, ctx))
+	}
+
+	fn abs(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::abs(*self), ctx)
+	}
+
+	fn sin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::sin(*self), ctx)
+	}
 
-		Ok(if ctx.cfg.sqrt_both {
-			Answer::Multiple(vec![sqrt, -sqrt])
+	fn cos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::cos(*self), ctx)
+	}
+
+	fn tan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::tan(*self), ctx)
+	}
+
+	fn asin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		check_domain("asin", *self, math::abs(*self) > 1.0, ctx)?;
+		checked(math::asin(*self), ctx)
+	}
+
+	fn acos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		check_domain("acos", *self, math::abs(*self) > 1.0, ctx)?;
+		checked(math::acos(*self), ctx)
+	}
+
+	fn atan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::atan(*self), ctx)
+	}
+
+	fn atan2(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::atan2(*self, *other), ctx)
+	}
+
+	fn floor(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::floor(*self), ctx)
+	}
+
+	fn ceil(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(math::ceil(*self), ctx)
+	}
+
+	/// Rounds with the `rounding` mode of the config
+	fn round(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(round(*self, ctx.cfg.rounding), ctx)
+	}
+
+	fn log(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		check_domain("log", *self, *self < 0.0, ctx)?;
+		check_domain("log", *other, *other < 0.0, ctx)?;
+		checked(math::log(*self, *other), ctx)
+	}
+
+	/// The factorial of non-negative integers is exact up to 22!, approximate up to 170!, and infinity
+	/// after that. The factorial of other numbers is the gamma function of the number plus one, which
+	/// is NaN for negative integers.
+	fn fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		Num::gamma(&(*self + 1.0), ctx)
+	}
+
+	fn double_fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let x = *self;
+		checked(if x % 1.0 == 0.0 && x >= -1.0 {
+			let mut product = 1.0;
+			let mut n = x;
+			while n > 1.0 && product.is_finite() {
+				product *= n;
+				n -= 2.0;
+			}
+			product
+		} else {
+			f64::NAN
+		}, ctx)
+	}
+
+	/// Calculated as a product for positive integers, which is exact up to 23, approximate up to 171,
+	/// and infinity after that. The gamma function of zero and negative integers is NaN.
+	fn gamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let x = *self;
+		checked(if x % 1.0 == 0.0 && x > 0.0 {
+			if x > 171.0 {
+				f64::INFINITY
+			} else {
+				(1..x as u32).fold(1.0, |acc, n| acc * f64::from(n))
+			}
+		} else if x % 1.0 == 0.0 {
+			f64::NAN
+		} else {
+			gamma(x)
+		}, ctx)
+	}
+
+	/// Infinity for zero and negative integers, where the gamma function has poles
+	fn lgamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let x = *self;
+		checked(if x % 1.0 == 0.0 && x <= 0.0 {
+			f64::INFINITY
+		} else if x == 1.0 || x == 2.0 {
+			0.0
 		} else {
-			Answer::Single(sqrt)
-		})
+			ln_gamma(x)
+		}, ctx)
 	}
 
-	fn abs(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::abs(*self)))
+	fn erf(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(erf(*self), ctx)
 	}
 
-	fn sin(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::sin(*self)))
+	fn erfc(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let x = *self;
+		checked(if math::abs(x) < 0.5 {
+			1.0 - erf(x)
+		} else if x > 0.0 {
+			erfc_positive(x)
+		} else {
+			2.0 - erfc_positive(-x)
+		}, ctx)
 	}
 
-	fn cos(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::cos(*self)))
+	/// Calculated with the logarithm of the gamma function for positive numbers whose gamma functions
+	/// would overflow
+	fn beta(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let (a, b) = (*self, *other);
+		if a > 0.0 && b > 0.0 && a + b > 171.0 {
+			checked(math::exp(ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)), ctx)
+		} else {
+			let gamma = |x: f64| Num::gamma(&x, ctx).map(Answer::unwrap_single);
+			checked(gamma(a)? * gamma(b)? / gamma(a + b)?, ctx)
+		}
 	}
 
-	fn tan(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::tan(*self)))
+	/// Infinity at the pole at 1
+	fn zeta(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		checked(zeta(*self), ctx)
 	}
 
-	fn asin(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::asin(*self)))
+	/// Calculated one factor at a time instead of with factorials, so it's exact while the result is
+	/// below 2^53 and only overflows to infinity if the result is too large for an `f64`
+	fn binomial(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let (n, k) = (*self, *k);
+		check_counts(n, k)?;
+		if k > n {
+			return Ok(Answer::Single(0.0));
+		}
+
+		let k = k.min(n - k);
+		let mut result = 1.0;
+		let mut i = 1.0;
+		while i <= k && result.is_finite() {
+			result = mul_ratio(result, n - k + i, i);
+			i += 1.0;
+		}
+		checked(result, ctx)
 	}
 
-	fn acos(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::acos(*self)))
+	fn permutations(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let (n, k) = (*self, *k);
+		check_counts(n, k)?;
+		if k > n {
+			return Ok(Answer::Single(0.0));
+		}
+
+		let mut result = 1.0;
+		let mut m = n;
+		while m > n - k && result.is_finite() {
+			result *= m;
+			m -= 1.0;
+		}
+		checked(result, ctx)
 	}
 
-	fn atan(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::atan(*self)))
+	fn bitand(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single((to_int(*self)? & to_int(*other)?) as f64))
 	}
 
-	fn atan2(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::atan2(*self, *other)))
+	fn bitor(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single((to_int(*self)? | to_int(*other)?) as f64))
 	}
 
-	fn floor(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::floor(*self)))
+	fn bitxor(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single((to_int(*self)? ^ to_int(*other)?) as f64))
 	}
 
-	fn ceil(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::ceil(*self)))
+	fn shl(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single((to_int(*self)? << to_shift(*other)?) as f64))
 	}
 
-	fn round(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::round(*self)))
+	fn shr(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single((to_int(*self)? >> to_shift(*other)?) as f64))
 	}
 
-	fn log(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Ok(Answer::Single(f64::log(*self, *other)))
+	fn bitnot(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(!to_int(*self)? as f64))
 	}
 }
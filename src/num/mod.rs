@@ -1,18 +1,21 @@
 //! This module contains the `Num` trait and its implementations.
 //!
 //! The `Num` trait defines the inner workings of this library. Any type that implements the `Num` trait
-//! can be used to represent a number in an equation. There are currently five predefined implementors
+//! can be used to represent a number in an equation. There are currently six predefined implementors
 //! of the `Num` trait, but that number is subject to change (with additions and removals). You can also
 //! define your own `Num`, but hopefully a fitting one already exists for you here.
 //!
-//! The five nums are:
+//! The six nums are:
 //! - `f64`
 //! - `ComplexFloat`
 //! - `ComplexRugRat`
 //! - `rug::Complex`
 //! - `rug::Rational`
+//! - `Decimal` (with the `decimal` feature)
 //!
 //! Each have different strengths and weaknesses.
+//! Whether a type supports an operation can be checked with `Num::supports`, and all the operations
+//! it supports can be gotten with `Num::capabilities`.
 //!
 //! `f64` implements all functions, but suffers the limitations `f64`s usually suffer from (low precision,
 //!  NaN/infinity errors, etc).
@@ -28,12 +31,19 @@
 //!
 //! `rug::Rational` is just a rational number, and also supports very few operations.
 //!
+//! There is also `AnyNum`, which holds one of `f64`, `rug::Complex`, `rug::Rational` or `Decimal`
+//! (depending on the features) chosen at runtime with the `num_kind` of the config, for applications
+//! that let their users choose the number type.
+//!
+//! `Exact` keeps `pi`, `e` and square roots symbolic, so that answers like `sin(pi)` and `sqrt(2)^2`
+//! are exact, and falls back to `f64`s for operations whose answers it can't represent.
+//!
 //! To see the progress on implementations of `Num` types, see the the [issues on GitHub](https://github.com/IntrepidPig/mexprp/issues?utf8=%E2%9C%93&q=is%3Aissue+is%3Aopen+label%3Anumber)
 //! with the label "number"
 
-use std::fmt;
-use std::marker::Sized;
-use std::cmp::Ordering;
+use core::fmt;
+use core::marker::Sized;
+use core::cmp::Ordering;
 
 #[cfg(feature = "rug")]
 mod complexrugrat;
@@ -43,21 +53,276 @@ mod rugrat;
 mod rugcomplex;
 mod complexfloat;
 mod float64;
+mod anynum;
+mod exact;
+#[cfg(feature = "decimal")]
+mod decimal;
 
 #[cfg(feature = "rug")]
 pub use self::complexrugrat::ComplexRugRat;
 #[cfg(feature = "rug")]
 pub use self::complexfloat::ComplexFloat;
+#[cfg(feature = "decimal")]
+pub use self::decimal::Decimal;
+pub use self::anynum::{AnyNum, NumKind};
+pub use self::exact::Exact;
+pub(crate) use self::float64::beta_inc;
 
 use crate::opers::Calculation;
 use crate::errors::MathError;
-use crate::context::Context;
+use crate::format::AnswerFormat;
+use crate::context::{Context, ZeroDivision};
+use crate::answer::{Answer, Label};
+use crate::prelude::*;
+
+/// The answer of dividing a number with the given sign by zero, according to the `zero_division`
+/// policy of the config
+pub(crate) fn div_by_zero<N: Num>(sign: Ordering, ctx: &Context<N>) -> Calculation<N> {
+	let value = match (ctx.cfg.zero_division, sign) {
+		(ZeroDivision::Error, _) => return Err(MathError::DivideByZero),
+		(ZeroDivision::Infinity, Ordering::Greater) => f64::INFINITY,
+		(ZeroDivision::Infinity, Ordering::Less) => f64::NEG_INFINITY,
+		(ZeroDivision::Infinity, Ordering::Equal) | (ZeroDivision::NaN, _) => f64::NAN,
+	};
+	// Types that can't represent the value can't divide by zero at all
+	N::from_f64(value, ctx).map_err(|_| MathError::DivideByZero)
+}
+
+/// The answer of `0^0`, according to the `zero_division` policy of the config
+pub(crate) fn zero_pow_zero<N: Num>(ctx: &Context<N>) -> Calculation<N> {
+	match ctx.cfg.zero_division {
+		ZeroDivision::NaN => N::from_f64(f64::NAN, ctx).map_err(|_| MathError::DivideByZero),
+		_ => N::from_f64(1.0, ctx),
+	}
+}
+
+/// Raise a number to an integer power by squaring, which is exact for the types that multiply exactly.
+/// Negative powers are the reciprocal of the positive power.
+pub(crate) fn pow_int<N: Num>(base: &N, exp: i128, ctx: &Context<N>) -> Calculation<N> {
+	let one = N::from_f64(1.0, ctx)?.unwrap_single();
+	if exp == 0 && base.to_f64_complex_lossy().ok() == Some((0.0, 0.0)) {
+		return zero_pow_zero(ctx);
+	}
+
+	let mut result = one.clone();
+	let mut base = base.clone();
+	let mut n = exp.unsigned_abs();
+	while n > 0 {
+		if n % 2 == 1 {
+			result = result.mul(&base, ctx)?.unwrap_single();
+		}
+		n /= 2;
+		if n > 0 {
+			base = base.mul(&base, ctx)?.unwrap_single();
+		}
+	}
+
+	if exp < 0 {
+		one.div(&result, ctx)
+	} else {
+		Ok(Answer::Single(result))
+	}
+}
+
+/// Check that the arguments of `Num::binomial` and `Num::permutations` are non-negative integers
+fn check_counts<N: Num>(n: &N, k: &N, zero: &N, ctx: &Context<N>) -> Result<(), MathError> {
+	for x in [n, k].iter() {
+		if x.floor(ctx)?.unwrap_single().tryord(x, ctx)? != Ordering::Equal {
+			return Err(MathError::NotAnInteger);
+		} else if x.tryord(zero, ctx)? == Ordering::Less {
+			return Err(MathError::IncorrectArguments);
+		}
+	}
+	Ok(())
+}
+
+/// Get a number as an integer exponent for `pow_int`, if it's exactly an integer
+pub(crate) fn int_exponent<N: Num>(n: &N, ctx: &Context<N>) -> Option<i128> {
+	let (re, im) = n.to_f64_complex_lossy().ok()?;
+	// Larger integers aren't exact as f64s, and would overflow anyway
+	if im != 0.0 || re % 1.0 != 0.0 || re.abs() >= 9_007_199_254_740_992.0 {
+		return None;
+	}
+	match N::from_f64(re, ctx) {
+		Ok(Answer::Single(ref m)) if m == n => Some(re as i128),
+		_ => None,
+	}
+}
+
+/// The answer of a root, which is the principal root along with its negation if the root is even and
+/// the config asks for both, like `sqrt` does
+pub(crate) fn roots<N: Num>(principal: N, negative: N, even: bool, ctx: &Context<N>) -> Answer<N> {
+	if even && ctx.cfg.sqrt_both && !ctx.cfg.single_valued {
+		Answer::Labeled(vec![(Label::Principal, principal), (Label::Negative, negative)])
+	} else {
+		Answer::Single(principal)
+	}
+}
+
+/// An operation of the `Num` trait, for checking whether a `Num` type supports it with
+/// `Num::supports`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+	/// Addition
+	Add,
+	/// Subtraction
+	Sub,
+	/// Multiplication
+	Mul,
+	/// Division, also used by the `%` operator
+	Div,
+	/// Exponentiation
+	Pow,
+	/// Square roots
+	Sqrt,
+	/// Nth roots
+	Nrt,
+	/// Absolute values
+	Abs,
+	/// Sine
+	Sin,
+	/// Cosine
+	Cos,
+	/// Tangent
+	Tan,
+	/// Arcsine
+	Asin,
+	/// Arccosine
+	Acos,
+	/// Arctangent
+	Atan,
+	/// Two argument arctangent
+	Atan2,
+	/// Rounding down
+	Floor,
+	/// Rounding up
+	Ceil,
+	/// Rounding
+	Round,
+	/// Logarithms
+	Log,
+	/// Factorials (the `!` operator)
+	Fact,
+	/// Double factorials (the `!!` operator)
+	DoubleFact,
+	/// Binomial coefficients (the `ncr` function)
+	Binomial,
+	/// Permutations (the `npr` function)
+	Permutations,
+	/// The gamma function
+	Gamma,
+	/// The logarithm of the gamma function
+	LnGamma,
+	/// The error function
+	Erf,
+	/// The complementary error function
+	Erfc,
+	/// The beta function
+	Beta,
+	/// The Riemann zeta function
+	Zeta,
+	/// Comparisons
+	Compare,
+	/// Bitwise and
+	BitAnd,
+	/// Bitwise or
+	BitOr,
+	/// Bitwise exclusive or
+	BitXor,
+	/// Left shifts
+	Shl,
+	/// Right shifts
+	Shr,
+	/// Bitwise not
+	BitNot,
+}
+
+impl Operation {
+	/// All of the operations
+	pub const ALL: [Operation; 36] = [
+		Operation::Add,
+		Operation::Sub,
+		Operation::Mul,
+		Operation::Div,
+		Operation::Pow,
+		Operation::Sqrt,
+		Operation::Nrt,
+		Operation::Abs,
+		Operation::Sin,
+		Operation::Cos,
+		Operation::Tan,
+		Operation::Asin,
+		Operation::Acos,
+		Operation::Atan,
+		Operation::Atan2,
+		Operation::Floor,
+		Operation::Ceil,
+		Operation::Round,
+		Operation::Log,
+		Operation::Fact,
+		Operation::DoubleFact,
+		Operation::Binomial,
+		Operation::Permutations,
+		Operation::Gamma,
+		Operation::LnGamma,
+		Operation::Erf,
+		Operation::Erfc,
+		Operation::Beta,
+		Operation::Zeta,
+		Operation::Compare,
+		Operation::BitAnd,
+		Operation::BitOr,
+		Operation::BitXor,
+		Operation::Shl,
+		Operation::Shr,
+		Operation::BitNot,
+	];
+}
+
+/// The set of operations a `Num` type supports, from `Num::capabilities`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct NumCapabilities {
+	bits: u64,
+}
+
+impl NumCapabilities {
+	/// Create an empty set of operations
+	pub fn new() -> Self {
+		NumCapabilities { bits: 0 }
+	}
+
+	/// Add an operation to the set
+	pub fn insert(&mut self, op: Operation) {
+		self.bits |= 1 << op as u64;
+	}
+
+	/// Check if an operation is in the set
+	pub fn contains(&self, op: Operation) -> bool {
+		self.bits & (1 << op as u64) != 0
+	}
+
+	/// The amount of operations in the set
+	pub fn len(&self) -> usize {
+		self.bits.count_ones() as usize
+	}
+
+	/// Check if there are no operations in the set
+	pub fn is_empty(&self) -> bool {
+		self.bits == 0
+	}
+
+	/// Iterate over the operations in the set
+	pub fn iter(&self) -> impl Iterator<Item = Operation> + '_ {
+		Operation::ALL.iter().cloned().filter(move |&op| self.contains(op))
+	}
+}
 
 /// A `Num` represents any type that can be used in an expression. It requires lots of operations to
 /// be implemented for it, any of which can fail, as well as the traits: Debug, Clone, Display, PartialOrd,
-/// and PartialEq.
+/// and PartialEq. Numbers also have to be `Send` and `Sync`, so that terms and contexts can be shared
+/// between threads.
 #[allow(missing_docs)]
-pub trait Num: fmt::Debug + fmt::Display + Clone + PartialEq
+pub trait Num: fmt::Debug + fmt::Display + Clone + PartialEq + Send + Sync
 where
 	Self: Sized,
 {
@@ -70,6 +335,127 @@ where
 	/// Returns the name of this Num type (used for errors)
 	fn typename() -> String;
 
+	/// The constant π, with the precision of the context. The default implementation converts it
+	/// from an `f64`, so types with more precision than that should override it.
+	fn pi(ctx: &Context<Self>) -> Calculation<Self> {
+		Self::from_f64(core::f64::consts::PI, ctx)
+	}
+
+	/// The constant e (Euler's number), with the precision of the context. The default
+	/// implementation converts it from an `f64`, so types with more precision than that should
+	/// override it.
+	fn e(ctx: &Context<Self>) -> Calculation<Self> {
+		Self::from_f64(core::f64::consts::E, ctx)
+	}
+
+	/// Converts this number to an `f64`, possibly losing precision. Complex numbers are converted
+	/// using their real part.
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		Err(MathError::Unimplemented {
+			op: "Conversion to f64".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+
+	/// Converts this number to a real and an imaginary `f64`, possibly losing precision. The default
+	/// implementation uses `to_f64_lossy` for the real part, with an imaginary part of zero.
+	fn to_f64_complex_lossy(&self) -> Result<(f64, f64), MathError> {
+		Ok((self.to_f64_lossy()?, 0.0))
+	}
+
+	/// Check whether this number is finite (not infinite or NaN). The default implementation checks
+	/// `to_f64_complex_lossy`, so types that can hold numbers too large for an `f64` should override
+	/// it.
+	fn is_finite(&self) -> bool {
+		match self.to_f64_complex_lossy() {
+			Ok((r, i)) => r.is_finite() && i.is_finite(),
+			Err(_) => true,
+		}
+	}
+
+	/// Check whether this number (or either of its parts) is NaN. The default implementation checks
+	/// `to_f64_complex_lossy`.
+	fn is_nan(&self) -> bool {
+		match self.to_f64_complex_lossy() {
+			Ok((r, i)) => r.is_nan() || i.is_nan(),
+			Err(_) => false,
+		}
+	}
+
+	/// Format this number with the given options. The default implementation ignores the options
+	/// and uses `Display`.
+	fn format(&self, _fmt: &AnswerFormat) -> String {
+		self.to_string()
+	}
+
+	/// Check whether this type supports an operation, so that frontends can disable operations that
+	/// aren't supported. The default implementation tries the operation with the number 2 and checks
+	/// whether it returns a `MathError::Unimplemented`.
+	fn supports(op: Operation) -> bool
+	where
+		Self: 'static,
+	{
+		let ctx = &Context::empty();
+		let a = match Self::from_f64(2.0, ctx) {
+			Ok(Answer::Single(a)) => a,
+			_ => return false,
+		};
+		let res = match op {
+			Operation::Add => a.add(&a, ctx),
+			Operation::Sub => a.sub(&a, ctx),
+			Operation::Mul => a.mul(&a, ctx),
+			Operation::Div => a.div(&a, ctx),
+			Operation::Pow => a.pow(&a, ctx),
+			Operation::Sqrt => a.sqrt(ctx),
+			Operation::Nrt => a.nrt(&a, ctx),
+			Operation::Abs => a.abs(ctx),
+			Operation::Sin => a.sin(ctx),
+			Operation::Cos => a.cos(ctx),
+			Operation::Tan => a.tan(ctx),
+			Operation::Asin => a.asin(ctx),
+			Operation::Acos => a.acos(ctx),
+			Operation::Atan => a.atan(ctx),
+			Operation::Atan2 => a.atan2(&a, ctx),
+			Operation::Floor => a.floor(ctx),
+			Operation::Ceil => a.ceil(ctx),
+			Operation::Round => a.round(ctx),
+			Operation::Log => a.log(&a, ctx),
+			Operation::Fact => a.fact(ctx),
+			Operation::DoubleFact => a.double_fact(ctx),
+			Operation::Binomial => a.binomial(&a, ctx),
+			Operation::Permutations => a.permutations(&a, ctx),
+			Operation::Gamma => a.gamma(ctx),
+			Operation::LnGamma => a.lgamma(ctx),
+			Operation::Erf => a.erf(ctx),
+			Operation::Erfc => a.erfc(ctx),
+			Operation::Beta => a.beta(&a, ctx),
+			Operation::Zeta => a.zeta(ctx),
+			Operation::Compare => a.tryord(&a, ctx).map(|_| Answer::Single(a.clone())),
+			Operation::BitAnd => a.bitand(&a, ctx),
+			Operation::BitOr => a.bitor(&a, ctx),
+			Operation::BitXor => a.bitxor(&a, ctx),
+			Operation::Shl => a.shl(&a, ctx),
+			Operation::Shr => a.shr(&a, ctx),
+			Operation::BitNot => a.bitnot(ctx),
+		};
+		!matches!(res, Err(MathError::Unimplemented { .. }))
+	}
+
+	/// Get the set of all the operations this type supports. The default implementation checks each
+	/// operation with `supports`.
+	fn capabilities() -> NumCapabilities
+	where
+		Self: 'static,
+	{
+		let mut capabilities = NumCapabilities::new();
+		for &op in Operation::ALL.iter() {
+			if Self::supports(op) {
+				capabilities.insert(op);
+			}
+		}
+		capabilities
+	}
+
 	fn tryord(&self, _other: &Self, _ctx: &Context<Self>) -> Result<Ordering, MathError> {
 		Err(MathError::Unimplemented {
 			op: "Comparison".to_string(),
@@ -100,11 +486,16 @@ where
 			num_type: Self::typename(),
 		})
 	}
-	fn pow(&self, _other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
-		Err(MathError::Unimplemented {
-			op: "Exponent".to_string(),
-			num_type: Self::typename(),
-		})
+	/// Raises the number to a power. The default implementation supports integer powers by squaring
+	/// with `mul` and `div`, which makes them exact for exact types like `rug::Rational`.
+	fn pow(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		match int_exponent(other, ctx) {
+			Some(exp) => pow_int(self, exp, ctx),
+			None => Err(MathError::Unimplemented {
+				op: "Exponent".to_string(),
+				num_type: Self::typename(),
+			}),
+		}
 	}
 	fn sqrt(&self, _ctx: &Context<Self>) -> Calculation<Self> {
 		Err(MathError::Unimplemented {
@@ -112,6 +503,8 @@ where
 			num_type: Self::typename(),
 		})
 	}
+	/// The `other`th root of the number. Even roots have a positive and a negative value, which are
+	/// both returned when `sqrt_both` is enabled in the config, like `sqrt`.
 	fn nrt(&self, _other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
 		Err(MathError::Unimplemented {
 			op: "Nth Root".to_string(),
@@ -190,4 +583,172 @@ where
 			num_type: Self::typename(),
 		})
 	}
+	/// The factorial of the number, or the gamma function of the number plus one for types that
+	/// support it
+	fn fact(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Factorial".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	/// The double factorial of the number, which is the product of the positive integers up to it
+	/// that are odd if it's odd or even if it's even. The default implementation multiplies them
+	/// with `mul`, and fails with a `MathError::NotAnInteger` for numbers that aren't integers
+	/// (checked with `floor`) or a `MathError::IncorrectArguments` for negative numbers other than -1.
+	fn double_fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let num = |n: f64| Self::from_f64(n, ctx).map(Answer::unwrap_single);
+		if self.floor(ctx)?.unwrap_single().tryord(self, ctx)? != Ordering::Equal {
+			return Err(MathError::NotAnInteger);
+		} else if self.tryord(&num(-1.0)?, ctx)? == Ordering::Less {
+			return Err(MathError::IncorrectArguments);
+		}
+
+		let one = num(1.0)?;
+		let two = num(2.0)?;
+		let mut product = one.clone();
+		let mut n = self.clone();
+		while n.tryord(&one, ctx)? == Ordering::Greater {
+			product = product.mul(&n, ctx)?.unwrap_single();
+			n = n.sub(&two, ctx)?.unwrap_single();
+		}
+		Ok(Answer::Single(product))
+	}
+	/// The binomial coefficient of the number and `k`, which is the amount of ways to choose `k`
+	/// things out of this many. The default implementation multiplies and divides by one factor at a
+	/// time, which is exact for the types that multiply and divide integers exactly. It fails with a
+	/// `MathError::NotAnInteger` for numbers that aren't integers (checked with `floor`) or a
+	/// `MathError::IncorrectArguments` for negative numbers, and is 0 if `k` is larger than the number.
+	fn binomial(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let num = |n: f64| Self::from_f64(n, ctx).map(Answer::unwrap_single);
+		let zero = num(0.0)?;
+		check_counts(self, k, &zero, ctx)?;
+		if k.tryord(self, ctx)? == Ordering::Greater {
+			return Ok(Answer::Single(zero));
+		}
+
+		// C(n, k) = C(n, n - k), and the smaller one needs fewer factors
+		let rest = self.sub(k, ctx)?.unwrap_single();
+		let k = if rest.tryord(k, ctx)? == Ordering::Less { rest } else { k.clone() };
+		let start = self.sub(&k, ctx)?.unwrap_single();
+		let one = num(1.0)?;
+		let mut result = one.clone();
+		let mut i = one.clone();
+		// Each partial result is C(n - k + i, i), which is an integer
+		while i.tryord(&k, ctx)? != Ordering::Greater {
+			let factor = start.add(&i, ctx)?.unwrap_single();
+			result = result.mul(&factor, ctx)?.unwrap_single().div(&i, ctx)?.unwrap_single();
+			i = i.add(&one, ctx)?.unwrap_single();
+		}
+		Ok(Answer::Single(result))
+	}
+	/// The amount of ways to arrange `k` things out of this many, which is the product of the `k`
+	/// integers up to the number. The default implementation multiplies them with `mul`, and fails
+	/// like `binomial` does.
+	fn permutations(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let num = |n: f64| Self::from_f64(n, ctx).map(Answer::unwrap_single);
+		let zero = num(0.0)?;
+		check_counts(self, k, &zero, ctx)?;
+		if k.tryord(self, ctx)? == Ordering::Greater {
+			return Ok(Answer::Single(zero));
+		}
+
+		let one = num(1.0)?;
+		let stop = self.sub(k, ctx)?.unwrap_single();
+		let mut result = one.clone();
+		let mut n = self.clone();
+		while n.tryord(&stop, ctx)? == Ordering::Greater {
+			result = result.mul(&n, ctx)?.unwrap_single();
+			n = n.sub(&one, ctx)?.unwrap_single();
+		}
+		Ok(Answer::Single(result))
+	}
+	/// The gamma function, which is the factorial of the number minus one for positive integers
+	fn gamma(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Gamma Function".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	/// The natural logarithm of the absolute value of the gamma function, which doesn't overflow for
+	/// numbers where the gamma function does
+	fn lgamma(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Log Gamma Function".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	/// The error function
+	fn erf(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Error Function".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	/// The complementary error function, `1 - erf(x)`. The default implementation subtracts `erf`
+	/// from one, so types that can be more precise for large numbers should override it.
+	fn erfc(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let one = Self::from_f64(1.0, ctx)?.unwrap_single();
+		self.erf(ctx)?.unop_ctx(ctx, |erf| one.sub(erf, ctx))
+	}
+	/// The beta function of the number and `other`. The default implementation is
+	/// `gamma(a) × gamma(b) / gamma(a + b)`.
+	fn beta(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let a = self.gamma(ctx)?.unwrap_single();
+		let b = other.gamma(ctx)?.unwrap_single();
+		let ab = self.add(other, ctx)?.unwrap_single().gamma(ctx)?.unwrap_single();
+		a.mul(&b, ctx)?.unop_ctx(ctx, |product| product.div(&ab, ctx))
+	}
+	/// The Riemann zeta function
+	fn zeta(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Zeta Function".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	fn bitand(&self, _other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Bitwise And".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	fn bitor(&self, _other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Bitwise Or".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	fn bitxor(&self, _other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Bitwise Xor".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	fn shl(&self, _other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Left Shift".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	fn shr(&self, _other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Right Shift".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	fn bitnot(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Err(MathError::Unimplemented {
+			op: "Bitwise Not".to_string(),
+			num_type: Self::typename(),
+		})
+	}
+	/// Returns -1, 0, or 1 depending on the sign of the number. The default implementation compares
+	/// the number to zero with `tryord`.
+	fn sign(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		let zero = Self::from_f64(0.0, ctx)?.unwrap_single();
+		match self.tryord(&zero, ctx)? {
+			Ordering::Less => Self::from_f64(-1.0, ctx),
+			Ordering::Equal => Self::from_f64(0.0, ctx),
+			Ordering::Greater => Self::from_f64(1.0, ctx),
+		}
+	}
 }
@@ -1,13 +1,52 @@
 use std::cmp::Ordering;
 
 
-use rug::Complex;
+use rug::{Complex, Float};
+use rug::float::Constant;
 use rug::ops::Pow;
 use crate::opers::Calculation;
 use crate::errors::MathError;
 use crate::answer::Answer;
-use crate::num::Num;
-use crate::context::Context;
+use crate::num::{self, Num};
+use crate::context::{Context, RoundingMode};
+use crate::format::AnswerFormat;
+
+/// Check whether a complex number is zero
+fn is_zero(c: &Complex) -> bool {
+	*c.real() == 0 && *c.imag() == 0
+}
+
+/// Apply a function of real numbers to a complex number, failing if it isn't real
+fn real_only<F: FnOnce(Float) -> Float>(c: &Complex, op: &str, ctx: &Context<Complex>, f: F) -> Calculation<Complex> {
+	if !c.imag().is_zero() {
+		return Err(MathError::Unimplemented {
+			op: format!("Complex {}", op),
+			num_type: Complex::typename(),
+		});
+	}
+
+	let r = f(Float::with_val(ctx.cfg.precision, c.real()));
+
+	Ok(Answer::Single(Complex::with_val(ctx.cfg.precision, r)))
+}
+
+/// Round a float to an integer with a rounding mode
+fn round(x: &Float, mode: RoundingMode) -> Float {
+	let t = Float::with_val(x.prec(), x.trunc_ref());
+	let frac = Float::with_val(x.prec(), x - &t).abs();
+	// Also catches infinities and NaN
+	if !x.is_finite() || frac == 0 {
+		return t;
+	}
+
+	let half = frac.partial_cmp(&0.5).unwrap_or(Ordering::Less);
+	let odd = !Float::with_val(x.prec(), &t / 2).is_integer();
+	if mode.rounds_away(odd, *x < 0, half, true) {
+		if *x < 0 { t - 1 } else { t + 1 }
+	} else {
+		t
+	}
+}
 
 impl Num for Complex {
 	fn from_f64(t: f64, ctx: &Context<Self>) -> Calculation<Self> {
@@ -22,6 +61,42 @@ impl Num for Complex {
 		String::from("Complex")
 	}
 
+	fn pi(ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Complex::with_val(ctx.cfg.precision, Float::with_val(ctx.cfg.precision, Constant::Pi))))
+	}
+
+	fn e(ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Complex::with_val(ctx.cfg.precision, Float::with_val(ctx.cfg.precision, 1).exp())))
+	}
+
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		Ok(self.real().to_f64())
+	}
+
+	/// Shows `precision` significant digits in the radix of the format, and the precision of the real
+	/// part in bits if the format says to
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		let radix = if fmt.radix >= 2 && fmt.radix <= 36 { fmt.radix } else { 10 };
+		let s = self.to_string_radix(radix as i32, fmt.precision);
+		if fmt.show_precision {
+			format!("{} ({} bits)", s, self.prec().0)
+		} else {
+			s
+		}
+	}
+
+	fn to_f64_complex_lossy(&self) -> Result<(f64, f64), MathError> {
+		Ok((self.real().to_f64(), self.imag().to_f64()))
+	}
+
+	fn is_finite(&self) -> bool {
+		self.real().is_finite() && self.imag().is_finite()
+	}
+
+	fn is_nan(&self) -> bool {
+		self.real().is_nan() || self.imag().is_nan()
+	}
+
 	fn tryord(&self, other: &Self, _ctx: &Context<Self>) -> Result<Ordering, MathError> {
 		if let Some(ord) = self.real().partial_cmp(other.real()) {
 			Ok(ord)
@@ -49,12 +124,23 @@ impl Num for Complex {
 	}
 
 	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if is_zero(other) {
+			let sign = if *self.real() != 0 { self.real().cmp0() } else { self.imag().cmp0() };
+			return num::div_by_zero(sign.unwrap_or(Ordering::Equal), ctx);
+		}
+
 		let r = Complex::with_val(ctx.cfg.precision, self / other);
 
 		Ok(Answer::Single(r))
 	}
 
 	fn pow(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if is_zero(self) && is_zero(other) {
+			return num::zero_pow_zero(ctx);
+		} else if is_zero(self) && *other.imag() == 0 && *other.real() < 0 {
+			return num::div_by_zero(Ordering::Greater, ctx);
+		}
+
 		let r = Complex::with_val(ctx.cfg.precision, Pow::pow(self, other));
 
 		Ok(Answer::Single(r))
@@ -63,11 +149,51 @@ impl Num for Complex {
 	fn sqrt(&self, ctx: &Context<Self>) -> Calculation<Self> {
 		let r = Complex::with_val(ctx.cfg.precision, Complex::sqrt_ref(self));
 
-		Ok(if ctx.cfg.sqrt_both {
-			Answer::Multiple(vec![r.clone(), -r])
-		} else {
-			Answer::Single(r)
-		})
+		Ok(num::roots(r.clone(), -r, true, ctx))
+	}
+
+	/// The principal `other`th root, which is along with its negation for even integer roots when
+	/// `sqrt_both` is enabled in the config
+	fn nrt(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if is_zero(other) {
+			return num::div_by_zero(Ordering::Greater, ctx);
+		}
+
+		let exp = Complex::with_val(ctx.cfg.precision, other.recip_ref());
+		let r = Complex::with_val(ctx.cfg.precision, Pow::pow(self, &exp));
+		let even = other.imag().is_zero() && Float::with_val(ctx.cfg.precision, other.real() / 2u32).is_integer();
+
+		Ok(num::roots(r.clone(), -r, even, ctx))
+	}
+
+	/// The gamma function of the number plus one. Only real numbers are supported.
+	fn fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		real_only(self, "Factorial", ctx, |x| (x + 1u32).gamma())
+	}
+
+	/// Only real numbers are supported
+	fn gamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		real_only(self, "Gamma Function", ctx, Float::gamma)
+	}
+
+	/// Only real numbers are supported
+	fn lgamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		real_only(self, "Log Gamma Function", ctx, |x| x.ln_abs_gamma().0)
+	}
+
+	/// Only real numbers are supported
+	fn erf(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		real_only(self, "Error Function", ctx, Float::erf)
+	}
+
+	/// Only real numbers are supported
+	fn erfc(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		real_only(self, "Complementary Error Function", ctx, Float::erfc)
+	}
+
+	/// Only real numbers are supported
+	fn zeta(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		real_only(self, "Zeta Function", ctx, Float::zeta)
 	}
 
 	fn abs(&self, ctx: &Context<Self>) -> Calculation<Self> {
@@ -129,9 +255,10 @@ impl Num for Complex {
 		Ok(Answer::Single(a))
 	}
 	
+	/// Rounds both parts with the `rounding` mode of the config
 	fn round(&self, ctx: &Context<Self>) -> Calculation<Self> {
-		let r = Complex::real(self).round_ref();
-		let i = Complex::imag(self).round_ref();
+		let r = round(Complex::real(self), ctx.cfg.rounding);
+		let i = round(Complex::imag(self), ctx.cfg.rounding);
 		let a = Complex::with_val(ctx.cfg.precision, (r, i));
 		
 		Ok(Answer::Single(a))
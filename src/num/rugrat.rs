@@ -1,11 +1,47 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
-use rug::Rational;
+use rug::{Integer, Rational};
 use crate::opers::Calculation;
 use crate::errors::MathError;
 use crate::answer::Answer;
-use crate::num::Num;
-use crate::context::Context;
+use crate::num::{self, Num};
+use crate::context::{Context, RoundingMode};
+use crate::format::{self, AnswerFormat, FractionStyle};
+
+/// Get the integer a rational is equal to for the bitwise operations
+fn to_integer(r: &Rational) -> Result<Integer, MathError> {
+	if *r.denom() == 1 {
+		Ok(r.numer().clone())
+	} else {
+		Err(MathError::NotAnInteger)
+	}
+}
+
+/// Round a rational to an integer with a rounding mode
+fn round(r: &Rational, mode: RoundingMode) -> Rational {
+	let t = Rational::from(r.trunc_ref());
+	let frac = Rational::from(r - &t).abs();
+	if frac == 0 {
+		return t;
+	}
+
+	let half = frac.cmp(&Rational::from((1, 2)));
+	if mode.rounds_away(t.numer().is_odd(), *r < 0, half, true) {
+		if *r < 0 { t - 1 } else { t + 1 }
+	} else {
+		t
+	}
+}
+
+/// Get the amount to shift by. Shifts are limited to 65536 bits to keep them from using too much
+/// memory.
+fn to_shift(r: &Rational) -> Result<u32, MathError> {
+	match to_integer(r)?.to_u32() {
+		Some(n) if n <= 65536 => Ok(n),
+		_ => Err(MathError::IncorrectArguments),
+	}
+}
 
 impl Num for Rational {
 	fn from_f64(t: f64, _ctx: &Context<Self>) -> Calculation<Self> {
@@ -28,6 +64,56 @@ impl Num for Rational {
 		String::from("Rational")
 	}
 
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		Ok(self.to_f64())
+	}
+
+	/// Rationals are always finite
+	fn is_finite(&self) -> bool {
+		true
+	}
+
+	fn is_nan(&self) -> bool {
+		false
+	}
+
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		format_rational(self, fmt)
+	}
+
+	/// The exact factorial of non-negative integers up to 100000. Other numbers aren't supported,
+	/// since their factorials aren't rational.
+	fn fact(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		match to_integer(self)?.to_u32() {
+			Some(n) if n <= 100_000 => Ok(Answer::Single(Rational::from(Integer::from(Integer::factorial(n))))),
+			_ => Err(MathError::IncorrectArguments),
+		}
+	}
+
+	fn bitand(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Rational::from(to_integer(self)? & to_integer(other)?)))
+	}
+
+	fn bitor(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Rational::from(to_integer(self)? | to_integer(other)?)))
+	}
+
+	fn bitxor(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Rational::from(to_integer(self)? ^ to_integer(other)?)))
+	}
+
+	fn shl(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Rational::from(to_integer(self)? << to_shift(other)?)))
+	}
+
+	fn shr(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Rational::from(to_integer(self)? >> to_shift(other)?)))
+	}
+
+	fn bitnot(&self, _ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(Rational::from(!to_integer(self)?)))
+	}
+
 	fn tryord(&self, other: &Self, _ctx: &Context<Self>) -> Result<Ordering, MathError> {
 		if let Some(ord) = self.partial_cmp(other) {
 			Ok(ord)
@@ -54,7 +140,11 @@ impl Num for Rational {
 		Ok(Answer::Single(r))
 	}
 
-	fn div(&self, other: &Self, _ctx: &Context<Self>) -> Calculation<Self> {
+	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		if *other == 0 {
+			return num::div_by_zero(self.cmp0(), ctx);
+		}
+
 		let r = Rational::from(self / other);
 
 		Ok(Answer::Single(r))
@@ -78,9 +168,56 @@ impl Num for Rational {
 		Ok(Answer::Single(r))
 	}
 	
-	fn round(&self, _ctx: &Context<Self>) -> Calculation<Self> {
-		let r = Rational::from(self.round_ref());
-		
-		Ok(Answer::Single(r))
+	/// Rounds with the `rounding` mode of the config
+	fn round(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(Answer::Single(round(self, ctx.cfg.rounding)))
+	}
+}
+
+/// Format a rational as a fraction, mixed number, or repeating decimal depending on the format
+pub(crate) fn format_rational(r: &Rational, fmt: &AnswerFormat) -> String {
+	let radix = if fmt.radix >= 2 && fmt.radix <= 36 { fmt.radix } else { 10 };
+	let sign = if *r < 0 { "-" } else { "" };
+	let (numer, denom) = r.clone().abs().into_numer_denom();
+	let int = |i: &Integer| format::separate(&i.to_string_radix(radix as i32), fmt);
+
+	if denom == 1 {
+		return format!("{}{}", sign, int(&numer));
+	}
+
+	match fmt.fraction {
+		FractionStyle::Improper => format!("{}{}/{}", sign, int(&numer), int(&denom)),
+		FractionStyle::Mixed => {
+			let (whole, rem) = numer.div_rem(denom.clone());
+			if whole == 0 {
+				format!("{}{}/{}", sign, int(&rem), int(&denom))
+			} else {
+				format!("{}{} {}/{}", sign, int(&whole), int(&rem), int(&denom))
+			}
+		}
+		FractionStyle::Decimal => {
+			let (whole, mut rem) = numer.div_rem(denom.clone());
+			let max = fmt.precision.unwrap_or(100);
+			let mut digits = String::new();
+			// The position in the digits each remainder was first seen at. Seeing a remainder again
+			// means the digits after it repeat.
+			let mut seen = HashMap::new();
+			while rem != 0 {
+				if let Some(&start) = seen.get(&rem) {
+					digits.insert(start, '(');
+					digits.push(')');
+					break;
+				} else if digits.len() >= max {
+					digits.push_str("...");
+					break;
+				}
+				seen.insert(rem.clone(), digits.len());
+				rem *= radix;
+				let (digit, next) = rem.div_rem(denom.clone());
+				digits.push(digit.to_u32().and_then(|d| std::char::from_digit(d, radix)).unwrap_or('?'));
+				rem = next;
+			}
+			format!("{}{}.{}", sign, int(&whole), digits)
+		}
 	}
 }
@@ -1,3 +1,6 @@
+use crate::context::Config;
+use crate::prelude::*;
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Op {
 	In(In),
@@ -13,6 +16,13 @@ pub(crate) enum In {
 	Add,
 	Sub,
 	PlusMinus,
+	BitAnd,
+	BitOr,
+	Shl,
+	Shr,
+	/// An implicit multiplication of a variable and the parentheses right after it, like `foo(3)`,
+	/// which might have been meant as a call to a function named by the variable
+	Call(String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,11 +30,13 @@ pub(crate) enum Pre {
 	Neg,
 	Pos,
 	PosNeg,
+	BitNot,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Post {
 	Fact,
+	DoubleFact,
 	Percent,
 }
 
@@ -36,33 +48,44 @@ impl Op {
 		match *self {
 			Op::In(ref op) => match *op {
 				Pow => 4,
-				Mul | Div => 3,
+				Mul | Div | Call(_) => 3,
 				Add | Sub | PlusMinus => 2,
+				Shl | Shr => 1,
+				BitAnd => 0,
+				BitOr => -1,
 			},
 			Op::Pre(ref op) => match *op {
-				Neg | Pos | PosNeg => 4,
+				Neg | Pos | PosNeg | BitNot => 4,
 			},
 			Op::Post(ref op) => match *op {
-				Fact => 4,
+				Fact | DoubleFact => 4,
 				Percent => 4,
 			},
 		}
 	}
 
-	pub fn is_left_associative(&self) -> bool {
+	/// The precedence of the operator with the given config
+	fn binding(&self, cfg: &Config) -> i32 {
+		match *self {
+			Op::Pre(Pre::Neg) | Op::Pre(Pre::Pos) | Op::Pre(Pre::PosNeg) if cfg.tight_unary_minus => 5,
+			_ => self.precedence(),
+		}
+	}
+
+	pub fn is_left_associative(&self, cfg: &Config) -> bool {
 		use self::In::*;
 		use self::Pre::*;
 		use self::Post::*;
 		match *self {
 			Op::In(ref op) => match *op {
-				Pow => false,
-				Mul | Div | Add | Sub | PlusMinus => true,
+				Pow => cfg.left_associative_pow,
+				Mul | Div | Add | Sub | PlusMinus | BitAnd | BitOr | Shl | Shr | Call(_) => true,
 			},
 			Op::Pre(ref op) => match *op {
-				Neg | Pos | PosNeg => false,
+				Neg | Pos | PosNeg | BitNot => false,
 			},
 			Op::Post(ref op) => match *op {
-				Fact => true,
+				Fact | DoubleFact => true,
 				Percent => true,
 			},
 		}
@@ -75,35 +98,37 @@ impl Op {
 		String::from(match *self {
 			Op::In(ref op) => match *op {
 				Pow => "^",
-				Mul => "*",
+				Mul | Call(_) => "*",
 				Div => "/",
 				Add => "+",
 				Sub => "-",
 				PlusMinus => "±",
+				BitAnd => "&",
+				BitOr => "|",
+				Shl => "<<",
+				Shr => ">>",
 			},
 			Op::Pre(ref op) => match *op {
 				Neg => "-",
 				Pos => "+",
 				PosNeg => "±",
+				BitNot => "~",
 			},
 			Op::Post(ref op) => match *op {
 				Fact => "!",
+				DoubleFact => "!!",
 				Percent => "%",
 			},
 		})
 	}
 
 	/// True if the operator should be evaluated before this one
-	pub fn should_shunt(&self, other: &Op) -> bool {
-		if (other.precedence() > self.precedence()) || (other.precedence() == self.precedence() && other.is_left_associative()) {
-			true
-		} else {
-			false
-		}
+	pub fn should_shunt(&self, other: &Op, cfg: &Config) -> bool {
+		(other.binding(cfg) > self.binding(cfg)) || (other.binding(cfg) == self.binding(cfg) && other.is_left_associative(cfg))
 	}
 }
 
-use std::fmt;
+use core::fmt;
 impl fmt::Display for Op {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		f.write_str(&self.to_string())
@@ -1,20 +1,84 @@
-use std::fmt::Debug;
+use core::fmt::{self, Debug};
+use alloc::sync::Arc;
 
 use crate::term::Term;
 use crate::context::Context;
 use crate::errors::MathError;
 use crate::num::Num;
 use crate::answer::Answer;
+use crate::matrix;
+use crate::func::Func;
+use crate::state::EvalState;
+use crate::prelude::*;
 
 /// The result of an evaluation
 pub type Calculation<N> = Result<Answer<N>, MathError>;
 
 /// A trait for operations
-pub trait Operate<N: Num>: Debug {
-	/// Evalute the operation or return an error
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N>;
+pub trait Operate<N: Num>: Debug + Send + Sync {
+	/// Evalute the operation as part of the evaluation with the given state, or return an error
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N>;
 	/// Convert the operation to a string representation
 	fn to_string(&self) -> String;
+	/// A name identifying the kind of this operation. Two operations with the same name and equal
+	/// operands are considered equal.
+	fn name(&self) -> &str;
+	/// Get the operands of this operation
+	fn operands(&self) -> Vec<&Term<N>>;
+	/// Create a new operation of the same kind with the given operands, in the same order they are
+	/// returned by `operands`. It's always given exactly as many operands as `operands` returns, so
+	/// implementations can take them without checking.
+	fn with_operands(&self, operands: Vec<Term<N>>) -> Arc<dyn Operate<N>>;
+	/// Whether or not the answer of the operation only depends on its operands, and not on anything
+	/// else in the context it's evaluated with
+	fn is_pure(&self) -> bool {
+		true
+	}
+	/// The kind of arithmetic this operation does along with its operands, if it's one that
+	/// `Term::eval_fast` can do directly on single numbers
+	fn arithmetic(&self) -> Option<ArithmeticOperands<'_, N>> {
+		None
+	}
+	/// The index of the value this operation reads from the slots of a `BoundTerm`, if it's a slot
+	fn slot(&self) -> Option<usize> {
+		None
+	}
+	/// The error to fail with when the operand at the index fails to evaluate
+	fn operand_error(&self, _index: usize, error: MathError, _ctx: &Context<N>) -> MathError {
+		error
+	}
+}
+
+/// The kind of arithmetic an operation does along with its operands, the second of which is `None` for
+/// negation
+pub type ArithmeticOperands<'a, N> = (Arithmetic, &'a Term<N>, Option<&'a Term<N>>);
+
+/// Arithmetic on single numbers that `Term::eval_fast` can do without the operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arithmetic {
+	Add,
+	Sub,
+	Mul,
+	Div,
+	Pow,
+	Neg,
+}
+
+impl Arithmetic {
+	/// Do the arithmetic on single numbers. `b` is ignored for negation.
+	pub fn apply<N: Num>(self, a: &N, b: &N, ctx: &Context<N>) -> Calculation<N> {
+		match self {
+			Arithmetic::Add => a.add(b, ctx),
+			Arithmetic::Sub => a.sub(b, ctx),
+			Arithmetic::Mul => a.mul(b, ctx),
+			Arithmetic::Div => a.div(b, ctx),
+			Arithmetic::Pow => a.pow(b, ctx),
+			Arithmetic::Neg => match N::from_f64(-1.0, ctx)? {
+				Answer::Single(minus) => a.mul(&minus, ctx),
+				minus => Answer::Single(a.clone()).op_ctx(&minus, ctx, |a, b| a.mul(b, ctx)),
+			},
+		}
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -24,11 +88,11 @@ pub(crate) struct Add<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Add<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
-		let b = self.b.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
 
-		a.op(&b, |a, b| {
+		a.op_ctx(&b, ctx, |a, b| {
 			a.add(b, ctx)
 		})
 	}
@@ -36,6 +100,25 @@ impl<N: Num + 'static> Operate<N> for Add<N> {
 	fn to_string(&self) -> String {
 		format!("({} + {})", self.a, self.b)
 	}
+
+	fn name(&self) -> &str {
+		"+"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Add { a, b })
+	}
+
+	fn arithmetic(&self) -> Option<ArithmeticOperands<'_, N>> {
+		Some((Arithmetic::Add, &self.a, Some(&self.b)))
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -45,11 +128,11 @@ pub(crate) struct Sub<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Sub<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
-		let b = self.b.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
 
-		a.op(&b, |a, b| {
+		a.op_ctx(&b, ctx, |a, b| {
 			a.sub(b, ctx)
 		})
 	}
@@ -57,20 +140,53 @@ impl<N: Num + 'static> Operate<N> for Sub<N> {
 	fn to_string(&self) -> String {
 		format!("({} - {})", self.a, self.b)
 	}
+
+	fn name(&self) -> &str {
+		"-"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Sub { a, b })
+	}
+
+	fn arithmetic(&self) -> Option<ArithmeticOperands<'_, N>> {
+		Some((Arithmetic::Sub, &self.a, Some(&self.b)))
+	}
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct Mul<N: Num> {
 	pub a: Term<N>,
 	pub b: Term<N>,
+	/// The name of the variable right before `b` if `b` was in parentheses, like `foo(3)`, in which
+	/// case it might have been meant as a function call
+	pub call: Option<String>,
 }
 
 impl<N: Num + 'static> Operate<N> for Mul<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
-		let b = self.b.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state).map_err(|e| self.operand_error(0, e, ctx))?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		// Matrices are multiplied with matrix multiplication, treating vectors as column vectors
+		if let Some(ma) = matrix::as_matrix(&a) {
+			if let Some(mb) = matrix::as_matrix(&b) {
+				return Ok(matrix::matrix_answer(matrix::mul(&ma, &mb, ctx)?));
+			} else if let Some(vb) = matrix::as_vector(&b) {
+				let column: Vec<Vec<N>> = vb.into_iter().map(|n| vec![n]).collect();
+				let product = matrix::mul(&ma, &column, ctx)?;
+				return Ok(matrix::vector_answer(product.into_iter().map(|mut row| row.remove(0)).collect()));
+			}
+		}
 
-		a.op(&b, |a, b| {
+		a.op_ctx(&b, ctx, |a, b| {
 			a.mul(b, ctx)
 		})
 	}
@@ -78,6 +194,39 @@ impl<N: Num + 'static> Operate<N> for Mul<N> {
 	fn to_string(&self) -> String {
 		format!("({} × {})", self.a, self.b)
 	}
+
+	fn name(&self) -> &str {
+		"*"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Mul {
+			a,
+			b,
+			call: self.call.clone(),
+		})
+	}
+
+	fn arithmetic(&self) -> Option<ArithmeticOperands<'_, N>> {
+		Some((Arithmetic::Mul, &self.a, Some(&self.b)))
+	}
+
+	fn operand_error(&self, index: usize, error: MathError, ctx: &Context<N>) -> MathError {
+		match (index, error, self.call.as_ref()) {
+			(0, MathError::UndefinedVariable { name, .. }, Some(call)) if name == *call => MathError::VariableCalledAsFunction {
+				suggestions: ctx.similar_funcs(&name),
+				name,
+			},
+			(_, error, _) => error,
+		}
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -87,11 +236,11 @@ pub(crate) struct Div<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Div<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
-		let b = self.b.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
 
-		a.op(&b, |a, b| {
+		a.op_ctx(&b, ctx, |a, b| {
 			a.div(b, ctx)
 		})
 	}
@@ -99,6 +248,25 @@ impl<N: Num + 'static> Operate<N> for Div<N> {
 	fn to_string(&self) -> String {
 		format!("({} ÷ {})", self.a, self.b)
 	}
+
+	fn name(&self) -> &str {
+		"/"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Div { a, b })
+	}
+
+	fn arithmetic(&self) -> Option<ArithmeticOperands<'_, N>> {
+		Some((Arithmetic::Div, &self.a, Some(&self.b)))
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -108,11 +276,11 @@ pub(crate) struct Pow<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Pow<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
-		let b = self.b.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
 
-		a.op(&b, |a, b| {
+		a.op_ctx(&b, ctx, |a, b| {
 			a.pow(b, ctx)
 		})
 	}
@@ -120,6 +288,25 @@ impl<N: Num + 'static> Operate<N> for Pow<N> {
 	fn to_string(&self) -> String {
 		format!("({} ^ {})", self.a, self.b)
 	}
+
+	fn name(&self) -> &str {
+		"^"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Pow { a, b })
+	}
+
+	fn arithmetic(&self) -> Option<ArithmeticOperands<'_, N>> {
+		Some((Arithmetic::Pow, &self.a, Some(&self.b)))
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -129,14 +316,17 @@ pub(crate) struct PlusMinus<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for PlusMinus<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
-		let b = self.b.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
 
-		let adds = a.op(&b, |a, b| {
+		let adds = a.op_ctx(&b, ctx, |a, b| {
 			a.add(b, ctx)
 		})?;
-		let subs = a.op(&b, |a, b| {
+		if ctx.cfg.single_valued {
+			return Ok(adds);
+		}
+		let subs = a.op_ctx(&b, ctx, |a, b| {
 			a.sub(b, ctx)
 		})?;
 
@@ -146,6 +336,21 @@ impl<N: Num + 'static> Operate<N> for PlusMinus<N> {
 	fn to_string(&self) -> String {
 		format!("({} ± {})", self.a, self.b)
 	}
+
+	fn name(&self) -> &str {
+		"±"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(PlusMinus { a, b })
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -154,10 +359,10 @@ pub(crate) struct Neg<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Neg<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
 
-		a.op(&N::from_f64(-1.0, ctx)?, |a, b| {
+		a.op_ctx(&N::from_f64(-1.0, ctx)?, ctx, |a, b| {
 			a.mul(b, ctx)
 		})
 	}
@@ -165,6 +370,24 @@ impl<N: Num + 'static> Operate<N> for Neg<N> {
 	fn to_string(&self) -> String {
 		format!("(-{})", self.a)
 	}
+
+	fn name(&self) -> &str {
+		"(-)"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let a = operands.pop().unwrap();
+		Arc::new(Neg { a })
+	}
+
+	fn arithmetic(&self) -> Option<ArithmeticOperands<'_, N>> {
+		Some((Arithmetic::Neg, &self.a, None))
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -173,8 +396,8 @@ pub(crate) struct Pos<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Pos<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
 
 		Ok(a)
 	}
@@ -182,6 +405,20 @@ impl<N: Num + 'static> Operate<N> for Pos<N> {
 	fn to_string(&self) -> String {
 		format!("(+{})", self.a)
 	}
+
+	fn name(&self) -> &str {
+		"(+)"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let a = operands.pop().unwrap();
+		Arc::new(Pos { a })
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -190,10 +427,13 @@ pub(crate) struct PosNeg<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for PosNeg<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		if ctx.cfg.single_valued {
+			return Ok(a);
+		}
 
-		a.unop(|a| {
+		a.unop_ctx(ctx, |a| {
 			let pos = a;
 			let neg = a.mul(&N::from_f64(-1.0, ctx)?.unwrap_single(), ctx)?;
 
@@ -204,6 +444,195 @@ impl<N: Num + 'static> Operate<N> for PosNeg<N> {
 	fn to_string(&self) -> String {
 		format!("(±{})", self.a)
 	}
+
+	fn name(&self) -> &str {
+		"(±)"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let a = operands.pop().unwrap();
+		Arc::new(PosNeg { a })
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BitAnd<N: Num> {
+	pub a: Term<N>,
+	pub b: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for BitAnd<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		a.op_ctx(&b, ctx, |a, b| {
+			a.bitand(b, ctx)
+		})
+	}
+
+	fn to_string(&self) -> String {
+		format!("({} & {})", self.a, self.b)
+	}
+
+	fn name(&self) -> &str {
+		"&"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(BitAnd { a, b })
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BitOr<N: Num> {
+	pub a: Term<N>,
+	pub b: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for BitOr<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		a.op_ctx(&b, ctx, |a, b| {
+			a.bitor(b, ctx)
+		})
+	}
+
+	fn to_string(&self) -> String {
+		format!("({} | {})", self.a, self.b)
+	}
+
+	fn name(&self) -> &str {
+		"|"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(BitOr { a, b })
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Shl<N: Num> {
+	pub a: Term<N>,
+	pub b: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for Shl<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		a.op_ctx(&b, ctx, |a, b| {
+			a.shl(b, ctx)
+		})
+	}
+
+	fn to_string(&self) -> String {
+		format!("({} << {})", self.a, self.b)
+	}
+
+	fn name(&self) -> &str {
+		"<<"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Shl { a, b })
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Shr<N: Num> {
+	pub a: Term<N>,
+	pub b: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for Shr<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		a.op_ctx(&b, ctx, |a, b| {
+			a.shr(b, ctx)
+		})
+	}
+
+	fn to_string(&self) -> String {
+		format!("({} >> {})", self.a, self.b)
+	}
+
+	fn name(&self) -> &str {
+		">>"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Shr { a, b })
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct BitNot<N: Num> {
+	pub a: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for BitNot<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+
+		a.unop_ctx(ctx, |a| a.bitnot(ctx))
+	}
+
+	fn to_string(&self) -> String {
+		format!("(~{})", self.a)
+	}
+
+	fn name(&self) -> &str {
+		"(~)"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let a = operands.pop().unwrap();
+		Arc::new(BitNot { a })
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -212,16 +641,60 @@ pub(crate) struct Fact<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Fact<N> {
-	fn eval(&self, _ctx: &Context<N>) -> Calculation<N> {
-		Err(MathError::Unimplemented {
-			op: "Factorial".to_string(),
-			num_type: "Any".to_string(),
-		})
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+
+		a.unop_ctx(ctx, |a| a.fact(ctx))
 	}
 
 	fn to_string(&self) -> String {
 		format!("({}!)", self.a)
 	}
+
+	fn name(&self) -> &str {
+		"!"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let a = operands.pop().unwrap();
+		Arc::new(Fact { a })
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DoubleFact<N: Num> {
+	pub a: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for DoubleFact<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+
+		a.unop_ctx(ctx, |a| a.double_fact(ctx))
+	}
+
+	fn to_string(&self) -> String {
+		format!("({}!!)", self.a)
+	}
+
+	fn name(&self) -> &str {
+		"!!"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let a = operands.pop().unwrap();
+		Arc::new(DoubleFact { a })
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -230,15 +703,343 @@ pub(crate) struct Percent<N: Num> {
 }
 
 impl<N: Num + 'static> Operate<N> for Percent<N> {
-	fn eval(&self, ctx: &Context<N>) -> Calculation<N> {
-		let a = self.a.eval_ctx(ctx)?;
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
 
-		a.op(&N::from_f64(0.01, ctx)?, |a, b| {
-			a.mul(b, ctx)
+		a.op_ctx(&N::from_f64(100.0, ctx)?, ctx, |a, b| {
+			a.div(b, ctx)
 		})
 	}
 
 	fn to_string(&self) -> String {
 		format!("({}%)", self.a)
 	}
+
+	fn name(&self) -> &str {
+		"%"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let a = operands.pop().unwrap();
+		Arc::new(Percent { a })
+	}
+}
+
+/// Addition where the right operand is a percentage. If `relative_percent` is enabled, the percentage
+/// is taken of the left operand (`a + b%` = `a × (1 + b/100)`), otherwise it's a plain addition.
+#[derive(Debug, Clone)]
+pub(crate) struct AddPercent<N: Num> {
+	pub a: Term<N>,
+	pub b: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for AddPercent<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		if ctx.cfg.relative_percent {
+			a.op_ctx(&b, ctx, |a, b| {
+				let part = a.mul(b, ctx)?;
+				part.unop_ctx(ctx, |part| a.add(part, ctx))
+			})
+		} else {
+			a.op_ctx(&b, ctx, |a, b| {
+				a.add(b, ctx)
+			})
+		}
+	}
+
+	fn to_string(&self) -> String {
+		format!("({} + {})", self.a, percent_string(&self.b))
+	}
+
+	fn name(&self) -> &str {
+		"+%"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(AddPercent { a, b })
+	}
+}
+
+/// Subtraction where the right operand is a percentage. If `relative_percent` is enabled, the
+/// percentage is taken of the left operand (`a - b%` = `a × (1 - b/100)`), otherwise it's a plain
+/// subtraction.
+#[derive(Debug, Clone)]
+pub(crate) struct SubPercent<N: Num> {
+	pub a: Term<N>,
+	pub b: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for SubPercent<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		if ctx.cfg.relative_percent {
+			a.op_ctx(&b, ctx, |a, b| {
+				let part = a.mul(b, ctx)?;
+				part.unop_ctx(ctx, |part| a.sub(part, ctx))
+			})
+		} else {
+			a.op_ctx(&b, ctx, |a, b| {
+				a.sub(b, ctx)
+			})
+		}
+	}
+
+	fn to_string(&self) -> String {
+		format!("({} - {})", self.a, percent_string(&self.b))
+	}
+
+	fn name(&self) -> &str {
+		"-%"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(SubPercent { a, b })
+	}
+}
+
+/// Write the percentage of an `AddPercent` or a `SubPercent` with the percent sign right after its
+/// operand, so that it's parsed as a percentage of the left operand again
+fn percent_string<N: Num + 'static>(b: &Term<N>) -> String {
+	match *b {
+		Term::Operation(ref oper) if oper.name() == "%" => format!("{}%", oper.operands()[0]),
+		_ => b.to_string(),
+	}
+}
+
+/// A constant whose value is computed whenever it's evaluated, so that it has the precision of the
+/// context it's evaluated with
+#[derive(Clone)]
+pub(crate) struct Constant<N: Num> {
+	pub name: &'static str,
+	pub value: fn(&Context<N>) -> Calculation<N>,
+}
+
+impl<N: Num> Constant<N> {
+	/// Create a term of a constant
+	pub fn term(name: &'static str, value: fn(&Context<N>) -> Calculation<N>) -> Term<N>
+	where
+		N: 'static,
+	{
+		Term::Operation(Arc::new(Constant { name, value }))
+	}
+}
+
+impl<N: Num> Debug for Constant<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Constant {{ name: {:?} }}", self.name)
+	}
+}
+
+impl<N: Num + 'static> Operate<N> for Constant<N> {
+	fn eval(&self, ctx: &Context<N>, _state: &EvalState<N>) -> Calculation<N> {
+		(self.value)(ctx)
+	}
+
+	fn to_string(&self) -> String {
+		self.name.to_string()
+	}
+
+	fn name(&self) -> &str {
+		self.name
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		Vec::new()
+	}
+
+	fn with_operands(&self, _operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		Arc::new(self.clone())
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Vector<N: Num> {
+	pub items: Vec<Term<N>>,
+}
+
+impl<N: Num + 'static> Operate<N> for Vector<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let items = self.items.iter()
+			.map(|item| item.eval_state(ctx, state))
+			.collect::<Result<Vec<Answer<N>>, MathError>>()?;
+
+		Ok(Answer::Vector(items))
+	}
+
+	fn to_string(&self) -> String {
+		let items: Vec<String> = self.items.iter().map(|item| item.to_string()).collect();
+		if items.len() == 1 {
+			// A single expression in brackets would just be grouped by them
+			format!("[{},]", items[0])
+		} else {
+			format!("[{}]", items.join(", "))
+		}
+	}
+
+	fn name(&self) -> &str {
+		"[,]"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		self.items.iter().collect()
+	}
+
+	fn with_operands(&self, operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		Arc::new(Vector { items: operands })
+	}
+}
+
+/// Indexes a vector. Indices start at 1.
+#[derive(Debug, Clone)]
+pub(crate) struct Index<N: Num> {
+	pub a: Term<N>,
+	pub b: Term<N>,
+}
+
+impl<N: Num + 'static> Operate<N> for Index<N> {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let a = self.a.eval_state(ctx, state)?;
+		let b = self.b.eval_state(ctx, state)?;
+
+		let items = match a {
+			Answer::Vector(items) => items,
+			_ => return Err(MathError::InvalidIndex),
+		};
+		let index = match b {
+			Answer::Single(n) => n.to_f64_lossy()?,
+			_ => return Err(MathError::InvalidIndex),
+		};
+		if index % 1.0 != 0.0 || index < 1.0 || index > items.len() as f64 {
+			return Err(MathError::InvalidIndex);
+		}
+
+		Ok(items[index as usize - 1].clone())
+	}
+
+	fn to_string(&self) -> String {
+		format!("{}[{}]", self.a, self.b)
+	}
+
+	fn name(&self) -> &str {
+		"[]"
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		vec![&self.a, &self.b]
+	}
+
+	fn with_operands(&self, mut operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		// There are as many operands as `operands` returned
+		let b = operands.pop().unwrap();
+		let a = operands.pop().unwrap();
+		Arc::new(Index { a, b })
+	}
+}
+
+/// A variable that has been bound to a slot in the values a `BoundTerm` is evaluated with
+#[derive(Debug, Clone)]
+pub(crate) struct Slot {
+	pub index: usize,
+	pub name: String,
+}
+
+impl<N: Num + 'static> Operate<N> for Slot {
+	fn eval(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		match state.slot(self.index) {
+			Some(n) => Ok(Answer::Single(n)),
+			None => Err(MathError::UndefinedVariable {
+				name: self.name.clone(),
+				suggestions: ctx.similar_names(&self.name),
+			}),
+		}
+	}
+
+	fn to_string(&self) -> String {
+		self.name.clone()
+	}
+
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		Vec::new()
+	}
+
+	fn with_operands(&self, _operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		Arc::new(self.clone())
+	}
+
+	fn is_pure(&self) -> bool {
+		false
+	}
+
+	fn slot(&self) -> Option<usize> {
+		Some(self.index)
+	}
+}
+
+/// A function call whose function has already been looked up in a context
+#[derive(Clone)]
+pub(crate) struct BoundFunction<N: Num> {
+	pub name: String,
+	pub func: Arc<dyn Func<N>>,
+	pub args: Vec<Term<N>>,
+}
+
+impl<N: Num> Debug for BoundFunction<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "BoundFunction {{ name: {:?}, args: {:?} }}", self.name, self.args)
+	}
+}
+
+impl<N: Num + 'static> Operate<N> for BoundFunction<N> {
+	fn eval(&self, ctx: &Context<N>, _state: &EvalState<N>) -> Calculation<N> {
+		self.func.eval(&self.args, ctx)
+	}
+
+	fn to_string(&self) -> String {
+		let args: Vec<String> = self.args.iter().map(|arg| arg.to_string()).collect();
+		format!("{}({})", self.name, args.join(", "))
+	}
+
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn operands(&self) -> Vec<&Term<N>> {
+		self.args.iter().collect()
+	}
+
+	fn with_operands(&self, operands: Vec<Term<N>>) -> Arc<dyn Operate<N>> {
+		Arc::new(BoundFunction {
+			name: self.name.clone(),
+			func: Arc::clone(&self.func),
+			args: operands,
+		})
+	}
 }
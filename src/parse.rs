@@ -1,33 +1,127 @@
+use core::fmt;
+use alloc::borrow::Cow;
+
 use crate::op::*;
 use crate::errors::*;
+use crate::context::Config;
+use crate::prelude::*;
 
+/// A token of an expression. Names are borrowed from the string being parsed.
 #[derive(Debug, Clone)]
-pub(crate) enum Token {
+pub(crate) enum Token<'a> {
 	Paren(Paren),
+	Bracket(Paren),
+	Brace(Paren),
 	Op(Op),
-	Name(String),
+	Name(&'a str),
 	Num(f64),
+	Frac(Fraction),
 	Comma,
 }
 
+/// A token of an expression with the tokens in parentheses and brackets grouped together
 #[derive(Debug, Clone)]
-pub(crate) enum ParenToken {
+pub(crate) enum ParenToken<'a> {
 	Op(Op),
 	Num(f64),
-	Name(String),
-	Sub(Vec<ParenToken>),
+	Frac(Fraction),
+	Name(&'a str),
+	Sub(Vec<ParenToken<'a>>),
+	Bracket(Vec<ParenToken<'a>>),
 	Comma,
 }
 
-/// Get a number at the beginning of a string
-fn next_num(raw: &str) -> Option<(Token, &str)> {
+/// A fraction written like `½` or the mixed number `1 1/2`, kept in parts so that number types that
+/// can represent it exactly do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Fraction {
+	pub whole: f64,
+	pub num: f64,
+	pub den: f64,
+}
+
+impl fmt::Display for Fraction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.whole != 0.0 {
+			write!(f, "{} ", self.whole)?;
+		}
+		write!(f, "{}/{}", self.num, self.den)
+	}
+}
+
+/// The character the parser understands that a character is normalized to, or `None` if it's
+/// removed. See `Config::normalize_input`.
+pub(crate) fn normalize_char(c: char) -> Option<char> {
+	match c {
+		'\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}' => Some(' '),
+		'\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => None,
+		// Fullwidth forms of the printable ASCII characters
+		'\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0),
+		'\u{2010}'..='\u{2013}' | '\u{2212}' | '\u{FE63}' => Some('-'),
+		'\u{2044}' | '\u{2215}' => Some('/'),
+		'\u{2217}' | '\u{22C5}' => Some('*'),
+		'\u{2018}' | '\u{2019}' | '\u{201B}' => Some('\''),
+		'\u{201C}' | '\u{201D}' | '\u{201F}' => Some('"'),
+		c => Some(c),
+	}
+}
+
+/// Normalize a string with `normalize_char`, only copying it if anything changes
+pub(crate) fn normalize(raw: &str) -> Cow<'_, str> {
+	if raw.chars().all(|c| normalize_char(c) == Some(c)) {
+		Cow::Borrowed(raw)
+	} else {
+		Cow::Owned(raw.chars().filter_map(normalize_char).collect())
+	}
+}
+
+/// Get an integer written with a `0x`, `0o` or `0b` prefix at the beginning of a string
+fn next_radix_num(raw: &str) -> Option<(Token<'_>, &str)> {
+	let radix = match raw.get(0..2) {
+		Some("0x") | Some("0X") => 16,
+		Some("0o") | Some("0O") => 8,
+		Some("0b") | Some("0B") => 2,
+		_ => return None,
+	};
+
+	let digits = &raw[2..];
+	let len = digits.find(|c: char| !c.is_digit(radix)).unwrap_or(digits.len());
+	if len == 0 {
+		return None;
+	}
+
+	let value = digits[..len]
+		.chars()
+		.filter_map(|c| c.to_digit(radix))
+		.fold(0.0, |acc, d| acc * f64::from(radix) + f64::from(d));
+	Some((Token::Num(value), &digits[len..]))
+}
+
+/// Parse the digits of a number written with the decimal separator of the config
+fn parse_num(buf: &str, cfg: &Config) -> Result<f64, core::num::ParseFloatError> {
+	if cfg.decimal_separator == '.' {
+		buf.parse()
+	} else {
+		buf.replace(cfg.decimal_separator, ".").parse()
+	}
+}
+
+/// Get a number at the beginning of a string, with the decimal separator of the config
+fn next_num<'a>(raw: &'a str, cfg: &Config) -> Option<(Token<'a>, &'a str)> {
+	if let Some(num) = next_radix_num(raw) {
+		return Some(num);
+	}
+	if let Some((num, den, rest)) = next_vulgar_fraction(raw) {
+		return Some((Token::Frac(Fraction { whole: 0.0, num, den }), rest));
+	}
+
 	let mut buf = "";
 	let mut dot = false;
 
 	for c in raw.chars() {
 		if c.is_digit(10) {
 			buf = &raw[0..buf.len() + c.len_utf8()];
-		} else if c == '.' {
+		} else if c == cfg.decimal_separator {
 			if !dot {
 				dot = true;
 				buf = &raw[0..buf.len() + c.len_utf8()];
@@ -35,48 +129,102 @@ fn next_num(raw: &str) -> Option<(Token, &str)> {
 				return None;
 			}
 		} else {
-			if buf.is_empty() {
-				return None;
-			} else {
-				return Some((
-					Token::Num(match buf.parse() {
-						Ok(v) => v,
-						Err(_e) => {
-							return None;
-						}
-					}),
-					&raw[buf.len()..raw.len()],
-				));
-			}
+			break;
 		}
 	}
 
 	if buf.is_empty() {
-		None
-	} else if buf == "-" {
-		Some((Token::Num(-1.0), &raw[buf.len()..raw.len()]))
-	} else {
-		Some((
-			Token::Num(match buf.parse() {
-				Ok(v) => v,
-				Err(_e) => {
-					return None;
-				}
-			}),
-			&raw[buf.len()..raw.len()],
-		))
+		return None;
+	}
+	let value = match parse_num(buf, cfg) {
+		Ok(v) => v,
+		Err(_e) => {
+			return None;
+		}
+	};
+	let rest = &raw[buf.len()..raw.len()];
+	// Only integers can be the whole part of a mixed number
+	if !dot {
+		if let Some((num, den, rest)) = next_mixed_fraction(rest, cfg) {
+			return Some((Token::Frac(Fraction { whole: value, num, den }), rest));
+		}
 	}
+	Some((Token::Num(value), rest))
+}
+
+/// Get a fraction character like `½` at the beginning of a string, as its numerator and denominator
+fn next_vulgar_fraction(raw: &str) -> Option<(f64, f64, &str)> {
+	let c = raw.chars().next()?;
+	let (num, den): (u8, u8) = match c {
+		'½' => (1, 2),
+		'⅓' => (1, 3),
+		'⅔' => (2, 3),
+		'¼' => (1, 4),
+		'¾' => (3, 4),
+		'⅕' => (1, 5),
+		'⅖' => (2, 5),
+		'⅗' => (3, 5),
+		'⅘' => (4, 5),
+		'⅙' => (1, 6),
+		'⅚' => (5, 6),
+		'⅐' => (1, 7),
+		'⅛' => (1, 8),
+		'⅜' => (3, 8),
+		'⅝' => (5, 8),
+		'⅞' => (7, 8),
+		'⅑' => (1, 9),
+		'⅒' => (1, 10),
+		'↉' => (0, 3),
+		_ => return None,
+	};
+	Some((f64::from(num), f64::from(den), &raw[c.len_utf8()..]))
+}
+
+/// Get the fraction of a mixed number after its whole part, as its numerator and denominator. A
+/// fraction character right after the whole part (like in `2½`) always is one, and with
+/// `mixed_numbers` in the config, so is a fraction character or a proper fraction like `1/2` after
+/// whitespace.
+fn next_mixed_fraction<'a>(raw: &'a str, cfg: &Config) -> Option<(f64, f64, &'a str)> {
+	if let Some(fraction) = next_vulgar_fraction(raw) {
+		return Some(fraction);
+	}
+	let trimmed = raw.trim_start();
+	if !cfg.mixed_numbers || trimmed.len() == raw.len() {
+		return None;
+	}
+	if let Some(fraction) = next_vulgar_fraction(trimmed) {
+		return Some(fraction);
+	}
+
+	let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+	let len = digits(trimmed);
+	let after = trimmed[len..].strip_prefix('/')?;
+	let den_len = digits(after);
+	if len == 0 || den_len == 0 {
+		return None;
+	}
+	let (num, den): (f64, f64) = (trimmed[..len].parse().ok()?, after[..den_len].parse().ok()?);
+	let rest = &after[den_len..];
+	// Anything else is division, like `1 3/2` or `1 1/2.5`
+	if num >= den || rest.starts_with(cfg.decimal_separator) {
+		return None;
+	}
+	Some((num, den, rest))
 }
 
 /// Function that can be used to retrieve a token
-type TokenFn = fn(&str) -> Option<(Token, &str)>;
+type TokenFn = for<'a> fn(&'a str, &Config) -> Option<(Token<'a>, &'a str)>;
 
-/// Get the parentheses at the beginning of a string
-fn next_paren(raw: &str) -> Option<(Token, &str)> {
+/// Get the parentheses, square bracket or curly brace at the beginning of a string
+fn next_paren<'a>(raw: &'a str, _cfg: &Config) -> Option<(Token<'a>, &'a str)> {
 	if let Some(c) = raw.chars().next() {
 		match c {
 			'(' => Some((Token::Paren(Paren::Open), &raw[c.len_utf8()..raw.len()])),
 			')' => Some((Token::Paren(Paren::Close), &raw[c.len_utf8()..raw.len()])),
+			'[' => Some((Token::Bracket(Paren::Open), &raw[c.len_utf8()..raw.len()])),
+			']' => Some((Token::Bracket(Paren::Close), &raw[c.len_utf8()..raw.len()])),
+			'{' => Some((Token::Brace(Paren::Open), &raw[c.len_utf8()..raw.len()])),
+			'}' => Some((Token::Brace(Paren::Close), &raw[c.len_utf8()..raw.len()])),
 			_ => None,
 		}
 	} else {
@@ -85,11 +233,12 @@ fn next_paren(raw: &str) -> Option<(Token, &str)> {
 }
 
 /// Get an infix operator at the beginning of a string
-fn next_in_op(raw: &str) -> Option<(Token, &str)> {
+fn next_in_op<'a>(raw: &'a str, _cfg: &Config) -> Option<(Token<'a>, &'a str)> {
 	if let Some(c) = raw.chars().next() {
 		match c {
 			'+' => Some((Token::Op(Op::In(In::Add)), &raw[c.len_utf8()..raw.len()])),
 			'-' => Some((Token::Op(Op::In(In::Sub)), &raw[c.len_utf8()..raw.len()])),
+			'*' if raw.starts_with("**") => Some((Token::Op(Op::In(In::Pow)), &raw[2..raw.len()])),
 			'*' | '×' => Some((Token::Op(Op::In(In::Mul)), &raw[c.len_utf8()..raw.len()])),
 			'/' | '÷' => Some((Token::Op(Op::In(In::Div)), &raw[c.len_utf8()..raw.len()])),
 			'^' => Some((Token::Op(Op::In(In::Pow)), &raw[c.len_utf8()..raw.len()])),
@@ -97,6 +246,10 @@ fn next_in_op(raw: &str) -> Option<(Token, &str)> {
 				Token::Op(Op::In(In::PlusMinus)),
 				&raw[c.len_utf8()..raw.len()],
 			)),
+			'&' => Some((Token::Op(Op::In(In::BitAnd)), &raw[c.len_utf8()..raw.len()])),
+			'|' => Some((Token::Op(Op::In(In::BitOr)), &raw[c.len_utf8()..raw.len()])),
+			'<' if raw.starts_with("<<") => Some((Token::Op(Op::In(In::Shl)), &raw[2..raw.len()])),
+			'>' if raw.starts_with(">>") => Some((Token::Op(Op::In(In::Shr)), &raw[2..raw.len()])),
 			_ => None,
 		}
 	} else {
@@ -105,7 +258,7 @@ fn next_in_op(raw: &str) -> Option<(Token, &str)> {
 }
 
 /// Get a prefix operator at the beginning of a string
-fn next_pre_op(raw: &str) -> Option<(Token, &str)> {
+fn next_pre_op<'a>(raw: &'a str, _cfg: &Config) -> Option<(Token<'a>, &'a str)> {
 	if let Some(c) = raw.chars().next() {
 		match c {
 			'-' => Some((Token::Op(Op::Pre(Pre::Neg)), &raw[c.len_utf8()..raw.len()])),
@@ -114,6 +267,7 @@ fn next_pre_op(raw: &str) -> Option<(Token, &str)> {
 				Token::Op(Op::Pre(Pre::PosNeg)),
 				&raw[c.len_utf8()..raw.len()],
 			)),
+			'~' => Some((Token::Op(Op::Pre(Pre::BitNot)), &raw[c.len_utf8()..raw.len()])),
 			_ => None,
 		}
 	} else {
@@ -122,7 +276,11 @@ fn next_pre_op(raw: &str) -> Option<(Token, &str)> {
 }
 
 /// Get a postfix operator at the beginning of a string
-fn next_post_op(raw: &str) -> Option<(Token, &str)> {
+fn next_post_op<'a>(raw: &'a str, _cfg: &Config) -> Option<(Token<'a>, &'a str)> {
+	if let Some(rest) = raw.strip_prefix("!!") {
+		// Two exclamation marks are a double factorial instead of two factorials
+		return Some((Token::Op(Op::Post(Post::DoubleFact)), rest));
+	}
 	if let Some(c) = raw.chars().next() {
 		match c {
 			'!' => Some((
@@ -140,33 +298,36 @@ fn next_post_op(raw: &str) -> Option<(Token, &str)> {
 	}
 }
 
-/// Get the name at the beginning of a string
-fn next_name(raw: &str) -> Option<(Token, &str)> {
-	let mut name = "";
-	for c in raw.chars() {
-		if c.is_alphabetic() || c == '_' {
-			name = &raw[0..name.len() + c.len_utf8()];
+/// Get the name at the beginning of a string. Names can contain dots between their parts to refer to
+/// functions in a namespace, like `stats.mean`.
+fn next_name<'a>(raw: &'a str, _cfg: &Config) -> Option<(Token<'a>, &'a str)> {
+	let is_name_char = |c: char| c.is_alphabetic() || c == '_';
+
+	let mut len = 0;
+	let mut chars = raw.char_indices().peekable();
+	while let Some((i, c)) = chars.next() {
+		if is_name_char(c) {
+			len = i + c.len_utf8();
+		} else if c == '.' && len > 0 && len == i && matches!(chars.peek(), Some(&(_, next)) if is_name_char(next)) {
+			// A dot is only part of the name if it's between two parts of it
+			continue;
 		} else {
-			if name.is_empty() {
-				return None;
-			} else {
-				return Some((Token::Name(name.to_string()), &raw[name.len()..raw.len()]));
-			}
+			break;
 		}
 	}
 
-	if name.is_empty() {
+	if len == 0 {
 		None
 	} else {
-		Some((Token::Name(name.to_string()), &raw[name.len()..raw.len()]))
+		Some((Token::Name(&raw[..len]), &raw[len..]))
 	}
 }
 
-/// Get the comma at the beginning of a string
-fn next_comma(raw: &str) -> Option<(Token, &str)> {
+/// Get the argument separator (a comma by default) at the beginning of a string
+fn next_comma<'a>(raw: &'a str, cfg: &Config) -> Option<(Token<'a>, &'a str)> {
 	if let Some(c) = raw.chars().next() {
 		match c {
-			',' => Some((Token::Comma, &raw[c.len_utf8()..raw.len()])),
+			c if c == cfg.arg_separator => Some((Token::Comma, &raw[c.len_utf8()..raw.len()])),
 			_ => None,
 		}
 	} else {
@@ -176,10 +337,10 @@ fn next_comma(raw: &str) -> Option<(Token, &str)> {
 
 /// Return a list of functions to use (in order) to try and parse the next token based on the last token
 /// that was parsed.
-fn get_parse_order(last: Option<&Token>) -> &[TokenFn] {
+fn get_parse_order(last: Option<&Token<'_>>) -> &'static [TokenFn] {
 	match last {
-		Some(&Token::Paren(Paren::Open)) => &[next_paren, next_name, next_num, next_pre_op],
-		Some(&Token::Paren(Paren::Close)) => &[
+		Some(&Token::Paren(Paren::Open)) | Some(&Token::Bracket(Paren::Open)) | Some(&Token::Brace(Paren::Open)) => &[next_paren, next_name, next_num, next_pre_op],
+		Some(&Token::Paren(Paren::Close)) | Some(&Token::Bracket(Paren::Close)) | Some(&Token::Brace(Paren::Close)) => &[
 			next_paren,
 			next_comma,
 			next_in_op,
@@ -197,7 +358,7 @@ fn get_parse_order(last: Option<&Token>) -> &[TokenFn] {
 			next_post_op,
 			next_num,
 		],
-		Some(&Token::Num(_)) => &[next_paren, next_comma, next_in_op, next_post_op, next_name],
+		Some(&Token::Num(_)) | Some(&Token::Frac(_)) => &[next_paren, next_comma, next_in_op, next_post_op, next_name],
 		Some(&Token::Name(_)) => &[
 			next_paren,
 			next_comma,
@@ -213,7 +374,7 @@ fn get_parse_order(last: Option<&Token>) -> &[TokenFn] {
 
 /// Get the next token of a string based on the last token. Returns either a Token and the rest of the
 /// string or an error
-fn next_token<'a>(raw: &'a str, last: Option<&Token>) -> Result<(Token, &'a str), ParseError> {
+pub(crate) fn next_token<'a>(raw: &'a str, last: Option<&Token<'_>>, cfg: &Config) -> Result<(Token<'a>, &'a str), ParseError> {
 	let parseorder = get_parse_order(last);
 
 	let mut tok_start = 0;
@@ -227,92 +388,105 @@ fn next_token<'a>(raw: &'a str, last: Option<&Token>) -> Result<(Token, &'a str)
 	let raw = &raw[tok_start..raw.len()];
 
 	for next_func in parseorder {
-		if let Some(new) = (*next_func)(raw) {
+		if let Some(new) = (*next_func)(raw, cfg) {
 			return Ok(new);
 		}
 	}
 
-	Err(ParseError::UnexpectedToken {
-		token: raw.chars().next().unwrap().to_string(),
-	})
+	match raw.chars().next() {
+		Some(c) => Err(ParseError::UnexpectedToken { token: c.to_string() }),
+		None => Err(ParseError::Expected {
+			expected: Expected::Expression,
+		}),
+	}
 }
 
 /// Convert a string to a list of tokens
-fn to_tokens(mut raw: &str) -> Result<Vec<Token>, ParseError> {
+fn to_tokens<'a>(mut raw: &'a str, cfg: &Config) -> Result<Vec<Token<'a>>, ParseError> {
 	let mut tokens = Vec::new();
 	while !raw.is_empty() {
-		let (tok, new_raw) = next_token(raw, tokens.last())?;
+		let (tok, new_raw) = next_token(raw, tokens.last(), cfg)?;
 		tokens.push(tok);
 		raw = new_raw;
 	}
 	Ok(tokens)
 }
 
-/// Convert tokens to a tree based on expression within parentheses
-fn to_paren_tokens(raw: Vec<Token>) -> Result<Vec<ParenToken>, ParseError> {
-	fn recurse(raw: &[Token]) -> Result<Vec<ParenToken>, ParseError> {
-		let mut parentokens = Vec::new();
-
-		let mut start = 0;
-		let mut paren_count = 0;
-		let mut counting = false;
+/// The kinds of groups that can be opened in an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+	Paren,
+	Bracket,
+	Brace,
+}
 
-		for (i, token) in raw.iter().enumerate() {
-			match *token {
-				Token::Num(num) => {
-					if !counting {
-						parentokens.push(ParenToken::Num(num)); // Only push the number if it's not part of a subexpression
-					}
-				}
-				Token::Op(ref op) => {
-					if !counting {
-						parentokens.push(ParenToken::Op(op.clone())); // Only push the op if it's not part of a subexpression
-					}
-				}
-				Token::Paren(Paren::Open) => {
-					if !counting {
-						start = i; // If we aren't already in a subexpression, start counting here
-					}
-					counting = true; // Say we are counting
-					paren_count += 1; // Up the open parentheses count
-				}
-				Token::Paren(Paren::Close) => {
-					paren_count -= 1; // Lower the open parentheses count
+/// Convert tokens to a tree based on expression within parentheses, square brackets and curly braces.
+/// Curly braces group the same way parentheses do. Errors if they are nested more than `max_depth`
+/// deep. The groups are kept on a stack instead of recursing, so deep nesting can't overflow the stack.
+fn to_paren_tokens(raw: Vec<Token<'_>>, max_depth: usize) -> Result<Vec<ParenToken<'_>>, ParseError> {
+	/// Get the kind of group a parenthesis, bracket or brace token opens or closes
+	fn group_of(token: &Token<'_>) -> Group {
+		match *token {
+			Token::Bracket(_) => Group::Bracket,
+			Token::Brace(_) => Group::Brace,
+			_ => Group::Paren,
+		}
+	}
 
-					if paren_count < 0 {
-						// Ensure we haven't gone below the amount of parentheses
-						return Err(ParseError::MismatchedParentheses);
-					}
+	let mut parentokens = Vec::new();
+	// The kinds of the groups that are currently open, along with the tokens in them so far
+	let mut open: Vec<(Group, Vec<ParenToken<'_>>)> = Vec::new();
 
-					if paren_count == 0 {
-						// If we have reached the matching end parentheses
-						counting = false; // Say we are not in a subexpression anymore
-						parentokens.push(ParenToken::Sub(recurse(&raw[start + 1..i])?)); // Just push the subexpression
-					}
-				}
-				Token::Name(ref name) => {
-					if !counting {
-						parentokens.push(ParenToken::Name(name.clone())); // Only push the var if it's not part of the subexpression
-					}
+	for token in raw {
+		let paren_token = match token {
+			Token::Num(num) => ParenToken::Num(num),
+			Token::Frac(frac) => ParenToken::Frac(frac),
+			Token::Op(op) => ParenToken::Op(op),
+			Token::Name(name) => ParenToken::Name(name),
+			Token::Comma => ParenToken::Comma,
+			Token::Paren(Paren::Open) | Token::Bracket(Paren::Open) | Token::Brace(Paren::Open) => {
+				if open.len() >= max_depth {
+					return Err(ParseError::TooDeep);
 				}
-				Token::Comma => {
-					if !counting {
-						parentokens.push(ParenToken::Comma); // Only push the comma if it's not part of the subexpression
+				open.push((group_of(&token), Vec::new()));
+				continue;
+			}
+			Token::Paren(Paren::Close) | Token::Bracket(Paren::Close) | Token::Brace(Paren::Close) => {
+				let group = group_of(&token);
+				// Ensure we haven't gone below the amount of parentheses and the kinds match
+				match open.pop() {
+					Some((open_group, sub)) if open_group == group => {
+						if group == Group::Bracket {
+							ParenToken::Bracket(sub)
+						} else {
+							ParenToken::Sub(sub)
+						}
 					}
+					_ => return Err(ParseError::MismatchedParentheses),
 				}
 			}
+		};
+		// Push the token to the innermost group that's open
+		match open.last_mut() {
+			Some(&mut (_, ref mut sub)) => sub.push(paren_token),
+			None => parentokens.push(paren_token),
 		}
+	}
 
-		Ok(parentokens)
+	if !open.is_empty() {
+		return Err(ParseError::MismatchedParentheses);
 	}
 
-	recurse(&raw)
+	Ok(parentokens)
 }
 
-/// Get ParenTokens from a string
-pub(crate) fn get_tokens(raw: &str) -> Result<Vec<ParenToken>, ParseError> {
-	let raw_tokens = to_tokens(raw)?;
-	let paren_tokens = to_paren_tokens(raw_tokens)?;
+/// Get ParenTokens from a string, respecting the limits in the config
+pub(crate) fn get_tokens<'a>(raw: &'a str, cfg: &Config) -> Result<Vec<ParenToken<'a>>, ParseError> {
+	let raw_tokens = to_tokens(raw, cfg)?;
+	if raw_tokens.len() > cfg.max_nodes {
+		return Err(ParseError::TooLarge);
+	}
+	let paren_tokens = to_paren_tokens(raw_tokens, cfg.max_depth)?;
 
 	Ok(paren_tokens)
 }
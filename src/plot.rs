@@ -0,0 +1,202 @@
+//! Quick plots of expressions. `sample` evaluates an expression over a range of values of a
+//! variable, and the points can then be drawn as text for a terminal with `render_text` or as an SVG
+//! image with `render_svg`. Enable it with the `"plot"` feature.
+//!
+//! ```rust
+//! # use mexprp::{Term, plot};
+//! let term: Term<f64> = Term::parse("x^2").unwrap();
+//! let points = plot::sample(&term, "x", -2.0..=2.0, 41);
+//! assert_eq!(points[0], (-2.0, Some(4.0)));
+//! println!("{}", plot::render_text(&points, 60, 15));
+//! ```
+
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+use crate::answer::Answer;
+use crate::context::Context;
+use crate::num::Num;
+use crate::term::Term;
+
+/// The space around the plot in an SVG, in pixels
+const SVG_MARGIN: f64 = 24.0;
+
+/// Evaluate an expression at `n` evenly spaced values of a variable from the start to the end of the
+/// range (inclusive) with the default context. See `sample_ctx`.
+pub fn sample<N: Num + 'static>(expr: &Term<N>, var: &str, range: RangeInclusive<f64>, n: usize) -> Vec<(f64, Option<f64>)> {
+	sample_ctx(expr, var, range, n, &Context::new())
+}
+
+/// Evaluate an expression at `n` evenly spaced values of a variable from the start to the end of the
+/// range (inclusive) with the given context. Returns the values of the variable along with the value
+/// of the expression, which is `None` where the expression couldn't be evaluated or wasn't a finite
+/// real number. Answers with several values are plotted with their principal value.
+pub fn sample_ctx<N: Num + 'static>(expr: &Term<N>, var: &str, range: RangeInclusive<f64>, n: usize, ctx: &Context<N>) -> Vec<(f64, Option<f64>)> {
+	let (start, end) = range.into_inner();
+	let mut ctx = ctx.clone();
+	(0..n)
+		.map(|i| {
+			let x = if n == 1 { start } else { start + (end - start) * i as f64 / (n - 1) as f64 };
+			(x, value_at(expr, var, x, &mut ctx))
+		})
+		.collect()
+}
+
+/// Evaluate an expression with the variable set to `x`
+fn value_at<N: Num + 'static>(expr: &Term<N>, var: &str, x: f64, ctx: &mut Context<N>) -> Option<f64> {
+	let x = match N::from_f64(x, ctx).ok()? {
+		Answer::Single(x) => x,
+		_ => return None,
+	};
+	ctx.set_var(var, x);
+	let y = expr.eval_ctx(ctx).ok()?.principal()?.to_f64_lossy().ok()?;
+	if y.is_finite() {
+		Some(y)
+	} else {
+		None
+	}
+}
+
+/// The ranges of the values of the points, never empty
+struct Bounds {
+	x0: f64,
+	x1: f64,
+	y0: f64,
+	y1: f64,
+}
+
+impl Bounds {
+	fn new(points: &[(f64, Option<f64>)]) -> Self {
+		let mut bounds = Bounds {
+			x0: f64::INFINITY,
+			x1: f64::NEG_INFINITY,
+			y0: f64::INFINITY,
+			y1: f64::NEG_INFINITY,
+		};
+		for &(x, y) in points {
+			if x.is_finite() {
+				bounds.x0 = bounds.x0.min(x);
+				bounds.x1 = bounds.x1.max(x);
+			}
+			if let Some(y) = y {
+				bounds.y0 = bounds.y0.min(y);
+				bounds.y1 = bounds.y1.max(y);
+			}
+		}
+		let (x0, x1) = widen(bounds.x0, bounds.x1);
+		let (y0, y1) = widen(bounds.y0, bounds.y1);
+		Bounds { x0, x1, y0, y1 }
+	}
+
+	/// Where a point is from 0 to 1 across each range, with y going downwards
+	fn scale(&self, x: f64, y: f64) -> (f64, f64) {
+		((x - self.x0) / (self.x1 - self.x0), (self.y1 - y) / (self.y1 - self.y0))
+	}
+}
+
+/// Make sure a range isn't empty, since nothing could be scaled to it
+fn widen(min: f64, max: f64) -> (f64, f64) {
+	if min > max {
+		(-1.0, 1.0)
+	} else if min == max {
+		(min - 1.0, max + 1.0)
+	} else {
+		(min, max)
+	}
+}
+
+/// Draw the points as text with `*`s, with axes where the x or y value is zero and the ranges of
+/// the values next to the plot. The plot area is `width` characters wide and `height` lines high.
+pub fn render_text(points: &[(f64, Option<f64>)], width: usize, height: usize) -> String {
+	let (width, height) = (width.max(2), height.max(2));
+	let bounds = Bounds::new(points);
+	let cell = |x: f64, y: f64| {
+		let (x, y) = bounds.scale(x, y);
+		((x * (width - 1) as f64).round() as usize, (y * (height - 1) as f64).round() as usize)
+	};
+
+	let mut grid = vec![vec![' '; width]; height];
+	if bounds.y0 <= 0.0 && bounds.y1 >= 0.0 {
+		let (_, row) = cell(bounds.x0, 0.0);
+		grid[row] = vec!['-'; width];
+	}
+	if bounds.x0 <= 0.0 && bounds.x1 >= 0.0 {
+		let (col, _) = cell(0.0, bounds.y0);
+		for line in &mut grid {
+			line[col] = if line[col] == '-' { '+' } else { '|' };
+		}
+	}
+	for &(x, y) in points {
+		if let Some(y) = y {
+			let (col, row) = cell(x, y);
+			grid[row][col] = '*';
+		}
+	}
+
+	let (top, bottom) = (label(bounds.y1), label(bounds.y0));
+	let margin = top.chars().count().max(bottom.chars().count());
+	let mut output = String::new();
+	for (i, line) in grid.iter().enumerate() {
+		let label = match i {
+			0 => &top,
+			_ if i == height - 1 => &bottom,
+			_ => "",
+		};
+		let line: String = line.iter().collect();
+		writeln!(output, "{:>margin$} {}", label, line.trim_end(), margin = margin).unwrap();
+	}
+	let (left, right) = (label(bounds.x0), label(bounds.x1));
+	let gap = width.saturating_sub(left.chars().count() + right.chars().count()).max(1);
+	writeln!(output, "{:margin$} {}{:gap$}{}", "", left, "", right, margin = margin, gap = gap).unwrap();
+	output
+}
+
+/// Draw the points as an SVG image `width` by `height` pixels big. Points next to each other are
+/// joined by lines, which are broken wherever the expression has no value. There are axes where the
+/// x or y value is zero, and the ranges of the values are written at the edges.
+pub fn render_svg(points: &[(f64, Option<f64>)], width: u32, height: u32) -> String {
+	let (w, h) = (f64::from(width), f64::from(height));
+	let bounds = Bounds::new(points);
+	let pixel = |x: f64, y: f64| {
+		let (x, y) = bounds.scale(x, y);
+		(SVG_MARGIN + x * (w - 2.0 * SVG_MARGIN), SVG_MARGIN + y * (h - 2.0 * SVG_MARGIN))
+	};
+
+	let mut svg = String::new();
+	writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#, width, height).unwrap();
+	writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+	if bounds.y0 <= 0.0 && bounds.y1 >= 0.0 {
+		let ((x0, y), (x1, _)) = (pixel(bounds.x0, 0.0), pixel(bounds.x1, 0.0));
+		writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="gray"/>"#, x0, y, x1, y).unwrap();
+	}
+	if bounds.x0 <= 0.0 && bounds.x1 >= 0.0 {
+		let ((x, y0), (_, y1)) = (pixel(0.0, bounds.y0), pixel(0.0, bounds.y1));
+		writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="gray"/>"#, x, y0, x, y1).unwrap();
+	}
+
+	for segment in points.split(|&(_, y)| y.is_none()).filter(|segment| !segment.is_empty()) {
+		let coords: Vec<String> = segment.iter()
+			.filter_map(|&(x, y)| y.map(|y| pixel(x, y)))
+			.map(|(x, y)| format!("{:.2},{:.2}", x, y))
+			.collect();
+		writeln!(svg, r#"<polyline points="{}" fill="none" stroke="steelblue" stroke-width="1.5"/>"#, coords.join(" ")).unwrap();
+	}
+
+	let text = r#"font-family="sans-serif" font-size="10""#;
+	writeln!(svg, r#"<text x="2" y="{}" {}>{}</text>"#, SVG_MARGIN - 4.0, text, label(bounds.y1)).unwrap();
+	writeln!(svg, r#"<text x="2" y="{}" {}>{}</text>"#, h - SVG_MARGIN + 12.0, text, label(bounds.y0)).unwrap();
+	writeln!(svg, r#"<text x="{}" y="{}" {}>{}</text>"#, SVG_MARGIN, h - 4.0, text, label(bounds.x0)).unwrap();
+	writeln!(svg, r#"<text x="{}" y="{}" text-anchor="end" {}>{}</text>"#, w - SVG_MARGIN, h - 4.0, text, label(bounds.x1)).unwrap();
+	svg.push_str("</svg>\n");
+	svg
+}
+
+/// A value shown next to a plot, with at most 3 decimal places
+fn label(value: f64) -> String {
+	let label = format!("{:.3}", value);
+	let label = label.trim_end_matches('0').trim_end_matches('.');
+	match label {
+		"-0" => "0".to_string(),
+		_ => label.to_string(),
+	}
+}
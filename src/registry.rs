@@ -0,0 +1,50 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+use crate::context::Context;
+use crate::func::Func;
+use crate::num::Num;
+
+/// The functions registered for one number type
+type Funcs<N> = HashMap<String, Arc<dyn Func<N>>>;
+
+/// The functions registered for each number type, as `Funcs`
+type Registry = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
+/// How many times functions have been registered, so that contexts created before can be updated
+static VERSION: AtomicUsize = AtomicUsize::new(0);
+
+fn registry() -> &'static RwLock<Registry> {
+	static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+	REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Add a function to the functions every default context gets, replacing any existing one with the
+/// same name
+pub(crate) fn register<N: Num + 'static>(name: String, func: Arc<dyn Func<N>>) {
+	// Inserting into a map can't leave it broken, so a panic while it was locked doesn't matter
+	let mut registry = registry().write().unwrap_or_else(PoisonError::into_inner);
+	let funcs = registry.entry(TypeId::of::<N>()).or_insert_with(|| Box::new(Funcs::<N>::new()));
+	// The functions of each number type are stored under its `TypeId`, so they're always `Funcs<N>`
+	if let Some(funcs) = funcs.downcast_mut::<Funcs<N>>() {
+		funcs.insert(name, func);
+	}
+	VERSION.fetch_add(1, Ordering::Release);
+}
+
+/// Add the registered functions to a context
+pub(crate) fn install<N: Num + 'static>(ctx: &mut Context<N>) {
+	let registry = registry().read().unwrap_or_else(PoisonError::into_inner);
+	if let Some(funcs) = registry.get(&TypeId::of::<N>()).and_then(|funcs| funcs.downcast_ref::<Funcs<N>>()) {
+		for (name, func) in funcs {
+			ctx.funcs.insert(name.clone(), Arc::clone(func));
+		}
+	}
+}
+
+/// How many times functions have been registered so far
+pub(crate) fn version() -> usize {
+	VERSION.load(Ordering::Acquire)
+}
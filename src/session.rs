@@ -0,0 +1,170 @@
+use alloc::collections::VecDeque;
+
+use crate::term::Term;
+use crate::context::Context;
+use crate::func::{Arity, Func};
+use crate::errors::{EvalError, MathError};
+use crate::opers::Calculation;
+use crate::answer::Answer;
+use crate::num::Num;
+use crate::prelude::*;
+
+/// The previous answers of a `Session`, from most to least recent
+#[derive(Debug, Clone)]
+pub struct History<N: Num> {
+	answers: VecDeque<Answer<N>>,
+	/// The maximum amount of answers to remember. Older answers are forgotten once there are more
+	/// than this (default = 100).
+	pub limit: usize,
+}
+
+impl<N: Num> History<N> {
+	/// Create an empty history with the default limit
+	pub fn new() -> Self {
+		History {
+			answers: VecDeque::new(),
+			limit: 100,
+		}
+	}
+
+	/// Add an answer as the most recent one, forgetting the oldest answer if there are too many
+	pub fn push(&mut self, answer: Answer<N>) {
+		self.answers.push_front(answer);
+		self.answers.truncate(self.limit);
+	}
+
+	/// Get the nth most recent answer, where 1 is the last answer
+	pub fn get(&self, n: usize) -> Option<&Answer<N>> {
+		n.checked_sub(1).and_then(|i| self.answers.get(i))
+	}
+
+	/// Get the last answer
+	pub fn last(&self) -> Option<&Answer<N>> {
+		self.get(1)
+	}
+
+	/// The amount of answers remembered
+	pub fn len(&self) -> usize {
+		self.answers.len()
+	}
+
+	/// Check if there are no answers
+	pub fn is_empty(&self) -> bool {
+		self.answers.is_empty()
+	}
+
+	/// Forget all the answers
+	pub fn clear(&mut self) {
+		self.answers.clear();
+	}
+
+	/// Iterate over the answers from most to least recent
+	pub fn iter(&self) -> impl Iterator<Item = &Answer<N>> {
+		self.answers.iter()
+	}
+}
+
+impl<N: Num> Default for History<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// A context along with the history of the answers evaluated with it, for interactive calculators.
+/// Expressions evaluated with a session can refer to the last answer as `ans`, and to the nth most
+/// recent one as `ans(n)`.
+///
+/// ```rust
+/// # use mexprp::{Session, Answer};
+/// let mut session: Session<f64> = Session::new();
+/// session.eval("2 + 3").unwrap();
+/// session.eval("ans * 2").unwrap();
+/// let res = session.eval("ans(2) + ans"); // Ok(Answer::Single(15.0))
+/// # assert_eq!(res.unwrap(), Answer::Single(15.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session<N: Num> {
+	/// The context expressions are evaluated with. Its `ans` variable and function are replaced
+	/// after every evaluation.
+	pub ctx: Context<N>,
+	/// The previous answers
+	pub history: History<N>,
+}
+
+impl<N: Num + 'static> Session<N> {
+	/// Create a new session with the default context
+	pub fn new() -> Self {
+		Self::with_context(Context::new())
+	}
+
+	/// Create a new session with the given context
+	pub fn with_context(ctx: Context<N>) -> Self {
+		let mut session = Session {
+			ctx,
+			history: History::new(),
+		};
+		session.sync();
+		session
+	}
+
+	/// Parse and evaluate a string with the context of the session, and add the answer to the history
+	/// if it succeeds
+	pub fn eval(&mut self, raw: &str) -> Result<Answer<N>, EvalError> {
+		let answer = Term::parse_ctx(raw, &self.ctx)?.eval_ctx(&self.ctx)?;
+		self.history.push(answer.clone());
+		self.sync();
+		Ok(answer)
+	}
+
+	/// Forget all the previous answers
+	pub fn clear(&mut self) {
+		self.history.clear();
+		self.sync();
+	}
+
+	/// Update the `ans` variable and function of the context to match the history
+	fn sync(&mut self) {
+		match self.history.last() {
+			Some(last) => self.ctx.set_var("ans", last.clone()),
+			None => {
+				self.ctx.remove_var("ans");
+			}
+		}
+		self.ctx.set_func("ans", Ans {
+			answers: self.history.iter().cloned().collect(),
+		});
+	}
+}
+
+impl<N: Num + 'static> Default for Session<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// The `ans(n)` function of a session
+struct Ans<N: Num> {
+	answers: Vec<Answer<N>>,
+}
+
+impl<N: Num + 'static> Func<N> for Ans<N> {
+	fn arity(&self) -> Arity {
+		Arity::range(0, 1)
+	}
+
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let n = match args.len() {
+			0 => 1.0,
+			1 => match args[0].eval_ctx(ctx)? {
+				Answer::Single(n) => n.to_f64_lossy()?,
+				_ => return Err(MathError::IncorrectArguments),
+			},
+			_ => return Err(MathError::IncorrectArguments),
+		};
+		if n < 1.0 || n % 1.0 != 0.0 {
+			return Err(MathError::InvalidIndex);
+		}
+
+		self.answers.get(n as usize - 1).cloned().ok_or(MathError::InvalidIndex)
+	}
+}
@@ -0,0 +1,430 @@
+#[cfg(feature = "std")]
+use core::any::Any;
+use core::cell::{Cell, RefCell};
+#[cfg(feature = "std")]
+use std::collections::HashMap as Map;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as Map;
+use alloc::rc::Rc;
+
+use crate::term::Term;
+use crate::context::Context;
+use crate::num::Num;
+use crate::answer::Answer;
+use crate::opers::Calculation;
+use crate::errors::MathError;
+use crate::prelude::*;
+
+/// The state of an evaluation with a context: how deep it is, how many steps it has taken, the values
+/// of the slots of the `BoundTerm` being evaluated and the answers cached along the way. It's kept
+/// outside of the context, so that the context doesn't change while it's evaluated with and any
+/// amount of threads can evaluate with it at once.
+///
+/// The evaluator passes the state down to the terms and operations it evaluates, but functions only
+/// get the context. So that evaluating a term with the context from inside a function continues the
+/// same evaluation, the evaluations in progress are also kept for the contexts they're with, in a
+/// thread local with `std` or in the context itself without it.
+pub struct EvalState<N: Num> {
+	/// How deep the evaluation is
+	depth: Cell<usize>,
+	/// How many steps the evaluation has taken
+	steps: Cell<usize>,
+	/// The values of the slots of the `BoundTerm` being evaluated
+	slots: Vec<N>,
+	/// Answers of subterms cached during the evaluation
+	cache: EvalCache<N>,
+	/// Values of variables cached during the evaluation
+	vars: VarCache<N>,
+}
+
+impl<N: Num + 'static> EvalState<N> {
+	/// The state of an evaluation that starts `depth` terms deep and has taken `steps` steps already
+	pub(crate) fn new(depth: usize, steps: usize, slots: Vec<N>) -> Self {
+		EvalState {
+			depth: Cell::new(depth),
+			steps: Cell::new(steps),
+			slots,
+			cache: EvalCache::new(),
+			vars: VarCache::new(),
+		}
+	}
+
+	/// Call a function with the state of the evaluation in progress with the context, or of a new one
+	/// if there isn't one
+	pub(crate) fn with<R, F: FnOnce(&EvalState<N>) -> R>(ctx: &Context<N>, f: F) -> R {
+		match Self::current(ctx) {
+			Some(state) => f(&state),
+			None => Self::run(ctx, EvalState::new(0, 0, Vec::new()), f),
+		}
+	}
+
+	/// Call a function with the state of a new evaluation with the context. Until the function returns,
+	/// evaluating with the context continues that evaluation.
+	pub(crate) fn run<R, F: FnOnce(&EvalState<N>) -> R>(ctx: &Context<N>, state: EvalState<N>, f: F) -> R {
+		let state = Rc::new(state);
+		let _active = Active::push(ctx, Rc::clone(&state));
+		f(&state)
+	}
+
+	/// The state of the innermost evaluation in progress with the context
+	#[cfg(feature = "std")]
+	pub(crate) fn current(ctx: &Context<N>) -> Option<Rc<EvalState<N>>> {
+		let address = address(ctx);
+		let state = ACTIVE.with(|active| {
+			active.borrow().iter().rev().find(|entry| entry.0 == address).map(|entry| Rc::clone(&entry.1))
+		})?;
+		// Two contexts can't be at the same address while they're being evaluated with, but if the state
+		// somehow isn't for this kind of context, a new evaluation is started instead
+		state.downcast().ok()
+	}
+
+	/// The state of the innermost evaluation in progress with the context
+	#[cfg(not(feature = "std"))]
+	pub(crate) fn current(ctx: &Context<N>) -> Option<Rc<EvalState<N>>> {
+		ctx.active.0.borrow().last().cloned()
+	}
+
+	pub(crate) fn depth(&self) -> usize {
+		self.depth.get()
+	}
+
+	pub(crate) fn set_depth(&self, depth: usize) {
+		self.depth.set(depth);
+	}
+
+	pub(crate) fn steps(&self) -> usize {
+		self.steps.get()
+	}
+
+	pub(crate) fn set_steps(&self, steps: usize) {
+		self.steps.set(steps);
+	}
+
+	/// Check whether the evaluation should stop, either because it was cancelled or because it ran out
+	/// of steps. `cost` is the amount of steps to use up.
+	pub(crate) fn step(&self, cost: usize, ctx: &Context<N>) -> Result<(), MathError> {
+		if let Some(ref token) = ctx.cancel {
+			if token.is_cancelled() {
+				return Err(MathError::Cancelled);
+			}
+		}
+		let steps = self.steps.get().saturating_add(cost);
+		self.steps.set(steps);
+		match ctx.cfg.max_steps {
+			Some(max) if steps > max => Err(MathError::Cancelled),
+			_ => Ok(()),
+		}
+	}
+
+	/// Get the value of a slot of the `BoundTerm` being evaluated
+	pub(crate) fn slot(&self, index: usize) -> Option<N> {
+		self.slots.get(index).cloned()
+	}
+
+	/// Clear the answers and values of variables cached so far
+	pub(crate) fn clear(&self) {
+		self.cache.clear();
+		self.vars.clear();
+	}
+
+	/// Call a function that evaluates the body of a function defined by an expression, with its
+	/// parameters set to the given values. Until it returns, the parameters shadow any variables with
+	/// the same names, including in the definitions of other variables. The body is evaluated as part
+	/// of the same evaluation as the call, so it shares its depth, steps and the variables being
+	/// evaluated. A function called outside of an evaluation starts one.
+	pub(crate) fn with_params<R, F: FnOnce(&EvalState<N>) -> R>(ctx: &Context<N>, params: Vec<(String, Answer<N>)>, f: F) -> R {
+		Self::with(ctx, |state| {
+			state.vars.scopes.borrow_mut().push(Scope::new(params));
+			state.vars.calls.set(state.vars.calls.get() + 1);
+			if ctx.cfg.memoize {
+				state.cache.answers.borrow_mut().push(Answers::new());
+			}
+			let res = f(state);
+			if ctx.cfg.memoize {
+				state.cache.answers.borrow_mut().pop();
+			}
+			state.vars.calls.set(state.vars.calls.get() - 1);
+			state.vars.scopes.borrow_mut().pop();
+			res
+		})
+	}
+
+	/// Get the value of a parameter of a function that's being called, starting from the innermost
+	/// call
+	pub(crate) fn param(&self, name: &str) -> Option<Answer<N>> {
+		self.vars.param(name)
+	}
+
+	/// Evaluate the variable `name` defined by `var` with the given function. See `VarCache::eval`.
+	pub(crate) fn eval_var<F: FnOnce() -> Calculation<N>>(&self, name: &str, var: &Term<N>, cache: bool, eval: F) -> Calculation<N> {
+		self.vars.eval(name, var, cache, eval)
+	}
+
+	/// Get the cached answer of a term, or evaluate it with the given function and cache the answer
+	pub(crate) fn eval_cached<F: FnOnce() -> Calculation<N>>(&self, term: &Term<N>, ctx: &Context<N>, eval: F) -> Calculation<N> {
+		self.cache.eval(term, ctx, eval)
+	}
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+	/// The evaluations in progress on this thread, innermost last, along with the addresses of the
+	/// contexts they're with
+	static ACTIVE: RefCell<Vec<(usize, Rc<dyn Any>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The address of a context, which identifies it while it's being evaluated with
+#[cfg(feature = "std")]
+fn address<N: Num>(ctx: &Context<N>) -> usize {
+	ctx as *const Context<N> as usize
+}
+
+/// The evaluations in progress with a context, innermost last. Without `std` there are no thread
+/// locals to keep them in, so they're kept in the context. Cloning it creates an empty one, since
+/// the clone isn't being evaluated with.
+#[cfg(not(feature = "std"))]
+pub(crate) struct Evaluations<N: Num>(RefCell<Vec<Rc<EvalState<N>>>>);
+
+// Safety: evaluations are only in progress while the context is borrowed by them, and they're
+// removed before the borrow ends, even when they panic. A context can only be sent to another
+// thread when nothing borrows it, so then there are no states whose `Rc`s could be shared.
+#[cfg(not(feature = "std"))]
+unsafe impl<N: Num> Send for Evaluations<N> {}
+
+#[cfg(not(feature = "std"))]
+impl<N: Num> Evaluations<N> {
+	pub(crate) fn new() -> Self {
+		Evaluations(RefCell::new(Vec::new()))
+	}
+}
+
+#[cfg(not(feature = "std"))]
+impl<N: Num> Clone for Evaluations<N> {
+	fn clone(&self) -> Self {
+		Evaluations::new()
+	}
+}
+
+/// An evaluation in progress with a context. It stops being in progress once this is dropped, even
+/// if the evaluation panicked, so that the next evaluation with the context starts a new one instead
+/// of continuing one that never finished.
+struct Active<'a, N: Num>(&'a Context<N>);
+
+impl<'a, N: Num + 'static> Active<'a, N> {
+	#[cfg(feature = "std")]
+	fn push(ctx: &'a Context<N>, state: Rc<EvalState<N>>) -> Self {
+		ACTIVE.with(|active| active.borrow_mut().push((address(ctx), state)));
+		Active(ctx)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn push(ctx: &'a Context<N>, state: Rc<EvalState<N>>) -> Self {
+		ctx.active.0.borrow_mut().push(state);
+		Active(ctx)
+	}
+}
+
+impl<'a, N: Num> Drop for Active<'a, N> {
+	#[cfg(feature = "std")]
+	fn drop(&mut self) {
+		let address = address(self.0);
+		// The thread local is only gone once the thread is exiting, when there's nothing left to remove
+		let _ = ACTIVE.try_with(|active| {
+			let mut active = active.borrow_mut();
+			if let Some(index) = active.iter().rposition(|entry| entry.0 == address) {
+				active.remove(index);
+			}
+		});
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn drop(&mut self) {
+		self.0.active.0.borrow_mut().pop();
+	}
+}
+
+/// Cached answers of terms
+#[cfg(feature = "std")]
+type Answers<N> = Map<Term<N>, Answer<N>>;
+/// Cached answers of terms. Terms can't be ordered to be stored in a `BTreeMap`, so without `std`
+/// they are searched for linearly instead.
+#[cfg(not(feature = "std"))]
+type Answers<N> = LinearMap<Term<N>, Answer<N>>;
+
+/// A map that stores its entries in a list
+#[cfg(not(feature = "std"))]
+struct LinearMap<K, V> {
+	entries: Vec<(K, V)>,
+}
+
+#[cfg(not(feature = "std"))]
+impl<K: PartialEq, V> LinearMap<K, V> {
+	fn new() -> Self {
+		LinearMap { entries: Vec::new() }
+	}
+
+	fn get(&self, key: &K) -> Option<&V> {
+		self.entries.iter().find(|entry| entry.0 == *key).map(|entry| &entry.1)
+	}
+
+	fn insert(&mut self, key: K, value: V) {
+		match self.entries.iter_mut().find(|entry| entry.0 == key) {
+			Some(entry) => entry.1 = value,
+			None => self.entries.push((key, value)),
+		}
+	}
+
+	fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	fn clear(&mut self) {
+		self.entries.clear();
+	}
+}
+
+/// A cache of the answers of subterms during an evaluation
+struct EvalCache<N: Num> {
+	/// The answers cached in each scope of the evaluation, innermost last. The same term can have a
+	/// different answer in the body of a function, since its parameters shadow variables.
+	answers: RefCell<Vec<Answers<N>>>,
+}
+
+impl<N: Num + 'static> EvalCache<N> {
+	fn new() -> Self {
+		EvalCache {
+			answers: RefCell::new(vec![Answers::new()]),
+		}
+	}
+
+	fn clear(&self) {
+		for answers in self.answers.borrow_mut().iter_mut() {
+			answers.clear();
+		}
+	}
+
+	/// Get the cached answer of a term, or evaluate it with the given function and cache the answer
+	fn eval<F: FnOnce() -> Calculation<N>>(&self, term: &Term<N>, ctx: &Context<N>, eval: F) -> Calculation<N> {
+		let cacheable = match *term {
+			Term::Operation(_) | Term::Function(_, _) => true,
+			Term::Num(_) | Term::Var(_) => false,
+		};
+		if cacheable {
+			if let Some(answer) = self.answers.borrow().last().and_then(|answers| answers.get(term).cloned()) {
+				return Ok(answer);
+			}
+		}
+
+		let res = eval();
+
+		if let Ok(ref answer) = res {
+			if cacheable {
+				if let Some(answers) = self.answers.borrow_mut().last_mut() {
+					if answers.len() < ctx.cfg.cache_limit {
+						answers.insert(term.clone(), answer.clone());
+					}
+				}
+			}
+		}
+		res
+	}
+}
+
+/// The values of variables during an evaluation, so that variables defined by terms are only
+/// evaluated the first time they're used, the parameters of the functions defined by expressions
+/// that are being called, and the variables that are being evaluated, to find variables defined in
+/// terms of themselves
+struct VarCache<N: Num> {
+	/// The scopes of the evaluation, innermost last. The first one is the whole evaluation, and every
+	/// call of a function defined by an expression adds one with the values of its parameters.
+	scopes: RefCell<Vec<Scope<N>>>,
+	/// How many scopes of calls there are, so that variables only have to be looked up in the scopes
+	/// while a function is being called
+	calls: Cell<usize>,
+	/// The names of the variables being evaluated, outermost first
+	path: RefCell<Vec<String>>,
+}
+
+/// A scope of an evaluation
+struct Scope<N: Num> {
+	/// The parameters of the function being called, with their values
+	params: Vec<(String, Answer<N>)>,
+	/// The values of variables evaluated in the scope. Variables can have different values in the
+	/// body of a function, since the parameters shadow the variables they're defined in terms of.
+	values: Map<String, Answer<N>>,
+}
+
+impl<N: Num> Scope<N> {
+	fn new(params: Vec<(String, Answer<N>)>) -> Self {
+		Scope {
+			params,
+			values: Map::new(),
+		}
+	}
+}
+
+impl<N: Num> VarCache<N> {
+	fn new() -> Self {
+		VarCache {
+			scopes: RefCell::new(vec![Scope::new(Vec::new())]),
+			calls: Cell::new(0),
+			path: RefCell::new(Vec::new()),
+		}
+	}
+
+	fn clear(&self) {
+		for scope in self.scopes.borrow_mut().iter_mut() {
+			scope.values.clear();
+		}
+	}
+
+	/// Get the value of a parameter of a function that's being called, starting from the innermost
+	/// call
+	fn param(&self, name: &str) -> Option<Answer<N>> {
+		if self.calls.get() == 0 {
+			return None;
+		}
+		self.scopes.borrow()
+			.iter()
+			.rev()
+			.flat_map(|scope| scope.params.iter())
+			.find(|param| param.0 == name)
+			.map(|param| param.1.clone())
+	}
+
+	/// Evaluate the variable `name` defined by `var` with the given function, failing with a
+	/// `MathError::CircularDefinition` if it's already being evaluated. If `cache` is true, the
+	/// value is cached, or taken from the cache if it was evaluated before.
+	fn eval<F: FnOnce() -> Calculation<N>>(&self, name: &str, var: &Term<N>, cache: bool, eval: F) -> Calculation<N> {
+		// Numbers are just as fast to get from the definition, and can't refer to other variables
+		if let Term::Num(_) = *var {
+			return eval();
+		}
+		if cache {
+			if let Some(value) = self.scopes.borrow().last().and_then(|scope| scope.values.get(name).cloned()) {
+				return Ok(value);
+			}
+		}
+
+		{
+			let mut path = self.path.borrow_mut();
+			if let Some(start) = path.iter().position(|var| var == name) {
+				let mut cycle = path[start..].to_vec();
+				cycle.push(name.to_string());
+				return Err(MathError::CircularDefinition { path: cycle });
+			}
+			path.push(name.to_string());
+		}
+
+		let res = eval();
+		self.path.borrow_mut().pop();
+		if let Ok(ref value) = res {
+			if cache {
+				if let Some(scope) = self.scopes.borrow_mut().last_mut() {
+					scope.values.insert(name.to_string(), value.clone());
+				}
+			}
+		}
+		res
+	}
+}
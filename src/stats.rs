@@ -0,0 +1,296 @@
+use core::cmp::Ordering;
+use alloc::sync::Arc;
+
+use crate::answer::Answer;
+use crate::context::Context;
+use crate::errors::MathError;
+use crate::func::{Arity, Func, FunctionPack};
+use crate::num::{self, Num};
+use crate::opers::Calculation;
+use crate::term::Term;
+use crate::prelude::*;
+
+/// Functions of common probability distributions, installed without a namespace with
+/// `Context::install`:
+///
+/// - `normal_pdf(x, [mu], [sigma])`: the probability density of the normal distribution with the
+///   mean `mu` (default 0) and standard deviation `sigma` (default 1)
+/// - `normal_cdf(x, [mu], [sigma])`: the probability that a number from the normal distribution is
+///   at most `x`
+/// - `binom_pmf(k, n, p)`: the probability of `k` successes out of `n` tries that each succeed with
+///   the probability `p`
+/// - `poisson_pmf(k, lambda)`: the probability of `k` events in the Poisson distribution with the
+///   mean `lambda`
+/// - `t_cdf(t, df)`: the probability that a number from Student's t-distribution with `df` degrees of
+///   freedom is at most `t`, for p-values of t-tests
+///
+/// The first argument can also be a vector, to get the values of the function for each of its
+/// elements. The functions are computed with the operations of the number type (like `Num::erfc` and
+/// `Num::lgamma`), except for `t_cdf`, which is computed with `f64` precision. Parameters outside of
+/// the domain of the distribution, like a negative standard deviation, result in a
+/// `MathError::DomainError`.
+///
+/// ```rust
+/// # use mexprp::{Context, StatsPack, Term};
+/// let mut ctx: Context<f64> = Context::new();
+/// ctx.install(&StatsPack);
+/// let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+/// // The two-sided p-value of a z-score of 1.96
+/// assert!((eval("2 * (1 - normal_cdf(1.96))") - 0.05).abs() < 1e-3);
+/// assert_eq!(eval("binom_pmf(3, 10, 0.5)"), 0.1171875);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StatsPack;
+
+impl<N: Num + 'static> FunctionPack<N> for StatsPack {
+	fn namespace(&self) -> &str {
+		""
+	}
+
+	fn funcs(&self) -> Vec<(String, Arc<dyn Func<N>>)> {
+		vec![
+			("normal_pdf".to_string(), Arc::new(NormalPdf)),
+			("normal_cdf".to_string(), Arc::new(NormalCdf)),
+			("binom_pmf".to_string(), Arc::new(BinomPmf)),
+			("poisson_pmf".to_string(), Arc::new(PoissonPmf)),
+			("t_cdf".to_string(), Arc::new(TCdf)),
+		]
+	}
+}
+
+/// Get the single value of an answer
+fn single<N: Num>(answer: Calculation<N>) -> Result<N, MathError> {
+	match answer? {
+		Answer::Single(n) => Ok(n),
+		_ => Err(MathError::IncorrectArguments),
+	}
+}
+
+/// Get a number from an `f64`
+fn float<N: Num>(n: f64, ctx: &Context<N>) -> Result<N, MathError> {
+	single(N::from_f64(n, ctx))
+}
+
+/// The error for a parameter outside of the domain of a distribution
+fn domain<N: Num>(func: &str, value: &N) -> MathError {
+	MathError::DomainError { func: func.to_string(), value: value.to_string() }
+}
+
+/// Evaluate the first argument of a distribution function, which can have many values, and the
+/// parameters after it, which have to be single numbers. Missing optional parameters are taken from
+/// `defaults`.
+fn dist_args<N: Num + 'static>(args: &[Term<N>], required: usize, defaults: &[f64], ctx: &Context<N>) -> Result<(Answer<N>, Vec<N>), MathError> {
+	if args.len() < required + 1 || args.len() > required + defaults.len() + 1 {
+		return Err(MathError::IncorrectArguments);
+	}
+
+	let x = args[0].eval_ctx(ctx)?;
+	let mut params = args[1..].iter().map(|arg| single(arg.eval_ctx(ctx))).collect::<Result<Vec<N>, MathError>>()?;
+	for &default in defaults[params.len() - required..].iter() {
+		params.push(float(default, ctx)?);
+	}
+	Ok((x, params))
+}
+
+/// Check that a number of events or successes is a non-negative integer
+fn check_count<N: Num>(k: &N, ctx: &Context<N>) -> Result<(), MathError> {
+	if single(k.floor(ctx))?.tryord(k, ctx)? != Ordering::Equal {
+		Err(MathError::NotAnInteger)
+	} else if k.tryord(&float(0.0, ctx)?, ctx)? == Ordering::Less {
+		Err(MathError::IncorrectArguments)
+	} else {
+		Ok(())
+	}
+}
+
+/// Check whether a number is finite and not NaN, for falling back to computing a probability with
+/// logarithms when the direct formula overflows
+fn is_usable<N: Num>(n: &N) -> bool {
+	n.is_finite() && !n.is_nan()
+}
+
+/// `e^x`
+fn exp<N: Num>(x: &N, ctx: &Context<N>) -> Result<N, MathError> {
+	single(single(N::e(ctx))?.pow(x, ctx))
+}
+
+/// The natural logarithm
+fn ln<N: Num>(x: &N, ctx: &Context<N>) -> Result<N, MathError> {
+	single(x.log(&single(N::e(ctx))?, ctx))
+}
+
+/// The standard score of `x` in the normal distribution with the mean `mu` and standard deviation
+/// `sigma`
+fn z_score<N: Num>(func: &str, x: &N, mu: &N, sigma: &N, ctx: &Context<N>) -> Result<N, MathError> {
+	if sigma.tryord(&float(0.0, ctx)?, ctx)? != Ordering::Greater {
+		return Err(domain(func, sigma));
+	}
+	single(single(x.sub(mu, ctx))?.div(sigma, ctx))
+}
+
+/// The probability density of the normal distribution
+struct NormalPdf;
+
+impl<N: Num + 'static> Func<N> for NormalPdf {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let (x, params) = dist_args(args, 0, &[0.0, 1.0], ctx)?;
+		let (mu, sigma) = (&params[0], &params[1]);
+
+		// e^(-z^2 / 2) / (sigma × (2π)^(1/2))
+		let tau = single(float(2.0, ctx)?.mul(&single(N::pi(ctx))?, ctx))?;
+		let root = single(tau.pow(&float(0.5, ctx)?, ctx))?;
+		let scale = single(sigma.mul(&root, ctx))?;
+		x.unop_ctx(ctx, |x| {
+			let z = z_score("normal_pdf", x, mu, sigma, ctx)?;
+			let exponent = single(single(z.mul(&z, ctx))?.div(&float(-2.0, ctx)?, ctx))?;
+			exp(&exponent, ctx)?.div(&scale, ctx)
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::range(1, 3)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The probability density of the normal distribution, like normal_pdf(x, [mu], [sigma])")
+	}
+}
+
+/// The cumulative distribution function of the normal distribution
+struct NormalCdf;
+
+impl<N: Num + 'static> Func<N> for NormalCdf {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let (x, params) = dist_args(args, 0, &[0.0, 1.0], ctx)?;
+		let (mu, sigma) = (&params[0], &params[1]);
+
+		// erfc(-z / 2^(1/2)) / 2, which is precise far into the lower tail
+		let root = single(float(2.0, ctx)?.pow(&float(0.5, ctx)?, ctx))?;
+		x.unop_ctx(ctx, |x| {
+			let z = z_score("normal_cdf", x, mu, sigma, ctx)?;
+			let scaled = single(single(float(0.0, ctx)?.sub(&z, ctx))?.div(&root, ctx))?;
+			single(scaled.erfc(ctx))?.div(&float(2.0, ctx)?, ctx)
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::range(1, 3)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The probability that a normally distributed number is at most x, like normal_cdf(x, [mu], [sigma])")
+	}
+}
+
+/// The probability mass function of the binomial distribution
+struct BinomPmf;
+
+impl<N: Num + 'static> Func<N> for BinomPmf {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let (k, params) = dist_args(args, 2, &[], ctx)?;
+		let (n, p) = (&params[0], &params[1]);
+		let one = float(1.0, ctx)?;
+		if p.tryord(&float(0.0, ctx)?, ctx)? == Ordering::Less || p.tryord(&one, ctx)? == Ordering::Greater {
+			return Err(domain("binom_pmf", p));
+		}
+		let q = single(one.sub(p, ctx))?;
+
+		k.unop_ctx(ctx, |k| {
+			// C(n, k) × p^k × (1 - p)^(n - k), which is exact for the types that multiply exactly
+			let ways = single(n.binomial(k, ctx))?;
+			if ways.to_f64_lossy()? == 0.0 {
+				return Ok(Answer::Single(ways));
+			}
+			let rest = single(n.sub(k, ctx))?;
+			let chance = single(single(p.pow(k, ctx))?.mul(&single(q.pow(&rest, ctx))?, ctx))?;
+			let direct = single(ways.mul(&chance, ctx))?;
+			if is_usable(&ways) {
+				return Ok(Answer::Single(direct));
+			}
+
+			// The amount of ways overflows for large n, so add the logarithms instead
+			let lgamma = |x: &N| single(single(x.add(&one, ctx))?.lgamma(ctx));
+			let log_ways = single(single(lgamma(n)?.sub(&lgamma(k)?, ctx))?.sub(&lgamma(&rest)?, ctx))?;
+			let log_p = single(k.mul(&ln(p, ctx)?, ctx))?;
+			let log_q = single(rest.mul(&ln(&q, ctx)?, ctx))?;
+			let log = single(single(log_ways.add(&log_p, ctx))?.add(&log_q, ctx))?;
+			Ok(Answer::Single(exp(&log, ctx)?))
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(3)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The probability of k successes out of n tries with the probability p, like binom_pmf(k, n, p)")
+	}
+}
+
+/// The probability mass function of the Poisson distribution
+struct PoissonPmf;
+
+impl<N: Num + 'static> Func<N> for PoissonPmf {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let (k, params) = dist_args(args, 1, &[], ctx)?;
+		let lambda = &params[0];
+		let zero = float(0.0, ctx)?;
+		if lambda.tryord(&zero, ctx)? == Ordering::Less {
+			return Err(domain("poisson_pmf", lambda));
+		}
+		let decay = exp(&single(zero.sub(lambda, ctx))?, ctx)?;
+
+		k.unop_ctx(ctx, |k| {
+			check_count(k, ctx)?;
+			// lambda^k × e^-lambda / k!
+			let power = single(lambda.pow(k, ctx))?;
+			let fact = single(k.fact(ctx))?;
+			if is_usable(&power) && is_usable(&fact) {
+				return single(power.mul(&decay, ctx))?.div(&fact, ctx);
+			}
+
+			// k ln(lambda) - lambda - ln(k!)
+			let log_power = single(k.mul(&ln(lambda, ctx)?, ctx))?;
+			let log_fact = single(single(k.add(&float(1.0, ctx)?, ctx))?.lgamma(ctx))?;
+			let log = single(single(log_power.sub(lambda, ctx))?.sub(&log_fact, ctx))?;
+			Ok(Answer::Single(exp(&log, ctx)?))
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(2)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The probability of k events with the mean lambda, like poisson_pmf(k, lambda)")
+	}
+}
+
+/// The cumulative distribution function of Student's t-distribution
+struct TCdf;
+
+impl<N: Num + 'static> Func<N> for TCdf {
+	fn eval(&self, args: &[Term<N>], ctx: &Context<N>) -> Calculation<N> {
+		let (t, params) = dist_args(args, 1, &[], ctx)?;
+		let df = params[0].to_f64_lossy()?;
+		if df.is_nan() || df <= 0.0 {
+			return Err(domain("t_cdf", &params[0]));
+		}
+
+		t.unop_ctx(ctx, |t| {
+			let t = t.to_f64_lossy()?;
+			// The tail probability is half of I_x(df / 2, 1 / 2), with x = df / (df + t^2)
+			let tail = num::beta_inc(df / 2.0, 0.5, df / (df + t * t)) / 2.0;
+			let cdf = if t > 0.0 { 1.0 - tail } else { tail };
+			N::from_f64(cdf, ctx)
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(2)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The probability that a number from Student's t-distribution is at most t, like t_cdf(t, df)")
+	}
+}
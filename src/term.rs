@@ -1,102 +1,783 @@
-use std::fmt;
-use std::rc::Rc;
+use core::fmt;
+use core::mem;
+use core::cmp::Ordering;
+use core::ops;
+use core::str::FromStr;
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
+use alloc::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::vec;
 
 use crate::op::*;
 use crate::opers::*;
 use crate::parse::*;
 use crate::errors::*;
+use crate::prelude::*;
 use crate::context::*;
 use crate::num::*;
 use crate::answer::*;
 use crate::expr::*;
+use crate::bound::*;
+use crate::future::EvalFuture;
+use crate::state::EvalState;
+use crate::diagnostic::Warning;
+
+/// How deep an evaluation recurses before it evaluates the operations deeper than that with an
+/// explicit stack, so that very deeply nested terms don't overflow the stack
+const RECURSION_DEPTH: usize = 32;
 
 /// The main representation of parsed equations. It is an operand that can contain an operation between
 /// more of itself. This form is the only one that can be directly evaluated. Does not include it's own
 /// context.
+///
+/// Terms are stored as a tree, with each operation behind an `Arc` so that cloning a term shares its
+/// operations instead of copying them. To evaluate a large term many times, bind it with `bind`,
+/// which flattens it into a list of nodes that's evaluated in one pass instead of by walking the
+/// tree.
 #[derive(Debug, Clone)]
 pub enum Term<N: Num> {
 	/// A number
 	Num(Answer<N>),
 	/// An operation
-	Operation(Rc<dyn Operate<N>>),
-	/// A function with the given arguments
-	Function(String, Vec<Term<N>>),
-	/// A variable
-	Var(String),
+	Operation(Arc<dyn Operate<N>>),
+	/// A function with the given arguments. Parsing shares the name between every use of it in the
+	/// term.
+	Function(Arc<str>, Vec<Term<N>>),
+	/// A variable. Parsing shares the name between every use of it in the term.
+	Var(Arc<str>),
 }
 
-/// An enum that represents the equation as a token that can be several types of operands, or an operator.
-/// This token has functions already parsed by their name and arguments, and has no parentheses, with
-/// a Vec of tokens representing an expression within parentheses instead.
+/// One step of evaluating a term with `Term::eval_steps`: an operation, function call or variable
+/// along with the answer it evaluated to
 #[derive(Debug, Clone)]
-enum Expr {
+pub struct Step<N: Num> {
+	/// The part of the term that was evaluated
+	pub term: String,
+	/// The part of the term with its operands or arguments replaced by their answers, like `(3 + 4)`
+	pub evaluated: String,
+	/// The answer it evaluated to
+	pub answer: Answer<N>,
+}
+
+impl<N: Num> fmt::Display for Step<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} = {}", self.evaluated, self.answer)
+	}
+}
+
+/// The names of the variables and functions a term uses that a context doesn't define. See
+/// `Term::missing_definitions`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Missing {
+	/// The names of the variables that aren't defined, in alphabetical order
+	pub vars: Vec<String>,
+	/// The names of the functions that aren't defined, in alphabetical order
+	pub funcs: Vec<String>,
+}
+
+impl Missing {
+	/// Whether or not every name is defined
+	pub fn is_empty(&self) -> bool {
+		self.vars.is_empty() && self.funcs.is_empty()
+	}
+}
+
+/// An enum that represents the equation as a token that can be several types of operands, or an operator.
+/// This token has no parentheses, with the subexpressions, function calls and vectors within them
+/// already parsed into terms instead.
+#[derive(Debug)]
+enum Expr<'a, N: Num> {
 	/// A number
 	Num(f64),
+	/// A fraction or mixed number
+	Frac(Fraction),
 	/// An operator
 	Op(Op),
-	/// An expression within parentheses (a subexpression)
-	Sub(Vec<Expr>),
-	/// A variable
-	Var(String),
-	/// A function with these args
-	Func(String, Vec<Vec<Expr>>),
+	/// A variable, borrowing its name from the string being parsed
+	Var(&'a str),
+	/// An operand that has already been parsed into a term along with its depth, like a subexpression,
+	/// a function call, a vector literal or an indexed operand
+	Term(Term<N>, usize),
+}
+
+/// A nested list of tokens whose contents are parsed before the operand they make up
+enum Group<'a, N: Num> {
+	/// A subexpression in parentheses or grouped by brackets
+	Sub,
+	/// The arguments of a call to this function
+	Func(&'a str),
+	/// The elements of a vector literal
+	Vector,
+	/// The indices of this operand with this depth
+	Index(Term<N>, usize),
+}
+
+/// One level of nesting that's being parsed. Nested groups get their own frame on an explicit stack
+/// instead of being parsed recursively, so deeply nested expressions can't overflow the stack.
+struct Frame<'a, N: Num> {
+	/// The tokens left to convert to exprs
+	tokens: vec::IntoIter<ParenToken<'a>>,
+	/// The exprs converted so far
+	exprs: Vec<Expr<'a, N>>,
+	/// A name that has yet to be decided to be a function or a variable
+	pending_name: Option<&'a str>,
+	/// The group being parsed by the frames above this one
+	group: Option<Group<'a, N>>,
+	/// The token lists of the group left to parse
+	lists: vec::IntoIter<Vec<ParenToken<'a>>>,
+	/// The terms parsed from the group so far along with their depths
+	terms: Vec<(Term<N>, usize)>,
 }
 
 impl<N: Num + 'static> Term<N> {
 	/// Parse a string into an expression
 	pub fn parse(raw: &str) -> Result<Self, ParseError> {
-		let ctx = Context::new();
-		Self::parse_ctx(raw, &ctx)
+		Context::with_default(|ctx| Self::parse_ctx(raw, ctx))
 	}
 
 	/// Parse a string into an expression with the given context
 	pub fn parse_ctx(raw: &str, ctx: &Context<N>) -> Result<Self, ParseError> {
+		Self::parse_with_warnings(raw, ctx).map(|(term, _)| term)
+	}
+
+	/// Parse a string into an expression with the given context, also returning warnings about the
+	/// decisions made while parsing it that might not be what the user meant, like inserting implicit
+	/// multiplications or treating unknown names as variables. Useful for showing users how their
+	/// input was understood.
+	///
+	/// ```rust
+	/// # use mexprp::{Term, Context, Warning};
+	/// let ctx: Context<f64> = Context::new();
+	/// let (term, warnings) = Term::<f64>::parse_with_warnings("2x", &ctx).unwrap();
+	/// assert_eq!(term.to_string(), "(2 × x)");
+	/// let warnings: Vec<String> = warnings.iter().map(|warning| warning.to_string()).collect();
+	/// assert_eq!(warnings, vec!["Name `x` treated as a variable", "Implicit multiplication inserted between `2` and `x`"]);
+	/// ```
+	pub fn parse_with_warnings(raw: &str, ctx: &Context<N>) -> Result<(Self, Vec<Warning>), ParseError> {
+		let raw = if ctx.cfg.normalize_input { normalize(raw) } else { Cow::Borrowed(raw) };
 		let raw = raw.trim();
-		let paren_tokens = get_tokens(raw)?;
-		let exprs = paren_to_exprs(paren_tokens, ctx)?;
-		let exprs = if ctx.cfg.implicit_multiplication {
-			insert_operators(exprs)
-		} else {
-			exprs
-		};
-		let postfix = tokenexprs_to_postfix(exprs);
-		let term = postfix_to_term(postfix, ctx)?;
+		let paren_tokens = get_tokens(raw, &ctx.cfg)?;
+		let mut warnings = Vec::new();
+		let (term, _) = tokens_to_term(paren_tokens, ctx, &mut warnings)?;
 
-		Ok(term)
+		Ok((term, warnings))
 	}
 
 	/// Evaluate the term with the default context
 	pub fn eval(&self) -> Calculation<N> {
-		let ctx = Context::new();
-		self.eval_ctx(&ctx)
+		Context::with_default(|ctx| self.eval_ctx(ctx))
 	}
 
 	/// Evaluate the term with the given context
 	pub fn eval_ctx(&self, ctx: &Context<N>) -> Calculation<N> {
+		EvalState::with(ctx, |state| self.eval_state(ctx, state))
+	}
+
+	/// Evaluate the term as part of an evaluation, passing it to the observer of the context if it
+	/// has one
+	pub(crate) fn eval_state(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		match ctx.observer {
+			Some(ref observer) => {
+				let depth = state.depth();
+				observer.enter(self, depth)?;
+				let res = self.eval_limited(ctx, state);
+				observer.exit(self, depth, &res);
+				res
+			}
+			None => self.eval_limited(ctx, state),
+		}
+	}
+
+	/// Evaluate the term with the given context asynchronously, yielding to the executor after every
+	/// `yield_every` operations so that long evaluations (like ones with arbitrary precision numbers)
+	/// don't block other tasks. See `EvalFuture`.
+	///
+	/// ```rust
+	/// # use mexprp::{Term, Context, Answer};
+	/// # use std::future::Future;
+	/// # use std::pin::pin;
+	/// # use std::task::{Context as TaskContext, Poll, Waker};
+	/// let ctx: Context<f64> = Context::new();
+	/// let term: Term<f64> = Term::parse_ctx("1 + 2 * 3 - 4 / 2", &ctx).unwrap();
+	/// let mut future = pin!(term.eval_async(&ctx, 2));
+	/// let mut cx = TaskContext::from_waker(Waker::noop());
+	/// let answer = loop {
+	///     if let Poll::Ready(answer) = future.as_mut().poll(&mut cx) {
+	///         break answer;
+	///     }
+	/// };
+	/// assert_eq!(answer.unwrap(), Answer::Single(5.0));
+	/// ```
+	pub fn eval_async<'a>(&'a self, ctx: &'a Context<N>, yield_every: usize) -> EvalFuture<'a, N> {
+		EvalFuture::new(self, ctx, yield_every)
+	}
+
+	/// Evaluate the term with the given context, but with another precision for arbitrary precision
+	/// floating point numbers, without changing the context or parsing the term again
+	///
+	/// ```rust
+	/// # use mexprp::{Term, Context, Answer};
+	/// let ctx: Context<f64> = Context::new();
+	/// let term: Term<f64> = Term::parse_ctx("2 pi", &ctx).unwrap();
+	/// assert_eq!(term.eval_ctx_with_precision(&ctx, 256).unwrap(), term.eval_ctx(&ctx).unwrap());
+	/// ```
+	pub fn eval_ctx_with_precision(&self, ctx: &Context<N>, precision: u32) -> Calculation<N> {
+		let mut ctx = ctx.clone();
+		ctx.cfg.precision = precision;
+		self.eval_ctx(&ctx)
+	}
+
+	/// Evaluate the term, stopping if it goes over any of the limits in the config
+	fn eval_limited(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		// Keep track of how deep the evaluation is to avoid overflowing the stack
+		let depth = state.depth();
+		if depth >= RECURSION_DEPTH && ctx.observer.is_none() && !ctx.cfg.memoize {
+			match *self {
+				Term::Operation(ref oper) if oper.is_pure() => return self.eval_iterative(ctx, state),
+				_ => {}
+			}
+		}
+		if depth >= ctx.cfg.max_depth {
+			return Err(MathError::RecursionLimit);
+		}
+		state.step(1, ctx)?;
+
+		state.set_depth(depth + 1);
+		let res = if ctx.cfg.memoize {
+			state.eval_cached(self, ctx, || self.eval_uncached(ctx, state))
+		} else {
+			self.eval_uncached(ctx, state)
+		};
+		state.set_depth(depth);
+		Self::check_answer(res, ctx, state)
+	}
+
+	/// The amount of values of an answer with multiple values, or 0 for other answers
+	fn values(answer: &Answer<N>) -> usize {
+		match *answer {
+			Answer::Multiple(ref answers) => answers.len(),
+			Answer::Labeled(ref answers) => answers.len(),
+			_ => 0,
+		}
+	}
+
+	/// Apply the config to the answer of a term that was just evaluated
+	pub(crate) fn check_answer(res: Calculation<N>, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		let res = if ctx.cfg.single_valued {
+			res.map(Answer::collapse)
+		} else if ctx.cfg.dedup_answers {
+			res.map(|answer| answer.dedup(ctx))
+		} else {
+			res
+		};
+
+		// Answers with lots of values are expensive to operate on further
+		let count = match res {
+			Ok(ref answer) => Self::values(answer),
+			Err(_) => 0,
+		};
+		if count > ctx.cfg.max_answers {
+			return Err(MathError::TooManyAnswers);
+		} else if count > 0 {
+			state.step(count, ctx)?;
+		}
+		res
+	}
+
+	/// Evaluate the term without recursing for its operations. See `Evaluation`.
+	fn eval_iterative(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
+		// Running without a limit on the tasks only stops once the evaluation finished
+		Evaluation::new(self, state).run(ctx, state, usize::MAX).unwrap()
+	}
+
+	/// Evaluate the term with the given context like `eval_ctx`, but faster when it's mostly
+	/// arithmetic (`+`, `-`, `*`, `/`, `^` and negation) on single numbers, which is done directly
+	/// instead of going through the checks every term goes through normally. Everything else is
+	/// evaluated normally, and the answer or error is always the same as from `eval_ctx`.
+	///
+	/// Contexts with an observer or a cancel token, a `max_steps` limit or `memoize` enabled need
+	/// every term to go through the normal evaluation, so the term is just evaluated with `eval_ctx`.
+	///
+	/// ```rust
+	/// # use mexprp::{Term, Context, Answer};
+	/// let mut ctx: Context<f64> = Context::new();
+	/// ctx.set_var("x", 3.0);
+	/// let term: Term<f64> = Term::parse_ctx("2 * x^2 - x / 4", &ctx).unwrap();
+	/// assert_eq!(term.eval_fast(&ctx).unwrap(), Answer::Single(17.25));
+	/// ```
+	pub fn eval_fast(&self, ctx: &Context<N>) -> Calculation<N> {
+		if ctx.observer.is_some() || ctx.cancel.is_some() || ctx.cfg.max_steps.is_some() || ctx.cfg.memoize {
+			return self.eval_ctx(ctx);
+		}
+		EvalState::with(ctx, |state| {
+			match self.eval_fast_depth(ctx, state, state.depth()) {
+				Ok(answer) => Ok(answer),
+				// Some errors are only reported properly by the normal evaluation, like calling a
+				// variable as a function, so it's used to get the exact same error
+				Err(_) => self.eval_state(ctx, state),
+			}
+		})
+	}
+
+	/// Evaluate the term on the fast path, `depth` terms deep
+	fn eval_fast_depth(&self, ctx: &Context<N>, state: &EvalState<N>, depth: usize) -> Calculation<N> {
+		if depth >= ctx.cfg.max_depth {
+			return Err(MathError::RecursionLimit);
+		}
+		let oper = match *self {
+			Term::Num(Answer::Single(ref n)) => return Ok(Answer::Single(n.clone())),
+			Term::Var(ref name) => {
+				if let Some(value) = state.param(name) {
+					return Ok(value);
+				}
+				return match ctx.vars.get(&**name) {
+					Some(var) => state.eval_var(name, var, ctx.cfg.cache_vars, || var.eval_fast_depth(ctx, state, depth + 1)),
+					None => self.eval_state(ctx, state),
+				}
+			}
+			Term::Operation(ref oper) if oper.is_pure() => oper,
+			Term::Num(_) | Term::Operation(_) | Term::Function(..) => return self.eval_state(ctx, state),
+		};
+		let (arithmetic, a, b) = match oper.arithmetic() {
+			Some(arithmetic) => arithmetic,
+			None => return self.eval_ctx(ctx),
+		};
+
+		let a = a.eval_fast_depth(ctx, state, depth + 1)?;
+		let b = match b {
+			Some(b) => Some(b.eval_fast_depth(ctx, state, depth + 1)?),
+			None => None,
+		};
+		let res = match (&a, &b) {
+			(Answer::Single(x), Some(Answer::Single(y))) => Some(arithmetic.apply(x, y, ctx)?),
+			(Answer::Single(x), None) => Some(arithmetic.apply(x, x, ctx)?),
+			_ => None,
+		};
+		match res {
+			Some(Answer::Single(n)) => Ok(Answer::Single(n)),
+			// Anything else needs the checks of the normal evaluation, which can start from the answers
+			// of the operands
+			_ => {
+				let operands = Some(a).into_iter().chain(b).map(Term::Num).collect();
+				Term::Operation(oper.with_operands(operands)).eval_ctx(ctx)
+			}
+		}
+	}
+
+	/// Evaluate the term with the given context, and if it fails, find the smallest part of the term
+	/// that fails with the same error. Useful for finding where the problem is in a long expression.
+	///
+	/// ```rust
+	/// # use mexprp::{Term, Context};
+	/// let ctx: Context<f64> = Context::new();
+	/// let term: Term<f64> = Term::parse_ctx("3 + 4 / (2 - 2)", &ctx).unwrap();
+	/// let err = term.eval_traced(&ctx).unwrap_err();
+	/// assert_eq!(err.term, "(4 ÷ (2 - 2))");
+	/// assert_eq!(err.path, vec![1]);
+	/// ```
+	pub fn eval_traced(&self, ctx: &Context<N>) -> Result<Answer<N>, TracedError> {
+		self.eval_ctx(ctx).map_err(|error| {
+			let mut path = Vec::new();
+			let term = self.find_error(&error, ctx, &mut path).to_string();
+			TracedError { error, term, path }
+		})
+	}
+
+	/// Find the smallest subterm that fails with the same error as this term, adding the indices of
+	/// the operands that lead to it to the path
+	fn find_error(&self, error: &MathError, ctx: &Context<N>, path: &mut Vec<usize>) -> &Term<N> {
+		let operands: Vec<&Term<N>> = match *self {
+			Term::Operation(ref oper) => oper.operands(),
+			Term::Function(_, ref args) => args.iter().collect(),
+			Term::Num(_) | Term::Var(_) => Vec::new(),
+		};
+		// Errors are compared by their messages since they can't be compared directly
+		let message = error.to_string();
+		for (i, operand) in operands.into_iter().enumerate() {
+			if let Err(e) = operand.eval_ctx(ctx) {
+				if e.to_string() == message {
+					path.push(i);
+					return operand.find_error(error, ctx, path);
+				}
+			}
+		}
+		self
+	}
+
+	/// Evaluate the term with the given context, recording each operation, function call and variable
+	/// that's evaluated along the way. The steps are in the order they were evaluated, so each one
+	/// only uses answers from the steps before it, and the last one is the whole term.
+	///
+	/// ```rust
+	/// # use mexprp::{Term, Context};
+	/// let mut ctx: Context<f64> = Context::new();
+	/// ctx.set_var("x", 4.0);
+	/// let term: Term<f64> = Term::parse_ctx("2 * (3 + x)", &ctx).unwrap();
+	/// let (answer, steps) = term.eval_steps(&ctx).unwrap();
+	/// let steps: Vec<String> = steps.iter().map(|step| step.to_string()).collect();
+	/// assert_eq!(steps, vec!["x = 4", "(3 + 4) = 7", "(2 × 7) = 14"]);
+	/// ```
+	pub fn eval_steps(&self, ctx: &Context<N>) -> Result<(Answer<N>, Vec<Step<N>>), MathError> {
+		let mut steps = Vec::new();
+		let answer = self.eval_step(ctx, &mut steps)?;
+		Ok((answer, steps))
+	}
+
+	/// Evaluate the operands of the term as steps, and then the term itself with its operands replaced
+	/// by their answers
+	fn eval_step(&self, ctx: &Context<N>, steps: &mut Vec<Step<N>>) -> Calculation<N> {
+		let mut operands = |terms: Vec<&Term<N>>| -> Result<Vec<Term<N>>, MathError> {
+			terms.into_iter().map(|term| term.eval_step(ctx, steps).map(Term::Num)).collect()
+		};
+		let evaluated = match *self {
+			Term::Num(ref answer) => return Ok(answer.clone()),
+			// Impure operations depend on more than their operands, so they're evaluated in one step
+			Term::Operation(ref oper) if oper.is_pure() => Term::Operation(oper.with_operands(operands(oper.operands())?)),
+			Term::Function(ref name, ref args) => Term::Function(name.clone(), operands(args.iter().collect())?),
+			Term::Operation(_) | Term::Var(_) => self.clone(),
+		};
+		let answer = evaluated.eval_ctx(ctx)?;
+		steps.push(Step {
+			term: self.to_string(),
+			evaluated: evaluated.to_string(),
+			answer: answer.clone(),
+		});
+		Ok(answer)
+	}
+
+	/// Evaluate the term with the given context without checking the cache
+	fn eval_uncached(&self, ctx: &Context<N>, state: &EvalState<N>) -> Calculation<N> {
 		// Evaluate each possible term type
 		match *self {
 			Term::Num(ref num) => Ok(num.clone()),       // Already evaluated
-			Term::Operation(ref oper) => oper.eval(ctx), // Perform the operation with the given context
+			Term::Operation(ref oper) => oper.eval(ctx, state), // Perform the operation with the given context
 			Term::Function(ref name, ref args) => {
 				// Execute the function if it exists
-				if let Some(func) = ctx.funcs.get(name) {
+				if let Some(func) = ctx.lookup_func(name) {
 					func.eval(args, ctx)
 				} else {
-					Err(MathError::UndefinedFunction { name: name.clone() })
+					Err(MathError::UndefinedFunction { name: name.to_string() })
 				}
 			}
 			Term::Var(ref name) => {
 				// Retrieve the value of the variable, if it exists
-				if let Some(var) = ctx.vars.get(name) {
-					var.eval_ctx(ctx)
+				if let Some(value) = state.param(name) {
+					Ok(value)
+				} else if let Some(var) = ctx.vars.get(&**name) {
+					state.eval_var(name, var, ctx.cfg.cache_vars, || var.eval_state(ctx, state))
 				} else {
-					Err(MathError::UndefinedVariable { name: name.clone() })
+					Err(MathError::UndefinedVariable {
+						name: name.to_string(),
+						suggestions: ctx.similar_names(name),
+					})
 				}
 			}
 		}
 	}
 
-	/// Express this term as a string
+	/// Copy this term along with all of its operations. Cloning a term normally shares its operations
+	/// with the original, since they're reference counted.
+	pub fn deep_clone(&self) -> Term<N> {
+		match *self {
+			Term::Operation(ref oper) => {
+				let operands = oper.operands().into_iter().map(Term::deep_clone).collect();
+				Term::Operation(oper.with_operands(operands))
+			}
+			Term::Function(ref name, ref args) => Term::Function(name.clone(), args.iter().map(Term::deep_clone).collect()),
+			Term::Num(_) | Term::Var(_) => self.clone(),
+		}
+	}
+
+	/// The radix the answer of this term should be shown in, if it's a call to a function like `hex`
+	/// that shows its answer in another radix
+	pub fn display_radix(&self, ctx: &Context<N>) -> Option<u32> {
+		match *self {
+			Term::Function(ref name, ref args) => ctx.lookup_func(name)?.display_radix(args, ctx),
+			_ => None,
+		}
+	}
+
+	/// Find the variables and functions this term uses that aren't defined in the given context, so
+	/// that they can be checked before evaluating it (like when the term was parsed with another
+	/// context). The definitions of the variables and of the functions defined by expressions in the
+	/// context are checked too, except for the parameters of the functions. Names followed by
+	/// parentheses are only functions if they were functions in the context the term was parsed with,
+	/// and are variables otherwise.
+	///
+	/// ```rust
+	/// # use mexprp::{Term, Context};
+	/// let mut ctx: Context<f64> = Context::new();
+	/// ctx.set_var("y", Term::parse("x * 3").unwrap());
+	/// let term: Term<f64> = Term::parse_ctx("sin(y) + z", &ctx).unwrap();
+	/// let missing = term.missing_definitions(&ctx);
+	/// assert_eq!(missing.vars, vec!["x", "z"]);
+	/// assert!(missing.funcs.is_empty());
+	/// assert_eq!(term.missing_definitions(&Context::empty()).funcs, vec!["sin"]);
+	/// ```
+	pub fn missing_definitions(&self, ctx: &Context<N>) -> Missing {
+		let mut missing = Missing::default();
+		self.find_missing(ctx, &[], &mut Vec::new(), &mut missing);
+		missing.vars.sort();
+		missing.vars.dedup();
+		missing.funcs.sort();
+		missing.funcs.dedup();
+		missing
+	}
+
+	/// Add the names this term uses that aren't defined in the context or in `params` to `missing`.
+	/// The names and parameters of the definitions that were already checked are kept in `checked`,
+	/// so that each one is only checked once and definitions in terms of themselves don't recurse.
+	fn find_missing(&self, ctx: &Context<N>, params: &[String], checked: &mut Vec<(String, Vec<String>)>, missing: &mut Missing) {
+		// Definitions are evaluated with the parameters of the functions they're called from, so they
+		// have to be checked again for different ones
+		let mut check = |name: &str, params: &[String]| {
+			let key = (name.to_string(), params.to_vec());
+			if checked.contains(&key) {
+				false
+			} else {
+				checked.push(key);
+				true
+			}
+		};
+
+		match *self {
+			Term::Num(_) => {}
+			Term::Operation(ref oper) => {
+				for operand in oper.operands() {
+					operand.find_missing(ctx, params, checked, missing);
+				}
+			}
+			Term::Function(ref name, ref args) => {
+				match ctx.lookup_func(name) {
+					Some(func) => {
+						if let Some((inner, body)) = func.body() {
+							let mut params = params.to_vec();
+							params.extend(inner.iter().cloned());
+							params.sort();
+							params.dedup();
+							if check(name, &params) {
+								body.find_missing(ctx, &params, checked, missing);
+							}
+						}
+					}
+					None => missing.funcs.push(name.to_string()),
+				}
+				for arg in args {
+					arg.find_missing(ctx, params, checked, missing);
+				}
+			}
+			Term::Var(ref name) => {
+				if params.iter().any(|param| **param == **name) {
+					return;
+				}
+				match ctx.vars.get(&**name) {
+					Some(var) => {
+						if check(name, params) {
+							var.find_missing(ctx, params, checked, missing);
+						}
+					}
+					None => missing.vars.push(name.to_string()),
+				}
+			}
+		}
+	}
+
+	/// Pre-evaluate all the parts of this term that only depend on numbers and on the variables and
+	/// functions defined in the context, so that `2 * pi * r` becomes `6.283185307179586 * r`. Parts
+	/// that fail to evaluate are left as they are, so the error happens when the term is. The folded
+	/// term won't see later changes to the variables it used, and functions are assumed to always
+	/// return the same answer for the same arguments.
+	pub fn fold_constants(&self, ctx: &Context<N>) -> Term<N> {
+		self.fold(ctx, 0).0
+	}
+
+	/// Fold the constant parts of this term, returning the new term and whether or not all of it was
+	/// folded into an answer
+	fn fold(&self, ctx: &Context<N>, depth: usize) -> (Term<N>, bool) {
+		// Variables defined in terms of themselves can't be folded
+		if depth >= ctx.cfg.max_depth {
+			return (self.clone(), false);
+		}
+
+		let (term, constant) = match *self {
+			Term::Num(_) => return (self.clone(), true),
+			Term::Operation(ref oper) => {
+				let mut constant = oper.is_pure();
+				let mut operands = Vec::new();
+				for operand in oper.operands() {
+					let (operand, c) = operand.fold(ctx, depth + 1);
+					constant = constant && c;
+					operands.push(operand);
+				}
+				(Term::Operation(oper.with_operands(operands)), constant)
+			}
+			Term::Function(ref name, ref args) => {
+				let mut constant = ctx.has_func(name);
+				let mut new = Vec::new();
+				for arg in args {
+					let (arg, c) = arg.fold(ctx, depth + 1);
+					constant = constant && c;
+					new.push(arg);
+				}
+				(Term::Function(name.clone(), new), constant)
+			}
+			Term::Var(ref name) => match ctx.vars.get(&**name) {
+				Some(var) => match var.fold(ctx, depth + 1) {
+					(value, true) => return (value, true),
+					_ => return (self.clone(), false),
+				},
+				None => return (self.clone(), false),
+			},
+		};
+
+		if !constant {
+			return (term, false);
+		}
+		match term.eval_ctx(ctx) {
+			Ok(answer) => (Term::Num(answer), true),
+			Err(_) => (term, false),
+		}
+	}
+
+	/// Evaluate all the parts of this term that can be evaluated with the context, leaving the
+	/// variables and functions it doesn't define in the resulting term instead of failing. Variables
+	/// the context defines are replaced with their own partially evaluated definitions. This allows
+	/// known parameters to be baked into a term ahead of time, so that it can be evaluated later with
+	/// only the rest. Unlike `fold_constants`, errors in parts that could be evaluated are returned.
+	pub fn partial_eval_ctx(&self, ctx: &Context<N>) -> Result<Term<N>, MathError> {
+		self.partial_eval_depth(ctx, 0).map(|(term, _)| term)
+	}
+
+	/// Partially evaluate this term, returning the new term and whether or not all of it was
+	/// evaluated into an answer
+	fn partial_eval_depth(&self, ctx: &Context<N>, depth: usize) -> Result<(Term<N>, bool), MathError> {
+		if depth >= ctx.cfg.max_depth {
+			return Err(MathError::RecursionLimit);
+		}
+
+		let (term, complete) = match *self {
+			Term::Num(_) => return Ok((self.clone(), true)),
+			Term::Operation(ref oper) => {
+				let mut complete = oper.is_pure();
+				let mut operands = Vec::new();
+				for operand in oper.operands() {
+					let (operand, c) = operand.partial_eval_depth(ctx, depth + 1)?;
+					complete = complete && c;
+					operands.push(operand);
+				}
+				(Term::Operation(oper.with_operands(operands)), complete)
+			}
+			Term::Function(ref name, ref args) => {
+				let mut complete = ctx.has_func(name);
+				let mut new = Vec::new();
+				for arg in args {
+					let (arg, c) = arg.partial_eval_depth(ctx, depth + 1)?;
+					complete = complete && c;
+					new.push(arg);
+				}
+				(Term::Function(name.clone(), new), complete)
+			}
+			Term::Var(ref name) => match ctx.vars.get(&**name) {
+				Some(var) => return var.partial_eval_depth(ctx, depth + 1),
+				None => return Ok((self.clone(), false)),
+			},
+		};
+
+		if complete {
+			Ok((Term::Num(term.eval_ctx(ctx)?), true))
+		} else {
+			Ok((term, false))
+		}
+	}
+
+	/// Bind this term to a context, resolving all of its names ahead of time. See
+	/// [`BoundTerm`](crate::BoundTerm) for more info.
+	pub fn bind(&self, ctx: &Context<N>) -> Result<BoundTerm<N>, MathError> {
+		BoundTerm::new(self, ctx)
+	}
+
+	/// Rewrite this term into a canonical form, so that terms that are written differently but are
+	/// equivalent by commutativity and associativity compare equal, eg `(b + a) * 2` and `2 * (a + b)`.
+	/// Subtractions become additions of negations, chains of the commutative operations (`+`, `*`,
+	/// `&` and `|`) are flattened, and their operands are sorted. Multiplication is treated as
+	/// commutative even though it isn't for matrices.
+	pub fn canonicalize(&self) -> Term<N> {
+		match *self {
+			Term::Operation(ref oper) => {
+				let mut operands: Vec<Term<N>> = oper.operands().into_iter().map(Term::canonicalize).collect();
+				if oper.name() == "-" {
+					// a - b is a + (-b)
+					let b = operands.pop().unwrap();
+					let a = operands.pop().unwrap();
+					let add: Arc<dyn Operate<N>> = Arc::new(Add { a, b: Term::Operation(Arc::new(Neg { a: b })) });
+					return canonical_chain(&add);
+				}
+
+				let oper = oper.with_operands(operands);
+				if COMMUTATIVE.contains(&oper.name()) {
+					canonical_chain(&oper)
+				} else {
+					Term::Operation(oper)
+				}
+			}
+			Term::Function(ref name, ref args) => Term::Function(name.clone(), args.iter().map(Term::canonicalize).collect()),
+			Term::Num(_) | Term::Var(_) => self.clone(),
+		}
+	}
+
+	/// Check whether this term and another are probably equivalent by evaluating both of them with
+	/// `samples` different random values between -10 and 10 for each of the variables in `vars`, and
+	/// checking that their answers are equal within the `tolerance` of the config. Points where both
+	/// terms fail to evaluate are skipped, but if only one of them fails they aren't equivalent. The
+	/// random values are the same every time, so the result is reproducible.
+	pub fn probably_equivalent(&self, other: &Term<N>, vars: &[&str], ctx: &Context<N>, samples: usize) -> bool {
+		let mut ctx = ctx.clone();
+		// xorshift64
+		let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+		let mut random = || {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			(state >> 11) as f64 / (1u64 << 53) as f64 * 20.0 - 10.0
+		};
+
+		let mut compared = 0;
+		for _ in 0..samples {
+			for var in vars {
+				match N::from_f64(random(), &ctx) {
+					Ok(value) => ctx.set_var(var, value),
+					Err(_) => return false,
+				}
+			}
+
+			match (self.eval_ctx(&ctx), other.eval_ctx(&ctx)) {
+				(Ok(a), Ok(b)) => {
+					if !approx_eq(a, b, ctx.cfg.tolerance) {
+						return false;
+					}
+					compared += 1;
+				}
+				(Err(_), Err(_)) => {}
+				_ => return false,
+			}
+		}
+
+		compared > 0
+	}
+
+	/// Express this term as a string. Operations are fully parenthesized, so a term parsed from a
+	/// string is written in a way that parses back into an equal term with the same context, as long
+	/// as its config uses the default decimal and argument separators. That isn't the case for terms
+	/// with numbers that can't be written as literals, like negative numbers or numbers with more
+	/// than one value from folding constants, or for bound terms.
+	///
+	/// ```rust
+	/// # use mexprp::Term;
+	/// let term: Term<f64> = Term::parse("2x^2 + 10% - [y,][0]").unwrap();
+	/// assert_eq!(term.to_string(), "(((2 × (x ^ 2)) + 10%) - [y,][0])");
+	/// assert_eq!(Term::parse(&term.to_string()).unwrap(), term);
+	/// ```
 	pub fn to_string(&self) -> String {
 		match *self {
 			Term::Num(ref num) => format!("{}", num),
@@ -114,6 +795,365 @@ impl<N: Num + 'static> Term<N> {
 			Term::Var(ref name) => format!("{}", name),
 		}
 	}
+
+	/// Describe the tree of this term as JSON, for tools that want to inspect how an expression was
+	/// parsed. Numbers are `{"type": "num", "value": "2"}` with the value as a string so that it's
+	/// exact for any type of number, variables are `{"type": "var", "name": "x"}`, function calls are
+	/// `{"type": "func", "name": "sin", "args": [...]}` and operations are
+	/// `{"type": "op", "op": "+", "args": [...]}`, where `op` is the name of the operation (eg `"(-)"`
+	/// for negation or `"[,]"` for a vector).
+	///
+	/// ```rust
+	/// # use mexprp::Term;
+	/// let term: Term<f64> = Term::parse("2 * sin(x)").unwrap();
+	/// assert_eq!(
+	///     term.to_json(),
+	///     r#"{"type":"op","op":"*","args":[{"type":"num","value":"2"},{"type":"func","name":"sin","args":[{"type":"var","name":"x"}]}]}"#
+	/// );
+	/// ```
+	pub fn to_json(&self) -> String {
+		let args = |args: Vec<&Term<N>>| args.iter().map(|arg| arg.to_json()).collect::<Vec<String>>().join(",");
+		match *self {
+			Term::Num(ref num) => format!(r#"{{"type":"num","value":{}}}"#, json_string(&num.to_string())),
+			Term::Var(ref name) => format!(r#"{{"type":"var","name":{}}}"#, json_string(name)),
+			Term::Function(ref name, ref list) => format!(r#"{{"type":"func","name":{},"args":[{}]}}"#, json_string(name), args(list.iter().collect())),
+			Term::Operation(ref oper) => format!(r#"{{"type":"op","op":{},"args":[{}]}}"#, json_string(oper.name()), args(oper.operands())),
+		}
+	}
+
+	/// Describe the tree of this term as an S-expression, like `(* 2 (sin x))`. Operations are
+	/// written with their names and function calls with the names of the functions. Atoms with
+	/// spaces, parentheses or quotes in them (eg `"(-)"` for negation) are quoted.
+	///
+	/// ```rust
+	/// # use mexprp::Term;
+	/// let term: Term<f64> = Term::parse("-x + max(1, 2)").unwrap();
+	/// assert_eq!(term.to_sexpr(), r#"(+ ("(-)" x) (max 1 2))"#);
+	/// ```
+	pub fn to_sexpr(&self) -> String {
+		let list = |head: &str, args: Vec<&Term<N>>| {
+			let mut buf = format!("({}", sexpr_atom(head));
+			for arg in args {
+				buf.push(' ');
+				buf.push_str(&arg.to_sexpr());
+			}
+			buf.push(')');
+			buf
+		};
+		match *self {
+			Term::Num(ref num) => sexpr_atom(&num.to_string()),
+			Term::Var(ref name) => sexpr_atom(name),
+			Term::Function(ref name, ref args) => list(name, args.iter().collect()),
+			Term::Operation(ref oper) => list(oper.name(), oper.operands()),
+		}
+	}
+}
+
+/// Quote a string for JSON
+fn json_string(raw: &str) -> String {
+	let mut buf = String::from("\"");
+	for c in raw.chars() {
+		match c {
+			'"' => buf.push_str("\\\""),
+			'\\' => buf.push_str("\\\\"),
+			'\n' => buf.push_str("\\n"),
+			c if (c as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}", c as u32)),
+			c => buf.push(c),
+		}
+	}
+	buf.push('"');
+	buf
+}
+
+/// Quote an atom of an S-expression if it would be ambiguous otherwise
+fn sexpr_atom(raw: &str) -> String {
+	if raw.is_empty() || raw.chars().any(|c| c.is_whitespace() || c == '(' || c == ')' || c == '"') {
+		json_string(raw)
+	} else {
+		raw.to_string()
+	}
+}
+
+/// Constructors for building terms in Rust instead of parsing them. Terms can also be combined with
+/// the `+`, `-`, `*` and `/` operators and negated with `-`.
+///
+/// ```rust
+/// # use mexprp::{Term, Context, Answer};
+/// let term: Term<f64> = Term::func("abs", vec![Term::var("x")]) * Term::num(2.0) + Term::pow(Term::num(3.0), Term::num(2.0));
+/// let mut ctx = Context::new();
+/// ctx.set_var("x", -4.0);
+/// assert_eq!(term.eval_ctx(&ctx).unwrap(), Answer::Single(17.0));
+/// ```
+#[allow(clippy::should_implement_trait)]
+impl<N: Num + 'static> Term<N> {
+	/// A number
+	pub fn num(n: N) -> Term<N> {
+		Term::Num(Answer::Single(n))
+	}
+
+	/// A variable
+	pub fn var(name: &str) -> Term<N> {
+		Term::Var(name.into())
+	}
+
+	/// A call to a function with the given arguments
+	pub fn func(name: &str, args: Vec<Term<N>>) -> Term<N> {
+		Term::Function(name.into(), args)
+	}
+
+	/// The sum of two terms
+	pub fn add(a: Term<N>, b: Term<N>) -> Term<N> {
+		Term::Operation(Arc::new(Add { a, b }))
+	}
+
+	/// The difference of two terms
+	pub fn sub(a: Term<N>, b: Term<N>) -> Term<N> {
+		Term::Operation(Arc::new(Sub { a, b }))
+	}
+
+	/// The product of two terms
+	pub fn mul(a: Term<N>, b: Term<N>) -> Term<N> {
+		Term::Operation(Arc::new(Mul { a, b, call: None }))
+	}
+
+	/// The quotient of two terms
+	pub fn div(a: Term<N>, b: Term<N>) -> Term<N> {
+		Term::Operation(Arc::new(Div { a, b }))
+	}
+
+	/// A term raised to the power of another
+	pub fn pow(a: Term<N>, b: Term<N>) -> Term<N> {
+		Term::Operation(Arc::new(Pow { a, b }))
+	}
+
+	/// The negation of a term
+	pub fn neg(a: Term<N>) -> Term<N> {
+		Term::Operation(Arc::new(Neg { a }))
+	}
+}
+
+/// Implement an operator for terms and references to terms with the constructor of the same name
+macro_rules! term_op {
+	($op:ident, $method:ident) => {
+		impl<N: Num + 'static> ops::$op for Term<N> {
+			type Output = Term<N>;
+
+			fn $method(self, other: Term<N>) -> Term<N> {
+				Term::$method(self, other)
+			}
+		}
+
+		impl<'a, N: Num + 'static> ops::$op for &'a Term<N> {
+			type Output = Term<N>;
+
+			fn $method(self, other: &'a Term<N>) -> Term<N> {
+				Term::$method(self.clone(), other.clone())
+			}
+		}
+	};
+}
+
+term_op!(Add, add);
+term_op!(Sub, sub);
+term_op!(Mul, mul);
+term_op!(Div, div);
+
+impl<N: Num + 'static> ops::Neg for Term<N> {
+	type Output = Term<N>;
+
+	fn neg(self) -> Term<N> {
+		Term::neg(self)
+	}
+}
+
+impl<N: Num + 'static> ops::Neg for &Term<N> {
+	type Output = Term<N>;
+
+	fn neg(self) -> Term<N> {
+		Term::neg(self.clone())
+	}
+}
+
+/// A term to evaluate, or an operation whose operands have been evaluated
+enum Task<'a, N: Num> {
+	Eval(&'a Term<N>, usize),
+	Apply {
+		oper: &'a Arc<dyn Operate<N>>,
+		depth: usize,
+		/// Where the answers of the operands start on the stack of answers
+		start: usize,
+	},
+}
+
+/// An evaluation of a term that doesn't recurse for its operations, using a stack of the operations
+/// that are waiting for their operands instead. Once all the operands of an operation have been
+/// evaluated, the operation is evaluated with its operands replaced by their answers. Other terms,
+/// like functions and variables, are evaluated normally. It can be run a few tasks at a time, which
+/// is how `Term::eval_async` yields in the middle of an evaluation.
+///
+/// Only used without an observer or memoizing, since the operations are evaluated with different
+/// operands.
+pub(crate) struct Evaluation<'a, N: Num> {
+	tasks: Vec<Task<'a, N>>,
+	answers: Vec<Answer<N>>,
+}
+
+impl<'a, N: Num + 'static> Evaluation<'a, N> {
+	/// Start evaluating a term
+	pub(crate) fn new(term: &'a Term<N>, state: &EvalState<N>) -> Self {
+		Evaluation {
+			tasks: vec![Task::Eval(term, state.depth())],
+			answers: Vec::new(),
+		}
+	}
+
+	/// Run at most `limit` tasks of the evaluation, returning the answer if it finished
+	pub(crate) fn run(&mut self, ctx: &Context<N>, state: &EvalState<N>, limit: usize) -> Option<Calculation<N>> {
+		let base = state.depth();
+		for _ in 0..limit {
+			let task = match self.tasks.pop() {
+				Some(task) => task,
+				None => break,
+			};
+			let res = match task {
+				Task::Eval(term, depth) => match *term {
+					Term::Operation(ref oper) if oper.is_pure() => {
+						if depth >= ctx.cfg.max_depth {
+							Err(MathError::RecursionLimit)
+						} else {
+							state.step(1, ctx).map(|_| {
+								self.tasks.push(Task::Apply { oper, depth, start: self.answers.len() });
+								self.tasks.extend(oper.operands().into_iter().rev().map(|operand| Task::Eval(operand, depth + 1)));
+								None
+							})
+						}
+					}
+					_ => {
+						state.set_depth(depth);
+						term.eval_limited(ctx, state).map(Some)
+					}
+				},
+				Task::Apply { oper, depth, start } => {
+					let operands: Vec<Answer<N>> = self.answers.split_off(start);
+					// The operation evaluates the answers of its operands again, which shouldn't be counted
+					// as more steps
+					let counted = operands.iter().map(|answer| 1 + Term::values(answer)).sum();
+					state.set_steps(state.steps().saturating_sub(counted));
+					state.set_depth(depth + 1);
+					let res = oper.with_operands(operands.into_iter().map(Term::Num).collect()).eval(ctx, state);
+					state.set_depth(depth);
+					Term::check_answer(res, ctx, state).map(Some)
+				}
+			};
+			match res {
+				Ok(Some(answer)) => self.answers.push(answer),
+				Ok(None) => {}
+				Err(mut error) => {
+					// Let the operations waiting for the operand that failed change the error, like they would
+					// if they had evaluated it themselves
+					let mut index = self.answers.len();
+					for task in self.tasks.iter().rev() {
+						if let Task::Apply { oper, start, .. } = *task {
+							error = oper.operand_error(index - start, error, ctx);
+							index = start;
+						}
+					}
+					self.tasks.clear();
+					state.set_depth(base);
+					return Some(Err(error));
+				}
+			}
+		}
+		state.set_depth(base);
+		if self.tasks.is_empty() {
+			self.answers.pop().map(Ok)
+		} else {
+			None
+		}
+	}
+}
+
+/// Terms are equal if they have the same structure. Operations are compared by their kind and
+/// operands rather than by the identity of the `Arc` they're stored in. Note that this is only an
+/// equivalence relation if the numbers in the terms are (eg not NaN).
+impl<N: Num> PartialEq for Term<N> {
+	fn eq(&self, other: &Term<N>) -> bool {
+		match (self, other) {
+			(Term::Num(a), Term::Num(b)) => a == b,
+			(Term::Operation(a), Term::Operation(b)) => Arc::ptr_eq(a, b) || (a.name() == b.name() && a.operands() == b.operands()),
+			(Term::Function(a, a_args), Term::Function(b, b_args)) => a == b && a_args == b_args,
+			(Term::Var(a), Term::Var(b)) => a == b,
+			_ => false,
+		}
+	}
+}
+
+impl<N: Num> Eq for Term<N> {}
+
+/// Numbers are hashed by their (lossy) conversion to `f64`, so that equal numbers always have equal
+/// hashes.
+impl<N: Num> Hash for Term<N> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		fn hash_num<N: Num, H: Hasher>(n: &N, state: &mut H) {
+			if let Ok(f) = n.to_f64_lossy() {
+				// Make sure 0.0 and -0.0 hash the same, since they're equal
+				let f = if f == 0.0 { 0.0 } else { f };
+				f.to_bits().hash(state);
+			}
+		}
+
+		fn hash_answer<N: Num, H: Hasher>(answer: &Answer<N>, state: &mut H) {
+			match *answer {
+				Answer::Single(ref n) => {
+					0u8.hash(state);
+					hash_num(n, state);
+				}
+				Answer::Multiple(ref ns) => {
+					1u8.hash(state);
+					ns.len().hash(state);
+					for n in ns {
+						hash_num(n, state);
+					}
+				}
+				Answer::Vector(ref ns) => {
+					2u8.hash(state);
+					ns.len().hash(state);
+					for n in ns {
+						hash_answer(n, state);
+					}
+				}
+				Answer::Labeled(ref ls) => {
+					3u8.hash(state);
+					ls.len().hash(state);
+					for &(label, ref n) in ls {
+						label.hash(state);
+						hash_num(n, state);
+					}
+				}
+			}
+		}
+
+		match *self {
+			Term::Num(ref num) => {
+				0u8.hash(state);
+				hash_answer(num, state);
+			}
+			Term::Operation(ref oper) => {
+				1u8.hash(state);
+				oper.name().hash(state);
+				oper.operands().hash(state);
+			}
+			Term::Function(ref name, ref args) => {
+				2u8.hash(state);
+				name.hash(state);
+				args.hash(state);
+			}
+			Term::Var(ref name) => {
+				3u8.hash(state);
+				name.hash(state);
+			}
+		}
+	}
 }
 
 impl<N: Num + 'static> fmt::Display for Term<N> {
@@ -140,98 +1180,424 @@ impl<N: Num> From<Answer<N>> for Term<N> {
 	}
 }
 
-/// Convert ParenTokens to exprs. This function accomplishes two things at once. First, it decides
-/// if names are functions or variables depending on their context. Second, it splits the arguments
-/// of a function up by their commas, removing the need for a comma in the token representation.
-fn paren_to_exprs<N: Num + 'static>(raw: Vec<ParenToken>, ctx: &Context<N>) -> Result<Vec<Expr>, ParseError> {
-	let mut mtokens = Vec::new();
-	// Names that have yet to be decided
-	let mut pending_name = None;
+/// Parses with the default context, like `Term::parse`
+impl<N: Num + 'static> FromStr for Term<N> {
+	type Err = ParseError;
+
+	fn from_str(raw: &str) -> Result<Self, ParseError> {
+		Self::parse(raw)
+	}
+}
+
+impl<'a, N: Num + 'static> TryFrom<&'a str> for Term<N> {
+	type Error = ParseError;
+
+	fn try_from(raw: &'a str) -> Result<Self, ParseError> {
+		Self::parse(raw)
+	}
+}
+
+/// The names used in a term that's being parsed, so that every use of a name shares the same string
+struct Names<'a> {
+	names: Vec<(&'a str, Arc<str>)>,
+}
+
+impl<'a> Names<'a> {
+	fn new() -> Self {
+		Names { names: Vec::new() }
+	}
+
+	/// Get the shared string for a name
+	fn get(&mut self, name: &'a str) -> Arc<str> {
+		if let Some(entry) = self.names.iter().find(|entry| entry.0 == name) {
+			return Arc::clone(&entry.1);
+		}
+		let shared: Arc<str> = Arc::from(name);
+		self.names.push((name, Arc::clone(&shared)));
+		shared
+	}
+}
 
-	for rt in raw {
-		match rt {
+/// Convert ParenTokens to a term along with its depth. Each nested group of tokens is parsed by a
+/// frame of its own and finished into a term before the frame containing it continues.
+fn tokens_to_term<N: Num + 'static>(raw: Vec<ParenToken<'_>>, ctx: &Context<N>, warnings: &mut Vec<Warning>) -> Result<(Term<N>, usize), ParseError> {
+	let mut frame = Frame::new(raw);
+	let mut parents = Vec::new();
+	let mut names = Names::new();
+
+	loop {
+		// Parse the next token list of the current group in a new frame
+		if let Some(list) = frame.lists.next() {
+			parents.push(mem::replace(&mut frame, Frame::new(list)));
+			continue;
+		}
+		// Every token list of the group has been parsed, so it can be made into an operand
+		if let Some(group) = frame.group.take() {
+			let terms = mem::take(&mut frame.terms);
+			frame.exprs.push(finish_group(group, terms, ctx, &mut names)?);
+		}
+
+		match frame.tokens.next() {
+			Some(token) => frame.push(token, ctx, warnings, &mut names)?,
+			None => match parents.pop() {
+				Some(parent) => {
+					let term = mem::replace(&mut frame, parent).finish(ctx, warnings, &mut names)?;
+					frame.terms.push(term);
+				}
+				None => return frame.finish(ctx, warnings, &mut names),
+			},
+		}
+	}
+}
+
+impl<'a, N: Num + 'static> Frame<'a, N> {
+	fn new(tokens: Vec<ParenToken<'a>>) -> Self {
+		Frame {
+			tokens: tokens.into_iter(),
+			exprs: Vec::new(),
+			pending_name: None,
+			group: None,
+			lists: Vec::new().into_iter(),
+			terms: Vec::new(),
+		}
+	}
+
+	/// Start parsing a group made of these token lists
+	fn start(&mut self, group: Group<'a, N>, lists: Vec<Vec<ParenToken<'a>>>) {
+		self.group = Some(group);
+		self.lists = lists.into_iter();
+	}
+
+	/// Convert a token to exprs. This accomplishes two things at once. First, it decides if names
+	/// are functions or variables depending on their context. Second, it splits the arguments of a
+	/// function up by their commas, removing the need for a comma in the token representation.
+	fn push(&mut self, token: ParenToken<'a>, ctx: &Context<N>, warnings: &mut Vec<Warning>, names: &mut Names<'a>) -> Result<(), ParseError> {
+		// A function followed by an operand is called with the tokens up to the next operator that
+		// binds looser than its argument
+		if ctx.cfg.loose_function_call && is_operand_start(&token) {
+			if let Some(name) = self.pending_name.filter(|name| ctx.has_func(name)) {
+				self.pending_name = None;
+				let len = self.tokens.as_slice().iter().position(ends_loose_arg).unwrap_or_else(|| self.tokens.len());
+				let mut arg = vec![token];
+				arg.extend(self.tokens.by_ref().take(len));
+				check_args(name, 1, ctx)?;
+				self.start(Group::Func(name), vec![arg]);
+				return Ok(());
+			}
+		}
+
+		match token {
 			ParenToken::Num(num) => {
 				// Names followed by numbers aren't functions
-				if let Some(pending_name) = pending_name.take() {
-					mtokens.push(Expr::Var(pending_name));
-				}
-				mtokens.push(Expr::Num(num));
+				self.flush_name();
+				self.exprs.push(Expr::Num(num));
+			}
+			ParenToken::Frac(frac) => {
+				self.flush_name();
+				self.exprs.push(Expr::Frac(frac));
 			}
 			ParenToken::Op(op) => {
 				// Names followed by operators aren't functions
-				if let Some(pending_name) = pending_name.take() {
-					mtokens.push(Expr::Var(pending_name));
-				}
-				mtokens.push(Expr::Op(op));
+				self.flush_name();
+				self.exprs.push(Expr::Op(op));
 			}
 			ParenToken::Sub(sub) => {
 				// If there was a name before this subexpression
-				if let Some(name) = pending_name.take() {
+				if let Some(name) = self.pending_name.take() {
 					// If we allow implicit multiplication it might be a variable
-					if ctx.cfg.implicit_multiplication {
-						if ctx.funcs.contains_key(&name) {
-							// If there's a function with the name
-							mtokens.push(Expr::Func(name, tokens_to_args(sub, ctx)?)); // Push as a function, with the args parsed
-						} else {
-							mtokens.push(Expr::Var(name)); // It's a variable
-							mtokens.push(Expr::Sub(paren_to_exprs(sub, ctx)?)); // Push the subexpression
+					if ctx.cfg.implicit_multiplication && !ctx.has_func(name) {
+						if ctx.cfg.strict_names && !ctx.vars.contains_key(name) {
+							return Err(ParseError::UnknownFunction {
+								suggestions: ctx.similar_funcs(name),
+								name: name.to_string(),
+							});
 						}
+						warnings.push(Warning::VariableCall { name: name.to_string() });
+						self.exprs.push(Expr::Var(name)); // It's a variable
+						self.exprs.push(Expr::Op(Op::In(In::Call(name.to_string())))); // Multiplied by the subexpression
+						self.start(Group::Sub, vec![sub]); // Parse the subexpression
 					} else {
-						// If not then it's definitely a function
-						mtokens.push(Expr::Func(name, tokens_to_args(sub, ctx)?)); // Push as a function, with the args parsed
+						// Otherwise it's a function, so parse the args
+						let args = split_args(sub);
+						check_args(name, args.len(), ctx)?;
+						self.start(Group::Func(name), args);
+					}
+				} else if sub.iter().any(|t| matches!(*t, ParenToken::Comma)) {
+					// Parentheses around a list, like the pairs of `piecewise`, make a vector
+					self.start(Group::Vector, split_args(sub));
+				} else {
+					// Just parse the subexpression
+					self.start(Group::Sub, vec![sub]);
+				}
+			}
+			ParenToken::Bracket(sub) => {
+				// Names followed by brackets are indexed variables
+				self.flush_name();
+				let target = match self.exprs.pop() {
+					Some(Expr::Var(name)) => {
+						check_name(name, ctx)?;
+						Some((Term::Var(names.get(name)), 1))
+					}
+					Some(Expr::Term(term, depth)) => Some((term, depth)),
+					other => {
+						self.exprs.extend(other);
+						None
+					}
+				};
+				if let Some((term, depth)) = target {
+					// Brackets after an operand index it
+					let indices = split_args(sub);
+					if indices.is_empty() {
+						return Err(ParseError::Expected {
+							expected: Expected::Expression,
+						});
 					}
+					self.start(Group::Index(term, depth), indices);
+				} else if is_bracket_group(&sub) {
+					// A single expression in brackets is grouped like it's in parentheses
+					self.start(Group::Sub, vec![sub]);
 				} else {
-					// Just push the subexpression
-					mtokens.push(Expr::Sub(paren_to_exprs(sub, ctx)?));
+					// Otherwise it's a vector literal
+					self.start(Group::Vector, split_args(sub));
 				}
 			}
 			ParenToken::Name(name) => {
 				// Names followed by names aren't functions
-				if let Some(pending_name) = pending_name.take() {
-					mtokens.push(Expr::Var(pending_name));
-				}
-				pending_name = Some(name);
+				self.flush_name();
+				self.pending_name = Some(name);
 			}
-			// There should be no commas here, they should have been removed during the Self::tokens_to_args calls
-			// that happen when pushing a function.
+			// There should be no commas here, they should have been removed by the split_args calls
+			// that happen when starting a group.
 			ParenToken::Comma => {
 				return Err(ParseError::UnexpectedToken {
 					token: String::from(","),
 				})
 			}
 		}
+		Ok(())
 	}
 
-	if let Some(pending_name) = pending_name.take() {
-		// Push a leftover pending name
-		mtokens.push(Expr::Var(pending_name));
+	/// Push a pending name as a variable
+	fn flush_name(&mut self) {
+		if let Some(name) = self.pending_name.take() {
+			self.exprs.push(Expr::Var(name));
+		}
+	}
+
+	/// Convert the exprs of this frame to a term
+	fn finish(mut self, ctx: &Context<N>, warnings: &mut Vec<Warning>, names: &mut Names<'a>) -> Result<(Term<N>, usize), ParseError> {
+		self.flush_name();
+		for (i, expr) in self.exprs.iter().enumerate() {
+			match *expr {
+				Expr::Var(name) if !ctx.vars.contains_key(name) && !ctx.has_func(name) => {
+					warnings.push(Warning::UnknownVariable { name: name.to_string() });
+				}
+				Expr::Term(Term::Function(ref name, _), _) if i > 0 && matches!(self.exprs[i - 1], Expr::Op(Op::Pre(Pre::Neg))) => {
+					warnings.push(Warning::NegatedCall { name: name.to_string() });
+				}
+				_ => {}
+			}
+		}
+		let exprs = if ctx.cfg.implicit_multiplication {
+			insert_operators(self.exprs, warnings)
+		} else {
+			self.exprs
+		};
+		let postfix = tokenexprs_to_postfix(exprs, &ctx.cfg);
+		postfix_to_term_depth(postfix, ctx, names)
 	}
+}
 
-	Ok(mtokens)
+/// Check whether a token can start the argument of a function called without parentheses
+fn is_operand_start(token: &ParenToken<'_>) -> bool {
+	matches!(*token, ParenToken::Num(_) | ParenToken::Frac(_) | ParenToken::Name(_))
 }
 
-/// Converts a Vec of ParenTokens into a Vec of a Vec of Exprs, splitting them by commas and
-/// then parsing them into Exprs.
-fn tokens_to_args<N: Num + 'static>(raw: Vec<ParenToken>, ctx: &Context<N>) -> Result<Vec<Vec<Expr>>, ParseError> {
-	let args: Vec<&[ParenToken]> = raw.split(|ptoken| match *ptoken {
+/// Check whether a token ends the argument of a function called without parentheses, which is
+/// when it's a separator or an infix operator that binds as loosely as addition or looser
+fn ends_loose_arg(token: &ParenToken<'_>) -> bool {
+	match *token {
 		ParenToken::Comma => true,
+		ParenToken::Op(ref op @ Op::In(_)) => op.precedence() <= Op::In(In::Add).precedence(),
 		_ => false,
-	}).collect();
+	}
+}
+
+/// Make the terms parsed from a group into an operand
+fn finish_group<'a, N: Num + 'static>(group: Group<'a, N>, terms: Vec<(Term<N>, usize)>, ctx: &Context<N>, names: &mut Names<'a>) -> Result<Expr<'a, N>, ParseError> {
+	// The depth of the deepest term
+	let depth = terms.iter().map(|&(_, d)| d).max().unwrap_or(0);
+	let terms = terms.into_iter().map(|(t, _)| t);
+	Ok(match group {
+		Group::Sub => {
+			let term = terms.last().ok_or(ParseError::Expected {
+				expected: Expected::Expression,
+			})?;
+			Expr::Term(term, depth)
+		}
+		Group::Func(name) => {
+			if ctx.cfg.strict_names && !ctx.has_func(name) {
+				return Err(ParseError::UnknownFunction {
+					suggestions: ctx.similar_funcs(name),
+					name: name.to_string(),
+				});
+			}
+			Expr::Term(Term::Function(names.get(name), terms.collect()), check_depth(depth + 1, ctx)?)
+		}
+		Group::Vector => {
+			let items = terms.collect();
+			Expr::Term(Term::Operation(Arc::new(Vector { items })), check_depth(depth + 1, ctx)?)
+		}
+		Group::Index(mut term, mut target_depth) => {
+			// Index the target with each index in turn
+			for index in terms {
+				target_depth = check_depth(::core::cmp::max(target_depth, depth) + 1, ctx)?;
+				term = Term::Operation(Arc::new(Index { a: term, b: index }));
+			}
+			Expr::Term(term, target_depth)
+		}
+	})
+}
+
+/// Check that a name is defined in the context as either a variable or a function if the config
+/// requires it
+fn check_name<N: Num + 'static>(name: &str, ctx: &Context<N>) -> Result<(), ParseError> {
+	if ctx.cfg.strict_names && !ctx.vars.contains_key(name) && !ctx.has_func(name) {
+		return Err(ParseError::UnknownName { name: name.to_string() });
+	}
+	Ok(())
+}
+
+/// Check that a function can take that many arguments if it's defined
+fn check_args<N: Num + 'static>(name: &str, count: usize, ctx: &Context<N>) -> Result<(), ParseError> {
+	if let Some(func) = ctx.lookup_func(name) {
+		let too_many = match func.max_args() {
+			Some(max) => count > max,
+			None => false,
+		};
+		if count < func.min_args() || too_many {
+			return Err(ParseError::IncorrectArguments { name: name.to_string(), count });
+		}
+	}
+	Ok(())
+}
+
+/// Check that a depth doesn't exceed the maximum depth in the config
+fn check_depth<N: Num>(depth: usize, ctx: &Context<N>) -> Result<usize, ParseError> {
+	if depth > ctx.cfg.max_depth {
+		Err(ParseError::TooDeep)
+	} else {
+		Ok(depth)
+	}
+}
+
+/// Check whether the contents of square brackets that don't index anything should group them like
+/// parentheses instead of being a vector literal, which is when they're a single expression that
+/// isn't a vector itself (so that `[[1, 2]]` is still a matrix with one row)
+fn is_bracket_group(sub: &[ParenToken]) -> bool {
+	!sub.is_empty()
+		&& !sub.iter().any(|t| matches!(*t, ParenToken::Comma))
+		&& !matches!(*sub, [ParenToken::Bracket(_)])
+}
+
+/// Splits a Vec of ParenTokens by their commas into the token lists of each argument
+fn split_args(raw: Vec<ParenToken>) -> Vec<Vec<ParenToken>> {
+	let mut args = Vec::new();
+	let mut arg = Vec::new();
+	for ptoken in raw {
+		match ptoken {
+			ParenToken::Comma => args.push(mem::take(&mut arg)),
+			ptoken => arg.push(ptoken),
+		}
+	}
+	args.push(arg);
+	// Ignore empty arguments (occurs when no arguments where passed to the function)
+	args.retain(|arg| !arg.is_empty());
+	args
+}
+
+/// Check whether all the values of two answers are equal within a relative tolerance. Values that
+/// are both NaN are considered equal.
+fn approx_eq<N: Num>(a: Answer<N>, b: Answer<N>, tolerance: f64) -> bool {
+	let close = |x: f64, y: f64| {
+		(x.is_nan() && y.is_nan()) || x == y || (x - y).abs() <= tolerance * x.abs().max(y.abs()).max(1.0)
+	};
+	let (a, b) = (a.to_vec(), b.to_vec());
+
+	a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| match (x.to_f64_complex_lossy(), y.to_f64_complex_lossy()) {
+		(Ok((xr, xi)), Ok((yr, yi))) => close(xr, yr) && close(xi, yi),
+		_ => false,
+	})
+}
+
+/// The names of the operations that are commutative and associative, whose operands are sorted by
+/// `Term::canonicalize`
+const COMMUTATIVE: [&str; 4] = ["+", "*", "&", "|"];
+
+/// Flatten a chain of the same commutative operation into all of its operands, sort them, and chain
+/// them back together with that operation
+fn canonical_chain<N: Num + 'static>(oper: &Arc<dyn Operate<N>>) -> Term<N> {
+	fn collect<N: Num>(term: &Term<N>, name: &str, out: &mut Vec<Term<N>>) {
+		match *term {
+			Term::Operation(ref oper) if oper.name() == name => {
+				for operand in oper.operands() {
+					collect(operand, name, out);
+				}
+			}
+			_ => out.push(term.clone()),
+		}
+	}
+
+	let mut operands = Vec::new();
+	for operand in oper.operands() {
+		collect(operand, oper.name(), &mut operands);
+	}
+	operands.sort_by(cmp_terms);
+
+	let mut operands = operands.into_iter();
+	let first = match operands.next() {
+		Some(first) => first,
+		None => return Term::Operation(Arc::clone(oper)),
+	};
+	operands.fold(first, |acc, operand| Term::Operation(oper.with_operands(vec![acc, operand])))
+}
 
-	let mut new = Vec::new();
-	for arg in args {
-		if arg.is_empty() {
-			continue; // Ignore empty arguments (occurs when no arguments where passed to the function)
+/// An arbitrary but consistent total order of terms, used to sort the operands of canonical terms.
+/// Numbers come first, then variables, functions, and operations.
+fn cmp_terms<N: Num>(a: &Term<N>, b: &Term<N>) -> Ordering {
+	fn rank<N: Num>(term: &Term<N>) -> u8 {
+		match *term {
+			Term::Num(_) => 0,
+			Term::Var(_) => 1,
+			Term::Function(_, _) => 2,
+			Term::Operation(_) => 3,
 		}
-		let arg = arg.to_vec();
-		new.push(paren_to_exprs(arg, ctx)?)
 	}
-	Ok(new)
+
+	fn cmp_all<'a, N: Num + 'a>(a: impl IntoIterator<Item = &'a Term<N>>, b: impl IntoIterator<Item = &'a Term<N>>) -> Ordering {
+		let (mut a, mut b) = (a.into_iter(), b.into_iter());
+		loop {
+			match (a.next(), b.next()) {
+				(Some(x), Some(y)) => match cmp_terms(x, y) {
+					Ordering::Equal => {}
+					ord => return ord,
+				},
+				(x, y) => return x.is_some().cmp(&y.is_some()),
+			}
+		}
+	}
+
+	match (a, b) {
+		(Term::Num(x), Term::Num(y)) => x.to_string().cmp(&y.to_string()),
+		(Term::Var(x), Term::Var(y)) => x.cmp(y),
+		(Term::Function(x, x_args), Term::Function(y, y_args)) => x.cmp(y).then_with(|| cmp_all(x_args, y_args)),
+		(Term::Operation(x), Term::Operation(y)) => x.name().cmp(y.name()).then_with(|| cmp_all(x.operands(), y.operands())),
+		_ => rank(a).cmp(&rank(b)),
+	}
 }
 
 /// Insert multiplication operations in between operands that are right next to each other
-#[cfg_attr(feature = "cargo-clippy", allow(redundant_closure))]
-fn insert_operators(mut raw: Vec<Expr>) -> Vec<Expr> {
+fn insert_operators<'a, N: Num + 'static>(mut raw: Vec<Expr<'a, N>>, warnings: &mut Vec<Warning>) -> Vec<Expr<'a, N>> {
 	let mut i = 0;
 
 	if raw.is_empty() {
@@ -241,11 +1607,21 @@ fn insert_operators(mut raw: Vec<Expr>) -> Vec<Expr> {
 
 	while i < raw.len() - 1 {
 		if raw[i].is_operand() && raw[i + 1].is_operand() {
+			warnings.push(Warning::ImplicitMultiplication {
+				left: raw[i].describe(),
+				right: raw[i + 1].describe(),
+			});
 			raw.insert(i + 1, Expr::Op(Op::In(In::Mul)));
 		} else {
 			match raw[i] {
 				Expr::Op(Op::Post(_)) => {
 					if raw[i + 1].is_operand() {
+						// The operand the postfix operator applies to is part of the left side
+						let operand = if i > 0 { raw[i - 1].describe() } else { String::new() };
+						warnings.push(Warning::ImplicitMultiplication {
+							left: format!("{}{}", operand, raw[i].describe()),
+							right: raw[i + 1].describe(),
+						});
 						raw.insert(i + 1, Expr::Op(Op::In(In::Mul)));
 					}
 				}
@@ -255,78 +1631,80 @@ fn insert_operators(mut raw: Vec<Expr>) -> Vec<Expr> {
 		}
 	}
 
-	let mut new = Vec::new();
-	for texpr in raw {
-		match texpr {
-			Expr::Sub(texprs) => new.push(Expr::Sub(insert_operators(texprs))),
-			Expr::Func(name, args) => new.push(Expr::Func(
-				name,
-				args.into_iter()
-					.map(|texprs| insert_operators(texprs))
-					.collect(),
-			)),
-			t => new.push(t),
-		}
-	}
-
-	new
+	raw
 }
 
 /// Convert a vector of infix exprs to a postfix representations (shunting yard)
-fn tokenexprs_to_postfix(raw: Vec<Expr>) -> Vec<Expr> {
-	fn recurse(raw: &[Expr]) -> Vec<Expr> {
-		let mut stack = Vec::new();
-		let mut ops: Vec<Op> = Vec::new();
-		for texpr in raw {
-			match *texpr {
-				Expr::Num(num) => stack.push(Expr::Num(num)), // Push number onto the stack
-				Expr::Op(ref op) => {
-					while let Some(top_op) = ops.pop() {
-						// Pop all operators with high enough precedence
-						if op.should_shunt(&top_op.clone()) {
-							stack.push(Expr::Op(top_op));
-						} else {
-							ops.push(top_op); // Put it back (not high enough precedence)
-							break;
-						}
-					}
-					ops.push(op.clone()); // Put the op on the stack
-				}
-				Expr::Var(ref name) => stack.push(Expr::Var(name.clone())), // Put the var on the stack
-				Expr::Func(ref name, ref texprs_args) => stack.push(Expr::Func(name.clone(), {
-					// Put the function on the stack
-					let mut new_texprs_args = Vec::new();
-					for texprs in texprs_args {
-						new_texprs_args.push(recurse(texprs)); // Do shunting yard for all of it's arguments
+fn tokenexprs_to_postfix<'a, N: Num>(raw: Vec<Expr<'a, N>>, cfg: &Config) -> Vec<Expr<'a, N>> {
+	let mut stack = Vec::new();
+	let mut ops: Vec<Op> = Vec::new();
+	for texpr in raw {
+		match texpr {
+			Expr::Op(op) => {
+				while let Some(top_op) = ops.pop() {
+					// Pop all operators with high enough precedence
+					if op.should_shunt(&top_op, cfg) {
+						stack.push(Expr::Op(top_op));
+					} else {
+						ops.push(top_op); // Put it back (not high enough precedence)
+						break;
 					}
-					new_texprs_args
-				})),
-				Expr::Sub(ref texprs) => stack.push(Expr::Sub(recurse(texprs))), // Push the subexpression onto the stack
+				}
+				ops.push(op); // Put the op on the stack
 			}
+			texpr => stack.push(texpr), // Put operands on the stack
 		}
+	}
 
-		while let Some(op) = ops.pop() {
-			// Push leftover operators onto stack
-			stack.push(Expr::Op(op));
-		}
-		stack
+	while let Some(op) = ops.pop() {
+		// Push leftover operators onto stack
+		stack.push(Expr::Op(op));
 	}
+	stack
+}
 
-	recurse(&raw)
+/// The value of a fraction, with its parts divided by the number type so that it's exact if the type
+/// can represent it
+fn fraction_value<N: Num>(frac: Fraction, ctx: &Context<N>) -> Calculation<N> {
+	let part = |x: f64| N::from_f64(x, ctx);
+	let fraction = part(frac.num)?.op_ctx(&part(frac.den)?, ctx, |a, b| a.div(b, ctx))?;
+	if frac.whole == 0.0 {
+		Ok(fraction)
+	} else {
+		part(frac.whole)?.op_ctx(&fraction, ctx, |a, b| a.add(b, ctx))
+	}
 }
 
-/// Parse a postfix token stream into a single term
-fn postfix_to_term<N: Num + 'static>(raw: Vec<Expr>, ctx: &Context<N>) -> Result<Term<N>, ParseError> {
+/// Parse a postfix token stream into a single term, also returning the depth of the term. Errors if the
+/// depth exceeds the maximum depth in the config.
+fn postfix_to_term_depth<'a, N: Num + 'static>(raw: Vec<Expr<'a, N>>, ctx: &Context<N>, names: &mut Names<'a>) -> Result<(Term<N>, usize), ParseError> {
+	// The terms on the stack along with their depths
 	let mut stack = Vec::new();
+	// Whether the last expr was a percent operator, meaning the operand on the top of the stack is a percentage
+	let mut last_percent = false;
 	for texpr in raw {
+		let is_percent = matches!(texpr, Expr::Op(Op::Post(Post::Percent)));
 		match texpr {
-			Expr::Num(num) => stack.push(Term::Num(N::from_f64(num, ctx).unwrap())), // Put num on the stack
+			Expr::Num(num) => {
+				// Put num on the stack
+				let num = N::from_f64(num, ctx).map_err(|_| ParseError::InvalidNumber { number: num.to_string() })?;
+				stack.push((Term::Num(num), 1))
+			}
+			Expr::Frac(frac) => {
+				let num = fraction_value(frac, ctx).map_err(|_| ParseError::InvalidNumber { number: frac.to_string() })?;
+				stack.push((Term::Num(num), 1))
+			}
 			Expr::Op(op) => {
+				// The depth of the deepest operand
+				let mut depth = 0;
 				// Push the operation with the last two operands on the stack
 				macro_rules! pop {
 						() => {
 							match stack.pop() {
-								Some(v) => v,
+								Some((v, d)) => {
+									depth = ::core::cmp::max(depth, d);
+									v
+								}
 								None => return Err(ParseError::Expected {
 									expected: Expected::Expression
 								}),
@@ -334,61 +1712,84 @@ fn postfix_to_term<N: Num + 'static>(raw: Vec<Expr>, ctx: &Context<N>) -> Result
 						}
 					}
 
-				let oper: Rc<dyn Operate<N>> = match op {
+				let oper: Arc<dyn Operate<N>> = match op {
 					Op::In(op) => match op {
-						In::Add => Rc::new(Add {
+						In::Add if last_percent => Arc::new(AddPercent {
 							b: pop!(),
 							a: pop!(),
 						}),
-						In::Sub => Rc::new(Sub {
+						In::Add => Arc::new(Add {
 							b: pop!(),
 							a: pop!(),
 						}),
-						In::Mul => Rc::new(Mul {
+						In::Sub if last_percent => Arc::new(SubPercent {
 							b: pop!(),
 							a: pop!(),
 						}),
-						In::Div => Rc::new(Div {
+						In::Sub => Arc::new(Sub {
 							b: pop!(),
 							a: pop!(),
 						}),
-						In::Pow => Rc::new(Pow {
+						In::Mul => Arc::new(Mul {
 							b: pop!(),
 							a: pop!(),
+							call: None,
 						}),
-						In::PlusMinus => Rc::new(PlusMinus {
+						In::Call(name) => Arc::new(Mul {
+							b: pop!(),
+							a: pop!(),
+							call: Some(name),
+						}),
+						In::Div => Arc::new(Div {
+							b: pop!(),
+							a: pop!(),
+						}),
+						In::Pow => Arc::new(Pow {
+							b: pop!(),
+							a: pop!(),
+						}),
+						In::PlusMinus => Arc::new(PlusMinus {
+							b: pop!(),
+							a: pop!(),
+						}),
+						In::BitAnd => Arc::new(BitAnd {
+							b: pop!(),
+							a: pop!(),
+						}),
+						In::BitOr => Arc::new(BitOr {
+							b: pop!(),
+							a: pop!(),
+						}),
+						In::Shl => Arc::new(Shl {
+							b: pop!(),
+							a: pop!(),
+						}),
+						In::Shr => Arc::new(Shr {
 							b: pop!(),
 							a: pop!(),
 						}),
 					},
 					Op::Pre(op) => match op {
-						Pre::Neg => Rc::new(Neg { a: pop!() }),
-						Pre::Pos => Rc::new(Pos { a: pop!() }),
-						Pre::PosNeg => Rc::new(PosNeg { a: pop!() }),
+						Pre::Neg => Arc::new(Neg { a: pop!() }),
+						Pre::Pos => Arc::new(Pos { a: pop!() }),
+						Pre::PosNeg => Arc::new(PosNeg { a: pop!() }),
+						Pre::BitNot => Arc::new(BitNot { a: pop!() }),
 					},
 					Op::Post(op) => match op {
-						Post::Fact => Rc::new(Fact { a: pop!() }),
-						Post::Percent => Rc::new(Percent { a: pop!() }),
+						Post::Fact => Arc::new(Fact { a: pop!() }),
+						Post::DoubleFact => Arc::new(DoubleFact { a: pop!() }),
+						Post::Percent => Arc::new(Percent { a: pop!() }),
 					},
 				};
-				stack.push(Term::Operation(oper));
-			}
-			Expr::Sub(texprs) => {
-				// Put subexpression on the stack
-				stack.push(postfix_to_term(texprs, ctx)?);
-			}
-			Expr::Var(name) => stack.push(Term::Var(name)), // Put var on the stack
-			Expr::Func(name, args) => {
-				// Put function with args converted to terms on the stack
-				stack.push(Term::Function(name, {
-					let mut new = Vec::new();
-					for texprs in args {
-						new.push(postfix_to_term(texprs, ctx)?);
-					}
-					new
-				}));
+				stack.push((Term::Operation(oper), check_depth(depth + 1, ctx)?));
+			}
+			Expr::Var(name) => {
+				check_name(name, ctx)?;
+				stack.push((Term::Var(names.get(name)), 1)) // Put var on the stack
 			}
+			Expr::Term(term, depth) => stack.push((term, depth)), // Put the parsed operand on the stack
 		}
+		last_percent = is_percent;
 	}
 	if stack.len() > 1 {
 		// If there's leftovers on the stack, oops
@@ -406,13 +1807,24 @@ fn postfix_to_term<N: Num + 'static>(raw: Vec<Expr>, ctx: &Context<N>) -> Result
 	}
 }
 
-impl Expr {
+impl<N: Num + 'static> Expr<'_, N> {
 	/// Returns true if this expr is an operand (not an operator)
 	fn is_operand(&self) -> bool {
 		use self::Expr::*;
 		match *self {
-			Num(_) | Var(_) | Func(_, _) | Sub(_) => true,
+			Num(_) | Frac(_) | Var(_) | Term(_, _) => true,
 			Op(_) => false,
 		}
 	}
+
+	/// How this expr is written, for warnings about it
+	fn describe(&self) -> String {
+		match *self {
+			Expr::Num(num) => num.to_string(),
+			Expr::Frac(frac) => frac.to_string(),
+			Expr::Op(ref op) => op.to_string(),
+			Expr::Var(name) => name.to_string(),
+			Expr::Term(ref term, _) => term.to_string(),
+		}
+	}
 }
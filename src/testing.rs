@@ -0,0 +1,169 @@
+//! Random expressions and a reference evaluator for testing, with the `"testing"` feature.
+//!
+//! `TermGen` generates the source of random expressions from a seed, and `oracle` evaluates `f64`
+//! terms in the most straightforward way possible, so that the answers of the normal evaluation can be
+//! checked against it.
+//!
+//! ```
+//! use mexprp::{Context, Term};
+//! use mexprp::testing::{oracle, TermGen};
+//!
+//! let mut ctx: Context<f64> = Context::new();
+//! ctx.set_var("x", 1.5);
+//! for raw in TermGen::new(7).take(100) {
+//!     let term: Term<f64> = Term::parse_ctx(&raw, &ctx).unwrap();
+//!     // Printed terms parse back into the same term
+//!     assert_eq!(Term::parse_ctx(&term.to_string(), &ctx).unwrap(), term);
+//!     let expected = oracle(&term, &|name| if name == "x" { Some(1.5) } else { None });
+//!     if let (Ok(answer), Some(expected)) = (term.eval_ctx(&ctx), expected) {
+//!         let answer = answer.unwrap_single();
+//!         if answer.is_finite() && expected.is_finite() {
+//!             assert!((answer - expected).abs() <= 1e-9 * expected.abs().max(1.0));
+//!         }
+//!     }
+//! }
+//! ```
+
+use crate::term::Term;
+use crate::answer::Answer;
+use crate::prelude::*;
+
+/// The functions `oracle` knows how to evaluate, with how many arguments they take
+pub const ORACLE_FUNCS: [(&str, usize); 6] = [("abs", 1), ("floor", 1), ("sin", 1), ("cos", 1), ("max", 2), ("min", 2)];
+
+/// A generator of the source of random expressions. The same seed always generates the same
+/// expressions. Numbers are small and are exact in binary and decimal, like `3` or `0.25`.
+#[derive(Debug, Clone)]
+pub struct TermGen {
+	/// The state of the random number generator
+	state: u64,
+	/// How deeply operations and function calls are nested at most (default = 5)
+	pub max_depth: usize,
+	/// The infix operators the expressions use (default = `+`, `-`, `*`, `/` and `^`)
+	pub ops: Vec<&'static str>,
+	/// The functions the expressions call, with how many arguments to call them with (default = `ORACLE_FUNCS`)
+	pub funcs: Vec<(&'static str, usize)>,
+	/// The variables the expressions use (default = `x`)
+	pub vars: Vec<String>,
+}
+
+impl TermGen {
+	/// Create a generator with the given seed
+	pub fn new(seed: u64) -> Self {
+		TermGen {
+			// The state of xorshift can't be zero
+			state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+			max_depth: 5,
+			ops: vec!["+", "-", "*", "/", "^"],
+			funcs: ORACLE_FUNCS.to_vec(),
+			vars: vec![String::from("x")],
+		}
+	}
+
+	/// Generate the source of a random expression
+	pub fn expression(&mut self) -> String {
+		let mut buf = String::new();
+		let depth = self.max_depth;
+		self.write(&mut buf, depth);
+		buf
+	}
+
+	/// Get the next random number (xorshift64*)
+	fn next_u64(&mut self) -> u64 {
+		self.state ^= self.state >> 12;
+		self.state ^= self.state << 25;
+		self.state ^= self.state >> 27;
+		self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+	}
+
+	/// Get a random number below `n`
+	fn below(&mut self, n: usize) -> usize {
+		(self.next_u64() >> 33) as usize % n
+	}
+
+	/// Write an expression that nests at most `depth` levels deep
+	fn write(&mut self, buf: &mut String, depth: usize) {
+		// Stop with a number or a variable sometimes, so expressions have all kinds of shapes
+		if depth == 0 || self.below(4) == 0 {
+			if !self.vars.is_empty() && self.below(3) == 0 {
+				let i = self.below(self.vars.len());
+				buf.push_str(&self.vars[i]);
+			} else if self.below(4) == 0 {
+				buf.push_str(&(self.below(40) as f64 / 4.0).to_string());
+			} else {
+				buf.push_str(&self.below(20).to_string());
+			}
+			return;
+		}
+
+		let choice = self.below(self.ops.len() + self.funcs.len() + 1);
+		if choice < self.ops.len() {
+			let op = self.ops[choice];
+			buf.push('(');
+			self.write(buf, depth - 1);
+			buf.push_str(&format!(" {} ", op));
+			self.write(buf, depth - 1);
+			buf.push(')');
+		} else if choice < self.ops.len() + self.funcs.len() {
+			let (name, args) = self.funcs[choice - self.ops.len()];
+			buf.push_str(name);
+			buf.push('(');
+			for i in 0..args {
+				if i > 0 {
+					buf.push_str(", ");
+				}
+				self.write(buf, depth - 1);
+			}
+			buf.push(')');
+		} else {
+			buf.push_str("-(");
+			self.write(buf, depth - 1);
+			buf.push(')');
+		}
+	}
+}
+
+impl Iterator for TermGen {
+	type Item = String;
+
+	fn next(&mut self) -> Option<String> {
+		Some(self.expression())
+	}
+}
+
+/// Evaluate a term recursively in the most straightforward way, with the values of variables given
+/// by `vars`. Returns `None` for anything it doesn't know how to evaluate, like multiple answers or
+/// functions that aren't in `ORACLE_FUNCS`. It doesn't check for errors, so dividing by zero is
+/// infinite like it is for `f64`s.
+pub fn oracle(term: &Term<f64>, vars: &dyn Fn(&str) -> Option<f64>) -> Option<f64> {
+	match *term {
+		Term::Num(Answer::Single(num)) => Some(num),
+		Term::Num(_) => None,
+		Term::Var(ref name) => vars(name),
+		Term::Function(ref name, ref args) => {
+			let args = args.iter().map(|arg| oracle(arg, vars)).collect::<Option<Vec<f64>>>()?;
+			match (&**name, args.as_slice()) {
+				("abs", &[a]) => Some(a.abs()),
+				("floor", &[a]) => Some(a.floor()),
+				("sin", &[a]) => Some(a.sin()),
+				("cos", &[a]) => Some(a.cos()),
+				("max", &[a, b]) => Some(a.max(b)),
+				("min", &[a, b]) => Some(a.min(b)),
+				_ => None,
+			}
+		}
+		Term::Operation(ref oper) => {
+			let args = oper.operands().into_iter().map(|arg| oracle(arg, vars)).collect::<Option<Vec<f64>>>()?;
+			match (oper.name(), args.as_slice()) {
+				("+", &[a, b]) => Some(a + b),
+				("-", &[a, b]) => Some(a - b),
+				("*", &[a, b]) => Some(a * b),
+				("/", &[a, b]) => Some(a / b),
+				("^", &[a, b]) => Some(a.powf(b)),
+				("(-)", &[a]) => Some(-a),
+				("(+)", &[a]) => Some(a),
+				_ => None,
+			}
+		}
+	}
+}
@@ -1,5 +1,6 @@
 use crate::{eval, Answer, Calculation, Context, Expression, Num, Term};
 use crate::num::{ComplexFloat};
+use crate::state::EvalState;
 
 #[test]
 fn plain() {
@@ -79,6 +80,2700 @@ fn funcs() {
 	assert!(eq(expr.eval().unwrap().unwrap_single(), 5.0));
 }
 
+#[test]
+fn percent() {
+	assert!(eq(eval::<f64>("50 + 10%").unwrap().unwrap_single(), 55.0));
+	assert!(eq(eval::<f64>("50 - 10%").unwrap().unwrap_single(), 45.0));
+	assert!(eq(eval::<f64>("50 * 10%").unwrap().unwrap_single(), 5.0));
+	let mut ctx: Context<f64> = Context::new();
+	ctx.cfg.relative_percent = false;
+	let expr = Expression::parse_ctx("50 + 10%", ctx).unwrap();
+	assert!(eq(expr.eval().unwrap().unwrap_single(), 50.1));
+}
+
+#[test]
+fn piecewise_builtins() {
+	assert!(eq(eval::<f64>("sign(-3.5)").unwrap().unwrap_single(), -1.0));
+	assert!(eq(eval::<f64>("sign(0)").unwrap().unwrap_single(), 0.0));
+	assert!(eq(eval::<f64>("step(-2)").unwrap().unwrap_single(), 0.0));
+	assert!(eq(eval::<f64>("step(0)").unwrap().unwrap_single(), 1.0));
+	assert!(eq(eval::<f64>("clamp(12, 0, 10)").unwrap().unwrap_single(), 10.0));
+	assert!(eq(eval::<f64>("clamp(-1, 0, 10)").unwrap().unwrap_single(), 0.0));
+	assert!(eq(eval::<f64>("clamp(4, 0, 10)").unwrap().unwrap_single(), 4.0));
+	assert!(eq(eval::<f64>("lerp(2, 6, 0.25)").unwrap().unwrap_single(), 3.0));
+}
+
+#[test]
+fn vectors() {
+	assert_eq!(eval::<f64>("[1, 2, 3] * 2").unwrap(), Answer::Vector(vec![Answer::Single(2.0), Answer::Single(4.0), Answer::Single(6.0)]));
+	assert_eq!(eval::<f64>("[1, 2] + [3, 4]").unwrap(), Answer::Vector(vec![Answer::Single(4.0), Answer::Single(6.0)]));
+	assert!(eval::<f64>("[1, 2] + [3, 4, 5]").is_err());
+	assert!(eq(eval::<f64>("[4, 5, 6][2]").unwrap().unwrap_single(), 5.0));
+	assert!(eq(eval::<f64>("len([4, 5, 6])").unwrap().unwrap_single(), 3.0));
+	assert!(eq(eval::<f64>("dot([1, 2, 3], [4, 5, 6])").unwrap().unwrap_single(), 32.0));
+	assert!(eq(eval::<f64>("norm([3, 4])").unwrap().unwrap_single(), 5.0));
+	assert_eq!(eval::<f64>("cross([1, 0, 0], [0, 1, 0])").unwrap(), Answer::Vector(vec![Answer::Single(0.0), Answer::Single(0.0), Answer::Single(1.0)]));
+	assert!(eval::<f64>("[4, 5, 6][4]").is_err());
+	assert!(eval::<f64>("[1, 2)").is_err());
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("v", Answer::Vector(vec![Answer::Single(7.0), Answer::Single(8.0)]));
+	let expr = Expression::parse_ctx("2v[1]", ctx).unwrap();
+	assert!(eq(expr.eval().unwrap().unwrap_single(), 14.0));
+}
+
+#[test]
+fn matrices() {
+	let single = |a: Answer<f64>| a.to_vec();
+	assert_eq!(single(eval("[[1, 2], [3, 4]] * [[5, 6], [7, 8]]").unwrap()), vec![19.0, 22.0, 43.0, 50.0]);
+	assert_eq!(single(eval("[[1, 2], [3, 4]] * [1, 1]").unwrap()), vec![3.0, 7.0]);
+	assert_eq!(single(eval("transpose([[1, 2], [3, 4]])").unwrap()), vec![1.0, 3.0, 2.0, 4.0]);
+	assert!(eq(eval::<f64>("det([[1, 2], [3, 4]])").unwrap().unwrap_single(), -2.0));
+	assert!(eq(eval::<f64>("inv([[4, 7], [2, 6]])[2, 1]").unwrap().unwrap_single(), -0.2));
+	let x = single(eval("solve([[2, 1], [1, 3]], [3, 5])").unwrap());
+	assert!(eq(x[0], 0.8) && eq(x[1], 1.4));
+	assert!(eval::<f64>("inv([[1, 2], [2, 4]])").is_err());
+}
+
+#[test]
+fn bound() {
+	let ctx: Context<f64> = Context::new();
+	let term: Term<f64> = Term::parse("max(x, y) * pi").unwrap();
+	let bound = term.bind(&ctx).unwrap();
+	assert_eq!(bound.slot("y"), Some(1));
+	assert!(eq(bound.eval(&[1.0, 2.0]).unwrap().unwrap_single(), 2.0 * ::std::f64::consts::PI));
+	assert!(bound.eval(&[1.0]).is_err());
+	let mut ctx: Context<f64> = Context::empty();
+	ctx.cfg.implicit_multiplication = false;
+	assert!(Term::parse_ctx("foo(2)", &ctx).unwrap().bind(&ctx).is_err());
+}
+
+#[test]
+fn term_equality() {
+	use std::collections::HashSet;
+
+	let a: Term<f64> = Term::parse("2x + sin(y)").unwrap();
+	let b: Term<f64> = Term::parse("2 * x + sin(y)").unwrap();
+	let c: Term<f64> = Term::parse("2x - sin(y)").unwrap();
+	assert_eq!(a, b);
+	assert_ne!(a, c);
+	let mut set = HashSet::new();
+	set.insert(a);
+	set.insert(b);
+	set.insert(c);
+	assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn memoize() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let mut ctx: Context<f64> = Context::new();
+	let counter = Arc::clone(&calls);
+	ctx.set_func("count", move |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+		counter.fetch_add(1, Ordering::Relaxed);
+		args[0].eval_ctx(ctx)
+	});
+	ctx.cfg.memoize = true;
+	let expr = Expression::parse_ctx("count(x + 1) ^ 2 + count(x + 1) * 2", ctx).unwrap();
+	let mut eval_ctx = expr.ctx.clone();
+	eval_ctx.set_var("x", 2.0);
+	assert!(eq(expr.eval_ctx(&eval_ctx).unwrap().unwrap_single(), 15.0));
+	assert_eq!(calls.load(Ordering::Relaxed), 1);
+	eval_ctx.set_var("x", 3.0);
+	assert!(eq(expr.eval_ctx(&eval_ctx).unwrap().unwrap_single(), 24.0));
+	assert_eq!(calls.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn cached_vars() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let mut ctx: Context<f64> = Context::new();
+	let counter = Arc::clone(&calls);
+	ctx.set_func("count", move |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+		counter.fetch_add(1, Ordering::Relaxed);
+		args[0].eval_ctx(ctx)
+	});
+	ctx.set_var("x", 2.0);
+	ctx.set_var("y", Term::parse_ctx("count(x * 3)", &ctx).unwrap());
+	let term = Term::parse_ctx("y + y * y - y / y", &ctx).unwrap();
+	assert!(eq(term.eval_ctx(&ctx).unwrap().unwrap_single(), 41.0));
+	assert_eq!(calls.load(Ordering::Relaxed), 1);
+	assert!(eq(term.eval_fast(&ctx).unwrap().unwrap_single(), 41.0));
+	assert_eq!(calls.load(Ordering::Relaxed), 2);
+	// Every evaluation starts with an empty cache
+	ctx.set_var("x", 3.0);
+	assert!(eq(term.eval_ctx(&ctx).unwrap().unwrap_single(), 89.0));
+	assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+	// Parameters of functions aren't mixed up with the variables they shadow
+	ctx.load_definitions("f(x) = y").unwrap();
+	let term = Term::parse_ctx("y + f(5) + y", &ctx).unwrap();
+	assert!(eq(term.eval_ctx(&ctx).unwrap().unwrap_single(), 33.0));
+
+	ctx.cfg.cache_vars = false;
+	calls.store(0, Ordering::Relaxed);
+	let term = Term::parse_ctx("y + y * y - y / y", &ctx).unwrap();
+	assert!(eq(term.eval_ctx(&ctx).unwrap().unwrap_single(), 89.0));
+	assert_eq!(calls.load(Ordering::Relaxed), 5);
+}
+
+#[test]
+fn circular_definitions() {
+	use crate::MathError;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.load_definitions("a = b + 1\nb = 2c\nc = a - 3\nd = c + 1\nf(x) = x + 1").unwrap();
+	let term = Term::parse_ctx("d * 2", &ctx).unwrap();
+	let path = |res: Calculation<f64>| match res {
+		Err(MathError::CircularDefinition { path }) => path,
+		res => panic!("expected a circular definition, got {:?}", res),
+	};
+	assert_eq!(path(term.eval_ctx(&ctx)), vec!["c", "a", "b", "c"]);
+	assert_eq!(path(term.eval_fast(&ctx)), vec!["c", "a", "b", "c"]);
+	let err = term.eval_ctx(&ctx).unwrap_err();
+	assert_eq!(err.to_string(), "The variable 'c' is defined in terms of itself (c -> a -> b -> c)");
+	assert_eq!(err.code(), "math.circular_definition");
+	ctx.cfg.cache_vars = false;
+	assert_eq!(path(term.eval_ctx(&ctx)), vec!["c", "a", "b", "c"]);
+
+	// Using a variable more than once, or in the arguments of a function whose parameter has the same
+	// name, isn't circular
+	ctx.load_definitions("c = 4\nx = f(c) + c\ny = f(x) * x").unwrap();
+	assert!(eq(Term::parse_ctx("y + x", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single(), 99.0));
+
+	// Cycles through the bodies of functions are found too
+	ctx.load_definitions("a = g(1)\ng(x) = a + x").unwrap();
+	assert_eq!(path(Term::parse_ctx("a", &ctx).unwrap().eval_ctx(&ctx)), vec!["a", "a"]);
+}
+
+#[test]
+fn missing_definitions() {
+	use crate::Missing;
+
+	let mut parse_ctx: Context<f64> = Context::new();
+	parse_ctx.set_func("area", |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+		args[0].eval_ctx(ctx)?.op(&args[1].eval_ctx(ctx)?, |a, b| a.mul(b, ctx))
+	});
+	let expr = Expression::parse_ctx("area(w, h) * scale + max(w, depth) + sqrt(2)", parse_ctx).unwrap();
+	assert!(expr.missing_definitions(&expr.ctx).funcs.is_empty());
+	assert_eq!(expr.missing_definitions(&expr.ctx).vars, vec!["depth", "h", "scale", "w"]);
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("w", 2.0);
+	ctx.set_var("h", Term::parse("w * ratio").unwrap());
+	ctx.load_definitions("scale = grow(depth)\ngrow(x) = x * factor + w").unwrap();
+	assert_eq!(expr.missing_definitions(&ctx), Missing {
+		vars: vec!["depth".to_string(), "factor".to_string(), "ratio".to_string()],
+		funcs: vec!["area".to_string()],
+	});
+	ctx.set_var("depth", 3.0);
+	ctx.set_var("ratio", 1.5);
+	ctx.set_var("factor", 4.0);
+	ctx.set_func("area", |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+		args[0].eval_ctx(ctx)?.op(&args[1].eval_ctx(ctx)?, |a, b| a.mul(b, ctx))
+	});
+	assert!(expr.missing_definitions(&ctx).is_empty());
+	assert!(expr.eval_ctx(&ctx).is_ok());
+
+	// Variables defined in terms of themselves are only checked once
+	ctx.load_definitions("a = b + c\nb = a + 1").unwrap();
+	assert_eq!(Term::parse_ctx("a", &ctx).unwrap().missing_definitions(&ctx).vars, vec!["c"]);
+}
+
+#[test]
+fn plus_minus() {
+	assert_eq!(eval::<f64>("1±2").unwrap(), Answer::Multiple(vec![3.0, -1.0]));
+	assert_eq!(eval::<f64>("2*±3").unwrap(), Answer::Multiple(vec![-6.0, 6.0]));
+	assert_eq!(eval::<f64>("1±2±4").unwrap(), Answer::Multiple(vec![7.0, 3.0, -1.0, -5.0]));
+	assert_eq!(eval::<f64>("(1±2)*3").unwrap(), Answer::Multiple(vec![9.0, -3.0]));
+	assert_eq!(eval::<f64>("1 ± 0").unwrap(), Answer::Multiple(vec![1.0]));
+	assert_eq!(Term::<f64>::parse("2^±1").unwrap().to_string(), "(2 ^ (±1))");
+	assert!(Term::<f64>::parse("3±").is_err());
+}
+
+#[test]
+fn lexer() {
+	use crate::lexer::{tokenize, TokenKind};
+
+	let kinds = |raw: &str| tokenize(raw).into_iter().map(|t| t.kind).collect::<Vec<TokenKind>>();
+	assert_eq!(kinds("-x^2!"), vec![TokenKind::PrefixOp, TokenKind::Name, TokenKind::InfixOp, TokenKind::Number, TokenKind::PostfixOp]);
+	assert_eq!(kinds("max([1, 2])"), vec![
+		TokenKind::Name,
+		TokenKind::OpenParen,
+		TokenKind::OpenBracket,
+		TokenKind::Number,
+		TokenKind::Comma,
+		TokenKind::Number,
+		TokenKind::CloseBracket,
+		TokenKind::CloseParen,
+	]);
+
+	// Bad characters don't stop the rest from being tokenized
+	let tokens = tokenize(" 2 × $ ab ");
+	assert_eq!(tokens.iter().map(|t| t.kind).collect::<Vec<TokenKind>>(), vec![TokenKind::Number, TokenKind::InfixOp, TokenKind::Unknown, TokenKind::Name]);
+	assert_eq!(tokens.iter().map(|t| t.text.as_str()).collect::<Vec<&str>>(), vec!["2", "×", "$", "ab"]);
+	assert_eq!(tokens[1].span, 3..5);
+	assert_eq!(tokens[3].span, 8..10);
+	assert!(tokenize("   ").is_empty());
+}
+
+#[test]
+fn check() {
+	use crate::{Diagnostic, DiagnosticKind};
+
+	let ctx: Context<f64> = Context::new();
+	let check = |raw: &str| Expression::check(raw, &ctx);
+	assert!(check("sin(pi) + max(1, 2)").is_empty());
+	assert!(check("  ").is_empty());
+	assert_eq!(check("2 + y"), vec![Diagnostic {
+		kind: DiagnosticKind::UnknownName { name: "y".to_string() },
+		span: 4..5,
+	}]);
+
+	let kinds = |raw: &str| check(raw).into_iter().map(|d| d.kind).collect::<Vec<DiagnosticKind>>();
+	assert_eq!(kinds("max(1,,2"), vec![DiagnosticKind::EmptyArgument, DiagnosticKind::Unclosed]);
+	assert_eq!(kinds("[1, 2,]"), vec![DiagnosticKind::EmptyArgument]);
+	assert_eq!(kinds("(1] + 2) * $"), vec![DiagnosticKind::Mismatched, DiagnosticKind::Unmatched, DiagnosticKind::UnexpectedCharacter]);
+	assert_eq!(kinds("2 * () +"), vec![DiagnosticKind::EmptyArgument, DiagnosticKind::MissingOperand]);
+	assert_eq!(check("sin(")[0].span, 3..4);
+}
+
+#[test]
+fn complete() {
+	use crate::{Arity, CompletionKind};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 1.0);
+	ctx.set_func("f", |args: &[Term<f64>], ctx: &Context<f64>| args[0].eval_ctx(ctx));
+
+	let names = |prefix: &str| ctx.complete(prefix).into_iter().map(|c| c.name).collect::<Vec<String>>();
+	assert_eq!(names("a"), vec!["abs", "acos", "asin", "atan", "atant"]);
+	assert_eq!(names("xyz"), Vec::<String>::new());
+
+	let max = &ctx.complete("max")[0];
+	assert_eq!(max.kind, CompletionKind::Function);
+	assert_eq!(max.arity, Some(Arity::at_least(1)));
+	assert!(!max.arity.unwrap().accepts(0));
+	assert_eq!(ctx.complete("f")[0].arity, Some(Arity::any()));
+	assert_eq!(ctx.complete("x")[0].kind, CompletionKind::Variable);
+	assert_eq!(ctx.complete("x")[0].arity, None);
+}
+
+#[test]
+fn func_metadata() {
+	use crate::{Arity, Func, MathError, ParseError};
+
+	struct Hypot;
+	impl Func<f64> for Hypot {
+		fn eval(&self, args: &[Term<f64>], ctx: &Context<f64>) -> Calculation<f64> {
+			let a = args[0].eval_ctx(ctx)?.unwrap_single();
+			let b = match args.get(1) {
+				Some(b) => b.eval_ctx(ctx)?.unwrap_single(),
+				None => 0.0,
+			};
+			Ok(Answer::Single(a.hypot(b)))
+		}
+
+		fn arity(&self) -> Arity {
+			Arity::range(1, 2)
+		}
+
+		fn help_text(&self) -> Option<&str> {
+			Some("hypot(a, b): the length of the hypotenuse of a right triangle")
+		}
+	}
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_func("hypot", Hypot);
+	assert_eq!(ctx.funcs["hypot"].max_args(), Some(2));
+	assert!(ctx.funcs["hypot"].help_text().is_some());
+	assert!(ctx.funcs["sin"].name().is_none());
+
+	assert!(eq(Term::parse_ctx("hypot(3, 4)", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single(), 5.0));
+	assert!(eq(Term::parse_ctx("hypot(3)", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single(), 3.0));
+	match Term::parse_ctx("hypot(1, 2, 3)", &ctx) {
+		Err(ParseError::IncorrectArguments { name, count }) => assert_eq!((name.as_str(), count), ("hypot", 3)),
+		_ => panic!("expected a parse error"),
+	}
+	assert!(matches!(Term::parse_ctx("sin(1, 2)", &ctx), Err(ParseError::IncorrectArguments { .. })));
+	assert!(matches!(Term::parse_ctx("max()", &ctx), Err(ParseError::IncorrectArguments { .. })));
+
+	// Functions without metadata are still checked when they're evaluated
+	ctx.set_func("one", |args: &[Term<f64>], _ctx: &Context<f64>| -> Calculation<f64> {
+		if args.len() != 1 { return Err(MathError::IncorrectArguments) }
+		Ok(Answer::Single(1.0))
+	});
+	let term = Term::parse_ctx("one(1, 2)", &ctx).unwrap();
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::IncorrectArguments)));
+}
+
+#[test]
+fn strict_names() {
+	use crate::ParseError;
+
+	let mut ctx: Context<f64> = Context::new();
+	assert!(Term::parse_ctx("2x + y", &ctx).is_ok());
+
+	ctx.cfg.strict_names = true;
+	ctx.set_var("x", 3.0);
+	assert!(eq(Term::parse_ctx("2x + sin(pi)", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single(), 6.0));
+	match Term::parse_ctx("2x + y", &ctx) {
+		Err(ParseError::UnknownName { name }) => assert_eq!(name, "y"),
+		_ => panic!("expected an unknown name error"),
+	}
+	ctx.cfg.implicit_multiplication = false;
+	assert!(matches!(Term::parse_ctx("f(x)", &ctx), Err(ParseError::UnknownFunction { .. })));
+}
+
+#[test]
+fn shared_names() {
+	use std::sync::Arc;
+
+	fn names<'a>(term: &'a Term<f64>, found: &mut Vec<&'a Arc<str>>) {
+		match *term {
+			Term::Var(ref name) => found.push(name),
+			Term::Function(ref name, ref args) => {
+				found.push(name);
+				for arg in args {
+					names(arg, found);
+				}
+			}
+			Term::Operation(ref oper) => {
+				for operand in oper.operands() {
+					names(operand, found);
+				}
+			}
+			Term::Num(_) => {}
+		}
+	}
+
+	// Every use of a name in a parsed term shares one string
+	let term: Term<f64> = Term::parse("x * max(x, y) + max(y, [x][1]) - x").unwrap();
+	let mut found = Vec::new();
+	names(&term, &mut found);
+	assert_eq!(found.len(), 8);
+	for name in ["x", "y", "max"] {
+		let uses: Vec<&Arc<str>> = found.iter().copied().filter(|found| ***found == *name).collect();
+		assert!(uses.len() >= 2);
+		assert!(uses.iter().all(|used| Arc::ptr_eq(used, uses[0])));
+	}
+	assert!(!Arc::ptr_eq(found[0], found[3]));
+
+	// Cloned terms share them too
+	let clone = term.clone();
+	let mut cloned = Vec::new();
+	names(&clone, &mut cloned);
+	assert!(found.iter().zip(&cloned).all(|(a, b)| Arc::ptr_eq(a, b)));
+}
+
+#[test]
+fn fold_constants() {
+	let mut ctx: Context<f64> = Context::new();
+	let term = Term::parse_ctx("2 * pi * r", &ctx).unwrap().fold_constants(&ctx);
+	assert_eq!(term.to_string(), format!("({} × r)", 2.0 * ::std::f64::consts::PI));
+	ctx.set_var("r", 2.0);
+	assert!(eq(term.eval_ctx(&ctx).unwrap().unwrap_single(), 4.0 * ::std::f64::consts::PI));
+
+	let term = Term::parse_ctx("max(1, 2) + sqrt(4) + sin(x) / (3 - 1)", &ctx).unwrap().fold_constants(&ctx);
+	assert_eq!(term.to_string(), "({4, 0} + (sin(x) ÷ 2))");
+
+	// Self-referential variables are left alone
+	ctx.set_var("x", Term::parse("x + 1").unwrap());
+	assert_eq!(Term::parse_ctx("x * (1 + 1)", &ctx).unwrap().fold_constants(&ctx).to_string(), "(x × 2)");
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn ffi() {
+	use std::ffi::{CStr, CString};
+	use std::ptr;
+	use crate::ffi::*;
+
+	unsafe {
+		let ctx = mexprp_context_new();
+		let x = CString::new("x").unwrap();
+		assert_eq!(mexprp_set_var(ctx, x.as_ptr(), 3.0), MEXPRP_OK);
+
+		let mut status = -1;
+		let raw = CString::new("sqrt(x + 1)").unwrap();
+		let term = mexprp_parse(raw.as_ptr(), ctx, &mut status);
+		assert_eq!(status, MEXPRP_OK);
+		let mut out = [0.0; 2];
+		let mut len = 0;
+		assert_eq!(mexprp_eval_f64(term, ctx, out.as_mut_ptr(), 2, &mut len), MEXPRP_OK);
+		assert_eq!((len, out), (2, [2.0, -2.0]));
+		assert_eq!(mexprp_eval_f64(term, ctx, out.as_mut_ptr(), 1, &mut len), MEXPRP_BUFFER_TOO_SMALL);
+		mexprp_term_free(term);
+
+		let raw = CString::new("2 * (3").unwrap();
+		assert!(mexprp_parse(raw.as_ptr(), ptr::null(), &mut status).is_null());
+		assert_eq!(status, MEXPRP_PARSE_ERROR);
+		assert!(!CStr::from_ptr(mexprp_last_error()).to_str().unwrap().is_empty());
+
+		let raw = CString::new("y").unwrap();
+		let term = mexprp_parse(raw.as_ptr(), ctx, ptr::null_mut());
+		assert_eq!(mexprp_eval_f64(term, ctx, out.as_mut_ptr(), 2, &mut len), MEXPRP_MATH_ERROR);
+		mexprp_term_free(term);
+
+		// A panic in a custom function is caught instead of unwinding into the caller
+		(*ctx).set_func("boom", |_: &[Term<f64>], _: &Context<f64>| -> Calculation<f64> { panic!("boom") });
+		let raw = CString::new("boom()").unwrap();
+		let term = mexprp_parse(raw.as_ptr(), ctx, ptr::null_mut());
+		assert_eq!(mexprp_eval_f64(term, ctx, out.as_mut_ptr(), 2, &mut len), MEXPRP_PANIC);
+		mexprp_term_free(term);
+		mexprp_context_free(ctx);
+	}
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn wasm() {
+	use crate::wasm::Calculator;
+
+	let mut calc = Calculator::new();
+	calc.set_var("x", 3.0);
+	assert_eq!(calc.eval("sqrt(x + 1)").unwrap(), vec![2.0, -2.0]);
+	calc.load_definitions("f(a) = a * x").unwrap();
+	let expr = calc.parse("f(2)").unwrap();
+	assert_eq!(expr.eval(&calc).unwrap(), vec![6.0]);
+
+	let e = calc.eval("2 * (3").unwrap_err();
+	assert_eq!(e.code, "parse.mismatched_parentheses");
+	assert_eq!((e.start, e.end), (Some(4), Some(5)));
+	let e = calc.eval("y").unwrap_err();
+	assert_eq!((e.code.as_str(), e.start), ("math.undefined_variable", None));
+}
+
+#[test]
+fn no_panics() {
+	/// A tiny xorshift random number generator, so the inputs are the same every run
+	struct Rng(u64);
+	impl Rng {
+		fn below(&mut self, n: usize) -> usize {
+			self.0 ^= self.0 << 13;
+			self.0 ^= self.0 >> 7;
+			self.0 ^= self.0 << 17;
+			(self.0 % n as u64) as usize
+		}
+	}
+
+	/// Generate a random (mostly) valid expression
+	fn gen(rng: &mut Rng, depth: u32) -> String {
+		let funcs = ["sin", "max", "sqrt", "det", "inv", "solve", "len", "norm", "cross", "dot", "clamp", "log"];
+		let atoms = ["0", "1", "2.5", "99999999999999999999", "x", "pi", "y"];
+		if depth > 4 {
+			return atoms[rng.below(atoms.len())].to_string();
+		}
+		let list = |rng: &mut Rng| (0..rng.below(4)).map(|_| gen(rng, depth + 1)).collect::<Vec<String>>().join(", ");
+		match rng.below(10) {
+			0 | 1 => atoms[rng.below(atoms.len())].to_string(),
+			2 => format!("{}({})", funcs[rng.below(funcs.len())], list(rng)),
+			3 | 4 => format!("{}{}{}", gen(rng, depth + 1), ["+", "-", "*", "/", "^", "±"][rng.below(6)], gen(rng, depth + 1)),
+			5 => format!("{}{}", ["-", "±"][rng.below(2)], gen(rng, depth + 1)),
+			6 => format!("{}{}", gen(rng, depth + 1), ["!", "%"][rng.below(2)]),
+			7 => format!("({})", gen(rng, depth + 1)),
+			8 => format!("[{}]", list(rng)),
+			_ => format!("{}[{}]", gen(rng, depth + 1), gen(rng, depth + 1)),
+		}
+	}
+
+	let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 2.0);
+	for _ in 0..5000 {
+		let mut raw: Vec<char> = gen(&mut rng, 0).chars().collect();
+		// Break some of the expressions
+		if rng.below(3) == 0 {
+			let i = rng.below(raw.len());
+			match rng.below(3) {
+				0 => drop(raw.remove(i)),
+				1 => raw.insert(i, "()[]{},+ $".chars().nth(rng.below(10)).unwrap()),
+				_ => raw.truncate(i),
+			}
+		}
+		let raw: String = raw.into_iter().collect();
+
+		let _ = Expression::check(&raw, &ctx);
+		if let Ok(term) = Term::parse_ctx(&raw, &ctx) {
+			let _ = term.eval_ctx(&ctx);
+			let _ = term.fold_constants(&ctx);
+		}
+	}
+}
+
+#[test]
+fn deep_clone() {
+	use std::sync::Arc;
+
+	let expr: Expression<f64> = Expression::parse("2 * (3 + sin(4 - 1))").unwrap();
+	let (shallow, deep) = (expr.clone(), expr.deep_clone());
+	assert_eq!(deep.term, expr.term);
+	assert_eq!(deep.to_string(), expr.to_string());
+	assert!(eq(deep.eval().unwrap().unwrap_single(), 6.0 + 2.0 * 3f64.sin()));
+	match (&expr.term, &shallow.term, &deep.term) {
+		(Term::Operation(a), Term::Operation(b), Term::Operation(c)) => {
+			assert!(Arc::ptr_eq(a, b));
+			assert!(!Arc::ptr_eq(a, c));
+		}
+		_ => panic!("expected operations"),
+	}
+}
+
+#[test]
+fn limits() {
+	use crate::{MathError, ParseError};
+
+	let deep = format!("{}1{}", "(".repeat(20_000), ")".repeat(20_000));
+	assert!(matches!(Term::<f64>::parse(&deep), Err(ParseError::TooDeep)));
+	let long = "1+".repeat(100_000) + "1";
+	assert!(matches!(Term::<f64>::parse(&long), Err(ParseError::TooLarge)));
+	let sum = "1+".repeat(300) + "1";
+	assert!(matches!(Term::<f64>::parse(&sum), Err(ParseError::TooDeep)));
+
+	// Just within the limits
+	let nested = format!("{}1{}", "(-".repeat(100), ")".repeat(100));
+	assert!(eq(eval::<f64>(&nested).unwrap().unwrap_single(), 1.0));
+	let sum = "1+".repeat(200) + "1";
+	assert!(eq(eval::<f64>(&sum).unwrap().unwrap_single(), 201.0));
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", Term::parse("x + 1").unwrap());
+	assert!(matches!(Term::parse_ctx("x", &ctx).unwrap().eval_ctx(&ctx), Err(MathError::CircularDefinition { .. })));
+}
+
+#[test]
+fn budget() {
+	use crate::{CancelToken, MathError};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.cfg.max_steps = Some(100);
+	let many = Term::parse_ctx(&distinct_roots(8), &ctx).unwrap();
+	assert!(matches!(many.eval_ctx(&ctx), Err(MathError::Cancelled)));
+	let few = Term::parse_ctx(&distinct_roots(3), &ctx).unwrap();
+	assert_eq!(few.eval_ctx(&ctx).unwrap().to_vec().len(), 8);
+
+	let token = CancelToken::new();
+	ctx.set_cancel_token(token.clone());
+	assert!(few.eval_ctx(&ctx).is_ok());
+	token.cancel();
+	assert!(matches!(few.eval_ctx(&ctx), Err(MathError::Cancelled)));
+	token.reset();
+	assert!(few.eval_ctx(&ctx).is_ok());
+}
+
+#[test]
+fn max_answers() {
+	use crate::MathError;
+
+	let ctx: Context<f64> = Context::new();
+	let term = Term::parse_ctx(&distinct_roots(11), &ctx).unwrap();
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::TooManyAnswers)));
+	let term = Term::parse_ctx(&distinct_roots(10), &ctx).unwrap();
+	assert_eq!(term.eval_ctx(&ctx).unwrap().to_vec().len(), 1024);
+
+	// Equal answers are only kept once
+	let term = Term::parse_ctx(&("sqrt(4)+".repeat(20) + "0"), &ctx).unwrap();
+	assert_eq!(term.eval_ctx(&ctx).unwrap().to_vec().len(), 21);
+
+	// Operations stop as soon as they have too many answers instead of building every combination
+	let raw = format!("({}) * ({})", distinct_roots(10), distinct_roots(10));
+	let term = Term::parse_ctx(&raw, &ctx).unwrap();
+	let start = ::std::time::Instant::now();
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::TooManyAnswers)));
+	assert!(start.elapsed() < ::std::time::Duration::from_secs(1));
+	assert!(matches!(Answer::Multiple(vec![1.0, 2.0]).op_ctx(&Answer::Multiple(vec![3.0, 4.0]), &Context::new(), |a, b| Ok(Answer::Single(a * b))), Ok(_)));
+	let mut small: Context<f64> = Context::new();
+	small.cfg.max_answers = 3;
+	assert!(matches!(Answer::Multiple(vec![1.0, 2.0]).op_ctx(&Answer::Multiple(vec![3.0, 4.0]), &small, |a, b| Ok(Answer::Single(a * b))), Err(MathError::TooManyAnswers)));
+}
+
+#[test]
+fn dedup() {
+	let mut ctx: Context<f64> = Context::new();
+	let zero = Term::parse_ctx("sqrt(0)", &ctx).unwrap();
+	assert_eq!(zero.eval_ctx(&ctx).unwrap().to_vec().len(), 2);
+	assert_eq!(zero.eval_ctx(&ctx).unwrap().dedup(&ctx), Answer::Single(0.0));
+	let answer = Answer::Multiple(vec![1.0, 2.0, 1.0]).dedup(&ctx);
+	assert_eq!(answer, Answer::Multiple(vec![1.0, 2.0]));
+
+	ctx.cfg.dedup_answers = true;
+	assert_eq!(zero.eval_ctx(&ctx).unwrap(), Answer::Single(0.0));
+	let term = Term::parse_ctx("[sqrt(0), sqrt(4)]", &ctx).unwrap();
+	assert_eq!(term.eval_ctx(&ctx).unwrap().unlabel(), Answer::Vector(vec![Answer::Single(0.0), Answer::Multiple(vec![2.0, -2.0])]));
+}
+
+#[test]
+fn format() {
+	use crate::{AnswerFormat, Notation};
+	let mut fmt = AnswerFormat::new();
+	assert_eq!(Answer::Single(1234.5).format(&fmt), "1234.5");
+	assert_eq!(Answer::Single(-0.000001).format(&fmt), "-1e-6");
+	assert_eq!(Answer::Multiple(vec![2.0, -2.0]).format(&fmt), "{2, -2}");
+	fmt.thousands_separator = Some(',');
+	assert_eq!(Answer::Single(1234567.0).format(&fmt), "1,234,567");
+	fmt.precision = Some(3);
+	assert_eq!(Answer::Single(3.14159).format(&fmt), "3.14");
+	assert_eq!(Answer::Single(2.0).format(&fmt), "2");
+	fmt.trim_zeros = false;
+	assert_eq!(Answer::Single(2.0).format(&fmt), "2.00");
+	fmt.notation = Notation::Scientific;
+	assert_eq!(Answer::Single(1234.5).format(&fmt), "1.23e3");
+	let mut fmt = AnswerFormat::new();
+	fmt.radix = 16;
+	assert_eq!(Answer::Single(255.5).format(&fmt), "ff.8");
+	fmt.radix = 2;
+	assert_eq!(Answer::Vector(vec![Answer::Single(5.0), Answer::Single(-0.25)]).format(&fmt), "[101, -0.01]");
+	let c = ComplexFloat { r: 1.0, i: 0.5 };
+	assert_eq!(Answer::Single(c).format(&AnswerFormat::new()), "(1 + 0.5i)");
+}
+
+#[test]
+fn to_fraction() {
+	let frac = |raw: &str| eval::<f64>(raw).unwrap().to_vec();
+	assert_eq!(frac("to_fraction(0.75, 100)"), vec![3.0, 4.0]);
+	assert_eq!(frac("to_fraction(pi, 1000)"), vec![355.0, 113.0]);
+	assert_eq!(frac("to_fraction(pi, 100)"), vec![311.0, 99.0]);
+	assert_eq!(frac("to_fraction(-1/3, 10)"), vec![-1.0, 3.0]);
+	assert_eq!(frac("to_fraction(2, 5)"), vec![2.0, 1.0]);
+	assert!(eval::<f64>("to_fraction(0.5, 0)").is_err());
+}
+
+#[test]
+fn radix() {
+	use crate::AnswerFormat;
+	let a: f64 = eval("0xff + 0o17 + 0b101").unwrap().unwrap_single();
+	assert_eq!(a, 275.0);
+	let fmt = AnswerFormat::new();
+	let format = |raw: &str| Expression::<f64>::parse(raw).unwrap().eval_format(&fmt).unwrap();
+	assert_eq!(format("hex(255)"), "ff");
+	assert_eq!(format("bin(0xa)"), "1010");
+	assert_eq!(format("oct(64)"), "100");
+	assert_eq!(format("base(35, 36)"), "z");
+	assert_eq!(format("hex(255) + 1"), "256");
+	assert!(eval::<f64>("base(10, 37)").is_err());
+}
+
+#[test]
+fn bitwise() {
+	use crate::{EvalError, MathError};
+	let a: f64 = eval("0b1100 & 0b1010").unwrap().unwrap_single();
+	assert_eq!(a, 8.0);
+	let a: f64 = eval("0b1100 | 0b1010").unwrap().unwrap_single();
+	assert_eq!(a, 14.0);
+	let a: f64 = eval("xor(12, 10)").unwrap().unwrap_single();
+	assert_eq!(a, 6.0);
+	let a: f64 = eval("1 << 2 + 1").unwrap().unwrap_single();
+	assert_eq!(a, 8.0);
+	let a: f64 = eval("-16 >> 2").unwrap().unwrap_single();
+	assert_eq!(a, -4.0);
+	let a: f64 = eval("~5 | 1 & 3").unwrap().unwrap_single();
+	assert_eq!(a, -5.0);
+	assert!(matches!(eval::<f64>("1.5 & 1"), Err(EvalError::MathError { error: MathError::NotAnInteger })));
+	assert!(matches!(eval::<f64>("1 << 64"), Err(EvalError::MathError { error: MathError::IncorrectArguments })));
+}
+
+#[test]
+fn session() {
+	use crate::Session;
+	let mut session: Session<f64> = Session::new();
+	assert!(session.eval("ans").is_err());
+	assert_eq!(session.eval("2 + 3").unwrap(), Answer::Single(5.0));
+	assert_eq!(session.eval("ans * 2").unwrap(), Answer::Single(10.0));
+	assert_eq!(session.eval("ans(2) + ans() + ans").unwrap(), Answer::Single(25.0));
+	assert!(session.eval("ans(5)").is_err());
+	assert!(session.eval("1 / x").is_err());
+	assert_eq!(session.history.len(), 3);
+	assert_eq!(session.history.get(3), Some(&Answer::Single(5.0)));
+	session.history.limit = 2;
+	session.eval("ans(3)").unwrap();
+	assert_eq!(session.history.len(), 2);
+	session.clear();
+	assert!(session.eval("ans").is_err());
+}
+
+#[test]
+fn labels() {
+	use crate::Label;
+	let root: Answer<f64> = eval("sqrt(9)").unwrap();
+	assert_eq!(root, Answer::Labeled(vec![(Label::Principal, 3.0), (Label::Negative, -3.0)]));
+	assert_eq!(root.principal(), Some(&3.0));
+	assert_eq!(root.labeled(Label::Negative), Some(&-3.0));
+	assert_eq!(format!("{}", root), "{3, -3}");
+
+	// Labels are kept through operations with single answers
+	let shifted: Answer<f64> = eval("1 - sqrt(9)").unwrap();
+	assert_eq!(shifted.principal(), Some(&-2.0));
+	assert_eq!(shifted.labeled(Label::Negative), Some(&4.0));
+
+	// But not between multiple answers, where the principal value is still first
+	let sum: Answer<f64> = eval("sqrt(4) + sqrt(9)").unwrap();
+	assert_eq!(sum, Answer::Multiple(vec![5.0, -1.0, 1.0, -5.0]));
+	assert_eq!(sum.principal(), Some(&5.0));
+	assert_eq!(sum.labeled(Label::Principal), None);
+}
+
+#[test]
+fn single_valued() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.cfg.single_valued = true;
+	ctx.set_func("both", |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+		let a = args[0].eval_ctx(ctx)?.unwrap_single();
+		Ok(Answer::Multiple(vec![a, -a]))
+	});
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap();
+	assert_eq!(eval("sqrt(9)"), Answer::Single(3.0));
+	assert_eq!(eval("5 ± 2"), Answer::Single(7.0));
+	assert_eq!(eval("±2"), Answer::Single(2.0));
+	assert_eq!(eval("both(4) + both(1)"), Answer::Single(5.0));
+	assert_eq!(eval("[sqrt(4), 1]"), Answer::Vector(vec![Answer::Single(2.0), Answer::Single(1.0)]));
+}
+
+#[test]
+fn factorial() {
+	use crate::num::Operation;
+	let a: f64 = eval("5! + 0!").unwrap().unwrap_single();
+	assert_eq!(a, 121.0);
+	let a: f64 = eval("0.5!").unwrap().unwrap_single();
+	assert!(eq(a, 0.886_226_925_452_758));
+	let a: f64 = eval("(-0.5)!").unwrap().unwrap_single();
+	assert!(eq(a, 1.772_453_850_905_516));
+	let a: f64 = eval("(-1)!").unwrap().unwrap_single();
+	assert!(a.is_nan());
+
+	assert!(f64::supports(Operation::Fact));
+	assert!(f64::supports(Operation::BitXor));
+	assert!(ComplexFloat::supports(Operation::Add));
+	assert!(!ComplexFloat::supports(Operation::Fact));
+	assert!(!ComplexFloat::supports(Operation::Sin));
+}
+
+#[test]
+fn double_factorial() {
+	use crate::num::Operation;
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 3.0);
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+	assert_eq!(eval("5!!"), 15.0);
+	assert_eq!(eval("6!! + 0!! + (-1)!!"), 50.0);
+	assert!(eval("(-3)!!").is_nan());
+	assert!(eval("2.5!!").is_nan());
+	assert!(eval("1000!!").is_infinite());
+
+	// Postfix operators chain and bind tighter than powers and implicit multiplication
+	assert_eq!(Term::parse_ctx("x!!!", &ctx).unwrap().to_string(), "((x!!)!)");
+	assert_eq!(eval("x!!!"), 6.0);
+	assert_eq!(eval("x! !"), 720.0);
+	assert_eq!(eval("3!!^2"), 9.0);
+	assert_eq!(eval("2x!!"), 6.0);
+	assert_eq!(eval("2^3!!"), 8.0);
+	assert_eq!(eval("-5!! x"), -45.0);
+
+	assert!(f64::supports(Operation::DoubleFact));
+	assert!(!ComplexFloat::supports(Operation::DoubleFact));
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+		use crate::MathError;
+
+		let ctx: Context<Decimal> = Context::new();
+		let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx);
+		assert_eq!(eval("7!!").unwrap().unwrap_single().to_string(), "105");
+		assert!(matches!(eval("2.5!!"), Err(MathError::NotAnInteger)));
+		assert!(matches!(eval("(-2)!!"), Err(MathError::IncorrectArguments)));
+	}
+}
+
+#[test]
+fn combinatorics() {
+	use crate::num::Operation;
+	use crate::MathError;
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 3.0);
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx);
+	assert_eq!(eval("ncr(5, 2)").unwrap(), Answer::Single(10.0));
+	assert_eq!(eval("binom(10, 3) + npr(5, 2)").unwrap(), Answer::Single(140.0));
+	assert_eq!(eval("fact(5) - fact(x)").unwrap(), Answer::Single(114.0));
+	assert_eq!(eval("ncr(3, 5) + npr(3, 5)").unwrap(), Answer::Single(0.0));
+	assert_eq!(eval("ncr(7, 0) + ncr(7, 7)").unwrap(), Answer::Single(2.0));
+	assert_eq!(eval("ncr(50, 25)").unwrap(), Answer::Single(126_410_606_437_752.0));
+	assert_eq!(eval("ncr(x, [1, 2])").unwrap(), Answer::Vector(vec![Answer::Single(3.0), Answer::Single(3.0)]));
+
+	// The factorials would overflow, but the results don't
+	let big = eval("ncr(1000, 500)").unwrap().unwrap_single();
+	assert!((big / 2.702_882_409_454_365_5e299 - 1.0).abs() < 1e-12);
+	assert!(eq(eval("ncr(10^20, 2)").unwrap().unwrap_single() / 5e39, 1.0));
+	assert!(eval("ncr(2000, 1000)").unwrap().unwrap_single().is_infinite());
+	assert!(eval("npr(200, 180)").unwrap().unwrap_single().is_infinite());
+
+	assert!(matches!(eval("ncr(2.5, 1)"), Err(MathError::NotAnInteger)));
+	assert!(matches!(eval("npr(5, -1)"), Err(MathError::IncorrectArguments)));
+	assert!(f64::supports(Operation::Binomial));
+	assert!(!ComplexFloat::supports(Operation::Permutations));
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		let ctx: Context<Decimal> = Context::new();
+		let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single().to_string();
+		// Exact past the integers an f64 can represent
+		assert_eq!(eval("ncr(60, 30)"), "118264581564861424");
+		assert_eq!(eval("npr(30, 12)"), "41430393164160000");
+		assert_eq!(eval("fact(25)"), "15511210043330985984000000");
+	}
+}
+
+#[test]
+fn special_functions() {
+	use std::f64::consts::PI;
+	use crate::num::Operation;
+	let ctx: Context<f64> = Context::new();
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+	let close = |x: f64, y: f64| (x / y - 1.0).abs() < 1e-12;
+
+	assert_eq!(eval("gamma(5)"), 24.0);
+	assert!(close(eval("gamma(0.5)^2"), PI));
+	assert!(close(eval("gamma(-2.5)"), -0.945_308_720_482_941_9));
+	assert!(eval("gamma(0)").is_nan() && eval("gamma(-2)").is_nan());
+	// Non-integer factorials are the gamma function, up to the largest one that doesn't overflow
+	assert!(close(eval("2.5!"), eval("gamma(3.5)")));
+	assert!(close(eval("170.5!"), 9.483_367_566_824_801e307));
+	assert!(eval("172!").is_infinite());
+	// Integer factorials are exact as long as they fit in an f64
+	assert_eq!(eval("22!"), 1_124_000_727_777_607_680_000.0);
+	assert!(close(eval("170!"), 7.257_415_615_307_994e306));
+	assert!(eval("171!").is_infinite());
+	assert!(close(eval("lgamma(1000)"), 5_905.220_423_209_181));
+	assert_eq!(eval("lgamma(1) + lgamma(2)"), 0.0);
+	assert!(eval("lgamma(-3)").is_infinite());
+
+	assert_eq!(eval("erf(0)"), 0.0);
+	assert!(close(eval("erf(0.5)"), 0.520_499_877_813_046_5));
+	assert!(close(eval("erf(10^-10)"), 1.128_379_167_095_512_6e-10));
+	assert!(close(eval("erf(-2) + erfc(-2)"), 1.0));
+	assert!(close(eval("erfc(10)"), 2.088_487_583_762_545e-45));
+	assert!(close(eval("erfc(-3)"), 1.999_977_909_503_001_5));
+
+	assert!(close(eval("beta(2, 3)"), 1.0 / 12.0));
+	assert!(close(eval("beta(100, 100)"), 2.208_760_693_199_436_4e-61));
+
+	assert!(close(eval("zeta(2)"), PI * PI / 6.0));
+	assert!(close(eval("zeta(-1)"), -1.0 / 12.0));
+	assert_eq!(eval("zeta(0)"), -0.5);
+	assert_eq!(eval("zeta(-4)"), 0.0);
+	assert!(eval("zeta(1)").is_infinite());
+
+	assert!(f64::supports(Operation::Gamma) && f64::supports(Operation::Zeta));
+	assert!(!ComplexFloat::supports(Operation::Erf));
+
+	// Exact numbers have exact gamma functions of integers, and beta functions made of them
+	let ctx: Context<crate::num::Exact> = Context::new();
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+	assert_eq!(eval("gamma(21)").to_string(), "2432902008176640000");
+	assert_eq!(eval("beta(2, 3)").to_string(), "1/12");
+}
+
+#[test]
+fn capabilities() {
+	use crate::num::Operation;
+	let caps = f64::capabilities();
+	assert!(caps.contains(Operation::Sin) && caps.contains(Operation::Fact) && caps.contains(Operation::Shl));
+	let caps = ComplexFloat::capabilities();
+	assert!(caps.contains(Operation::Div));
+	assert!(!caps.contains(Operation::Sqrt));
+	// Integer powers are supported by every type that can multiply and divide
+	assert_eq!(caps.iter().collect::<Vec<_>>(), vec![Operation::Add, Operation::Sub, Operation::Mul, Operation::Div, Operation::Pow, Operation::Compare]);
+}
+
+#[test]
+fn convert() {
+	let ctx: Context<ComplexFloat> = Context::new();
+	let real: Answer<f64> = eval("[1.5, sqrt(4)]").unwrap();
+	let complex = real.convert(&ctx).unwrap();
+	assert_eq!(complex.to_vec(), vec![ComplexFloat::from(1.5), ComplexFloat::from(2.0), ComplexFloat::from(-2.0)]);
+
+	let complex: Answer<ComplexFloat> = eval("3 + 4i").unwrap();
+	assert_eq!(complex.convert(&Context::new()).unwrap(), Answer::Single(3.0));
+	let back = Answer::Single(ComplexFloat { r: 1.0, i: 2.0 }).convert(&ctx).unwrap();
+	assert_eq!(back, Answer::Single(ComplexFloat { r: 1.0, i: 2.0 }));
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn decimal() {
+	use crate::RoundingMode;
+	use crate::num::Decimal;
+	let d = |s: &str| s.parse::<Decimal>().unwrap();
+	let mut ctx: Context<Decimal> = Context::new();
+	let eval = |raw: &str, ctx: &Context<Decimal>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+
+	assert_eq!(eval("0.1 + 0.2", &ctx), d("0.3"));
+	assert_eq!(eval("1.10 * 3", &ctx).to_string(), "3.3");
+	assert_eq!(eval("-0.05 * 0.2", &ctx).to_string(), "-0.01");
+	assert_eq!(eval("1 / 8", &ctx), d("0.125"));
+	assert_eq!(eval("2 ^ -2 + 1.5 ^ 2", &ctx), d("2.5"));
+	assert_eq!(eval("5!", &ctx), d("120"));
+	assert_eq!(eval("floor(-2.5)", &ctx), d("-3"));
+	assert_eq!(eval("round(1.2345, 3)", &ctx), d("1.235"));
+	// The product of the mantissas doesn't fit in an i128, but the rounded product does
+	assert_eq!(eval("(1 / 3) * (1 / 3)", &ctx), d("0.1111111111111111111111111111"));
+	// Exact quotients don't need all of the decimal places
+	assert_eq!(eval("10^20 / 4", &ctx), d("25000000000000000000"));
+	assert_eq!(eval("10 / 0.01", &ctx), d("1000"));
+
+	ctx.cfg.decimal_places = 2;
+	assert_eq!(eval("2 / 3", &ctx), d("0.67"));
+	assert_eq!(eval("0.125 / 1", &ctx), d("0.13"));
+	assert_eq!(eval("round(2.5)", &ctx), d("3"));
+	ctx.cfg.rounding = RoundingMode::HalfEven;
+	assert_eq!(eval("0.125 / 1", &ctx), d("0.12"));
+	assert_eq!(eval("round(2.5)", &ctx), d("2"));
+	ctx.cfg.rounding = RoundingMode::ToZero;
+	assert_eq!(eval("-2 / 3", &ctx), d("-0.66"));
+	ctx.cfg.rounding = RoundingMode::Floor;
+	assert_eq!(eval("-2 / 3", &ctx), d("-0.67"));
+
+	assert_eq!(Decimal::new(12345, 2).unwrap().to_string(), "123.45");
+	assert!(d("1.5") < d("1.50001"));
+	assert!("1.2.3".parse::<Decimal>().is_err());
+	let big = Term::<Decimal>::parse("10 ^ 40").unwrap();
+	assert!(matches!(big.eval(), Err(crate::MathError::Overflow)));
+}
+
+#[test]
+fn rounding() {
+	use crate::RoundingMode;
+	let mut ctx: Context<f64> = Context::new();
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+
+	assert_eq!(eval("round(2.5)", &ctx), 3.0);
+	assert_eq!(eval("round(-2.5)", &ctx), -3.0);
+	assert_eq!(eval("round(3.14159, 2)", &ctx), 3.14);
+	assert_eq!(eval("round(1234, -2)", &ctx), 1200.0);
+	ctx.cfg.rounding = RoundingMode::HalfEven;
+	assert_eq!(eval("round(2.5)", &ctx), 2.0);
+	assert_eq!(eval("round(3.5)", &ctx), 4.0);
+	assert_eq!(eval("round(2.6)", &ctx), 3.0);
+	ctx.cfg.rounding = RoundingMode::ToZero;
+	assert_eq!(eval("round(-2.7)", &ctx), -2.0);
+	ctx.cfg.rounding = RoundingMode::Floor;
+	assert_eq!(eval("round(-2.1)", &ctx), -3.0);
+	ctx.cfg.rounding = RoundingMode::Ceil;
+	assert_eq!(eval("round(2.1)", &ctx), 3.0);
+	assert_eq!(eval("round(2.11, 1)", &ctx), 2.2);
+	assert!(Term::<f64>::parse("round(2, 0.5)").unwrap().eval().is_err());
+}
+
+#[test]
+fn separators() {
+	use crate::lexer::{tokenize_cfg, TokenKind};
+	let mut ctx: Context<f64> = Context::new();
+	ctx.cfg.decimal_separator = ',';
+	ctx.cfg.arg_separator = ';';
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap();
+
+	assert_eq!(eval("3,14 * 2"), Answer::Single(6.28));
+	assert_eq!(eval("max(1,5; 2)"), Answer::Single(2.0));
+	assert_eq!(eval("[0,5; 1][2]"), Answer::Single(1.0));
+	assert!(Term::parse_ctx("max(1, 2)", &ctx).is_err());
+	assert!(Term::<f64>::parse("3,14").is_err());
+
+	let tokens = tokenize_cfg("f(1,5; 2)", &ctx.cfg);
+	assert_eq!(tokens[2].text, "1,5");
+	assert_eq!(tokens[3].kind, TokenKind::Comma);
+}
+
+#[test]
+fn grouping() {
+	use crate::ParseError;
+
+	assert_eq!(eval::<f64>("{1 + 2} * 3").unwrap(), Answer::Single(9.0));
+	assert_eq!(eval::<f64>("2[3 + 4]").unwrap(), Answer::Single(14.0));
+	assert_eq!(eval::<f64>("2{3 + [4 - 1]}^2").unwrap(), Answer::Single(72.0));
+	assert_eq!(eval::<f64>("max{1, 2}").unwrap(), Answer::Single(2.0));
+	assert_eq!(eval::<f64>("len([[1, 2]])").unwrap(), Answer::Single(1.0));
+	assert!(matches!(Term::<f64>::parse("[1 + 2)"), Err(ParseError::MismatchedParentheses)));
+	assert!(matches!(Term::<f64>::parse("{1 + 2]"), Err(ParseError::MismatchedParentheses)));
+	assert!(matches!(Term::<f64>::parse("(1 + 2}"), Err(ParseError::MismatchedParentheses)));
+}
+
+#[test]
+fn zero_division() {
+	use crate::{MathError, ZeroDivision};
+	let mut ctx: Context<f64> = Context::new();
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx);
+
+	assert!(matches!(eval("1 / 0", &ctx), Err(MathError::DivideByZero)));
+	assert!(matches!(eval("0 ^ -2", &ctx), Err(MathError::DivideByZero)));
+	assert_eq!(eval("0 ^ 0", &ctx).unwrap(), Answer::Single(1.0));
+	ctx.cfg.zero_division = ZeroDivision::Infinity;
+	assert_eq!(eval("1 / 0", &ctx).unwrap(), Answer::Single(f64::INFINITY));
+	assert_eq!(eval("-1 / 0", &ctx).unwrap(), Answer::Single(f64::NEG_INFINITY));
+	assert_eq!(eval("0 ^ -2", &ctx).unwrap(), Answer::Single(f64::INFINITY));
+	assert!(eval("0 / 0", &ctx).unwrap().unwrap_single().is_nan());
+	ctx.cfg.zero_division = ZeroDivision::NaN;
+	assert!(eval("1 / 0", &ctx).unwrap().unwrap_single().is_nan());
+	assert!(eval("0 ^ 0", &ctx).unwrap().unwrap_single().is_nan());
+
+	let mut ctx: Context<ComplexFloat> = Context::new();
+	ctx.cfg.zero_division = ZeroDivision::Infinity;
+	let answer = Term::parse_ctx("-2 / 0", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+	assert_eq!(answer.r, f64::NEG_INFINITY);
+}
+
+#[test]
+fn nan_policy() {
+	use crate::{MathError, NanPolicy};
+	let mut ctx: Context<f64> = Context::new();
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx);
+
+	assert!(eval("asin(2) + 1", &ctx).unwrap().has_nan());
+	assert!(!eval("2 ^ 2000", &ctx).unwrap().is_finite());
+	assert!(eval("[1, 2]", &ctx).unwrap().is_finite());
+	ctx.cfg.nan_policy = NanPolicy::Error;
+	assert!(matches!(eval("asin(2) + 1", &ctx), Err(MathError::NaN)));
+	assert!(matches!(eval("sqrt(-1)", &ctx), Err(MathError::NaN)));
+	assert!(matches!(eval("(2 ^ 2000) - (2 ^ 2000)", &ctx), Err(MathError::NaN)));
+	assert_eq!(eval("sin(0)", &ctx).unwrap(), Answer::Single(0.0));
+}
+
+#[test]
+fn negative_sqrt() {
+	use crate::{DomainPolicy, MathError};
+	let mut ctx: Context<f64> = Context::new();
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx);
+
+	assert!(eval("sqrt(-4)", &ctx).unwrap().has_nan());
+	assert!(eval("log(-1, 10)", &ctx).unwrap().has_nan());
+	for &policy in [DomainPolicy::Error, DomainPolicy::PromoteComplex].iter() {
+		ctx.cfg.negative_sqrt = policy;
+		let err = eval("1 + sqrt(-4)", &ctx).unwrap_err();
+		assert!(matches!(err, MathError::DomainError { ref func, ref value } if func == "sqrt" && value == "-4"));
+		assert_eq!(err.code(), "math.domain_error");
+		assert!(matches!(eval("asin(2)", &ctx), Err(MathError::DomainError { .. })));
+		assert!(matches!(eval("acos(-1.5)", &ctx), Err(MathError::DomainError { .. })));
+		assert!(matches!(eval("log(-1, 10)", &ctx), Err(MathError::DomainError { .. })));
+		assert!(matches!(eval("log(8, -2)", &ctx), Err(MathError::DomainError { .. })));
+		// Numbers inside the domains are fine
+		assert_eq!(eval("sqrt(4)", &ctx).unwrap().principal(), Some(&2.0));
+		assert_eq!(eval("asin(1) * 2", &ctx).unwrap(), Answer::Single(::std::f64::consts::PI));
+		assert_eq!(eval("log(100, 10)", &ctx).unwrap(), Answer::Single(2.0));
+	}
+}
+
+#[test]
+fn domain_errors() {
+	use crate::{DomainPolicy, MathError};
+	let mut ctx: Context<f64> = Context::new();
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx);
+
+	assert_eq!(eval("nrt(27, 3)", &ctx).unwrap(), Answer::Single(3.0));
+	assert_eq!(eval("nrt(-32, 5)", &ctx).unwrap(), Answer::Single(-2.0));
+	assert!(eval("nrt(-16, 4)", &ctx).unwrap().has_nan());
+	ctx.cfg.negative_sqrt = DomainPolicy::Error;
+	assert_eq!(eval("asin(2)", &ctx).unwrap_err().to_string(), "asin is undefined for 2");
+	assert_eq!(eval("acos(-3)", &ctx).unwrap_err().to_string(), "acos is undefined for -3");
+	assert_eq!(eval("log(-0.5, 2)", &ctx).unwrap_err().to_string(), "log is undefined for -0.5");
+	assert_eq!(eval("nrt(-16, 4)", &ctx).unwrap_err().to_string(), "nrt is undefined for -16");
+	assert!(matches!(eval("nrt(-8, 1.5)", &ctx), Err(MathError::DomainError { .. })));
+	assert_eq!(eval("nrt(-8, 3)", &ctx).unwrap(), Answer::Single(-2.0));
+}
+
+#[test]
+fn canonicalize() {
+	let canonical = |raw: &str| Term::<f64>::parse(raw).unwrap().canonicalize();
+
+	assert_eq!(canonical("b + a"), canonical("a + b"));
+	assert_eq!(canonical("(a + b) + c"), canonical("c + (b + a)"));
+	assert_eq!(canonical("a - b"), canonical("-b + a"));
+	assert_eq!(canonical("2 * x * y"), canonical("y * (x * 2)"));
+	assert_eq!(canonical("sin(b * a) & 3"), canonical("3 & sin(a * b)"));
+	assert_ne!(canonical("a - b"), canonical("b - a"));
+	assert_ne!(canonical("a / b"), canonical("b / a"));
+
+	let mut ctx = Context::new();
+	ctx.set_var("a", 2.0);
+	ctx.set_var("b", 5.0);
+	let term = Term::parse_ctx("(a - b) * (b + 3 - a)", &ctx).unwrap();
+	assert_eq!(term.canonicalize().eval_ctx(&ctx).unwrap(), term.eval_ctx(&ctx).unwrap());
+}
+
+#[test]
+fn probably_equivalent() {
+	let ctx: Context<f64> = Context::new();
+	let term = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap();
+
+	assert!(term("(x + 1)^2").probably_equivalent(&term("x^2 + 2x + 1"), &["x"], &ctx, 20));
+	assert!(term("sin(x)^2 + cos(y)^2").probably_equivalent(&term("1 - cos(x)^2 + cos(y)^2"), &["x", "y"], &ctx, 20));
+	assert!(!term("(x + 1)^2").probably_equivalent(&term("x^2 + 1"), &["x"], &ctx, 20));
+	assert!(!term("x / x").probably_equivalent(&term("1"), &["y"], &ctx, 20));
+	// Neither can be evaluated anywhere
+	assert!(!term("1 / 0").probably_equivalent(&term("2 / 0"), &[], &ctx, 20));
+}
+
+#[test]
+fn partial_eval() {
+	use crate::MathError;
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("a", 3.0);
+	ctx.set_var("k", Term::parse("2 * y").unwrap());
+
+	let term = Term::parse_ctx("a * x + sin(0) + k", &ctx).unwrap();
+	let partial = term.partial_eval_ctx(&ctx).unwrap();
+	assert_eq!(partial, Term::parse("3 * x + 0 + 2 * y").unwrap());
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::UndefinedVariable { .. })));
+
+	let mut runtime: Context<f64> = Context::new();
+	runtime.set_var("x", 2.0);
+	runtime.set_var("y", 1.0);
+	assert_eq!(partial.eval_ctx(&runtime).unwrap(), Answer::Single(8.0));
+
+	let unknown = Term::parse_ctx("f(a + 1) + 1 / 0", &ctx).unwrap();
+	assert!(matches!(unknown.partial_eval_ctx(&ctx), Err(MathError::DivideByZero)));
+	ctx.cfg.implicit_multiplication = false;
+	let unknown = Term::parse_ctx("f(a + 1)", &ctx).unwrap();
+	assert_eq!(unknown.partial_eval_ctx(&ctx).unwrap().to_string(), "f(4)");
+}
+
+#[test]
+fn builder() {
+	let x = || Term::<f64>::var("x");
+	let term = -(x() * Term::num(2.0) - Term::func("max", vec![x(), Term::num(10.0)])) / Term::pow(x(), Term::num(0.5));
+	assert_eq!(term, Term::parse("-(x * 2 - max(x, 10)) / x ^ 0.5").unwrap());
+
+	let mut ctx = Context::new();
+	ctx.set_var("x", 4.0);
+	assert_eq!(term.eval_ctx(&ctx).unwrap(), Answer::Single(1.0));
+	assert_eq!(Term::add(x(), Term::num(1.0)).to_string(), "(x + 1)");
+}
+
+#[test]
+fn answer_ops() {
+	use crate::MathError;
+	let a = Answer::Single(6.0);
+	let v = Answer::Vector(vec![Answer::Single(1.0), Answer::Single(2.0)]);
+
+	assert_eq!((&a + &Answer::Single(2.0)).unwrap(), Answer::Single(8.0));
+	assert_eq!((a.clone() / Answer::Single(4.0)).unwrap(), Answer::Single(1.5));
+	assert_eq!((&v * &a).unwrap(), Answer::Vector(vec![Answer::Single(6.0), Answer::Single(12.0)]));
+	assert_eq!((-Answer::Multiple(vec![1.0, -2.0])).unwrap(), Answer::Multiple(vec![-1.0, 2.0]));
+	assert!(matches!(a / Answer::Single(0.0), Err(MathError::DivideByZero)));
+
+	let x = Term::<f64>::var("x");
+	assert_eq!((&x - &x).to_string(), "(x - x)");
+	assert_eq!((-&x).to_string(), "(-x)");
+}
+
+#[test]
+fn from_str() {
+	use std::convert::TryFrom;
+
+	let term: Term<f64> = "2x + 1".parse().unwrap();
+	assert_eq!(term, Term::parse("2 * x + 1").unwrap());
+	let expr: Expression<f64> = "3 * 4".parse().unwrap();
+	assert_eq!(expr.eval().unwrap(), Answer::Single(12.0));
+	assert!(Term::<f64>::try_from("(1 +").is_err());
+	assert_eq!(Expression::<f64>::try_from("sin(0)").unwrap().string, "sin(0)");
+}
+
+#[test]
+fn context_builder() {
+	use std::collections::HashMap;
+
+	let ctx: Context<f64> = Context::builder()
+		.var("a", 2.0)
+		.func("double", |args: &[Term<f64>], ctx: &Context<f64>| args[0].eval_ctx(ctx)?.op(&Answer::Single(2.0), |a, b| a.mul(b, ctx)))
+		.sqrt_both(false)
+		.precision(128)
+		.build();
+	assert_eq!(ctx.cfg.precision, 128);
+	assert_eq!(Term::parse_ctx("double(a) + sqrt(4)", &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(6.0));
+
+	let mut params = HashMap::new();
+	params.insert("x".to_string(), 3.0);
+	params.insert("y".to_string(), 4.0);
+	let mut ctx = Context::from_map(params);
+	assert_eq!(Term::parse_ctx("x * y", &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(12.0));
+	ctx.extend_vars(vec![("x".to_string(), 5.0)]);
+	assert_eq!(Term::parse_ctx("x * y", &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(20.0));
+}
+
+#[test]
+fn context_introspection() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 2.0);
+	ctx.set_var("y", 3.0);
+
+	assert!(ctx.has_var("x") && ctx.has_func("sin") && !ctx.has_func("x"));
+	assert_eq!(ctx.get_var("x"), Some(&Term::from(2.0)));
+	let mut names: Vec<&str> = ctx.iter_vars().map(|(name, _)| name).collect();
+	names.sort();
+	assert_eq!(names, vec!["e", "i", "pi", "x", "y"]);
+	assert!(ctx.iter_funcs().any(|(name, _)| name == "max"));
+
+	assert_eq!(ctx.remove_var("x"), Some(Term::from(2.0)));
+	assert_eq!(ctx.remove_var("x"), None);
+	assert!(ctx.remove_func("sin").is_some());
+	assert!(Term::parse_ctx("sin(y)", &ctx).unwrap().eval_ctx(&ctx).is_err());
+
+	ctx.clear();
+	assert_eq!(ctx.iter_vars().count() + ctx.iter_funcs().count(), 0);
+}
+
+#[test]
+fn function_packs() {
+	use std::sync::Arc;
+	use crate::{Func, FunctionPack};
+	use crate::lexer::{tokenize, TokenKind};
+
+	struct Stats;
+	impl FunctionPack<f64> for Stats {
+		fn namespace(&self) -> &str {
+			"stats"
+		}
+
+		fn funcs(&self) -> Vec<(String, Arc<dyn Func<f64>>)> {
+			let mean = |args: &[Term<f64>], ctx: &Context<f64>| {
+				let mut sum = 0.0;
+				for arg in args {
+					sum += arg.eval_ctx(ctx)?.unwrap_single();
+				}
+				Ok(Answer::Single(sum / args.len() as f64))
+			};
+			vec![("mean".to_string(), Arc::new(mean))]
+		}
+	}
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.install(&Stats);
+	ctx.install_as("s", &Stats);
+	ctx.set_func_ns("geom", "double", |args: &[Term<f64>], ctx: &Context<f64>| args[0].eval_ctx(ctx)?.unop(|x| Ok(Answer::Single(x * 2.0))));
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap();
+
+	assert_eq!(eval("stats.mean(1, 2, 6)"), Answer::Single(3.0));
+	assert_eq!(eval("s.mean(4) + geom.double(1.5)"), Answer::Single(7.0));
+	assert_eq!(ctx.complete("stats.").len(), 1);
+	assert!(Term::parse_ctx("stats.(1)", &ctx).is_err());
+
+	let tokens = tokenize("geom.double(2.5)");
+	assert_eq!(tokens[0].text, "geom.double");
+	assert_eq!(tokens[2].kind, TokenKind::Number);
+}
+
+#[test]
+fn builtin_sets() {
+	let minimal: Context<f64> = Context::minimal();
+	let scientific: Context<f64> = Context::scientific();
+	let full: Context<f64> = Context::full();
+
+	assert!(minimal.has_func("abs") && minimal.has_func("ceil") && minimal.has_func("clamp"));
+	assert!(!minimal.has_func("sin") && !minimal.has_var("pi"));
+	assert!(scientific.has_func("abs") && scientific.has_func("sin") && scientific.has_var("pi"));
+	assert!(!scientific.has_func("det") && !scientific.has_func("hex"));
+	assert!(full.has_func("sin") && full.has_func("det") && full.has_func("hex"));
+	assert_eq!(Context::<f64>::new().iter_funcs().count(), full.iter_funcs().count());
+
+	assert_eq!(Term::parse_ctx("ceil(1.2)", &minimal).unwrap().eval_ctx(&minimal).unwrap(), Answer::Single(2.0));
+	let sin = Term::parse_ctx("sin(0)", &minimal);
+	assert!(sin.is_err() || sin.unwrap().eval_ctx(&minimal).is_err());
+}
+
+#[test]
+fn func_table() {
+	use crate::{Builtins, FuncTable};
+
+	let names: Vec<&str> = <Builtins as FuncTable<f64>>::FUNCS.iter().map(|&(name, _)| name).collect();
+	let mut sorted = names.clone();
+	sorted.sort();
+	assert_eq!(names, sorted);
+	let full: Context<f64> = Context::full();
+	assert_eq!(names.len(), full.iter_funcs().count());
+
+	let mut ctx: Context<f64> = Context::with_table::<Builtins>();
+	assert_eq!(ctx.iter_funcs().count(), 0);
+	assert!(ctx.has_func("sqrt") && ctx.has_func("to_fraction") && !ctx.has_func("sq"));
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap();
+	assert_eq!(eval("max(abs(-3), floor(2.5))", &ctx), Answer::Single(3.0));
+	assert!(Term::<f64>::parse_ctx("sin(1, 2)", &ctx).is_err());
+
+	let names: Vec<String> = ctx.complete("s").into_iter().map(|c| c.name).collect();
+	assert_eq!(names, vec!["sign", "sin", "solve", "sqrt", "step"]);
+
+	// Functions in the map are looked up before the table
+	ctx.set_func("abs", |_: &[Term<f64>], _: &Context<f64>| Ok(Answer::Single(0.0)));
+	assert_eq!(eval("abs(-3)", &ctx), Answer::Single(0.0));
+	assert_eq!(ctx.complete("abs").len(), 1);
+
+	ctx.set_var("x", 0.0);
+	let bound = Term::parse_ctx("step(y) + abs(y)", &ctx).unwrap().bind(&ctx).unwrap();
+	assert_eq!(bound.eval(&[2.0]).unwrap(), Answer::Single(1.0));
+
+	ctx.clear();
+	assert!(!ctx.has_func("sqrt"));
+}
+
+#[cfg(feature = "plot")]
+#[test]
+fn plot() {
+	use crate::plot;
+
+	let term: Term<f64> = Term::parse("1 / x").unwrap();
+	let points = plot::sample(&term, "x", -1.0..=1.0, 5);
+	assert_eq!(points, vec![(-1.0, Some(-1.0)), (-0.5, Some(-2.0)), (0.0, None), (0.5, Some(2.0)), (1.0, Some(1.0))]);
+
+	let text = plot::render_text(&points, 5, 5);
+	let lines: Vec<&str> = text.lines().collect();
+	assert_eq!(lines.len(), 6);
+	assert_eq!(lines[0], " 2   |*");
+	assert_eq!(lines[2], "   --+--");
+	assert_eq!(lines[4], "-2  *|");
+	assert_eq!(lines[5], "   -1  1");
+
+	// The line is broken where 1/x has no value
+	let svg = plot::render_svg(&points, 200, 100);
+	assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+	assert_eq!(svg.matches("<polyline").count(), 2);
+
+	let term: Term<f64> = Term::parse("sqrt(x)").unwrap();
+	assert_eq!(plot::sample(&term, "x", 4.0..=4.0, 1), vec![(4.0, Some(2.0))]);
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time_pack() {
+	use crate::time::{Quantity, TimePack};
+	use crate::MathError;
+
+	let mut ctx: Context<Quantity<f64>> = Context::new();
+	ctx.install(&TimePack);
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).map(|a| a.unwrap_single());
+
+	assert_eq!(eval("hours(2) + minutes(30)").unwrap(), Quantity::seconds(9000.0));
+	assert_eq!(eval("hours(2) + minutes(30)").unwrap().to_string(), "2h 30m");
+	assert_eq!(eval("days(1) + hours(2) + seconds(5.5)").unwrap().to_string(), "1d 2h 5.5s");
+	assert_eq!(eval("minutes(5) - hours(1)").unwrap().to_string(), "-55m");
+	assert_eq!(eval("hours(0)").unwrap().to_string(), "0s");
+	assert_eq!(eval("hours(3) * 2").unwrap(), Quantity::seconds(21600.0));
+	assert_eq!(eval("hours(5) / hours(1)").unwrap(), Quantity::number(5.0));
+	assert_eq!(eval("in_minutes(hours(2) + minutes(30))").unwrap(), Quantity::number(150.0));
+	assert_eq!(eval("in_weeks(days(14))").unwrap(), Quantity::number(2.0));
+	assert_eq!(eval("max(hours(1), minutes(90))").unwrap(), Quantity::seconds(5400.0));
+	assert_eq!(eval("hours(1)^2").unwrap().to_string(), "12960000 s^2");
+	let root = Term::parse_ctx("sqrt(hours(1)^2)", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+	assert_eq!(root.principal(), Some(&Quantity::seconds(3600.0)));
+
+	// Time spans and plain numbers can't be mixed where it doesn't make sense
+	assert!(matches!(eval("hours(2) + 30"), Err(MathError::UnitMismatch { .. })));
+	assert!(matches!(eval("sin(hours(1))"), Err(MathError::UnitMismatch { .. })));
+	assert!(matches!(eval("in_hours(5)"), Err(MathError::UnitMismatch { .. })));
+	assert!(matches!(eval("hours(1)^0.5"), Err(MathError::NotAnInteger)));
+
+	assert_eq!(eval("days_between(date(2024, 1, 1), date(2024, 3, 1))").unwrap(), Quantity::number(60.0));
+	assert_eq!(eval("days_between(date(2023, 1, 1), date(2023, 3, 1))").unwrap(), Quantity::number(59.0));
+	assert_eq!(eval("date(1970, 1, 2)").unwrap(), Quantity::seconds(86400.0));
+	// Days and months carry over like in spreadsheets
+	assert_eq!(eval("date(2024, 1, 32)").unwrap(), eval("date(2024, 2, 1)").unwrap());
+	assert_eq!(eval("date(2023, 13, 1)").unwrap(), eval("date(2024, 1, 1)").unwrap());
+	assert_eq!(eval("date(2024, 3, 0)").unwrap(), eval("date(2024, 2, 29)").unwrap());
+	assert_eq!(eval("date(2024, 3, 1) + days(7) - date(2024, 3, 1)").unwrap().to_string(), "7d");
+	assert!(matches!(eval("date(2024, 1.5, 1)"), Err(MathError::NotAnInteger)));
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn time_config() {
+	use std::sync::Arc;
+	use crate::time::{Quantity, TimePack};
+	use crate::MathError;
+
+	// The numbers inside quantities are operated on with the config of the quantities' context, in a
+	// context that's reused until the config changes
+	let mut ctx: Context<Quantity<f64>> = Context::new();
+	ctx.install(&TimePack);
+	let term = Term::parse_ctx("hours(1) / 0", &ctx).unwrap();
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::DivideByZero)));
+	assert!(Arc::ptr_eq(&Context::<f64>::inner(&ctx.cfg), &Context::inner(&ctx.cfg)));
+	ctx.cfg.zero_division = crate::ZeroDivision::Infinity;
+	assert_eq!(term.eval_ctx(&ctx).unwrap().unwrap_single(), Quantity::seconds(f64::INFINITY));
+}
+
+#[test]
+fn tree_export() {
+	let term: Term<f64> = Term::parse("[1, x] + -y!").unwrap();
+	assert_eq!(
+		term.to_json(),
+		r#"{"type":"op","op":"+","args":[{"type":"op","op":"[,]","args":[{"type":"num","value":"1"},{"type":"var","name":"x"}]},{"type":"op","op":"(-)","args":[{"type":"op","op":"!","args":[{"type":"var","name":"y"}]}]}]}"#
+	);
+	assert_eq!(term.to_sexpr(), r#"(+ ([,] 1 x) ("(-)" (! y)))"#);
+
+	let term: Term<f64> = Term::func("f", vec![Term::var("a\"b"), Term::num(-2.5)]);
+	assert_eq!(term.to_json(), r#"{"type":"func","name":"f","args":[{"type":"var","name":"a\"b"},{"type":"num","value":"-2.5"}]}"#);
+	assert_eq!(term.to_sexpr(), r#"(f "a\"b" -2.5)"#);
+	assert_eq!(Term::<f64>::func("g", vec![]).to_sexpr(), "(g)");
+}
+
+#[test]
+fn definitions() {
+	use crate::{DefinitionError, MathError};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.load_definitions("
+		# Comments and blank lines are skipped
+
+		sum_three(a, b, c) = add(a, b) + c
+		add(a, b) = a + b
+		k = 2 * base
+		base = 5
+	").unwrap();
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap();
+	assert_eq!(eval("sum_three(1, 2, k)", &ctx), Answer::Single(13.0));
+	assert!(Term::parse_ctx("add(1)", &ctx).is_err());
+
+	let dump = ctx.dump_definitions();
+	assert_eq!(dump, "base = 5\nk = (2 × base)\nadd(a, b) = (a + b)\nsum_three(a, b, c) = (add(a, b) + c)\n");
+	let mut loaded: Context<f64> = Context::new();
+	loaded.load_definitions(&dump).unwrap();
+	assert_eq!(loaded.dump_definitions(), dump);
+	assert_eq!(eval("sum_three(1, 2, k)", &loaded), Answer::Single(13.0));
+
+	// Nothing is loaded if any definition is invalid
+	match ctx.load_definitions("x = 1\n2 = 3") {
+		Err(DefinitionError::InvalidDefinition { line: 2 }) => {}
+		other => panic!("{:?}", other),
+	}
+	match ctx.load_definitions("f(x) = x +") {
+		Err(DefinitionError::ParseError { line: 1, .. }) => {}
+		other => panic!("{:?}", other),
+	}
+	assert!(ctx.load_definitions("f(x, 1) = x").is_err());
+	assert!(!ctx.has_var("x") && !ctx.has_func("f"));
+
+	// Functions can recurse until the recursion limit
+	ctx.load_definitions("loop(x) = loop(x)").unwrap();
+	assert!(Term::parse_ctx("loop(1)", &ctx).unwrap().eval_ctx(&ctx).is_err());
+
+	// The steps taken in the bodies of functions count towards the limit of the whole evaluation
+	ctx.load_definitions("
+		leaf(x) = x + 1
+		two(x) = leaf(x) + leaf(x) + leaf(x) + leaf(x) + leaf(x) + leaf(x) + leaf(x) + leaf(x)
+		three(x) = two(x) + two(x) + two(x) + two(x) + two(x) + two(x) + two(x) + two(x)
+		four(x) = three(x) + three(x) + three(x) + three(x) + three(x) + three(x) + three(x) + three(x)
+	").unwrap();
+	let term = Term::parse_ctx("four(1)", &ctx).unwrap();
+	assert_eq!(term.eval_ctx(&ctx).unwrap(), Answer::Single(1024.0));
+	ctx.cfg.max_steps = Some(2000);
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::Cancelled)));
+
+	// Answers memoized in the body of a function are only used for the same arguments
+	ctx.cfg.max_steps = None;
+	ctx.cfg.memoize = true;
+	assert_eq!(eval("leaf(1) * 10 + leaf(2) + two(1)", &ctx), Answer::Single(39.0));
+}
+
+#[test]
+fn unknown_function() {
+	use crate::{MathError, ParseError};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 2.0);
+	assert!(eq(Term::parse_ctx("x(3)", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single(), 6.0));
+	let err = Term::parse_ctx("sqr(4)", &ctx).unwrap().eval_ctx(&ctx).unwrap_err();
+	match err {
+		MathError::VariableCalledAsFunction { ref name, ref suggestions } => {
+			assert_eq!(name, "sqr");
+			assert_eq!(suggestions, &["sqrt".to_string()]);
+		}
+		ref other => panic!("{:?}", other),
+	}
+	assert_eq!(err.to_string(), "'sqr' was called like a function, but it isn't a function or a variable (did you mean 'sqrt'?)");
+	assert!(matches!(Term::parse_ctx("y * 4", &ctx).unwrap().eval_ctx(&ctx), Err(MathError::UndefinedVariable { .. })));
+	assert_eq!(ctx.similar_funcs("mix"), vec!["max".to_string(), "min".to_string()]);
+	assert!(ctx.similar_funcs("foo").is_empty());
+
+	ctx.cfg.strict_names = true;
+	match Term::parse_ctx("2 + coss(x)", &ctx) {
+		Err(ParseError::UnknownFunction { name, suggestions }) => {
+			assert_eq!(name, "coss");
+			assert_eq!(suggestions, vec!["cos".to_string(), "cross".to_string()]);
+		}
+		other => panic!("{:?}", other),
+	}
+}
+
+#[test]
+fn undefined_variable_suggestions() {
+	use crate::MathError;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("radius", 2.0);
+	let err = Term::parse_ctx("2 * pii * radus", &ctx).unwrap().eval_ctx(&ctx).unwrap_err();
+	match err {
+		MathError::UndefinedVariable { ref name, ref suggestions } => {
+			assert_eq!(name, "pii");
+			assert_eq!(suggestions, &["pi".to_string()]);
+		}
+		ref other => panic!("{:?}", other),
+	}
+	assert_eq!(err.to_string(), "Variable 'pii' is not defined (did you mean 'pi'?)");
+	assert_eq!(ctx.similar_names("radus"), vec!["radius".to_string()]);
+	assert!(ctx.similar_names("qqq").is_empty());
+}
+
+#[test]
+fn traced_errors() {
+	use crate::MathError;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 2.0);
+	let term = Term::parse_ctx("abs(-9) + max(1, 5 / (x - 2)) * 3", &ctx).unwrap();
+	let err = term.eval_traced(&ctx).unwrap_err();
+	assert!(matches!(err.error, MathError::DivideByZero));
+	assert_eq!(err.term, "(5 ÷ (x - 2))");
+	assert_eq!(err.path, vec![1, 0, 1]);
+	assert_eq!(err.to_string(), "Attempted to divide by zero in `(5 ÷ (x - 2))`");
+
+	// The whole term is blamed if none of its operands fail on their own
+	let err = Term::parse_ctx("x / 0", &ctx).unwrap().eval_traced(&ctx).unwrap_err();
+	assert_eq!((err.term.as_str(), err.path), ("(x ÷ 0)", vec![]));
+	ctx.set_var("x", 3.0);
+	assert!(eq(term.eval_traced(&ctx).unwrap().unwrap_single(), 24.0));
+}
+
+#[test]
+fn error_codes() {
+	use std::error::Error;
+	use crate::EvalError;
+
+	let err = eval::<f64>("1 / 0").unwrap_err();
+	assert_eq!(err.code(), "math.divide_by_zero");
+	assert!(err.parse_error().is_none());
+	assert_eq!(err.math_error().unwrap().code(), "math.divide_by_zero");
+	assert_eq!(err.source().unwrap().to_string(), "Attempted to divide by zero");
+
+	let err = eval::<f64>("(1 + 2").unwrap_err();
+	assert_eq!(err.code(), "parse.mismatched_parentheses");
+	assert!(matches!(err, EvalError::ParseError { .. }));
+	assert!(err.source().unwrap().source().is_none());
+
+	let mut ctx: Context<f64> = Context::new();
+	let err = ctx.load_definitions("f(x) = x +").unwrap_err();
+	assert!(err.source().is_some());
+}
+
+#[test]
+fn eval_steps() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("r", 2.0);
+	let term = Term::parse_ctx("max(1, r^2) - 3", &ctx).unwrap();
+	let (answer, steps) = term.eval_steps(&ctx).unwrap();
+	assert_eq!(answer, Answer::Single(1.0));
+	let shown: Vec<String> = steps.iter().map(|step| step.to_string()).collect();
+	assert_eq!(shown, vec!["r = 2", "(2 ^ 2) = 4", "max(1, 4) = 4", "(4 - 3) = 1"]);
+	assert_eq!(steps[1].term, "(r ^ 2)");
+	assert_eq!(steps.last().unwrap().term, term.to_string());
+
+	assert!(Term::parse_ctx("1 + 1 / 0", &ctx).unwrap().eval_steps(&ctx).is_err());
+}
+
+#[test]
+fn eval_observer() {
+	use std::sync::{Arc, Mutex};
+	use crate::{EvalObserver, MathError};
+
+	#[derive(Default)]
+	struct Log {
+		lines: Mutex<Vec<String>>,
+	}
+
+	impl EvalObserver<f64> for Arc<Log> {
+		fn enter(&self, term: &Term<f64>, depth: usize) -> Result<(), MathError> {
+			if depth > 2 {
+				return Err(MathError::Other);
+			}
+			self.lines.lock().unwrap().push(format!("enter {} {}", depth, term));
+			Ok(())
+		}
+
+		fn exit(&self, term: &Term<f64>, depth: usize, result: &Calculation<f64>) {
+			let result = match *result {
+				Ok(ref answer) => answer.to_string(),
+				Err(ref e) => e.to_string(),
+			};
+			self.lines.lock().unwrap().push(format!("exit {} {} {}", depth, term, result));
+		}
+	}
+
+	let log = Arc::new(Log::default());
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 3.0);
+	ctx.set_observer(log.clone());
+	let term = Term::parse_ctx("x / 0", &ctx).unwrap();
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::DivideByZero)));
+	assert_eq!(*log.lines.lock().unwrap(), vec![
+		"enter 0 (x ÷ 0)",
+		"enter 1 x",
+		"enter 2 3",
+		"exit 2 3 3",
+		"exit 1 x 3",
+		"enter 1 0",
+		"exit 1 0 0",
+		"exit 0 (x ÷ 0) Attempted to divide by zero",
+	]);
+
+	// Errors from the observer stop the evaluation
+	let term = Term::parse_ctx("1 + (2 + x)", &ctx).unwrap();
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::Other)));
+
+	ctx.remove_observer();
+	assert!(eq(term.eval_ctx(&ctx).unwrap().unwrap_single(), 6.0));
+}
+
+#[test]
+fn eval_fast() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 3.0);
+	ctx.set_var("y", Term::parse_ctx("x^2 - 1", &ctx).unwrap());
+	ctx.set_var("z", Term::parse_ctx("z + 1", &ctx).unwrap());
+	for raw in &["2 * x^2 - x / 4", "-y + 1", "sqrt(4) + 1", "x * [1, 2]", "max(x, y) / 2", "1 / 0", "foo(2) * 3", "z"] {
+		let term = Term::parse_ctx(raw, &ctx).unwrap();
+		match (term.eval_fast(&ctx), term.eval_ctx(&ctx)) {
+			(Ok(a), Ok(b)) => assert_eq!(a, b, "{}", raw),
+			(Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string(), "{}", raw),
+			(a, b) => panic!("{}: {:?} != {:?}", raw, a, b),
+		}
+	}
+	assert_eq!(Term::<f64>::parse("2 * pi").unwrap().eval().unwrap(), Answer::Single(2.0 * ::std::f64::consts::PI));
+}
+
+#[test]
+fn default_context() {
+	for _ in 0..3 {
+		assert_eq!(eval::<f64>("max(2, 3) * pi").unwrap(), Answer::Single(3.0 * ::std::f64::consts::PI));
+		assert_eq!(eval::<ComplexFloat>("i * i").unwrap(), Answer::Single(ComplexFloat::from(-1.0)));
+		assert!(eval::<f64>("nope(1)").is_err());
+	}
+	let term: Term<f64> = Term::parse("hex(255) + len([1, 2])").unwrap();
+	assert_eq!(term.eval().unwrap(), Answer::Single(257.0));
+}
+
+#[test]
+fn bound_program() {
+	let ctx: Context<f64> = Context::new();
+	let raws = [
+		"3 * x^2 - 4 / (x + 1) + -x", "sqrt(x) + 1", "max(x, 2) / x", "x / (x - 2)", "[x, 1] * 2",
+		"-sqrt(x) * sqrt(4) - x", "(x ± 1) / 2 + 1", "[[x, 1], [0, 1]] * [x, 2] + 1", "[x, 2] ^ 2 - sqrt(x)",
+	];
+	for raw in &raws {
+		let term: Term<f64> = Term::parse(raw).unwrap();
+		let bound = term.bind(&ctx).unwrap();
+		for &x in &[2.0, 4.0, -1.0] {
+			let mut var_ctx = ctx.clone();
+			var_ctx.set_var("x", x);
+			match (bound.eval(&[x]), term.eval_ctx(&var_ctx)) {
+				// NaNs aren't equal to themselves, so the answers are compared as strings
+				(Ok(a), Ok(b)) => assert_eq!(format!("{:?}", a), format!("{:?}", b), "{} at {}", raw, x),
+				(Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string(), "{} at {}", raw, x),
+				(a, b) => panic!("{} at {}: {:?} != {:?}", raw, x, a, b),
+			}
+		}
+	}
+
+	// The values of the slots of evaluations on other threads don't mix
+	let bound = Term::parse("sqrt(x) * max(x, 0) + x").unwrap().bind(&ctx).unwrap();
+	::std::thread::scope(|scope| {
+		for i in 0..4 {
+			let bound = &bound;
+			scope.spawn(move || {
+				for _ in 0..100 {
+					let x = f64::from(i * i);
+					let expected = vec![x * f64::from(i) + x, -x * f64::from(i) + x];
+					assert_eq!(bound.eval(&[x]).unwrap().to_vec(), expected);
+				}
+			});
+		}
+	});
+
+	// Terms nested too deeply still fail
+	let long = vec!["x"; 200].join(" + ");
+	let mut ctx = ctx;
+	ctx.cfg.max_depth = 1000;
+	let term: Term<f64> = Term::parse_ctx(&long, &ctx).unwrap();
+	assert!(eq(term.bind(&ctx).unwrap().eval(&[1.0]).unwrap().unwrap_single(), 200.0));
+	ctx.cfg.max_depth = 100;
+	assert!(term.bind(&ctx).unwrap().eval(&[1.0]).is_err());
+}
+
+#[test]
+fn deep_terms() {
+	use crate::MathError;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.cfg.max_depth = 10_000;
+	ctx.set_var("x", 2.0);
+
+	// Too deep to evaluate by recursing on a small stack
+	let sum = vec!["x"; 5000].join(" + ");
+	let term = Term::parse_ctx(&sum, &ctx).unwrap();
+	assert!(eq(term.eval_ctx(&ctx).unwrap().unwrap_single(), 10_000.0));
+	let nested = format!("{}1{}", "(2 * ".repeat(2000), ")".repeat(2000));
+	assert!(Term::parse_ctx(&nested, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single().is_infinite());
+
+	// Deep terms are evaluated the same as shallow ones
+	let terms = vec!["max(x, 3)", "-x", "x!"].repeat(20).join(" + ") + " + sqrt(4)";
+	let answer = Term::parse_ctx(&terms, &ctx).unwrap().eval_ctx(&ctx).unwrap();
+	let expected: Vec<f64> = vec![62.0, 58.0];
+	assert_eq!(answer.to_vec(), expected);
+	let err = Term::parse_ctx(&(vec!["1"; 50].join(" + ") + " + foo(3)"), &ctx).unwrap().eval_ctx(&ctx).unwrap_err();
+	assert!(matches!(err, MathError::VariableCalledAsFunction { .. }));
+	let err = Term::parse_ctx(&(vec!["1"; 50].join(" + ") + " + 1 / 0"), &ctx).unwrap().eval_ctx(&ctx).unwrap_err();
+	assert!(matches!(err, MathError::DivideByZero));
+
+	ctx.cfg.max_depth = 100;
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::RecursionLimit)));
+
+	// Deeply nested expressions work with the default config
+	let ctx: Context<f64> = Context::new();
+	let parens = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+	assert_eq!(Term::parse_ctx(&parens, &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(1.0));
+	let negations = "-".repeat(200) + "1";
+	assert_eq!(Term::parse_ctx(&negations, &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(1.0));
+	let calls = format!("{}1{}", "max(0, ".repeat(200), ")".repeat(200));
+	assert_eq!(Term::parse_ctx(&calls, &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(1.0));
+}
+
+#[test]
+fn generated_terms() {
+	use crate::testing::{oracle, TermGen};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 1.5);
+	ctx.set_var("y", -2.0);
+	let vars = |name: &str| match name {
+		"x" => Some(1.5),
+		"y" => Some(-2.0),
+		_ => None,
+	};
+
+	let mut gen = TermGen::new(2024);
+	gen.vars.push(String::from("y"));
+	let mut compared = 0;
+	for raw in gen.take(500) {
+		let term: Term<f64> = Term::parse_ctx(&raw, &ctx).unwrap();
+		// Printing and parsing again gives the same term
+		assert_eq!(Term::parse_ctx(&term.to_string(), &ctx).unwrap(), term, "{}", raw);
+
+		// The answer agrees with the oracle whenever both are finite
+		if let (Ok(answer), Some(expected)) = (term.eval_ctx(&ctx), oracle(&term, &vars)) {
+			let answer = answer.unwrap_single();
+			if answer.is_finite() && expected.is_finite() {
+				assert!((answer - expected).abs() <= 1e-9 * expected.abs().max(1.0), "{} = {}, expected {}", raw, answer, expected);
+				compared += 1;
+			}
+		}
+	}
+	assert!(compared > 250);
+
+	// Arithmetic on small integers is exact, so every type of number gets the same answer
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		let decimal_ctx: Context<Decimal> = Context::new();
+		let mut gen = TermGen::new(99);
+		gen.max_depth = 3;
+		gen.ops = vec!["+", "-", "*"];
+		gen.funcs = vec![("abs", 1), ("max", 2), ("min", 2)];
+		gen.vars.clear();
+		for raw in gen.take(200) {
+			let a = Term::<f64>::parse(&raw).unwrap().eval().unwrap().unwrap_single();
+			let b = Term::parse_ctx(&raw, &decimal_ctx).unwrap().eval_ctx(&decimal_ctx).unwrap().unwrap_single();
+			assert_eq!(a, b.to_string().parse::<f64>().unwrap(), "{}", raw);
+		}
+	}
+}
+
+proptest::proptest! {
+	#[test]
+	fn printed_terms_parse_back(seed: u64) {
+		use crate::testing::TermGen;
+
+		let mut ctx: Context<f64> = Context::new();
+		ctx.set_var("x", 1.5);
+		let raw = TermGen::new(seed).expression();
+		let term: Term<f64> = Term::parse_ctx(&raw, &ctx).unwrap();
+		proptest::prop_assert_eq!(Term::parse_ctx(&term.to_string(), &ctx).unwrap(), term, "{}", raw);
+	}
+
+	#[test]
+	fn terms_agree_with_oracle(seed: u64) {
+		use crate::testing::{oracle, TermGen};
+
+		let mut ctx: Context<f64> = Context::new();
+		ctx.set_var("x", 1.5);
+		let raw = TermGen::new(seed).expression();
+		let term: Term<f64> = Term::parse_ctx(&raw, &ctx).unwrap();
+		if let (Ok(answer), Some(expected)) = (term.eval_ctx(&ctx), oracle(&term, &|name| if name == "x" { Some(1.5) } else { None })) {
+			let answer = answer.unwrap_single();
+			if answer.is_finite() && expected.is_finite() {
+				proptest::prop_assert!((answer - expected).abs() <= 1e-9 * expected.abs().max(1.0), "{} = {}, expected {}", raw, answer, expected);
+			}
+		}
+	}
+
+	#[test]
+	fn number_types_agree(seed: u64) {
+		use crate::num::{AnyNum, Exact};
+		use crate::testing::TermGen;
+
+		// Arithmetic on the generated numbers is exact, so every type of number gets the same answer
+		let mut gen = TermGen::new(seed);
+		gen.max_depth = 3;
+		gen.ops = vec!["+", "-", "*"];
+		gen.funcs = vec![("abs", 1), ("max", 2), ("min", 2)];
+		gen.vars.clear();
+		let raw = gen.expression();
+		let expected = Term::<f64>::parse(&raw).unwrap().eval().unwrap().unwrap_single();
+
+		let exact = Term::<Exact>::parse(&raw).unwrap().eval().unwrap().unwrap_single();
+		proptest::prop_assert_eq!(exact.to_f64_lossy().unwrap(), expected, "{}", raw);
+		let any = Term::<AnyNum>::parse(&raw).unwrap().eval().unwrap().unwrap_single();
+		proptest::prop_assert_eq!(any.to_f64_lossy().unwrap(), expected, "{}", raw);
+		#[cfg(feature = "decimal")]
+		{
+			let decimal = Term::<crate::num::Decimal>::parse(&raw).unwrap().eval().unwrap().unwrap_single();
+			proptest::prop_assert_eq!(decimal.to_f64_lossy().unwrap(), expected, "{}", raw);
+		}
+	}
+}
+
+#[test]
+fn round_trip() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("v", Term::parse("[1, 2, 3]").unwrap());
+	let exprs = [
+		"5 + 10% - 3%",
+		"(2 + 3)%",
+		"x% + 1",
+		"[x,]",
+		"[[1, 2]]",
+		"[]",
+		"v[0][1]",
+		"max(1, 2)[0]",
+		"y(x + 1)",
+		"-x^2! × 3 ÷ 4",
+		"±3 ± +x",
+		"~1 & 2 | 3 << 4 >> 5",
+		"min(1, 2.5, 0.125) + a.b",
+		"0x1f + 1e3",
+	];
+	for raw in exprs.iter() {
+		let term = Term::parse_ctx(raw, &ctx).unwrap();
+		assert_eq!(Term::parse_ctx(&term.to_string(), &ctx).unwrap(), term, "{} printed as {}", raw, term);
+	}
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		let ctx: Context<Decimal> = Context::new();
+		let term = Term::parse_ctx("0.1 + 2.25 × 10% - 3", &ctx).unwrap();
+		assert_eq!(term.to_string(), "((0.1 + (2.25 × (10%))) - 3)");
+		assert_eq!(Term::parse_ctx(&term.to_string(), &ctx).unwrap(), term);
+	}
+}
+
+#[test]
+fn loose_function_call() {
+	use crate::ParseError;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 0.5);
+	let parse = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().to_string();
+
+	// Without the option it's implicit multiplication
+	assert_eq!(parse("sin x", &ctx), "(sin × x)");
+
+	ctx.cfg.loose_function_call = true;
+	assert_eq!(parse("sin x", &ctx), "sin(x)");
+	assert_eq!(parse("sin 2x + 1", &ctx), "(sin((2 × x)) + 1)");
+	assert_eq!(parse("2 sin x^2 / 3 - cos x!", &ctx), "((2 × sin(((x ^ 2) ÷ 3))) - cos((x!)))");
+	assert_eq!(parse("sin cos x", &ctx), "sin(cos(x))");
+	assert_eq!(parse("max(sin x, 1)", &ctx), "max(sin(x), 1)");
+	assert_eq!(parse("sin(x) + x sin 2", &ctx), "(sin(x) + (x × sin(2)))");
+	assert!(eq(Term::parse_ctx("sin 2x", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single(), 1.0f64.sin()));
+	assert!(matches!(Term::<f64>::parse_ctx("atant 1", &ctx), Err(ParseError::IncorrectArguments { .. })));
+}
+
+#[test]
+fn pow_associativity() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 2.0);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+	assert_eq!(eval("2^3^2", &ctx), 512.0);
+	assert_eq!(eval("2**3**2", &ctx), 512.0);
+	assert_eq!(eval("2 ** 3 ^ 2 * 2", &ctx), 1024.0);
+	assert_eq!(eval("-x**2", &ctx), -4.0);
+	assert_eq!(eval("x**-1", &ctx), 0.5);
+	assert_eq!(Term::parse_ctx("2^3^2", &ctx).unwrap().to_string(), "(2 ^ (3 ^ 2))");
+
+	ctx.cfg.left_associative_pow = true;
+	assert_eq!(eval("2^3^2", &ctx), 64.0);
+	assert_eq!(eval("2**3**2**0.5", &ctx), 8.0);
+	assert_eq!(eval("2^(3^2)", &ctx), 512.0);
+	assert_eq!(Term::parse_ctx("2^3^2", &ctx).unwrap().to_string(), "((2 ^ 3) ^ 2)");
+}
+
+#[test]
+fn unary_minus_precedence() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 3.0);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+	let parse = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().to_string();
+	assert_eq!(eval("-3^2", &ctx), -9.0);
+	assert_eq!(eval("-x^2", &ctx), -9.0);
+	assert_eq!(eval("2 * -3^2", &ctx), -18.0);
+	assert_eq!(eval("2^-1^2", &ctx), 0.5);
+	assert_eq!(eval("-3!", &ctx), -6.0);
+	assert_eq!(parse("-3^2", &ctx), "(-(3 ^ 2))");
+
+	ctx.cfg.tight_unary_minus = true;
+	assert_eq!(eval("-3^2", &ctx), 9.0);
+	assert_eq!(eval("-x^2", &ctx), 9.0);
+	assert_eq!(eval("2 * -3^2", &ctx), 18.0);
+	assert_eq!(eval("2^-1^2", &ctx), 2.0);
+	assert_eq!(eval("--2^2", &ctx), 4.0);
+	assert_eq!(eval("1 - 3^2", &ctx), -8.0);
+	assert_eq!(parse("-3^2", &ctx), "((-3) ^ 2)");
+	assert_eq!(parse("-(3)^2", &ctx), "((-3) ^ 2)");
+}
+
+#[test]
+fn negation() {
+	use crate::lexer::{tokenize, TokenKind};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 3.0);
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+	let parse = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().to_string();
+	// A minus that isn't subtracting is always a negation of the operand after it
+	assert_eq!(eval("-x^2"), -9.0);
+	assert_eq!(parse("-x^2"), "(-(x ^ 2))");
+	assert_eq!(eval("2-- 3"), 5.0);
+	assert_eq!(parse("2-- 3"), "(2 - (-3))");
+	assert_eq!(eval("- 3"), -3.0);
+	assert_eq!(eval("-(-3)"), 3.0);
+	assert_eq!(eval("1--1--1"), 3.0);
+	assert_eq!(eval("-2(3)"), -6.0);
+	assert!(eval("(-3)!").is_nan());
+	assert_eq!(parse("(-3)!"), "((-3)!)");
+	assert_eq!(eval("-3!"), -6.0);
+	for raw in ["-x^2", "2-- 3", "(-3)!", "-(-3)"].iter() {
+		let term = Term::parse_ctx(raw, &ctx).unwrap();
+		assert_eq!(Term::parse_ctx(&term.to_string(), &ctx).unwrap(), term);
+	}
+
+	let kinds = |raw: &str| tokenize(raw).into_iter().map(|t| t.kind).collect::<Vec<TokenKind>>();
+	assert_eq!(kinds("2-- 3"), vec![TokenKind::Number, TokenKind::InfixOp, TokenKind::PrefixOp, TokenKind::Number]);
+	assert_eq!(kinds("- 3"), vec![TokenKind::PrefixOp, TokenKind::Number]);
+}
+
+#[test]
+fn input_normalization() {
+	use crate::lexer::{tokenize, TokenKind};
+	use crate::DiagnosticKind;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 4.0);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+	assert_eq!(eval("１２＋３", &ctx), 15.0);
+	assert_eq!(eval("５ × （ｘ − ２）", &ctx), 10.0);
+	assert_eq!(eval("5 − 2", &ctx), 3.0);
+	assert_eq!(eval("6 – 2", &ctx), 4.0);
+	assert_eq!(eval("1⁄2 + 3∕4", &ctx), 1.25);
+	assert_eq!(eval("2\u{a0}⋅\u{a0}3", &ctx), 6.0);
+	assert_eq!(eval("\u{feff}max（1，\u{200b}2）", &ctx), 2.0);
+
+	// The tokens have the normalized text, but their spans are in the original string
+	let tokens = tokenize("１２＋ｘ");
+	assert_eq!(tokens.iter().map(|t| t.kind).collect::<Vec<_>>(), vec![TokenKind::Number, TokenKind::InfixOp, TokenKind::Name]);
+	assert_eq!(tokens.iter().map(|t| t.text.as_str()).collect::<Vec<_>>(), vec!["12", "+", "x"]);
+	assert_eq!(tokens.iter().map(|t| t.span.clone()).collect::<Vec<_>>(), vec![0..6, 6..9, 9..12]);
+	let diagnostics = Expression::check("１ − ｙ", &ctx);
+	assert_eq!(diagnostics.len(), 1);
+	assert_eq!(diagnostics[0].kind, DiagnosticKind::UnknownName { name: "y".to_string() });
+	assert_eq!(diagnostics[0].span, 8..11);
+
+	ctx.cfg.normalize_input = false;
+	assert!(Term::parse_ctx("5 − 2", &ctx).is_err());
+	assert_eq!(eval("2\u{a0}*\u{a0}3", &ctx), 6.0);
+}
+
+#[test]
+fn fraction_literals() {
+	use crate::lexer::{tokenize, TokenKind};
+	use crate::num::Exact;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 4.0);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+	assert_eq!(eval("½", &ctx), 0.5);
+	assert_eq!(eval("¾ + ¼", &ctx), 1.0);
+	assert_eq!(eval("2½", &ctx), 2.5);
+	assert_eq!(eval("-1½", &ctx), -1.5);
+	assert_eq!(eval("x½", &ctx), 2.0);
+	assert_eq!(eval("2^½", &ctx), 2f64.sqrt());
+	// Only integers can be the whole part, and without the option numbers can't be separated by spaces
+	let fails = |raw: &str, ctx: &Context<f64>| Term::<f64>::parse_ctx(raw, ctx).is_err();
+	assert!(fails("2.5½", &ctx));
+	assert!(fails("2 ½", &ctx));
+	assert!(fails("1 1/2", &ctx));
+
+	ctx.cfg.mixed_numbers = true;
+	assert_eq!(eval("1 1/2", &ctx), 1.5);
+	assert_eq!(eval("2 ½ + 1", &ctx), 3.5);
+	assert_eq!(eval("-3 3/4", &ctx), -3.75);
+	assert_eq!(eval("1 1/2^2", &ctx), 2.25);
+	// Improper fractions and fractions that go on aren't part of mixed numbers
+	assert!(fails("2 3/2", &ctx));
+	assert!(fails("1 1/2.5", &ctx));
+	assert!(fails("1.5 1/2", &ctx));
+
+	let kinds = |raw: &str| tokenize(raw).into_iter().map(|t| (t.kind, t.span)).collect::<Vec<_>>();
+	assert_eq!(kinds("2½+1"), vec![(TokenKind::Number, 0..3), (TokenKind::InfixOp, 3..4), (TokenKind::Number, 4..5)]);
+
+	// Fractions are exact for number types that can represent them
+	let mut ctx: Context<Exact> = Context::new();
+	ctx.cfg.mixed_numbers = true;
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+	assert_eq!(eval("⅓"), Exact::rational(1, 3).unwrap());
+	assert_eq!(eval("1 2/3"), Exact::rational(5, 3).unwrap());
+	assert_eq!(eval("3 * ⅔"), Exact::rational(2, 1).unwrap());
+}
+
+#[test]
+fn constants() {
+	use crate::ConstantsPack;
+
+	let mut ctx: Context<f64> = Context::with_constants(ConstantsPack::Physics);
+	ctx.install_constants(ConstantsPack::Math);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+	assert_eq!(eval("c", &ctx), 299_792_458.0);
+	assert!(eq(eval("N_A", &ctx), 6.022_140_76e23));
+	assert!((eval("h", &ctx) / 6.626_070_15e-34 - 1.0).abs() < 1e-15);
+	assert!((eval("G", &ctx) / 6.674_30e-11 - 1.0).abs() < 1e-15);
+	assert!(eq(eval("tau", &ctx), 2.0 * ::std::f64::consts::PI));
+	assert!(eq(eval("phi^2 - phi", &ctx), 1.0));
+	assert!(eq(eval("2 tau", &ctx), 4.0 * ::std::f64::consts::PI));
+	for &pack in ConstantsPack::ALL.iter() {
+		assert!(pack.names().iter().all(|name| ctx.vars.contains_key(*name)));
+	}
+
+	// Types that can't compute a constant get its f64 value
+	let ctx: Context<ComplexFloat> = Context::with_constants(ConstantsPack::Math);
+	assert_eq!(ctx.vars["tau"], Term::Num(Answer::Single(ComplexFloat::from(2.0 * ::std::f64::consts::PI))));
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		let ctx: Context<Decimal> = Context::with_constants(ConstantsPack::Physics);
+		assert_eq!(ctx.vars["N_A"].to_string(), "602214076000000000000000");
+		assert_eq!(ctx.vars["G"].to_string(), "0.000000000066743");
+	}
+}
+
+#[test]
+fn finance_pack() {
+	use crate::{FinancePack, MathError};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.install(&FinancePack);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).map(|a| a.unwrap_single());
+
+	assert!((eval("pmt(0.05 / 12, 360, 200000)", &ctx).unwrap() + 1073.643_246).abs() < 1e-6);
+	assert!(eq(eval("pmt(0, 10, 1000)", &ctx).unwrap(), -100.0));
+	// Payments at the beginning of each period earn one less period of interest
+	assert!(eq(eval("pmt(0.1, 2, 1000, 0, 1) * 1.1 - pmt(0.1, 2, 1000)", &ctx).unwrap(), 0.0));
+	assert!((eval("fv(0.06 / 12, 10, -200, -500, 1)", &ctx).unwrap() - 2581.403_374).abs() < 1e-6);
+	assert!(eq(eval("fv(0, 12, -100)", &ctx).unwrap(), 1200.0));
+	assert!((eval("pv(0.08 / 12, 240, 500)", &ctx).unwrap() + 59_777.145_851).abs() < 1e-6);
+	assert!(eq(eval("pv(0.05, 10, pmt(0.05, 10, 1000))", &ctx).unwrap(), 1000.0));
+	assert!((eval("npv(0.1, -10000, 3000, 4200, 6800)", &ctx).unwrap() - 1188.443_412).abs() < 1e-6);
+	assert!(eq(eval("npv(0.1, [-10000, 3000], 4200, 6800)", &ctx).unwrap(), eval("npv(0.1, -10000, 3000, 4200, 6800)", &ctx).unwrap()));
+	assert!((eval("irr(-70000, 12000, 15000, 18000, 21000, 26000)", &ctx).unwrap() - 0.086_630_948).abs() < 1e-9);
+	assert!(eq(eval("npv(irr([-100, 60, 60]), 60, 60) - 100", &ctx).unwrap(), 0.0));
+	assert!(matches!(eval("irr(100, 200)", &ctx), Err(MathError::IncorrectArguments)));
+	assert_eq!(eval("round_bankers(2.5)", &ctx).unwrap(), 2.0);
+	assert_eq!(eval("round_bankers(-3.5)", &ctx).unwrap(), -4.0);
+	assert_eq!(eval("round_bankers(0.125, 2)", &ctx).unwrap(), 0.12);
+	assert_eq!(eval("round_bankers(1250, -2)", &ctx).unwrap(), 1200.0);
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		let mut ctx: Context<Decimal> = Context::new();
+		ctx.install(&FinancePack);
+		let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single().to_string();
+		assert_eq!(eval("round_bankers(pmt(0.05 / 12, 360, 200000), 2)"), "-1073.64");
+		assert_eq!(eval("fv(0.01, 2, -100)"), "201");
+		assert_eq!(eval("npv(0.1, 110, 121)"), "200");
+		// Exact halves, which aren't exact in f64
+		assert_eq!(eval("round_bankers(2.675, 2)"), "2.68");
+		assert_eq!(eval("round_bankers(2.665, 2)"), "2.66");
+	}
+}
+
+#[test]
+fn stats_pack() {
+	use crate::{MathError, StatsPack};
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.install(&StatsPack);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).map(|a| a.unwrap_single());
+
+	assert!(eq(eval("normal_pdf(0)", &ctx).unwrap(), 0.398_942_280_401_432_7));
+	assert!(eq(eval("normal_pdf(12, 10, 2)", &ctx).unwrap(), 0.120_985_362_259_571_7));
+	assert!(eq(eval("normal_cdf(0)", &ctx).unwrap(), 0.5));
+	assert!(eq(eval("normal_cdf(1.96)", &ctx).unwrap(), 0.975_002_104_851_779_5));
+	assert!(eq(eval("normal_cdf(85, 100, 15)", &ctx).unwrap(), 0.158_655_253_931_457_05));
+	// The lower tail stays precise instead of cancelling to 0
+	assert!((eval("normal_cdf(-10)", &ctx).unwrap() / 7.619_853_024_160_593e-24 - 1.0).abs() < 1e-9);
+	assert_eq!(eval("binom_pmf(3, 10, 0.5)", &ctx).unwrap(), 0.117_187_5);
+	assert_eq!(eval("binom_pmf(11, 10, 0.5)", &ctx).unwrap(), 0.0);
+	assert!(eq(eval("binom_pmf(1000, 2000, 0.5)", &ctx).unwrap(), 0.017_839_011_145_854_37));
+	assert!(eq(eval("poisson_pmf(2, 3)", &ctx).unwrap(), 0.224_041_807_655_387_75));
+	assert!((eval("poisson_pmf(500, 400)", &ctx).unwrap() / 1.681_877_526_673_121e-7 - 1.0).abs() < 1e-9);
+	assert!(eq(eval("t_cdf(2, 10)", &ctx).unwrap(), 0.963_305_982_614_629_9));
+	assert!(eq(eval("t_cdf(-1.5, 5)", &ctx).unwrap(), 0.096_951_840_121_236_71));
+	assert!(eq(eval("t_cdf(3, 1)", &ctx).unwrap(), 0.897_583_617_650_433_3));
+	assert!(eq(eval("t_cdf(0, 7)", &ctx).unwrap(), 0.5));
+	let probs = Term::parse_ctx("binom_pmf([0, 1, 2], 2, 0.5)", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+	assert_eq!(probs.to_vec(), vec![0.25, 0.5, 0.25]);
+
+	assert!(matches!(eval("normal_pdf(0, 0, 0)", &ctx), Err(MathError::DomainError { .. })));
+	assert!(matches!(eval("binom_pmf(1, 2, 1.5)", &ctx), Err(MathError::DomainError { .. })));
+	assert!(matches!(eval("poisson_pmf(1.5, 2)", &ctx), Err(MathError::NotAnInteger)));
+	assert!(matches!(eval("t_cdf(1, 0)", &ctx), Err(MathError::DomainError { .. })));
+	assert!(eval("normal_cdf(0, 0)", &ctx).is_ok());
+	assert!(Term::parse_ctx("normal_cdf(0, 0, 1, 2)", &ctx).is_err());
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		let mut ctx: Context<Decimal> = Context::new();
+		ctx.install(&StatsPack);
+		let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single().to_string();
+		assert_eq!(eval("binom_pmf(3, 10, 0.5)"), "0.1171875");
+		assert_eq!(eval("binom_pmf(2, 4, 0.1)"), "0.0486");
+	}
+}
+
+#[test]
+fn precise_constants() {
+	use std::f64::consts::{E, PI};
+
+	// pi, e and i are computed when they're evaluated, with the precision of the context
+	let ctx: Context<f64> = Context::new();
+	assert_eq!(Term::parse_ctx("pi", &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(PI));
+	assert_eq!(Term::parse_ctx("e", &ctx).unwrap().eval_ctx(&ctx).unwrap(), Answer::Single(E));
+	assert_eq!(ctx.vars["pi"].to_string(), "pi");
+	assert_eq!(Term::parse_ctx("2pi", &ctx).unwrap().fold_constants(&ctx), Term::Num(Answer::Single(2.0 * PI)));
+	assert_eq!(Context::<f64>::new().dump_definitions(), "");
+
+	let ctx: Context<ComplexFloat> = Context::new();
+	let answer = Term::parse_ctx("pi i", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+	assert_eq!(answer, Answer::Single(ComplexFloat { r: 0.0, i: PI }));
+
+	#[cfg(feature = "rug")]
+	{
+		use rug::Complex;
+
+		let ctx: Context<Complex> = Context::builder().precision(200).build();
+		let i = Term::parse_ctx("i", &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+		assert_eq!(i.prec(), (200, 200));
+	}
+}
+
+#[test]
+fn precision_override() {
+	use crate::func::Func;
+
+	// Answers the precision of the context it's called with
+	struct Precision;
+	impl Func<f64> for Precision {
+		fn eval(&self, _args: &[Term<f64>], ctx: &Context<f64>) -> Calculation<f64> {
+			Ok(Answer::Single(f64::from(ctx.cfg.precision)))
+		}
+	}
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_func("prec", Precision);
+	let expr: Expression<f64> = Expression::parse_ctx("prec()", ctx).unwrap();
+	assert_eq!(expr.eval_with_precision(256).unwrap(), Answer::Single(256.0));
+	// The context isn't changed
+	assert_eq!(expr.eval().unwrap(), Answer::Single(53.0));
+	assert_eq!(expr.term.eval_ctx_with_precision(&expr.ctx, 1024).unwrap(), Answer::Single(1024.0));
+
+	// Types other than rug::Complex don't show a precision
+	let fmt = crate::AnswerFormat { show_precision: true, ..crate::AnswerFormat::new() };
+	assert_eq!(Answer::Single(1.5).format(&fmt), "1.5");
+}
+
+#[test]
+fn nth_roots() {
+	use crate::answer::Label;
+	let mut ctx: Context<f64> = Context::new();
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap();
+
+	// Even roots have both a positive and a negative value, like sqrt
+	assert_eq!(eval("nrt(16, 4)", &ctx), Answer::Labeled(vec![(Label::Principal, 2.0), (Label::Negative, -2.0)]));
+	assert_eq!(eval("nrt(9, 2)", &ctx), eval("sqrt(9)", &ctx));
+	assert_eq!(eval("nrt(16, 4) + 1", &ctx).principal(), Some(&3.0));
+	// Odd and fractional roots only have one
+	assert_eq!(eval("nrt(8, 3)", &ctx), Answer::Single(2.0));
+	assert_eq!(eval("nrt(-8, 3)", &ctx), Answer::Single(-2.0));
+	assert_eq!(eval("nrt(-32, -5)", &ctx), Answer::Single(-0.5));
+	assert_eq!(eval("nrt(4, 0.5)", &ctx), Answer::Single(16.0));
+	assert!(eval("nrt(-4, 2)", &ctx).has_nan());
+
+	ctx.cfg.sqrt_both = false;
+	assert_eq!(eval("nrt(16, 4)", &ctx), Answer::Single(2.0));
+	ctx.cfg.sqrt_both = true;
+	ctx.cfg.single_valued = true;
+	assert_eq!(eval("nrt(16, 4)", &ctx), Answer::Single(2.0));
+}
+
+#[test]
+fn integer_powers() {
+	use crate::MathError;
+
+	assert_eq!(eval::<f64>("3^33").unwrap(), Answer::Single(5_559_060_566_555_523.0));
+	assert_eq!(eval::<f64>("2^-3").unwrap(), Answer::Single(0.125));
+	assert_eq!(eval::<f64>("(-2)^63").unwrap(), Answer::Single(-(2.0f64.powi(63))));
+	assert_eq!(eval::<f64>("1.5^0").unwrap(), Answer::Single(1.0));
+	assert!(eval::<f64>("0^-2").is_err());
+	// Large and negative powers of floats are as accurate as `powf`
+	assert_eq!(eval::<f64>("10^-320").unwrap(), Answer::Single(10f64.powf(-320.0)));
+	assert_eq!(eval::<f64>("2^-1074").unwrap(), Answer::Single(2f64.powf(-1074.0)));
+	assert!(eval::<f64>("2^-1074").unwrap().unwrap_single() > 0.0);
+	assert_eq!(eval::<f64>("1.0000001^1000000000").unwrap(), Answer::Single(1.0000001f64.powf(1e9)));
+	assert_eq!(eval::<f64>("2^1023").unwrap(), Answer::Single(2f64.powi(1023)));
+
+	// Types without their own pow get integer powers from the default implementation
+	let ctx: Context<ComplexFloat> = Context::new();
+	let eval_complex = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx);
+	assert_eq!(eval_complex("i^2").unwrap(), Answer::Single(ComplexFloat::from(-1.0)));
+	assert_eq!(eval_complex("(1 + i)^-2").unwrap(), Answer::Single(ComplexFloat { r: 0.0, i: -0.5 }));
+	assert_eq!(eval_complex("(2i)^0").unwrap(), Answer::Single(ComplexFloat::from(1.0)));
+	assert!(matches!(eval_complex("2^0.5"), Err(MathError::Unimplemented { .. })));
+	assert!(matches!(eval_complex("2^i"), Err(MathError::Unimplemented { .. })));
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		let ctx: Context<Decimal> = Context::new();
+		let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single().to_string();
+		assert_eq!(eval("1.1^10"), "2.5937424601");
+		assert_eq!(eval("2^-4"), "0.0625");
+	}
+}
+
+#[test]
+fn piecewise() {
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 3.0);
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx);
+
+	assert_eq!(eval("piecewise([0, 1], [x - 3, 2], [x, 3], 4)", &ctx).unwrap(), Answer::Single(3.0));
+	assert_eq!(eval("piecewise([0, 1], 4) * 2", &ctx).unwrap(), Answer::Single(8.0));
+	assert_eq!(eval("piecewise([step(x - 5), 1], [step(x), x^2], 0)", &ctx).unwrap(), Answer::Single(9.0));
+	// Only the chosen value and the conditions before it are evaluated
+	assert_eq!(eval("piecewise([1, 2], [1 / 0, y], z)", &ctx).unwrap(), Answer::Single(2.0));
+	assert!(eval("piecewise([0, 2], [1 / 0, y], z)", &ctx).is_err());
+	let folded = Term::parse_ctx("piecewise([0, 2], [1, 3], y)", &ctx).unwrap().fold_constants(&ctx);
+	assert_eq!(folded.eval_ctx(&ctx).unwrap(), Answer::Single(3.0));
+
+	// The pairs can be written in parentheses
+	assert_eq!(eval("piecewise((step(x - 5), 1), (step(x), x^2), 0)", &ctx).unwrap(), Answer::Single(9.0));
+	assert_eq!(eval("piecewise((0, 1), (x - 3, 2), 4)", &ctx).unwrap(), Answer::Single(4.0));
+	assert_eq!(eval("piecewise((1, 2), [1 / 0, y], z)", &ctx).unwrap(), Answer::Single(2.0));
+	assert_eq!(eval("(1, 2) + 1", &ctx).unwrap(), eval("[2, 3]", &ctx).unwrap());
+
+	// Conditions that are NaN don't hold
+	ctx.set_var("nan", f64::NAN);
+	assert_eq!(eval("piecewise((nan, 1), 2)", &ctx).unwrap(), Answer::Single(2.0));
+	assert_eq!(eval("piecewise([nan * 0, 1], (nan, 2), (1, 3), 4)", &ctx).unwrap(), Answer::Single(3.0));
+
+	// Every case has to be a pair, and conditions have to be single numbers
+	assert!(eval("piecewise(1, 2, 3)", &ctx).is_err());
+	assert!(eval("piecewise([1, 2, 3], 4)", &ctx).is_err());
+	assert!(eval("piecewise([sqrt(4), 2], 4)", &ctx).is_err());
+	assert!(Term::<f64>::parse_ctx("piecewise(1)", &ctx).is_err());
+}
+
+#[test]
+fn sandboxed() {
+	use crate::MathError;
+
+	let mut ctx: Context<f64> = Context::sandboxed();
+	assert!(ctx.is_sandboxed() && !Context::<f64>::new().is_sandboxed());
+	let eval = |raw: &str, ctx: &Context<f64>| Term::parse_ctx(raw, ctx).map(|term| term.eval_ctx(ctx));
+
+	assert_eq!(eval("max(2, 3) * pi", &ctx).unwrap().unwrap(), Answer::Single(3.0 * ::std::f64::consts::PI));
+	assert!(eval("y + 1", &ctx).is_err());
+	assert!(eval(&"(".repeat(100), &ctx).is_err());
+	assert!(eval(&vec!["1"; 3000].join("+"), &ctx).is_err());
+	assert!(matches!(eval("sqrt(1) + sqrt(4) + sqrt(16) + sqrt(64) + sqrt(256) + sqrt(1024) + sqrt(4096)", &ctx).unwrap(), Err(MathError::TooManyAnswers)));
+
+	// Functions added in Rust can't be called, but functions defined by expressions can
+	ctx.set_func("host", |_args: &[Term<f64>], _ctx: &Context<f64>| -> Calculation<f64> { Ok(Answer::Single(1.0)) });
+	assert!(eval("host(1)", &ctx).is_err());
+	ctx.load_definitions("f(x) = x + 1\ng(x) = g(x)").unwrap();
+	assert!(ctx.load_definitions("h(x) = x + y").is_err());
+	assert_eq!(eval("f(2)", &ctx).unwrap().unwrap(), Answer::Single(3.0));
+	assert!(matches!(eval("g(1)", &ctx).unwrap(), Err(MathError::RecursionLimit) | Err(MathError::Cancelled)));
+
+	// The steps taken in the bodies of nested definitions count towards the limit of the evaluation
+	// that calls them
+	ctx.load_definitions("
+		fa(x) = x + 1
+		fb(x) = fa(x) + fa(x) + fa(x) + fa(x) + fa(x) + fa(x) + fa(x) + fa(x)
+		fc(x) = fb(x) + fb(x) + fb(x) + fb(x) + fb(x) + fb(x) + fb(x) + fb(x)
+		fd(x) = fc(x) + fc(x) + fc(x) + fc(x) + fc(x) + fc(x) + fc(x) + fc(x)
+		fe(x) = fd(x) + fd(x) + fd(x) + fd(x) + fd(x) + fd(x) + fd(x) + fd(x)
+		ff(x) = fe(x) + fe(x) + fe(x) + fe(x) + fe(x) + fe(x) + fe(x) + fe(x)
+	").unwrap();
+	assert_eq!(eval("fc(1)", &ctx).unwrap().unwrap(), Answer::Single(128.0));
+	assert!(matches!(eval("ff(1)", &ctx).unwrap(), Err(MathError::Cancelled)));
+	let mut limited = ctx.clone();
+	limited.cfg.max_steps = Some(2000);
+	assert!(matches!(eval("fd(1)", &limited).unwrap(), Err(MathError::Cancelled)));
+	ctx.cfg.strict_names = false;
+	assert!(eval("host(1)", &ctx).unwrap().is_err());
+	assert!(Term::parse_ctx("host(1) + 1", &ctx).unwrap().bind(&ctx).and_then(|bound| bound.eval(&[])).is_err());
+}
+
+#[test]
+fn eval_async() {
+	use std::future::Future;
+	use std::pin::pin;
+	use std::task::{Context as TaskContext, Poll, Waker};
+	use crate::{CancelToken, MathError};
+
+	/// Poll a future until it's done, returning the answer and how many times it yielded
+	fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+		let mut future = pin!(future);
+		let mut cx = TaskContext::from_waker(Waker::noop());
+		let mut yields = 0;
+		loop {
+			match future.as_mut().poll(&mut cx) {
+				Poll::Ready(output) => return (output, yields),
+				Poll::Pending => yields += 1,
+			}
+		}
+	}
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 2.0);
+	let raw = (1..=100).map(|n| format!("{} * x", n)).collect::<Vec<String>>().join(" + ");
+	let term = Term::parse_ctx(&raw, &ctx).unwrap();
+	let (answer, yields) = block_on(term.eval_async(&ctx, 10));
+	assert_eq!(answer.unwrap(), term.eval_ctx(&ctx).unwrap());
+	assert!(yields > 30);
+	let (answer, yields) = block_on(term.eval_async(&ctx, usize::MAX));
+	assert_eq!(answer.unwrap(), Answer::Single(10100.0));
+	assert_eq!(yields, 0);
+
+	// Functions, errors and multiple answers work like they do normally
+	let expr: Expression<f64> = Expression::parse("max(1, 2) + sqrt(4) * (1 / 0)").unwrap();
+	assert!(matches!(block_on(expr.eval_async(1)).0, Err(MathError::DivideByZero)));
+	let expr: Expression<f64> = Expression::parse("sqrt(16) + 1").unwrap();
+	assert_eq!(block_on(expr.eval_async(1)).0.unwrap(), expr.eval().unwrap());
+
+	// The step limit is the same as for a normal evaluation
+	let term = Term::parse_ctx("(1 + 2) * sqrt(4) - max(3, 4)", &ctx).unwrap();
+	let steps = (1..1000).find(|&steps| {
+		ctx.cfg.max_steps = Some(steps);
+		term.eval_ctx(&ctx).is_ok()
+	}).unwrap();
+	ctx.cfg.max_steps = Some(steps);
+	block_on(term.eval_async(&ctx, 1)).0.unwrap();
+	ctx.cfg.max_steps = Some(steps - 1);
+	assert!(matches!(block_on(term.eval_async(&ctx, 1)).0, Err(MathError::Cancelled)));
+
+	let token = CancelToken::new();
+	ctx.set_cancel_token(token.clone());
+	token.cancel();
+	assert!(matches!(block_on(term.eval_async(&ctx, 1)).0, Err(MathError::Cancelled)));
+}
+
+#[test]
+fn send_sync() {
+	use std::sync::OnceLock;
+	use std::thread;
+
+	fn assert_send_sync<T: Send + Sync>() {}
+	assert_send_sync::<Term<f64>>();
+	assert_send_sync::<Answer<f64>>();
+	assert_send_sync::<Context<f64>>();
+	assert_send_sync::<Expression<f64>>();
+	assert_send_sync::<Term<ComplexFloat>>();
+
+	static EXPR: OnceLock<Expression<f64>> = OnceLock::new();
+	let expr = EXPR.get_or_init(|| {
+		let mut ctx = Context::new();
+		ctx.cfg.max_depth = 16;
+		ctx.cfg.max_steps = Some(10_000);
+		Expression::parse_ctx("sqrt(16) + 2 * 3", ctx).unwrap()
+	});
+	// Every thread gets its own depth and steps, so they don't go over the limits together
+	thread::scope(|scope| {
+		let threads: Vec<_> = (0..8).map(|_| scope.spawn(|| {
+			(0..200).map(|_| expr.eval().unwrap()).last().unwrap()
+		})).collect();
+		for thread in threads {
+			let answer = thread.join().unwrap();
+			assert_eq!(answer.principal(), Some(&10.0));
+		}
+	});
+	assert!(EvalState::current(&expr.ctx).is_none());
+
+	// The threads evaluate with the context itself instead of copies of it
+	static CTX: OnceLock<Context<f64>> = OnceLock::new();
+	let ctx = CTX.get_or_init(|| {
+		let mut ctx = Context::new();
+		ctx.set_func("same", |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+			assert!(std::ptr::eq(ctx, CTX.get().unwrap()));
+			args[0].eval_ctx(ctx)
+		});
+		ctx
+	});
+	let term = Term::parse_ctx("same(same(1) + 1)", ctx).unwrap();
+	thread::scope(|scope| {
+		let threads: Vec<_> = (0..8).map(|_| scope.spawn(|| {
+			(0..200).map(|_| term.eval_ctx(ctx).unwrap()).last().unwrap()
+		})).collect();
+		for thread in threads {
+			assert_eq!(thread.join().unwrap(), Answer::Single(2.0));
+		}
+	});
+
+	// A function that panics in the middle of an evaluation doesn't leave the context stuck in it
+	use std::panic::{self, AssertUnwindSafe};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+	use crate::MathError;
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let counter = Arc::clone(&calls);
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_func("count", move |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+		if counter.fetch_add(1, Ordering::Relaxed) == 0 {
+			panic!("first call");
+		}
+		args[0].eval_ctx(ctx)
+	});
+	ctx.set_var("y", Term::parse_ctx("count(2)", &ctx).unwrap());
+	ctx.load_definitions("a = b\nb = a").unwrap();
+	let term = Term::parse_ctx("y * y", &ctx).unwrap();
+	assert!(panic::catch_unwind(AssertUnwindSafe(|| term.eval_ctx(&ctx))).is_err());
+	assert!(EvalState::current(&ctx).is_none());
+	assert_eq!(term.eval_ctx(&ctx).unwrap(), Answer::Single(4.0));
+	assert_eq!(calls.load(Ordering::Relaxed), 2);
+	let term = Term::parse_ctx("a", &ctx).unwrap();
+	assert!(matches!(term.eval_ctx(&ctx), Err(MathError::CircularDefinition { .. })));
+}
+
+#[test]
+fn default_registry() {
+	use std::sync::Arc;
+	use crate::{Func, FunctionPack};
+
+	struct Plugin;
+
+	impl FunctionPack<f64> for Plugin {
+		fn namespace(&self) -> &str {
+			"registry_test"
+		}
+
+		fn funcs(&self) -> Vec<(String, Arc<dyn Func<f64>>)> {
+			let triple = |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+				args[0].eval_ctx(ctx)?.unop(|x| Ok(Answer::Single(x * 3.0)))
+			};
+			vec![("triple".to_string(), Arc::new(triple))]
+		}
+	}
+
+	// The default context of this thread is created before the functions are registered
+	assert!(eval::<f64>("registry_test.triple(2)").is_err());
+	Context::<f64>::register_default_pack(&Plugin);
+	Context::<f64>::register_default_func("registry_test_square", |args: &[Term<f64>], ctx: &Context<f64>| -> Calculation<f64> {
+		args[0].eval_ctx(ctx)?.unop(|x| Ok(Answer::Single(x * x)))
+	});
+
+	assert_eq!(eval::<f64>("registry_test.triple(2) + registry_test_square(3)").unwrap(), Answer::Single(15.0));
+	assert!(Context::<f64>::new().has_func("registry_test.triple"));
+	assert!(Expression::<f64>::parse("registry_test_square(2)").is_ok());
+	assert!(!Context::<f64>::full().has_func("registry_test.triple"));
+	assert!(!Context::<f64>::sandboxed().has_func("registry_test_square"));
+	// Functions are registered for one number type
+	assert!(!Context::<ComplexFloat>::new().has_func("registry_test_square"));
+}
+
+#[test]
+fn any_num() {
+	use crate::num::{AnyNum, NumKind, Operation};
+
+	let mut ctx: Context<AnyNum> = Context::new();
+	ctx.set_var("x", AnyNum::F64(0.5));
+	let term = Term::parse_ctx("x + 0.1 + 0.2", &ctx).unwrap();
+	assert_eq!(term.eval_ctx(&ctx).unwrap(), Answer::Single(AnyNum::F64(0.5 + 0.1 + 0.2)));
+	let sqrt = Term::parse_ctx("sqrt(4)", &ctx).unwrap().eval_ctx(&ctx).unwrap();
+	assert_eq!(sqrt.principal(), Some(&AnyNum::F64(2.0)));
+	assert_eq!(NumKind::F64.typename(), "f64");
+	assert!(NumKind::F64.capabilities().contains(Operation::Sin));
+
+	// The numbers inside use the current config, even after it changes
+	let div = Term::parse_ctx("1 / 0", &ctx).unwrap();
+	assert!(div.eval_ctx(&ctx).is_err());
+	ctx.cfg.zero_division = crate::ZeroDivision::Infinity;
+	assert_eq!(div.eval_ctx(&ctx).unwrap(), Answer::Single(AnyNum::F64(f64::INFINITY)));
+	ctx.cfg.zero_division = crate::ZeroDivision::Error;
+	assert!(div.eval_ctx(&ctx).is_err());
+
+	#[cfg(feature = "decimal")]
+	{
+		use crate::num::Decimal;
+
+		// Numbers are created when the expression is parsed, so it has to be parsed again
+		ctx.cfg.num_kind = NumKind::Decimal;
+		assert_eq!(term.eval_ctx(&ctx).unwrap().unwrap_single().kind(), NumKind::F64);
+		let term = Term::parse_ctx("x + 0.1 + 0.2", &ctx).unwrap();
+		// The variable is converted to a decimal to be added to the decimals in the expression
+		let answer = term.eval_ctx(&ctx).unwrap().unwrap_single();
+		assert_eq!(answer, AnyNum::Decimal("0.8".parse().unwrap()));
+		assert_eq!(answer.kind(), NumKind::Decimal);
+		assert_eq!(answer.to_string(), "0.8");
+		ctx.set_var("d", AnyNum::from("2.5".parse::<Decimal>().unwrap()));
+		assert_eq!(crate::eval_ctx("d * 2", &ctx).unwrap(), Answer::Single(AnyNum::Decimal(Decimal::new(5, 0).unwrap())));
+		assert!(!NumKind::Decimal.capabilities().contains(Operation::Sin));
+	}
+}
+
+#[test]
+fn number_promotion() {
+	use crate::num::{AnyNum, NumKind};
+
+	let mut ctx: Context<AnyNum> = Context::new();
+	ctx.cfg.promote_numbers = true;
+	ctx.cfg.sqrt_both = false;
+	let eval = |raw: &str, ctx: &Context<AnyNum>| Term::parse_ctx(raw, ctx).unwrap().eval_ctx(ctx).unwrap().unwrap_single();
+	// There's nothing more general than f64s without rug, so it's NaN like without promotion
+	#[cfg(not(feature = "rug"))]
+	assert!(eval("sqrt(-1)", &ctx).is_nan());
+	assert_eq!(eval("pi", &ctx).kind(), NumKind::F64);
+
+	#[cfg(feature = "decimal")]
+	{
+		ctx.cfg.num_kind = NumKind::Decimal;
+		assert_eq!(eval("0.1 + 0.2", &ctx), AnyNum::Decimal("0.3".parse().unwrap()));
+		// Decimals don't have square roots or fractional powers
+		assert!(eq(eval("sqrt(2)", &ctx).to_f64_lossy().unwrap(), 2f64.sqrt()));
+		assert_eq!(eval("2^0.5 + 1", &ctx).kind(), NumKind::F64);
+		assert_eq!(eval("2^3", &ctx).kind(), NumKind::Decimal);
+		let sum = eval("pi + 1", &ctx);
+		assert_eq!(sum.kind(), NumKind::F64);
+		assert!(eq(sum.to_f64_lossy().unwrap(), ::std::f64::consts::PI + 1.0));
+
+		// Without promotion, the operations fail
+		ctx.cfg.promote_numbers = false;
+		assert!(Term::parse_ctx("sqrt(2)", &ctx).unwrap().eval_ctx(&ctx).is_err());
+	}
+}
+
+#[test]
+fn exact_constants() {
+	use crate::num::Exact;
+
+	let mut ctx: Context<Exact> = Context::new();
+	ctx.cfg.sqrt_both = false;
+	let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+	let rational = |num, den| Exact::rational(num, den).unwrap();
+	assert_eq!(eval("sin(pi)"), rational(0, 1));
+	assert_eq!(eval("sqrt(2)^2"), rational(2, 1));
+	assert_eq!(eval("tan(pi/4)"), rational(1, 1));
+	assert_eq!(eval("0.1 + 0.2"), rational(3, 10));
+	assert_eq!(eval("log(8, 2)"), rational(3, 1));
+	assert_eq!(eval("log(e^3, e)"), rational(3, 1));
+	assert_eq!(eval("cos(pi/3)").to_string(), "1/2");
+	assert_eq!(eval("sin(pi/4)").to_string(), "√2/2");
+	assert_eq!(eval("asin(1/2)").to_string(), "π/6");
+	assert_eq!(eval("sqrt(8)").to_string(), "2√2");
+	assert_eq!(eval("1/sqrt(3) + pi").to_string(), "√3/3 + π");
+	assert_eq!(eval("3/(2pi)").to_string(), "3/(2π)");
+	assert_eq!(eval("sqrt(2)*sqrt(6)").to_string(), "2√3");
+
+	// Answers that can't be written exactly are floats
+	let x = eval("sin(1)");
+	assert!(!x.is_exact());
+	assert!(eq(x, 1f64.sin()));
+	assert!(eq(eval("sqrt(pi)"), ::std::f64::consts::PI.sqrt()));
+	assert!(eval("sqrt(2) + 1").is_exact());
+	assert!(!eval("sqrt(2) + sin(1)").is_exact());
+}
+
+#[test]
+fn parse_warnings() {
+	use crate::Warning;
+
+	let mut ctx: Context<f64> = Context::new();
+	ctx.set_var("x", 2.0);
+	let warnings = |raw: &str| Term::<f64>::parse_with_warnings(raw, &ctx).unwrap().1;
+	assert_eq!(warnings("2 * x + sin(x)"), vec![]);
+	assert_eq!(
+		warnings("2x"),
+		vec![Warning::ImplicitMultiplication {
+			left: "2".to_string(),
+			right: "x".to_string(),
+		}]
+	);
+	assert_eq!(
+		warnings("3! (x + 1)"),
+		vec![Warning::ImplicitMultiplication {
+			left: "3!".to_string(),
+			right: "(x + 1)".to_string(),
+		}]
+	);
+	assert_eq!(warnings("foo + 1"), vec![Warning::UnknownVariable { name: "foo".to_string() }]);
+	assert_eq!(warnings("x(1)"), vec![Warning::VariableCall { name: "x".to_string() }]);
+	assert_eq!(warnings("-sin(x)^2"), vec![Warning::NegatedCall { name: "sin".to_string() }]);
+	// Warnings are found in nested groups too
+	assert_eq!(warnings("max(1, -cos(x))"), vec![Warning::NegatedCall { name: "cos".to_string() }]);
+
+	// The term is the same as without warnings
+	let (term, _) = Term::<f64>::parse_with_warnings("2x", &ctx).unwrap();
+	assert_eq!(term.eval_ctx(&ctx).unwrap(), Answer::Single(4.0));
+	let (expr, warnings) = Expression::parse_with_warnings("2x", ctx.clone()).unwrap();
+	assert_eq!(expr.string, "2x");
+	assert_eq!(warnings.len(), 1);
+}
+
+/// A sum of `n` square roots whose combinations all have different values, eg
+/// `sqrt(1)+sqrt(4)+sqrt(16)`
+fn distinct_roots(n: u32) -> String {
+	(0..n).map(|i| format!("sqrt({})", 4u64.pow(i))).collect::<Vec<String>>().join("+")
+}
+
 fn eq<N: Num + 'static>(x: N, y: f64) -> bool {
 	use std::cmp::Ordering;
 	let ctx = &Context::empty();
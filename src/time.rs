@@ -0,0 +1,555 @@
+//! Time spans and dates for spreadsheet-style formulas. `Quantity` wraps another number type and
+//! keeps track of the power of time each number has, so that `hours(2) + minutes(30)` is a time span
+//! shown as `2h 30m`, `hours(5) / hours(1)` is the plain number 5, and adding a plain number to a time
+//! span is a `MathError::UnitMismatch`. The functions for making and converting them are in
+//! `TimePack`. Enable it with the `"time"` feature.
+//!
+//! ```rust
+//! # use mexprp::{Context, Term};
+//! # use mexprp::time::{Quantity, TimePack};
+//! let mut ctx: Context<Quantity<f64>> = Context::new();
+//! ctx.install(&TimePack);
+//! let eval = |raw: &str| Term::parse_ctx(raw, &ctx).unwrap().eval_ctx(&ctx).unwrap().unwrap_single();
+//! assert_eq!(eval("hours(2) + minutes(30)").to_string(), "2h 30m");
+//! assert_eq!(eval("in_minutes(hours(2) + minutes(30)) * 4"), Quantity::number(600.0));
+//! assert_eq!(eval("days_between(date(2024, 1, 1), date(2024, 3, 1))"), Quantity::number(60.0));
+//! ```
+
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use alloc::sync::Arc;
+
+use crate::answer::Answer;
+use crate::context::Context;
+use crate::errors::MathError;
+use crate::format::AnswerFormat;
+use crate::func::{Arity, Func, FunctionPack};
+use crate::num::{self, Num};
+use crate::opers::Calculation;
+use crate::term::Term;
+use crate::prelude::*;
+
+/// The units time spans can be made from and converted to by `TimePack`, with their lengths in
+/// seconds
+const UNITS: [(&str, f64); 5] = [("seconds", 1.0), ("minutes", 60.0), ("hours", 3600.0), ("days", 86400.0), ("weeks", 604_800.0)];
+
+/// A number along with the power of time it has, in seconds. Plain numbers have a power of 0 and time
+/// spans a power of 1, and multiplying or dividing them adds or subtracts the powers.
+///
+/// Adding, subtracting and comparing need both numbers to have the same power, and functions like
+/// `sin` and `log` only take plain numbers. Everything else is done by the type inside with the same
+/// config.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity<N: Num> {
+	/// The number, in seconds to the power of `time`
+	pub value: N,
+	/// The power of time the number has
+	pub time: i32,
+}
+
+impl<N: Num> Quantity<N> {
+	/// A plain number
+	pub fn number(value: N) -> Self {
+		Quantity { value, time: 0 }
+	}
+
+	/// A time span of this many seconds
+	pub fn seconds(value: N) -> Self {
+		Quantity { value, time: 1 }
+	}
+
+	/// Check whether this is a time span
+	pub fn is_span(&self) -> bool {
+		self.time == 1
+	}
+}
+
+/// A description of a quantity with a power of time, for errors
+fn describe(time: i32) -> String {
+	match time {
+		0 => String::from("a plain number"),
+		1 => String::from("a time span"),
+		time => format!("a quantity in s^{}", time),
+	}
+}
+
+/// Check that a quantity has a power of time
+fn expect<N: Num + 'static>(q: &Quantity<N>, time: i32) -> Result<(), MathError> {
+	if q.time == time {
+		Ok(())
+	} else {
+		Err(MathError::UnitMismatch {
+			expected: describe(time),
+			found: describe(q.time),
+		})
+	}
+}
+
+/// A context for the type inside a `Quantity`, with the same config
+fn inner<N: Num + 'static>(ctx: &Context<Quantity<N>>) -> Arc<Context<N>> {
+	Context::inner(&ctx.cfg)
+}
+
+/// Give the values of an answer of the type inside a power of time
+fn with_time<N: Num + 'static>(answer: Answer<N>, time: i32) -> Answer<Quantity<N>> {
+	let wrap = |value| Quantity { value, time };
+	match answer {
+		Answer::Single(n) => Answer::Single(wrap(n)),
+		Answer::Multiple(ns) => Answer::Multiple(ns.into_iter().map(wrap).collect()),
+		Answer::Vector(ns) => Answer::Vector(ns.into_iter().map(|n| with_time(n, time)).collect()),
+		Answer::Labeled(ls) => Answer::Labeled(ls.into_iter().map(|(label, n)| (label, wrap(n))).collect()),
+	}
+}
+
+/// The power of time of the result of an operation, failing if it's too large
+fn checked_time(time: Option<i128>) -> Result<i32, MathError> {
+	time.and_then(|time| i32::try_from(time).ok()).ok_or(MathError::Overflow)
+}
+
+/// Evaluate an operation on plain numbers
+macro_rules! plain {
+	($ctx:expr, $op:ident, $($q:expr),+) => {{
+		$(expect($q, 0)?;)+
+		Ok(with_time(plain!(@call $ctx, $op, $($q),+)?, 0))
+	}};
+	(@call $ctx:expr, $op:ident, $a:expr) => {
+		$a.value.$op(&inner($ctx))
+	};
+	(@call $ctx:expr, $op:ident, $a:expr, $b:expr) => {
+		$a.value.$op(&$b.value, &inner($ctx))
+	};
+}
+
+/// Evaluate an operation that keeps the power of time of the number
+macro_rules! keeping {
+	($q:expr, $ctx:expr, $op:ident) => {
+		Ok(with_time($q.value.$op(&inner($ctx))?, $q.time))
+	};
+}
+
+/// Shows plain numbers like the type inside, time spans in days, hours, minutes and seconds like
+/// `1d 2h 30m`, and other quantities in seconds like `3 s^2`
+impl<N: Num> fmt::Display for Quantity<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match (self.time, self.value.to_f64_lossy()) {
+			(0, _) => write!(f, "{}", self.value),
+			(1, Ok(seconds)) if seconds.is_finite() => {
+				if seconds < 0.0 {
+					write!(f, "-")?;
+				}
+				let mut rest = seconds.abs();
+				let mut parts = Vec::new();
+				for &(suffix, length) in [("d", 86400.0), ("h", 3600.0), ("m", 60.0)].iter() {
+					let count = (rest / length) as u64;
+					if count > 0 {
+						parts.push(format!("{}{}", count, suffix));
+						rest -= count as f64 * length;
+					}
+				}
+				if rest > 0.0 || parts.is_empty() {
+					parts.push(format!("{}s", rest));
+				}
+				write!(f, "{}", parts.join(" "))
+			}
+			(1, _) => write!(f, "{} s", self.value),
+			(time, _) => write!(f, "{} s^{}", self.value, time),
+		}
+	}
+}
+
+impl<N: Num + 'static> Num for Quantity<N> {
+	fn from_f64(t: f64, ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(with_time(N::from_f64(t, &inner(ctx))?, 0))
+	}
+
+	fn from_f64_complex(t: (f64, f64), ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(with_time(N::from_f64_complex(t, &inner(ctx))?, 0))
+	}
+
+	fn typename() -> String {
+		format!("Quantity<{}>", N::typename())
+	}
+
+	fn pi(ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(with_time(N::pi(&inner(ctx))?, 0))
+	}
+
+	fn e(ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(with_time(N::e(&inner(ctx))?, 0))
+	}
+
+	/// The value in seconds to the power of time it has
+	fn to_f64_lossy(&self) -> Result<f64, MathError> {
+		self.value.to_f64_lossy()
+	}
+
+	fn to_f64_complex_lossy(&self) -> Result<(f64, f64), MathError> {
+		self.value.to_f64_complex_lossy()
+	}
+
+	fn is_finite(&self) -> bool {
+		self.value.is_finite()
+	}
+
+	fn is_nan(&self) -> bool {
+		self.value.is_nan()
+	}
+
+	fn format(&self, fmt: &AnswerFormat) -> String {
+		if self.time == 0 {
+			self.value.format(fmt)
+		} else {
+			self.to_string()
+		}
+	}
+
+	fn tryord(&self, other: &Self, ctx: &Context<Self>) -> Result<Ordering, MathError> {
+		expect(other, self.time)?;
+		self.value.tryord(&other.value, &inner(ctx))
+	}
+
+	fn add(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		expect(other, self.time)?;
+		Ok(with_time(self.value.add(&other.value, &inner(ctx))?, self.time))
+	}
+
+	fn sub(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		expect(other, self.time)?;
+		Ok(with_time(self.value.sub(&other.value, &inner(ctx))?, self.time))
+	}
+
+	fn mul(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let time = checked_time(Some(i128::from(self.time) + i128::from(other.time)))?;
+		Ok(with_time(self.value.mul(&other.value, &inner(ctx))?, time))
+	}
+
+	fn div(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		let time = checked_time(Some(i128::from(self.time) - i128::from(other.time)))?;
+		Ok(with_time(self.value.div(&other.value, &inner(ctx))?, time))
+	}
+
+	/// Quantities with a power of time can only be raised to integer powers
+	fn pow(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		expect(other, 0)?;
+		let inner = inner(ctx);
+		let time = if self.time == 0 {
+			0
+		} else {
+			let exp = num::int_exponent(&other.value, &inner).ok_or(MathError::NotAnInteger)?;
+			checked_time(exp.checked_mul(i128::from(self.time)))?
+		};
+		Ok(with_time(self.value.pow(&other.value, &inner)?, time))
+	}
+
+	/// Quantities with a power of time can only have square roots if the power is even
+	fn sqrt(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		if self.time % 2 != 0 {
+			return Err(MathError::UnitMismatch {
+				expected: String::from("a quantity in an even power of s"),
+				found: describe(self.time),
+			});
+		}
+		Ok(with_time(self.value.sqrt(&inner(ctx))?, self.time / 2))
+	}
+
+	fn nrt(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		expect(other, 0)?;
+		let inner = inner(ctx);
+		let time = if self.time == 0 {
+			0
+		} else {
+			match num::int_exponent(&other.value, &inner) {
+				Some(n) if n != 0 && i128::from(self.time) % n == 0 => checked_time(Some(i128::from(self.time) / n))?,
+				_ => {
+					return Err(MathError::UnitMismatch {
+						expected: format!("a quantity in a power of s divisible by {}", other.value),
+						found: describe(self.time),
+					})
+				}
+			}
+		};
+		Ok(with_time(self.value.nrt(&other.value, &inner)?, time))
+	}
+
+	fn abs(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		keeping!(self, ctx, abs)
+	}
+
+	fn sin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, sin, self)
+	}
+
+	fn cos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, cos, self)
+	}
+
+	fn tan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, tan, self)
+	}
+
+	fn asin(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, asin, self)
+	}
+
+	fn acos(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, acos, self)
+	}
+
+	fn atan(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, atan, self)
+	}
+
+	/// The angle is a plain number, for any two quantities with the same power of time
+	fn atan2(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		expect(other, self.time)?;
+		Ok(with_time(self.value.atan2(&other.value, &inner(ctx))?, 0))
+	}
+
+	fn floor(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		keeping!(self, ctx, floor)
+	}
+
+	fn ceil(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		keeping!(self, ctx, ceil)
+	}
+
+	fn round(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		keeping!(self, ctx, round)
+	}
+
+	fn log(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, log, self, other)
+	}
+
+	fn fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, fact, self)
+	}
+
+	fn double_fact(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, double_fact, self)
+	}
+
+	fn binomial(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, binomial, self, k)
+	}
+
+	fn permutations(&self, k: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, permutations, self, k)
+	}
+
+	fn gamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, gamma, self)
+	}
+
+	fn lgamma(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, lgamma, self)
+	}
+
+	fn erf(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, erf, self)
+	}
+
+	fn erfc(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, erfc, self)
+	}
+
+	fn beta(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, beta, self, other)
+	}
+
+	fn zeta(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, zeta, self)
+	}
+
+	fn bitand(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, bitand, self, other)
+	}
+
+	fn bitor(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, bitor, self, other)
+	}
+
+	fn bitxor(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, bitxor, self, other)
+	}
+
+	fn shl(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, shl, self, other)
+	}
+
+	fn shr(&self, other: &Self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, shr, self, other)
+	}
+
+	fn bitnot(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		plain!(ctx, bitnot, self)
+	}
+
+	/// The sign is a plain number
+	fn sign(&self, ctx: &Context<Self>) -> Calculation<Self> {
+		Ok(with_time(self.value.sign(&inner(ctx))?, 0))
+	}
+}
+
+/// A function on quantities
+type QuantityFunc<N> = Arc<dyn Func<Quantity<N>>>;
+
+/// Functions for time spans and dates, installed without a namespace:
+///
+/// - `seconds(n)`, `minutes(n)`, `hours(n)`, `days(n)` and `weeks(n)` make time spans
+/// - `in_seconds(t)`, `in_minutes(t)`, `in_hours(t)`, `in_days(t)` and `in_weeks(t)` convert time
+///   spans to plain numbers
+/// - `date(year, month, day)` is the time since the start of 1970-01-01, so that subtracting dates
+///   gives the time between them. Months and days past the end of the year or month carry over like
+///   in spreadsheets, so `date(2024, 1, 32)` is February 1st.
+/// - `days_between(a, b)` is the number of days from the date `a` to the date `b`
+///
+/// See the [module docs](index.html) for an example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimePack;
+
+impl<N: Num + 'static> FunctionPack<Quantity<N>> for TimePack {
+	fn namespace(&self) -> &str {
+		""
+	}
+
+	fn funcs(&self) -> Vec<(String, Arc<dyn Func<Quantity<N>>>)> {
+		let mut funcs: Vec<(String, QuantityFunc<N>)> = Vec::new();
+		for &(name, seconds) in UNITS.iter() {
+			funcs.push((name.to_string(), Arc::new(Span { seconds })));
+			funcs.push((format!("in_{}", name), Arc::new(InUnit { seconds })));
+		}
+		funcs.push(("date".to_string(), Arc::new(Date)));
+		funcs.push(("days_between".to_string(), Arc::new(DaysBetween)));
+		funcs
+	}
+}
+
+/// Evaluate the arguments of a function that takes a single number each
+fn single_args<N: Num + 'static>(args: &[Term<Quantity<N>>], count: usize, ctx: &Context<Quantity<N>>) -> Result<Vec<Quantity<N>>, MathError> {
+	if args.len() != count {
+		return Err(MathError::IncorrectArguments);
+	}
+	args.iter()
+		.map(|arg| match arg.eval_ctx(ctx)? {
+			Answer::Single(q) => Ok(q),
+			_ => Err(MathError::IncorrectArguments),
+		})
+		.collect()
+}
+
+/// A number of the type inside a `Quantity` that's an integer in `f64`
+fn int<N: Num + 'static>(n: f64, ctx: &Context<Quantity<N>>) -> Result<N, MathError> {
+	Ok(N::from_f64(n, &inner(ctx))?.unwrap_single())
+}
+
+/// Makes a time span from a number of a unit
+struct Span {
+	seconds: f64,
+}
+
+impl<N: Num + 'static> Func<Quantity<N>> for Span {
+	fn eval(&self, args: &[Term<Quantity<N>>], ctx: &Context<Quantity<N>>) -> Calculation<Quantity<N>> {
+		let n = args.first().ok_or(MathError::IncorrectArguments)?.eval_ctx(ctx)?;
+		n.unop_ctx(ctx, |n| {
+			expect(n, 0)?;
+			Ok(with_time(n.value.mul(&int(self.seconds, ctx)?, &inner(ctx))?, 1))
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(1)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("A time span of this many of the unit")
+	}
+}
+
+/// Converts a time span to a number of a unit
+struct InUnit {
+	seconds: f64,
+}
+
+impl<N: Num + 'static> Func<Quantity<N>> for InUnit {
+	fn eval(&self, args: &[Term<Quantity<N>>], ctx: &Context<Quantity<N>>) -> Calculation<Quantity<N>> {
+		let t = args.first().ok_or(MathError::IncorrectArguments)?.eval_ctx(ctx)?;
+		t.unop_ctx(ctx, |t| {
+			expect(t, 1)?;
+			Ok(with_time(t.value.div(&int(self.seconds, ctx)?, &inner(ctx))?, 0))
+		})
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(1)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("How many of the unit a time span is")
+	}
+}
+
+/// The number of days from 1970-01-01 to a date in the proleptic Gregorian calendar. Months outside
+/// of 1 to 12 carry over to other years, and days carry over to other months.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+	let year = year + (month - 1).div_euclid(12);
+	let month = (month - 1).rem_euclid(12) + 1;
+	// Count years from March so that the leap day is at the end of the year
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year.div_euclid(400);
+	let year_of_era = year - era * 400;
+	let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	era * 146_097 + day_of_era - 719_468
+}
+
+/// The time since the start of 1970-01-01
+struct Date;
+
+impl<N: Num + 'static> Func<Quantity<N>> for Date {
+	fn eval(&self, args: &[Term<Quantity<N>>], ctx: &Context<Quantity<N>>) -> Calculation<Quantity<N>> {
+		let mut parts = [0i64; 3];
+		for (part, q) in parts.iter_mut().zip(single_args(args, 3, ctx)?) {
+			expect(&q, 0)?;
+			let n = q.value.to_f64_lossy()?;
+			// Dates further away than a million years would lose precision as seconds
+			if n % 1.0 != 0.0 || !(-1e6..=1e6).contains(&n) {
+				return Err(MathError::NotAnInteger);
+			}
+			*part = n as i64;
+		}
+		let days = days_from_civil(parts[0], parts[1], parts[2]);
+		Ok(Answer::Single(Quantity::seconds(int(days as f64 * 86400.0, ctx)?)))
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(3)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The time since 1970-01-01 of a date, like date(2024, 3, 1)")
+	}
+}
+
+/// The number of days between two dates
+struct DaysBetween;
+
+impl<N: Num + 'static> Func<Quantity<N>> for DaysBetween {
+	fn eval(&self, args: &[Term<Quantity<N>>], ctx: &Context<Quantity<N>>) -> Calculation<Quantity<N>> {
+		let dates = single_args(args, 2, ctx)?;
+		expect(&dates[0], 1)?;
+		expect(&dates[1], 1)?;
+		let inner = inner(ctx);
+		let span = dates[1].value.sub(&dates[0].value, &inner)?;
+		Ok(with_time(span.unop_ctx(&inner, |s| s.div(&int(86400.0, ctx)?, &inner))?, 0))
+	}
+
+	fn arity(&self) -> Arity {
+		Arity::exact(2)
+	}
+
+	fn help_text(&self) -> Option<&str> {
+		Some("The number of days from the first date to the second")
+	}
+}
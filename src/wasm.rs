@@ -0,0 +1,149 @@
+//! Bindings for using MEXPRP from JavaScript with `wasm-bindgen`. Only `f64` numbers are supported.
+//! Enable them with the `"wasm"` feature and build for `wasm32-unknown-unknown` (eg with
+//! `wasm-pack build --features wasm`).
+//!
+//! A `Calculator` holds the variables and functions expressions are evaluated with. Failures are
+//! thrown as `Error` objects, which have the `code` and message of the error, and for errors in the
+//! input, where in the input the problem is.
+//!
+//! ```js
+//! import { Calculator } from "mexprp";
+//!
+//! const calc = new Calculator();
+//! calc.set_var("x", 3);
+//! calc.eval("sqrt(x + 1)"); // Float64Array [2, -2]
+//! try {
+//!     calc.eval("2 * (3");
+//! } catch (e) {
+//!     console.log(e.code, e.message, e.start, e.end); // "parse.mismatched_parentheses" ... 4 5
+//! }
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::context::Context;
+use crate::diagnostic;
+use crate::errors::{DefinitionError, MathError, ParseError};
+use crate::term::Term;
+
+/// An error thrown by the bindings
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+	/// A short code identifying the kind of error, like `"parse.unexpected_token"`
+	pub code: String,
+	/// A description of the error
+	pub message: String,
+	/// The byte offset in the input where the problem starts, if it's known
+	pub start: Option<usize>,
+	/// The byte offset in the input where the problem ends, if it's known
+	pub end: Option<usize>,
+}
+
+impl Error {
+	/// An error in the given input, pointing at the first problem found in it
+	fn parse(e: ParseError, raw: &str, ctx: &Context<f64>) -> Self {
+		let span = diagnostic::check(raw, ctx).into_iter().next().map(|diagnostic| diagnostic.span);
+		Error {
+			code: e.code().to_string(),
+			message: e.to_string(),
+			start: span.as_ref().map(|span| span.start),
+			end: span.map(|span| span.end),
+		}
+	}
+
+	fn math(e: MathError) -> Self {
+		Error {
+			code: e.code().to_string(),
+			message: e.to_string(),
+			start: None,
+			end: None,
+		}
+	}
+}
+
+/// The variables and functions expressions are evaluated with, along with the config. It starts with
+/// the builtin constants and functions of `Context::new()`.
+#[wasm_bindgen]
+pub struct Calculator {
+	ctx: Context<f64>,
+}
+
+#[wasm_bindgen]
+impl Calculator {
+	/// Create a calculator with the builtin constants and functions
+	#[wasm_bindgen(constructor)]
+	pub fn new() -> Calculator {
+		Calculator { ctx: Context::new() }
+	}
+
+	/// Create a calculator with `Context::sandboxed()`, for evaluating input from other users
+	pub fn sandboxed() -> Calculator {
+		Calculator { ctx: Context::sandboxed() }
+	}
+
+	/// Set a variable to a number, replacing any existing one with the same name
+	pub fn set_var(&mut self, name: &str, value: f64) {
+		self.ctx.set_var(name, value);
+	}
+
+	/// Remove a variable, returning whether there was one
+	pub fn remove_var(&mut self, name: &str) -> bool {
+		self.ctx.remove_var(name).is_some()
+	}
+
+	/// Add the definitions in the format read by `Context::load_definitions`, like `f(a, b) = a*b + 1`
+	pub fn load_definitions(&mut self, raw: &str) -> Result<(), Error> {
+		self.ctx.load_definitions(raw).map_err(|e| {
+			let code = match e {
+				DefinitionError::InvalidDefinition { .. } => "definition.invalid",
+				DefinitionError::ParseError { ref error, .. } => error.code(),
+			};
+			Error {
+				code: code.to_string(),
+				message: e.to_string(),
+				start: None,
+				end: None,
+			}
+		})
+	}
+
+	/// Parse an expression, so that it can be evaluated many times without parsing it again
+	pub fn parse(&self, raw: &str) -> Result<Expr, Error> {
+		Term::parse_ctx(raw, &self.ctx)
+			.map(|term| Expr { term })
+			.map_err(|e| Error::parse(e, raw, &self.ctx))
+	}
+
+	/// Parse and evaluate an expression, returning all of its answers. Vectors are flattened.
+	pub fn eval(&self, raw: &str) -> Result<Vec<f64>, Error> {
+		self.parse(raw)?.eval(self)
+	}
+}
+
+impl Default for Calculator {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// A parsed expression
+#[wasm_bindgen]
+pub struct Expr {
+	term: Term<f64>,
+}
+
+#[wasm_bindgen]
+impl Expr {
+	/// Evaluate the expression with the variables and functions of a calculator, returning all of its
+	/// answers. Vectors are flattened.
+	pub fn eval(&self, calc: &Calculator) -> Result<Vec<f64>, Error> {
+		self.term.eval_ctx(&calc.ctx).map(|answer| answer.to_vec()).map_err(Error::math)
+	}
+
+	/// The expression written out with full parentheses
+	#[wasm_bindgen(js_name = toString)]
+	pub fn to_js_string(&self) -> String {
+		self.term.to_string()
+	}
+}
@@ -0,0 +1,195 @@
+//! Tests of the `mexprp` command line calculator, which run the binary
+
+#![cfg(feature = "cli")]
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{self, Command, Stdio};
+
+/// Run the calculator with some input, returning its exit code, stdout and stderr
+fn run(args: &[&str], input: &str) -> (i32, String, String) {
+	let mut child = Command::new(env!("CARGO_BIN_EXE_mexprp"))
+		.args(args)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+	child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+	let output = child.wait_with_output().unwrap();
+	(
+		output.status.code().unwrap(),
+		String::from_utf8(output.stdout).unwrap(),
+		String::from_utf8(output.stderr).unwrap(),
+	)
+}
+
+/// Run the calculator without a session file
+fn mexprp(args: &[&str]) -> (i32, String, String) {
+	let mut all = vec!["--no-session"];
+	all.extend_from_slice(args);
+	run(&all, "")
+}
+
+/// A session file for a test in the temporary directory, which is deleted when it's dropped
+struct SessionFile(PathBuf);
+
+impl SessionFile {
+	fn new(name: &str) -> Self {
+		let path = env::temp_dir().join(format!("mexprp_session_{}_{}", process::id(), name));
+		let _ = fs::remove_file(&path);
+		SessionFile(path)
+	}
+
+	fn path(&self) -> &str {
+		self.0.to_str().unwrap()
+	}
+}
+
+impl Drop for SessionFile {
+	fn drop(&mut self) {
+		let _ = fs::remove_file(&self.0);
+	}
+}
+
+#[test]
+fn expressions() {
+	let (code, out, _) = mexprp(&["1 + 2", "sqrt(4)", "[1, 2]"]);
+	assert_eq!(code, 0);
+	assert_eq!(out, "3\n{2, -2}\n[1, 2]\n");
+
+	// The other expressions are still evaluated after one fails
+	let (code, out, err) = mexprp(&["x", "2 * 3"]);
+	assert_eq!(code, 1);
+	assert_eq!(out, "6\n");
+	assert!(err.contains("'x' is not defined"));
+
+	// Everything after -- is an expression
+	let (code, out, _) = mexprp(&["--", "-1"]);
+	assert_eq!((code, out.as_str()), (0, "-1\n"));
+}
+
+#[test]
+fn options() {
+	// Variables can use the ones before them
+	let (code, out, _) = mexprp(&["--ctx", "x=2", "--ctx", "y = x + 1", "x * y"]);
+	assert_eq!((code, out.as_str()), (0, "6\n"));
+
+	let (code, _, err) = mexprp(&["--ctx", "=2"]);
+	assert_eq!(code, 2);
+	assert!(err.starts_with("Invalid definition '=2'"));
+	let (code, _, err) = mexprp(&["--ctx"]);
+	assert_eq!(code, 2);
+	assert!(err.starts_with("Missing a definition after --ctx"));
+	let (code, _, err) = mexprp(&["--bogus"]);
+	assert_eq!(code, 2);
+	assert!(err.starts_with("Unknown option '--bogus'"));
+
+	let (code, out, _) = mexprp(&["--help"]);
+	assert_eq!(code, 0);
+	assert!(out.starts_with("Usage: mexprp"));
+}
+
+#[test]
+fn precision() {
+	// The precision only affects arbitrary precision numbers
+	let (code, out, _) = mexprp(&["--precision", "64", "1 / 4"]);
+	assert_eq!((code, out.as_str()), (0, "0.25\n"));
+
+	let (code, _, err) = mexprp(&["--precision", "abc", "1"]);
+	assert_eq!(code, 2);
+	assert!(err.starts_with("Invalid precision 'abc'"));
+}
+
+#[test]
+fn number_types() {
+	let (code, out, _) = mexprp(&["--type", "f64", "1 / 4"]);
+	assert_eq!((code, out.as_str()), (0, "0.25\n"));
+
+	let (code, _, err) = mexprp(&["--type", "nope", "1"]);
+	assert_eq!(code, 2);
+	assert!(err.starts_with("Unknown number type 'nope'"));
+}
+
+#[cfg(feature = "rug")]
+#[test]
+fn rug_number_types() {
+	let (code, out, _) = mexprp(&["--type", "rational", "1 / 3"]);
+	assert_eq!((code, out.as_str()), (0, "1/3\n"));
+}
+
+#[cfg(not(feature = "rug"))]
+#[test]
+fn rug_number_types() {
+	let (code, _, err) = mexprp(&["--type", "rational", "1"]);
+	assert_eq!(code, 2);
+	assert_eq!(err, "The rational type needs mexprp to be built with the `rug` feature\n");
+}
+
+#[test]
+fn session_round_trip() {
+	let session = SessionFile::new("round_trip");
+	let (code, out, _) = run(&["--session", session.path()], "x = 3\nf(a) = a * x\ny = 1\n:forget y\n");
+	assert_eq!(code, 0);
+	assert!(out.contains("x = 3"));
+	assert_eq!(fs::read_to_string(&session.0).unwrap(), "x = 3\nf(a) = a * x\n");
+
+	// The definitions are loaded again when the calculator starts
+	let (code, out, _) = run(&["--session", session.path(), "f(2)"], "");
+	assert_eq!((code, out.as_str()), (0, "6\n"));
+	let (code, _, err) = run(&["--session", session.path(), "y"], "");
+	assert_eq!(code, 1);
+	assert!(err.contains("'y' is not defined"));
+}
+
+#[test]
+fn session_errors() {
+	// Lines that fail to load are reported with their line number, and the rest are still loaded
+	let session = SessionFile::new("errors");
+	fs::write(&session.0, "x = 2\ny = oops(\n\n# a comment\nz = x + 1\n").unwrap();
+	let (code, out, err) = run(&["--session", session.path(), "z"], "");
+	assert_eq!((code, out.as_str()), (0, "3\n"));
+	assert!(err.starts_with(&format!("{}:2: ", session.path())));
+	assert_eq!(err.lines().count(), 1);
+
+	// A missing session file is an empty session
+	let missing = SessionFile::new("missing");
+	let (code, out, err) = run(&["--session", missing.path(), "1 + 1"], "");
+	assert_eq!((code, out.as_str(), err.as_str()), (0, "2\n", ""));
+}
+
+#[test]
+fn sweep_table() {
+	// Cells that fail to evaluate are left empty, and the columns are padded to line up
+	let (code, out, _) = mexprp(&["--sweep", "x=0:1:0.5", "x^2", "x + y", "max(x, 1)"]);
+	assert_eq!(code, 0);
+	assert_eq!(out, "\
+x    x^2   x + y  max(x, 1)
+0    0            1
+0.5  0.25         1
+1    1            1
+");
+
+	let (code, _, err) = mexprp(&["--sweep", "x=0:1:-1", "x"]);
+	assert_eq!(code, 1);
+	assert_eq!(err, "The sweep step doesn't go from the start to the end\n");
+}
+
+#[test]
+fn sweep_csv() {
+	// Cells with commas are quoted
+	let (code, out, _) = mexprp(&["--format", "csv", "--sweep", "x=0:2:1", "x^2", "[x, 1]", "y"]);
+	assert_eq!(code, 0);
+	assert_eq!(out, "\
+x,x^2,\"[x, 1]\",y
+0,0,\"[0, 1]\",
+1,1,\"[1, 1]\",
+2,4,\"[2, 1]\",
+");
+
+	let (code, _, err) = mexprp(&["--format", "json", "--sweep", "x=0:2:1", "x"]);
+	assert_eq!(code, 2);
+	assert!(err.starts_with("Unknown format 'json'"));
+}